@@ -1,23 +1,30 @@
 use crate::{
-    core::{GrassSystem, GravitySystem, RoadSystem, Skybox, SphericalWorld, Timer, TreeSystem},
-    input::InputState,
+    core::{GrassSystem, GravitySystem, Picker, RoadSystem, Skybox, Timer, TreeSystem},
+    input::{CameraControllers, InputState},
     log,
-    math::Vec3,
+    math::{Vec2, Vec3},
     renderer::SceneRenderer,
-    scene::{Node, Scene},
-    ui::{FPSCounter, UIRenderer},
+    scene::{CameraPath, CameraPathPlayer, DepthMapping, MeshSource, Node, Scene},
+    ui::{FPSCounter, LabelPlacer, UIRenderer},
 };
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use winit::{
     application::ApplicationHandler,
-    event::{DeviceEvent, DeviceId, ElementState, KeyEvent, WindowEvent},
+    event::{
+        DeviceEvent, DeviceId, ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent,
+    },
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     keyboard::{KeyCode, PhysicalKey},
     raw_window_handle::HasWindowHandle,
     window::{CursorGrabMode, Window, WindowAttributes, WindowId},
 };
 
+/// Height of the tallest trees and props above the ground, for fitting the clip planes
+const FEATURE_HEIGHT: f32 = 12.0;
+
+/// Field of view change per press of `[` or `]`
+const FOV_STEP_DEGREES: f32 = 5.0;
+
 pub struct App {
     window: Option<Window>,
     renderer: Option<SceneRenderer>,
@@ -27,32 +34,61 @@ pub struct App {
     frame_count: u32,
     fps_counter: FPSCounter,
     input_state: InputState,
+    camera_controllers: CameraControllers,
+    /// Controller that takes the camera once the renderer exists
+    initial_controller: usize,
     gravity_system: GravitySystem,
     planet_radius: f32,
     skybox: Skybox,
     grass_system: Option<GrassSystem>,
     road_system: Option<RoadSystem>,
     tree_system: Option<TreeSystem>,
+    screenshot_requested: bool,
+    /// Last cursor position in pixels, used for picking once Tab frees the cursor
+    cursor_position: Vec2,
+    cursor_free: bool,
+    /// Label and position of the last pick, marked on screen
+    pick_marker: Option<(String, Vec3)>,
 }
 
 impl App {
     pub fn new() -> Self {
         let planet_radius = 25.0; // Reduced from 50.0 for a smaller planet
+        Self::with_scene(Self::create_spherical_scene(planet_radius))
+    }
+
+    /// Runs `scene`; its "Planet" node's spherical world recipe, if any, sets the
+    /// planet radius used for gravity and world generation
+    pub fn with_scene(scene: Scene) -> Self {
+        let planet_radius = scene
+            .find_by_name("Planet")
+            .and_then(|id| scene.get(id)?.mesh_source())
+            .and_then(|source| match source {
+                MeshSource::SphericalWorld { radius, .. } => Some(*radius),
+                _ => None,
+            })
+            .unwrap_or(25.0);
         Self {
             window: None,
             renderer: None,
             ui_renderer: None,
-            scene: Self::create_spherical_scene(planet_radius),
+            scene,
             timer: Timer::new(),
             frame_count: 0,
             fps_counter: FPSCounter::new(),
             input_state: InputState::new(),
+            camera_controllers: CameraControllers::for_planet(planet_radius),
+            initial_controller: 0,
             gravity_system: GravitySystem::new(Vec3::zero(), 9.8),
             planet_radius,
             skybox: Skybox::new(),
             grass_system: None,
             road_system: None,
             tree_system: None,
+            screenshot_requested: false,
+            cursor_position: Vec2::zero(),
+            cursor_free: false,
+            pick_marker: None,
         }
     }
 
@@ -60,12 +96,16 @@ impl App {
         let mut scene = Scene::new();
 
         // Create spherical world
-        let world = SphericalWorld::new(planet_radius, 4); // 4 subdivisions for smooth sphere
-        let sphere_node = Rc::new(RefCell::new(Node::with_mesh(
-            "Planet".to_string(),
-            world.generate_mesh(),
-        )));
-        scene.add_node(sphere_node);
+        let world = MeshSource::SphericalWorld {
+            radius: planet_radius,
+            subdivision_level: 4, // 4 subdivisions for smooth sphere
+        };
+        match Node::from_source("Planet".to_string(), world) {
+            Ok(planet) => {
+                scene.add_node(planet);
+            }
+            Err(e) => log!("Failed to create planet: {}", e),
+        }
 
         // Set light above the planet
         scene.light.position = Vec3::new(10.0, planet_radius + 20.0, 10.0);
@@ -73,6 +113,64 @@ impl App {
         scene
     }
 
+    /// Saves the frame captured by the F12 hotkey into the working directory
+    fn save_screenshot(renderer: &SceneRenderer) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let path = format!("screenshot-{timestamp}.png");
+        match renderer.save_screenshot(&path) {
+            Ok(()) => log!("Saved screenshot to {}", path),
+            Err(e) => log!("Failed to save screenshot: {}", e),
+        }
+    }
+
+    /// Adds a controller playing `path` and starts with it; C still cycles through
+    /// the interactive cameras
+    pub fn with_camera_path(mut self, path: CameraPath) -> Self {
+        self.initial_controller = self
+            .camera_controllers
+            .push(Box::new(CameraPathPlayer::new(path)));
+        self
+    }
+
+    /// Logs what is under the cursor, or under the screen center while the cursor
+    /// is captured for mouse look
+    fn pick(&mut self) {
+        let (Some(window), Some(renderer)) = (&self.window, &self.renderer) else {
+            return;
+        };
+        let size = window.inner_size();
+        let viewport = Vec2::new(size.width as f32, size.height as f32);
+        let pixel = if self.cursor_free {
+            self.cursor_position
+        } else {
+            Vec2::new(viewport.x / 2.0, viewport.y / 2.0)
+        };
+        let ray = renderer.camera().screen_ray(pixel, viewport);
+
+        let mut picker = Picker::new()
+            .with_planet(Vec3::zero(), self.planet_radius)
+            .with_scene(&self.scene);
+        if let Some(trees) = &self.tree_system {
+            picker = picker.with_trees(trees);
+        }
+        if let Some(grass) = &self.grass_system {
+            picker = picker.with_grass(grass);
+        }
+        if let Some(hit) = picker.pick(&ray) {
+            let (lat, lon) = hit.lat_lon.to_degrees();
+            log!(
+                "Picked {:?} at {:.1} away, lat {:.2}, lon {:.2}",
+                hit.target,
+                hit.distance,
+                lat,
+                lon
+            );
+            self.pick_marker = Some((format!("{:?}", hit.target), hit.point));
+        }
+    }
+
     fn format_fps(&self) -> String {
         // Using String::with_capacity to avoid multiple allocations
         // This is still more efficient than format! which allocates multiple times
@@ -83,11 +181,24 @@ impl App {
         result
     }
 
+    /// Opens the window and runs the event loop. A scene file passed as the first
+    /// command-line argument replaces the built-in planet scene, and a camera path
+    /// file passed as the second plays from the start.
     pub fn run() -> Result<(), Box<dyn std::error::Error>> {
         let event_loop = EventLoop::new()?;
         event_loop.set_control_flow(ControlFlow::Poll);
 
-        let mut app = App::new();
+        let mut app = match std::env::args().nth(1) {
+            Some(path) => {
+                log!("Loading scene {}", path);
+                App::with_scene(Scene::load(&path)?)
+            }
+            None => App::new(),
+        };
+        if let Some(path) = std::env::args().nth(2) {
+            log!("Loading camera path {}", path);
+            app = app.with_camera_path(CameraPath::load(&path)?);
+        }
         event_loop.run_app(&mut app)?;
         Ok(())
     }
@@ -136,6 +247,11 @@ impl ApplicationHandler for App {
                                                 let camera = renderer.camera_mut();
                                                 camera.set_position(initial_position);
                                                 camera.set_up_vector(initial_position.normalize());
+                                                camera.set_depth_mapping(
+                                                    DepthMapping::ReverseZInfinite,
+                                                );
+                                                self.camera_controllers
+                                                    .switch_to(self.initial_controller, camera);
                                             }
 
                                             log!("Renderer initialized successfully");
@@ -267,11 +383,44 @@ impl ApplicationHandler for App {
                         event_loop.exit();
                     }
                 }
+                PhysicalKey::Code(KeyCode::F12) => {
+                    if state == ElementState::Pressed {
+                        if let Some(renderer) = &mut self.renderer {
+                            renderer.request_capture();
+                            self.screenshot_requested = true;
+                        }
+                    }
+                }
+                PhysicalKey::Code(KeyCode::KeyC) => {
+                    if state == ElementState::Pressed {
+                        if let Some(renderer) = &mut self.renderer {
+                            self.camera_controllers.cycle(renderer.camera_mut());
+                            if let Some(controller) = self.camera_controllers.active() {
+                                log!("Camera mode: {}", controller.name());
+                            }
+                        }
+                    }
+                }
+                PhysicalKey::Code(code @ (KeyCode::BracketLeft | KeyCode::BracketRight)) => {
+                    if state == ElementState::Pressed {
+                        if let Some(renderer) = &mut self.renderer {
+                            let step = if code == KeyCode::BracketLeft {
+                                -FOV_STEP_DEGREES
+                            } else {
+                                FOV_STEP_DEGREES
+                            };
+                            let camera = renderer.camera_mut();
+                            camera.set_fov_y(camera.fov_y() + step.to_radians());
+                            log!("Field of view: {:.0} degrees", camera.fov_y().to_degrees());
+                        }
+                    }
+                }
                 PhysicalKey::Code(KeyCode::Tab) => {
                     if state == ElementState::Pressed {
                         if let Some(window) = &self.window {
                             window.set_cursor_grab(CursorGrabMode::None).ok();
                             window.set_cursor_visible(true);
+                            self.cursor_free = true;
                         }
                     }
                 }
@@ -280,6 +429,22 @@ impl ApplicationHandler for App {
                     ElementState::Released => self.input_state.key_released(physical_key),
                 },
             },
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Vec2::new(position.x as f32, position.y as f32);
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => self.pick(),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    // Roughly one line per 40 pixels of trackpad scrolling
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+                };
+                self.input_state.add_scroll(lines);
+            }
             WindowEvent::RedrawRequested => {
                 let delta = self.timer.delta();
                 self.frame_count += 1;
@@ -295,81 +460,18 @@ impl ApplicationHandler for App {
                     renderer.update_time(delta);
                 }
 
-                // Update camera with first-person movement
+                // Move the camera with the active controller
                 if let Some(renderer) = &mut self.renderer {
                     let camera = renderer.camera_mut();
 
-                    // Get current camera state
-                    let current_position = camera.position();
-
                     // Update gravity system with current position
                     self.gravity_system.planet_center = Vec3::zero();
 
-                    // Handle camera rotation with mouse
-                    let (dx, dy) = self.input_state.mouse_delta();
-                    if dx.abs() > 0.0 || dy.abs() > 0.0 {
-                        let sensitivity = self.input_state.mouse_sensitivity();
-                        camera.rotate(-dx * sensitivity, -dy * sensitivity);
-                        self.input_state.reset_mouse_delta();
-                    }
-
-                    // Handle movement input
-                    let mut movement = Vec3::zero();
-                    let speed = if self
-                        .input_state
-                        .is_key_pressed(PhysicalKey::Code(KeyCode::ShiftLeft))
-                        || self
-                            .input_state
-                            .is_key_pressed(PhysicalKey::Code(KeyCode::ShiftRight))
-                    {
-                        10.0 // Running speed
-                    } else {
-                        5.0 // Walking speed
-                    };
-
-                    if self
-                        .input_state
-                        .is_key_pressed(PhysicalKey::Code(KeyCode::KeyW))
-                    {
-                        movement = movement.add(&camera.forward().scale(speed * delta));
-                    }
-                    if self
-                        .input_state
-                        .is_key_pressed(PhysicalKey::Code(KeyCode::KeyS))
-                    {
-                        movement = movement.sub(&camera.forward().scale(speed * delta));
-                    }
-                    if self
-                        .input_state
-                        .is_key_pressed(PhysicalKey::Code(KeyCode::KeyA))
-                    {
-                        movement = movement.sub(&camera.right().scale(speed * delta));
-                    }
-                    if self
-                        .input_state
-                        .is_key_pressed(PhysicalKey::Code(KeyCode::KeyD))
-                    {
-                        movement = movement.add(&camera.right().scale(speed * delta));
-                    }
-
-                    // Apply movement and constrain to sphere surface
-                    if movement.length() > 0.0 {
-                        let new_position = current_position.add(&movement);
-
-                        // Constrain to sphere surface
-                        let height_above_surface = 15.0; // Eye height (increased for better view)
-                        let constrained_position = new_position
-                            .normalize()
-                            .scale(self.planet_radius + height_above_surface);
-
-                        camera.set_position(constrained_position);
-
-                        // Update up vector to match new position on sphere
-                        let new_up = constrained_position.normalize();
-                        camera.set_up_vector(new_up);
-                    }
-
-                    camera.update(delta);
+                    self.camera_controllers
+                        .update(camera, &self.input_state, delta);
+                    self.input_state.reset_mouse_delta();
+                    self.input_state.reset_scroll_delta();
+                    camera.fit_clip_planes(Vec3::zero(), self.planet_radius, FEATURE_HEIGHT);
 
                     // Update grass LOD system with camera position
                     if let Some(grass_system) = &mut self.grass_system {
@@ -382,6 +484,15 @@ impl ApplicationHandler for App {
                             }
                         }
                     }
+
+                    // Upload the trees left in view after the camera moved
+                    if let (Some(tree_system), Some(renderer)) =
+                        (&self.tree_system, &mut self.renderer)
+                    {
+                        if let Err(e) = renderer.update_trees(tree_system) {
+                            log!("Failed to update tree buffers: {}", e);
+                        }
+                    }
                 }
 
                 // Prepare UI rendering
@@ -395,6 +506,36 @@ impl ApplicationHandler for App {
                         [1.0, 1.0, 1.0, 1.0],
                     );
 
+                    // Draw camera mode; C switches to the next one
+                    if let Some(controller) = self.camera_controllers.active() {
+                        ui_renderer.draw_text(
+                            controller.name(),
+                            crate::math::Vec2::new(10.0, 30.0),
+                            [1.0, 1.0, 1.0, 1.0],
+                        );
+                    }
+
+                    // Label the last picked point, kept on screen while it is in front
+                    if let (Some((label, point)), Some(renderer), Some(window)) =
+                        (&self.pick_marker, &self.renderer, &self.window)
+                    {
+                        let size = window.inner_size();
+                        // Slightly inside the surface, so points on the faceted planet
+                        // mesh are not hidden by the ideal sphere
+                        let placer =
+                            LabelPlacer::new(Vec2::new(size.width as f32, size.height as f32))
+                                .with_planet(Vec3::zero(), self.planet_radius * 0.99)
+                                .with_fade(40.0, 80.0)
+                                .with_edge_clamping(10.0);
+                        ui_renderer.draw_label(
+                            &placer,
+                            renderer.camera(),
+                            *point,
+                            label,
+                            [1.0, 0.9, 0.3, 1.0],
+                        );
+                    }
+
                     ui_renderer.end_frame();
                 }
 
@@ -403,6 +544,11 @@ impl ApplicationHandler for App {
                     if let Err(e) = renderer.render(&self.scene, self.ui_renderer.as_ref()) {
                         log!("Render error: {}", e);
                     }
+
+                    if self.screenshot_requested {
+                        self.screenshot_requested = false;
+                        Self::save_screenshot(renderer);
+                    }
                 }
 
                 if let Some(window) = &self.window {
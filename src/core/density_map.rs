@@ -1,12 +1,17 @@
 //! Density map system for controlling vegetation distribution
 
-use crate::math::Vec3;
+use crate::core::noise::{Fbm, Noise, Octaves, Simplex};
+use crate::math::{LatLon, Vec3};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+const DEFAULT_SEED: u64 = 42;
 
 /// Density map for controlling vegetation placement
 pub struct DensityMap {
     data: Vec<f32>,
     width: u32,
     height: u32,
+    natural: Option<NaturalDensity>,
 }
 
 impl DensityMap {
@@ -17,48 +22,32 @@ impl DensityMap {
             data: vec![1.0; (width * height) as usize],
             width,
             height,
+            natural: None,
         }
     }
 
     /// Creates a procedural density map with natural distribution patterns
     #[must_use]
     pub fn generate_natural(width: u32, height: u32) -> Self {
+        Self::generate_natural_seeded(width, height, DEFAULT_SEED)
+    }
+
+    /// Like [`Self::generate_natural`], with an explicit noise seed
+    ///
+    /// The noise is evaluated on the unit sphere, so the map has no seam at u = 0/1 and
+    /// no pinching at the poles. Texel (x, y) holds the density at the latitude and
+    /// longitude that [`Self::sample_uv`] maps it to.
+    #[must_use]
+    pub fn generate_natural_seeded(width: u32, height: u32, seed: u64) -> Self {
+        let natural = NaturalDensity::new(seed);
         let mut data = vec![0.0; (width * height) as usize];
 
-        // Generate using multiple octaves of noise for natural patterns
         for y in 0..height {
             for x in 0..width {
-                let u = x as f32 / width as f32;
-                let v = y as f32 / height as f32;
-
-                // Base density with multiple noise octaves
-                let mut density = 0.5;
-
-                // Large scale variation (continent scale)
-                density += Self::noise_2d(u * 4.0, v * 4.0) * 0.3;
-
-                // Medium scale (region scale)
-                density += Self::noise_2d(u * 8.0, v * 8.0) * 0.2;
-
-                // Small scale (local variation)
-                density += Self::noise_2d(u * 16.0, v * 16.0) * 0.1;
-
-                // Create bare patches
-                let patch_noise = Self::noise_2d(u * 6.0 + 100.0, v * 6.0 + 100.0);
-                if patch_noise > 0.7 {
-                    density *= 0.1; // Sparse area
-                }
-
-                // Create dense clusters
-                let cluster_noise = Self::noise_2d(u * 5.0 + 200.0, v * 5.0 + 200.0);
-                if cluster_noise > 0.6 {
-                    density = density.max(0.8); // Dense area
-                }
-
-                // Clamp to valid range
-                density = density.clamp(0.0, 1.0);
-
-                data[(y * width + x) as usize] = density;
+                let u = x as f32 / width.saturating_sub(1).max(1) as f32;
+                let v = y as f32 / height.saturating_sub(1).max(1) as f32;
+                let coords = LatLon::new(v * PI - FRAC_PI_2, u * TAU - PI);
+                data[(y * width + x) as usize] = natural.sample(&coords.to_unit_vector());
             }
         }
 
@@ -66,56 +55,21 @@ impl DensityMap {
             data,
             width,
             height,
+            natural: Some(natural),
         }
     }
 
-    /// Simple 2D noise function for procedural generation
-    fn noise_2d(x: f32, y: f32) -> f32 {
-        let xi = x.floor() as i32;
-        let yi = y.floor() as i32;
-        let xf = x - xi as f32;
-        let yf = y - yi as f32;
-
-        // Smooth interpolation
-        let u = xf * xf * (3.0 - 2.0 * xf);
-        let v = yf * yf * (3.0 - 2.0 * yf);
-
-        // Hash corners
-        let a = Self::hash_2d(xi, yi);
-        let b = Self::hash_2d(xi + 1, yi);
-        let c = Self::hash_2d(xi, yi + 1);
-        let d = Self::hash_2d(xi + 1, yi + 1);
-
-        // Interpolate
-        let k1 = a * (1.0 - u) + b * u;
-        let k2 = c * (1.0 - u) + d * u;
-
-        k1 * (1.0 - v) + k2 * v
-    }
-
-    /// Hash function for noise generation
-    fn hash_2d(x: i32, y: i32) -> f32 {
-        let mut n = x + y * 57;
-        n = (n << 13) ^ n;
-        let nn = (n.wrapping_mul(n.wrapping_mul(n).wrapping_mul(15731).wrapping_add(789221)))
-            .wrapping_add(1376312589);
-        1.0 - (nn & 0x7fffffff) as f32 / 1073741824.0
-    }
-
     /// Samples the density map at spherical coordinates
+    ///
+    /// Procedural maps evaluate their noise directly at the point's direction; other
+    /// maps are looked up at its latitude/longitude.
     pub fn sample_spherical(&self, position: &Vec3, planet_radius: f32) -> f32 {
-        // Convert 3D position on sphere to 2D UV coordinates
-        let normalized = position.scale(1.0 / planet_radius);
-
-        // Convert to spherical coordinates
-        let theta = normalized.y.atan2(normalized.x);
-        let phi = normalized.z.acos();
-
-        // Convert to UV (0-1 range)
-        let u = (theta + std::f32::consts::PI) / (2.0 * std::f32::consts::PI);
-        let v = phi / std::f32::consts::PI;
-
-        self.sample_uv(u, v)
+        let position = position.scale(1.0 / planet_radius);
+        if let (Some(natural), Some(direction)) = (&self.natural, position.try_normalize()) {
+            return natural.sample(&direction);
+        }
+        let uv = LatLon::from_cartesian(&position).to_uv();
+        self.sample_uv(uv.x, uv.y)
     }
 
     /// Samples the density map at UV coordinates with bilinear filtering
@@ -166,6 +120,46 @@ impl DensityMap {
     }
 }
 
+/// Noise layers behind [`DensityMap::generate_natural`], sampled on the unit sphere
+struct NaturalDensity {
+    base: Fbm<Simplex>,
+    patches: Simplex,
+    clusters: Simplex,
+}
+
+impl NaturalDensity {
+    fn new(seed: u64) -> Self {
+        Self {
+            // Continent, region and local scale variation
+            base: Fbm::new(
+                Simplex::new(seed),
+                Octaves {
+                    frequency: 2.0,
+                    ..Octaves::new(3)
+                },
+            ),
+            patches: Simplex::new(seed.wrapping_add(1)),
+            clusters: Simplex::new(seed.wrapping_add(2)),
+        }
+    }
+
+    fn sample(&self, direction: &Vec3) -> f32 {
+        let mut density = 0.5 + self.base.sample(direction) * 0.6;
+
+        // Create bare patches
+        if self.patches.sample(&(*direction * 3.0)) > 0.5 {
+            density *= 0.1; // Sparse area
+        }
+
+        // Create dense clusters
+        if self.clusters.sample(&(*direction * 2.5)) > 0.4 {
+            density = density.max(0.8); // Dense area
+        }
+
+        density.clamp(0.0, 1.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +194,51 @@ mod tests {
 
         assert!(max - min > 0.1, "Natural density map should have variation");
     }
+
+    #[test]
+    fn test_natural_map_has_no_seam() {
+        let map = DensityMap::generate_natural(64, 32);
+        for y in 0..32 {
+            let row = (y * 64) as usize;
+            assert!((map.data[row] - map.data[row + 63]).abs() < 1e-4);
+        }
+
+        // Either side of the antimeridian
+        for lat in [-1.0_f32, 0.0, 0.8] {
+            let west = LatLon::new(lat, PI - 1e-4).to_cartesian(50.0);
+            let east = LatLon::new(lat, -PI + 1e-4).to_cartesian(50.0);
+            assert!(
+                (map.sample_spherical(&west, 50.0) - map.sample_spherical(&east, 50.0)).abs()
+                    < 0.01
+            );
+        }
+    }
+
+    #[test]
+    fn test_natural_map_has_no_pole_pinching() {
+        // Every longitude converges on the same value at the poles
+        let map = DensityMap::generate_natural(64, 32);
+        let pole = map.sample_spherical(&Vec3::new(0.0, 50.0, 0.0), 50.0);
+        for i in 0..8 {
+            let lon = i as f32 * TAU / 8.0;
+            let near_pole = LatLon::new(FRAC_PI_2 - 1e-4, lon).to_cartesian(50.0);
+            assert!((map.sample_spherical(&near_pole, 50.0) - pole).abs() < 0.01);
+        }
+        assert!(map.data[..64]
+            .windows(2)
+            .all(|w| (w[0] - w[1]).abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_natural_map_matches_direct_sampling() {
+        let map = DensityMap::generate_natural_seeded(33, 17, 7);
+        let (x, y) = (8, 4);
+        let texel = map.data[(y * 33 + x) as usize];
+        let uv_sample = map.sample_uv(x as f32 / 32.0, y as f32 / 16.0);
+        assert!((texel - uv_sample).abs() < 1e-5);
+
+        let coords = LatLon::new(y as f32 / 16.0 * PI - FRAC_PI_2, x as f32 / 32.0 * TAU - PI);
+        let direct = map.sample_spherical(&coords.to_cartesian(10.0), 10.0);
+        assert!((texel - direct).abs() < 1e-5);
+    }
 }
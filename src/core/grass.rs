@@ -1,14 +1,16 @@
 //! Grass system for rendering instanced grass blades on the spherical world
 
 use crate::core::{DensityMap, LodLevel, VegetationInstance, VegetationLodSystem};
-use crate::math::{Mat4, Vec3, Vec4};
-use crate::scene::{InstanceData, InstancedMesh, Mesh};
+use crate::math::{sphere, Frustum, LatLon, Mat4, Vec3};
+use crate::scene::{Bvh, InstanceData, InstancedMesh, Mesh};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 
 pub struct GrassSystem {
     lod_system: VegetationLodSystem,
     instances: Vec<VegetationInstance>,
+    /// Boxes of the blades at full detail, keyed by index into `instances`
+    bvh: Bvh<usize>,
     #[allow(dead_code)]
     planet_radius: f32,
     #[allow(dead_code)]
@@ -20,10 +22,19 @@ impl GrassSystem {
         let lod_system = VegetationLodSystem::new();
         let density_map = DensityMap::generate_natural(256, 128);
         let instances = Self::generate_grass_instances(planet_radius, density, &density_map);
+        let instance_data: Vec<InstanceData> = instances
+            .iter()
+            .map(VegetationInstance::instance_data)
+            .collect();
+        let bvh = Bvh::from_instances(
+            lod_system.grass_lods.get_mesh(LodLevel::Full),
+            &instance_data,
+        );
 
         Self {
             lod_system,
             instances,
+            bvh,
             planet_radius,
             density_map,
         }
@@ -47,15 +58,7 @@ impl GrassSystem {
             // Generate random position on sphere
             let u: f32 = rng.gen();
             let v: f32 = rng.gen();
-            let theta = 2.0 * std::f32::consts::PI * u;
-            let phi = (1.0 - 2.0 * v).acos();
-
-            // Convert to cartesian coordinates on sphere surface
-            let x = planet_radius * phi.sin() * theta.cos();
-            let y = planet_radius * phi.sin() * theta.sin();
-            let z = planet_radius * phi.cos();
-
-            let position = Vec3::new(x, y, z);
+            let position = LatLon::from_uniform(u, v).to_cartesian(planet_radius);
 
             // Sample density at this position
             let density_value = density_map.sample_spherical(&position, planet_radius);
@@ -65,47 +68,13 @@ impl GrassSystem {
                 // Calculate up vector (radial from planet center)
                 let up = position.normalize();
 
-                // Create a random forward direction in the tangent plane
-                let world_up = Vec3::new(0.0, 1.0, 0.0);
-                let right = if (up.dot(&world_up).abs() - 1.0).abs() < 0.01 {
-                    Vec3::new(1.0, 0.0, 0.0)
-                } else {
-                    world_up.cross(&up).normalize()
-                };
-                let forward = up.cross(&right).normalize();
-
-                // Add some random rotation around the up axis
-                let angle: f32 = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
-                let cos_angle = angle.cos();
-                let sin_angle = angle.sin();
-                let rotated_forward = forward.scale(cos_angle).add(&right.scale(sin_angle));
-                let rotated_right = forward.scale(-sin_angle).add(&right.scale(cos_angle));
-
-                // Create transformation matrix
-                let mut transform = Mat4::identity();
+                // Align +Y with the surface normal, then add some random rotation around it
+                let yaw: f32 = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
 
                 // Add more size variation
                 let scale = 0.5 + rng.gen::<f32>() * 1.0; // 0.5 to 1.5 - wider range
 
-                // Set rotation columns (Metal uses column-major)
-                transform.cols[0] = Vec4::new(
-                    rotated_right.x * scale,
-                    rotated_right.y * scale,
-                    rotated_right.z * scale,
-                    0.0,
-                );
-
-                transform.cols[1] = Vec4::new(up.x * scale, up.y * scale, up.z * scale, 0.0);
-
-                transform.cols[2] = Vec4::new(
-                    rotated_forward.x * scale,
-                    rotated_forward.y * scale,
-                    rotated_forward.z * scale,
-                    0.0,
-                );
-
-                // Set position column
-                transform.cols[3] = Vec4::new(position.x, position.y, position.z, 1.0);
+                let transform = sphere::instance_matrix(&position, &up, yaw, scale);
 
                 // More natural color variation
                 let color_var = rng.gen::<f32>();
@@ -152,20 +121,49 @@ impl GrassSystem {
         }
     }
 
+    /// Moves blades with `f`, given each blade's index and transform, and refits
+    /// [`Self::bvh`] to where they ended up
+    pub fn move_instances(&mut self, mut f: impl FnMut(usize, &mut Mat4)) {
+        for (index, instance) in self.instances.iter_mut().enumerate() {
+            f(index, &mut instance.transform);
+        }
+        let instance_data: Vec<InstanceData> = self
+            .instances
+            .iter()
+            .map(VegetationInstance::instance_data)
+            .collect();
+        self.bvh.refit_instances(
+            self.lod_system.grass_lods.get_mesh(LodLevel::Full),
+            &instance_data,
+        );
+    }
+
     pub fn get_instances_by_lod(&self, lod_level: LodLevel) -> Vec<InstanceData> {
         self.instances
             .iter()
             .filter(|inst| inst.lod_level == lod_level)
-            .map(|inst| InstanceData {
-                transform: inst.transform,
-                color_variation: inst.color_variation,
-                lod_level: inst.lod_level as u32,
-                texture_index: inst.texture_index,
-                _padding: [0; 3],
-            })
+            .map(VegetationInstance::instance_data)
             .collect()
     }
 
+    /// Indices of the blades at least partly inside `frustum`, in ascending order
+    pub fn visible_instances(&self, frustum: &Frustum) -> Vec<usize> {
+        let mut visible = self.bvh.query_frustum(frustum);
+        visible.sort_unstable();
+        visible
+    }
+
+    /// Every blade with its current LOD, in generation order
+    pub fn instances(&self) -> &[VegetationInstance] {
+        &self.instances
+    }
+
+    /// Hierarchy over the blades' full-detail boxes, keyed by index into
+    /// [`Self::instances`]
+    pub fn bvh(&self) -> &Bvh<usize> {
+        &self.bvh
+    }
+
     pub fn get_lod_mesh(&self, lod_level: LodLevel) -> &Mesh {
         self.lod_system.grass_lods.get_mesh(lod_level)
     }
@@ -180,13 +178,7 @@ impl GrassSystem {
         let instances: Vec<InstanceData> = self
             .instances
             .iter()
-            .map(|inst| InstanceData {
-                transform: inst.transform,
-                color_variation: inst.color_variation,
-                lod_level: inst.lod_level as u32,
-                texture_index: inst.texture_index,
-                _padding: [0; 3],
-            })
+            .map(VegetationInstance::instance_data)
             .collect();
 
         InstancedMesh {
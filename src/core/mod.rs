@@ -2,13 +2,17 @@
 //!
 //! This module provides fundamental utilities including:
 //! - High-resolution timing for frame delta calculations
-//! - Texture loading and management
+//! - Texture loading and management (GPU textures require the `metal` feature)
+//! - Seeded coherent noise for procedural generation
+//! - Ray picking against the planet, scene nodes and vegetation instances
 //! - Logging macros
 
 mod density_map;
 mod grass;
 mod grass_texture;
 mod gravity;
+pub mod noise;
+mod picking;
 mod road;
 mod skybox;
 mod spherical_world;
@@ -20,10 +24,13 @@ pub use density_map::DensityMap;
 pub use grass::GrassSystem;
 pub use grass_texture::GrassTextureGenerator;
 pub use gravity::GravitySystem;
+pub use picking::{PickHit, PickTarget, Picker};
 pub use road::RoadSystem;
 pub use skybox::Skybox;
 pub use spherical_world::SphericalWorld;
-pub use texture::{Texture, TextureArray, TextureFormat};
+pub use texture::TextureFormat;
+#[cfg(all(feature = "metal", target_os = "macos"))]
+pub use texture::{Texture, TextureArray};
 pub use tree::TreeSystem;
 pub use vegetation_lod::{GrassLodMeshes, LodLevel, VegetationInstance, VegetationLodSystem};
 
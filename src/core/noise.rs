@@ -0,0 +1,655 @@
+//! Seeded coherent noise for procedural generation
+//!
+//! [`Perlin`], [`Simplex`] and [`Worley`] are the base generators; [`Fbm`], [`Ridged`],
+//! [`Turbulence`] and [`DomainWarp`] combine them. Everything implements [`Noise`],
+//! so combinators nest and any of them can be sampled at a 3D point on the sphere,
+//! which avoids the seams and pole pinching of sampling in UV space.
+//!
+//! Perlin, Simplex and fBm return values in roughly `[-1, 1]`; Worley, ridged and
+//! turbulence return values in `[0, 1]`.
+
+use crate::math::Vec3;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// A deterministic noise function of 2D and 3D position
+pub trait Noise {
+    fn sample_2d(&self, x: f32, y: f32) -> f32;
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32;
+
+    fn sample(&self, point: &Vec3) -> f32 {
+        self.sample_3d(point.x, point.y, point.z)
+    }
+}
+
+/// Gradient directions shared by Perlin and Simplex noise: the midpoints of a cube's
+/// edges. 2D lookups use the x and y components.
+const GRADIENTS_3D: [[f32; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+const GRADIENTS_2D: [[f32; 2]; 8] = [
+    [1.0, 0.0],
+    [-1.0, 0.0],
+    [0.0, 1.0],
+    [0.0, -1.0],
+    [
+        std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ],
+    [
+        -std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ],
+    [
+        std::f32::consts::FRAC_1_SQRT_2,
+        -std::f32::consts::FRAC_1_SQRT_2,
+    ],
+    [
+        -std::f32::consts::FRAC_1_SQRT_2,
+        -std::f32::consts::FRAC_1_SQRT_2,
+    ],
+];
+
+/// Seeded permutation table, doubled so lookups never need to wrap
+#[derive(Debug, Clone)]
+struct Permutation {
+    table: [u8; 512],
+}
+
+impl Permutation {
+    fn new(seed: u64) -> Self {
+        let mut values: Vec<u8> = (0..=255).collect();
+        values.shuffle(&mut ChaCha8Rng::seed_from_u64(seed));
+
+        let mut table = [0; 512];
+        for (i, slot) in table.iter_mut().enumerate() {
+            *slot = values[i & 255];
+        }
+        Self { table }
+    }
+
+    fn hash2(&self, x: i32, y: i32) -> usize {
+        let x = self.table[(x & 255) as usize] as usize;
+        self.table[x + (y & 255) as usize] as usize
+    }
+
+    fn hash3(&self, x: i32, y: i32, z: i32) -> usize {
+        let xy = self.hash2(x, y);
+        self.table[xy + (z & 255) as usize] as usize
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn grad2(hash: usize, x: f32, y: f32) -> f32 {
+    let g = GRADIENTS_2D[hash & 7];
+    g[0] * x + g[1] * y
+}
+
+fn grad3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    let g = GRADIENTS_3D[hash % 12];
+    g[0] * x + g[1] * y + g[2] * z
+}
+
+/// Classic gradient noise on a square/cubic lattice
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    permutation: Permutation,
+}
+
+impl Perlin {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: Permutation::new(seed),
+        }
+    }
+}
+
+impl Noise for Perlin {
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor() as i32, y.floor() as i32);
+        let (xf, yf) = (x - xi as f32, y - yi as f32);
+        let p = &self.permutation;
+
+        let n00 = grad2(p.hash2(xi, yi), xf, yf);
+        let n10 = grad2(p.hash2(xi + 1, yi), xf - 1.0, yf);
+        let n01 = grad2(p.hash2(xi, yi + 1), xf, yf - 1.0);
+        let n11 = grad2(p.hash2(xi + 1, yi + 1), xf - 1.0, yf - 1.0);
+
+        let (u, v) = (fade(xf), fade(yf));
+        // Unit gradients peak at √½ in 2D
+        lerp(lerp(n00, n10, u), lerp(n01, n11, u), v) * std::f32::consts::SQRT_2
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, yi, zi) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let (xf, yf, zf) = (x - xi as f32, y - yi as f32, z - zi as f32);
+        let p = &self.permutation;
+
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            grad3(
+                p.hash3(xi + dx, yi + dy, zi + dz),
+                xf - dx as f32,
+                yf - dy as f32,
+                zf - dz as f32,
+            )
+        };
+
+        let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+        let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+        let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+        let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+        let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+        // Edge gradients of length √2 peak at √1.5 in 3D
+        lerp(lerp(x00, x10, v), lerp(x01, x11, v), w) / 1.5_f32.sqrt()
+    }
+}
+
+/// Simplex noise: gradient noise on a triangular/tetrahedral lattice, cheaper than
+/// Perlin in 3D and free of axis-aligned artifacts
+#[derive(Debug, Clone)]
+pub struct Simplex {
+    permutation: Permutation,
+}
+
+impl Simplex {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: Permutation::new(seed),
+        }
+    }
+}
+
+impl Noise for Simplex {
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        const F2: f32 = 0.366_025_42; // (√3 - 1) / 2
+        const G2: f32 = 0.211_324_87; // (3 - √3) / 6
+
+        // Skew into the simplex grid to find the containing cell
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor() as i32, (y + s).floor() as i32);
+        let t = (i + j) as f32 * G2;
+        let (x0, y0) = (x - (i as f32 - t), y - (j as f32 - t));
+
+        // Which of the cell's two triangles the point is in
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let corners = [
+            (0, 0, x0, y0),
+            (i1, j1, x0 - i1 as f32 + G2, y0 - j1 as f32 + G2),
+            (1, 1, x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2),
+        ];
+
+        let sum: f32 = corners
+            .iter()
+            .map(|&(di, dj, cx, cy)| {
+                let falloff = 0.5 - cx * cx - cy * cy;
+                if falloff <= 0.0 {
+                    0.0
+                } else {
+                    let hash = self.permutation.hash2(i + di, j + dj);
+                    falloff.powi(4) * grad3(hash, cx, cy, 0.0)
+                }
+            })
+            .sum();
+        70.0 * sum
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+
+        let s = (x + y + z) * F3;
+        let (i, j, k) = (
+            (x + s).floor() as i32,
+            (y + s).floor() as i32,
+            (z + s).floor() as i32,
+        );
+        let t = (i + j + k) as f32 * G3;
+        let (x0, y0, z0) = (x - (i as f32 - t), y - (j as f32 - t), z - (k as f32 - t));
+
+        // Order the offsets to pick which of the cube's six tetrahedra contains the point
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let corner = |di: i32, dj: i32, dk: i32, offset: f32| {
+            let cx = x0 - di as f32 + offset;
+            let cy = y0 - dj as f32 + offset;
+            let cz = z0 - dk as f32 + offset;
+            let falloff = 0.6 - cx * cx - cy * cy - cz * cz;
+            if falloff <= 0.0 {
+                0.0
+            } else {
+                let hash = self.permutation.hash3(i + di, j + dj, k + dk);
+                falloff.powi(4) * grad3(hash, cx, cy, cz)
+            }
+        };
+
+        32.0 * (corner(0, 0, 0, 0.0)
+            + corner(i1, j1, k1, G3)
+            + corner(i2, j2, k2, 2.0 * G3)
+            + corner(1, 1, 1, 3.0 * G3))
+    }
+}
+
+/// Cellular noise: distance to the nearest of one random feature point per cell
+#[derive(Debug, Clone)]
+pub struct Worley {
+    seed: u32,
+}
+
+impl Worley {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed: (seed ^ (seed >> 32)) as u32,
+        }
+    }
+
+    /// Hash of a lattice cell mapped to `[0, 1)`, varied by `channel`
+    fn cell_random(&self, x: i32, y: i32, z: i32, channel: u32) -> f32 {
+        let mut h = self.seed ^ channel.wrapping_mul(0x9E37_79B9);
+        for v in [x, y, z] {
+            h ^= (v as u32).wrapping_mul(0x85EB_CA6B);
+            h = h.rotate_left(13).wrapping_mul(0xC2B2_AE35);
+        }
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x7FEB_352D);
+        h ^= h >> 15;
+        (h >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+impl Noise for Worley {
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor() as i32, y.floor() as i32);
+        let mut nearest = f32::MAX;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (cx, cy) = (xi + dx, yi + dy);
+                let fx = cx as f32 + self.cell_random(cx, cy, 0, 0) - x;
+                let fy = cy as f32 + self.cell_random(cx, cy, 0, 1) - y;
+                nearest = nearest.min(fx * fx + fy * fy);
+            }
+        }
+        nearest.sqrt().min(1.0)
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, yi, zi) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let mut nearest = f32::MAX;
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (cx, cy, cz) = (xi + dx, yi + dy, zi + dz);
+                    let fx = cx as f32 + self.cell_random(cx, cy, cz, 0) - x;
+                    let fy = cy as f32 + self.cell_random(cx, cy, cz, 1) - y;
+                    let fz = cz as f32 + self.cell_random(cx, cy, cz, 2) - z;
+                    nearest = nearest.min(fx * fx + fy * fy + fz * fz);
+                }
+            }
+        }
+        nearest.sqrt().min(1.0)
+    }
+}
+
+/// Octave settings shared by the fractal combinators
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Octaves {
+    pub count: u32,
+    /// Frequency of the first octave
+    pub frequency: f32,
+    /// Frequency multiplier between octaves
+    pub lacunarity: f32,
+    /// Amplitude multiplier between octaves
+    pub gain: f32,
+}
+
+impl Octaves {
+    #[must_use]
+    pub const fn new(count: u32) -> Self {
+        Self {
+            count,
+            frequency: 1.0,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    /// Sums `octave(frequency)` weighted by amplitude, divided by the total amplitude
+    fn accumulate(&self, mut octave: impl FnMut(f32) -> f32) -> f32 {
+        let mut frequency = self.frequency;
+        let mut amplitude = 1.0;
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        for _ in 0..self.count {
+            sum += octave(frequency) * amplitude;
+            total += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        if total > 0.0 {
+            sum / total
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for Octaves {
+    fn default() -> Self {
+        Self::new(4)
+    }
+}
+
+/// Fractional Brownian motion: octaves of `noise` at rising frequency and falling
+/// amplitude
+#[derive(Debug, Clone)]
+pub struct Fbm<N> {
+    pub noise: N,
+    pub octaves: Octaves,
+}
+
+impl<N: Noise> Fbm<N> {
+    #[must_use]
+    pub const fn new(noise: N, octaves: Octaves) -> Self {
+        Self { noise, octaves }
+    }
+}
+
+impl<N: Noise> Noise for Fbm<N> {
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        self.octaves
+            .accumulate(|f| self.noise.sample_2d(x * f, y * f))
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.octaves
+            .accumulate(|f| self.noise.sample_3d(x * f, y * f, z * f))
+    }
+}
+
+/// Ridged multifractal: inverted absolute noise, giving sharp crests like mountain
+/// ridges
+#[derive(Debug, Clone)]
+pub struct Ridged<N> {
+    pub noise: N,
+    pub octaves: Octaves,
+}
+
+impl<N: Noise> Ridged<N> {
+    #[must_use]
+    pub const fn new(noise: N, octaves: Octaves) -> Self {
+        Self { noise, octaves }
+    }
+}
+
+impl<N: Noise> Noise for Ridged<N> {
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        self.octaves.accumulate(|f| {
+            let ridge = 1.0 - self.noise.sample_2d(x * f, y * f).abs().min(1.0);
+            ridge * ridge
+        })
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.octaves.accumulate(|f| {
+            let ridge = 1.0 - self.noise.sample_3d(x * f, y * f, z * f).abs().min(1.0);
+            ridge * ridge
+        })
+    }
+}
+
+/// Sum of absolute noise octaves, giving billowy, cloud-like patterns
+#[derive(Debug, Clone)]
+pub struct Turbulence<N> {
+    pub noise: N,
+    pub octaves: Octaves,
+}
+
+impl<N: Noise> Turbulence<N> {
+    #[must_use]
+    pub const fn new(noise: N, octaves: Octaves) -> Self {
+        Self { noise, octaves }
+    }
+}
+
+impl<N: Noise> Noise for Turbulence<N> {
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        self.octaves
+            .accumulate(|f| self.noise.sample_2d(x * f, y * f).abs().min(1.0))
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.octaves
+            .accumulate(|f| self.noise.sample_3d(x * f, y * f, z * f).abs().min(1.0))
+    }
+}
+
+/// Samples `noise` at a position displaced by `warp`, bending its features
+#[derive(Debug, Clone)]
+pub struct DomainWarp<N, W> {
+    pub noise: N,
+    pub warp: W,
+    /// How far, in input units, the position may be displaced
+    pub strength: f32,
+}
+
+impl<N: Noise, W: Noise> DomainWarp<N, W> {
+    // Decorrelates the warp's per-axis samples
+    const OFFSETS: [f32; 3] = [0.0, 31.7, 67.3];
+
+    #[must_use]
+    pub const fn new(noise: N, warp: W, strength: f32) -> Self {
+        Self {
+            noise,
+            warp,
+            strength,
+        }
+    }
+}
+
+impl<N: Noise, W: Noise> Noise for DomainWarp<N, W> {
+    fn sample_2d(&self, x: f32, y: f32) -> f32 {
+        let [a, b, _] = Self::OFFSETS;
+        let dx = self.warp.sample_2d(x + a, y + a);
+        let dy = self.warp.sample_2d(x + b, y + b);
+        self.noise
+            .sample_2d(x + dx * self.strength, y + dy * self.strength)
+    }
+
+    fn sample_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let [a, b, c] = Self::OFFSETS;
+        let dx = self.warp.sample_3d(x + a, y + a, z + a);
+        let dy = self.warp.sample_3d(x + b, y + b, z + b);
+        let dz = self.warp.sample_3d(x + c, y + c, z + c);
+        self.noise.sample_3d(
+            x + dx * self.strength,
+            y + dy * self.strength,
+            z + dz * self.strength,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random sample positions spread over a wide range
+    fn sample_points() -> impl Iterator<Item = (f32, f32, f32)> {
+        (0..4000).map(|i| {
+            let i = i as f32;
+            (
+                (i * 0.618_034).fract() * 40.0 - 20.0,
+                (i * 0.414_213_5).fract() * 40.0 - 20.0,
+                (i * 0.732_050_8).fract() * 40.0 - 20.0,
+            )
+        })
+    }
+
+    fn assert_range(noise: &impl Noise, min: f32, max: f32) {
+        for (x, y, z) in sample_points() {
+            let n2 = noise.sample_2d(x, y);
+            let n3 = noise.sample_3d(x, y, z);
+            assert!(
+                (min..=max).contains(&n2),
+                "2D value {} at ({}, {})",
+                n2,
+                x,
+                y
+            );
+            assert!(
+                (min..=max).contains(&n3),
+                "3D value {} at ({}, {}, {})",
+                n3,
+                x,
+                y,
+                z
+            );
+        }
+    }
+
+    fn spread(noise: &impl Noise) -> f32 {
+        let values: Vec<f32> = sample_points()
+            .map(|(x, y, z)| noise.sample_3d(x, y, z))
+            .collect();
+        let min = values.iter().copied().fold(f32::MAX, f32::min);
+        let max = values.iter().copied().fold(f32::MIN, f32::max);
+        max - min
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_range(&Perlin::new(1), -1.0, 1.0);
+        assert_range(&Simplex::new(2), -1.0, 1.0);
+        assert_range(&Worley::new(3), 0.0, 1.0);
+        assert_range(&Fbm::new(Simplex::new(4), Octaves::default()), -1.0, 1.0);
+        assert_range(&Ridged::new(Perlin::new(5), Octaves::default()), 0.0, 1.0);
+        assert_range(
+            &Turbulence::new(Simplex::new(6), Octaves::default()),
+            0.0,
+            1.0,
+        );
+    }
+
+    #[test]
+    fn test_noise_varies() {
+        assert!(spread(&Perlin::new(1)) > 1.0);
+        assert!(spread(&Simplex::new(1)) > 1.0);
+        assert!(spread(&Worley::new(1)) > 0.5);
+    }
+
+    #[test]
+    fn test_perlin_is_zero_on_lattice() {
+        let perlin = Perlin::new(7);
+        for i in -3..3 {
+            let p = i as f32;
+            assert_eq!(perlin.sample_2d(p, p * 2.0), 0.0);
+            assert_eq!(perlin.sample_3d(p, 1.0, -p), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_seeds_are_deterministic_and_distinct() {
+        let a = Simplex::new(42);
+        let b = Simplex::new(42);
+        let c = Simplex::new(43);
+        let point = Vec3::new(1.3, -2.7, 0.4);
+        assert_eq!(a.sample(&point), b.sample(&point));
+
+        let differs = sample_points().any(|(x, y, z)| a.sample_3d(x, y, z) != c.sample_3d(x, y, z));
+        assert!(differs);
+        assert_eq!(
+            Worley::new(9).sample_2d(3.3, 4.4),
+            Worley::new(9).sample_2d(3.3, 4.4)
+        );
+    }
+
+    #[test]
+    fn test_noise_is_continuous() {
+        let noises: [&dyn Noise; 3] = [&Perlin::new(3), &Simplex::new(3), &Worley::new(3)];
+        for noise in noises {
+            for (x, y, z) in sample_points().take(500) {
+                let a = noise.sample_3d(x, y, z);
+                let b = noise.sample_3d(x + 1e-3, y, z);
+                assert!((a - b).abs() < 0.02, "jump from {} to {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_worley_is_zero_at_feature_points() {
+        let worley = Worley::new(11);
+        let (cx, cy) = (2, -5);
+        let fx = cx as f32 + worley.cell_random(cx, cy, 0, 0);
+        let fy = cy as f32 + worley.cell_random(cx, cy, 0, 1);
+        assert!(worley.sample_2d(fx, fy) < 1e-5);
+    }
+
+    #[test]
+    fn test_fbm_single_octave_matches_base() {
+        let fbm = Fbm::new(Perlin::new(5), Octaves::new(1));
+        let base = Perlin::new(5);
+        for (x, y, z) in sample_points().take(100) {
+            assert_eq!(fbm.sample_3d(x, y, z), base.sample_3d(x, y, z));
+        }
+    }
+
+    #[test]
+    fn test_domain_warp() {
+        let base = Simplex::new(1);
+        let unwarped = DomainWarp::new(Simplex::new(1), Simplex::new(2), 0.0);
+        let warped = DomainWarp::new(Simplex::new(1), Simplex::new(2), 2.0);
+        let point = Vec3::new(0.3, 0.7, -1.1);
+        assert_eq!(unwarped.sample(&point), base.sample(&point));
+        assert!(
+            sample_points().any(|(x, y, z)| warped.sample_3d(x, y, z) != base.sample_3d(x, y, z))
+        );
+    }
+
+    #[test]
+    fn test_sphere_sampling_is_seamless() {
+        // Points either side of the u = 0/1 seam are neighbours in 3D, so the noise agrees
+        let fbm = Fbm::new(Simplex::new(8), Octaves::default());
+        let radius = 3.0;
+        for lat in [-1.2_f32, -0.4, 0.0, 0.7, 1.3] {
+            let west = Vec3::new(-lat.cos(), lat.sin(), -1e-4) * radius;
+            let east = Vec3::new(-lat.cos(), lat.sin(), 1e-4) * radius;
+            assert!((fbm.sample(&west) - fbm.sample(&east)).abs() < 1e-2);
+        }
+    }
+}
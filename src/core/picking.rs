@@ -0,0 +1,330 @@
+//! CPU hit testing of rays against the planet, scene nodes and vegetation
+//!
+//! Pair with [`Camera::screen_ray`](crate::scene::Camera::screen_ray) to find what
+//! is under the mouse. Nodes and instances are searched through a [`Bvh`], so only
+//! meshes whose box the ray reaches before the closest hit so far are tested.
+//! Triangles are hit from both sides, matching the renderer, which does not cull
+//! back faces.
+
+use crate::core::{GrassSystem, LodLevel, TreeSystem};
+use crate::math::{BoundingSphere, LatLon, Mat4, Ray, Vec3};
+use crate::scene::{Bvh, Mesh, NodeId, Scene};
+use std::hash::Hash;
+
+/// What a ray hit; instances are indexed in the order their system stores them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PickTarget {
+    Planet,
+    Node(NodeId),
+    Tree(usize),
+    Grass(usize),
+}
+
+/// Closest hit along a ray
+#[derive(Debug, Clone, Copy)]
+pub struct PickHit {
+    pub target: PickTarget,
+    /// Distance from the ray origin
+    pub distance: f32,
+    pub point: Vec3,
+    /// Unit surface normal, facing back towards the ray
+    pub normal: Vec3,
+    /// Position of `point` over the planet
+    pub lat_lon: LatLon,
+}
+
+/// The things a ray can hit, added with the `with_*` methods
+#[derive(Default)]
+pub struct Picker<'a> {
+    planet: Option<BoundingSphere>,
+    scene: Option<(&'a Scene, Bvh<NodeId>)>,
+    trees: Option<&'a TreeSystem>,
+    grass: Option<&'a GrassSystem>,
+}
+
+impl<'a> Picker<'a> {
+    /// Picks nothing; lat/lon are measured around the origin until a planet is set
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tests the planet as a perfect sphere
+    #[must_use]
+    pub fn with_planet(mut self, center: Vec3, radius: f32) -> Self {
+        self.planet = Some(BoundingSphere::new(center, radius));
+        self
+    }
+
+    /// Tests the triangles of every node with a mesh, in world space, building a
+    /// hierarchy over their bounds
+    #[must_use]
+    pub fn with_scene(mut self, scene: &'a Scene) -> Self {
+        self.scene = Some((scene, Bvh::from_scene(scene)));
+        self
+    }
+
+    #[must_use]
+    pub fn with_trees(mut self, trees: &'a TreeSystem) -> Self {
+        self.trees = Some(trees);
+        self
+    }
+
+    /// Tests grass blades at full detail, whatever LOD they are drawn at
+    #[must_use]
+    pub fn with_grass(mut self, grass: &'a GrassSystem) -> Self {
+        self.grass = Some(grass);
+        self
+    }
+
+    /// Closest hit along `ray`, if anything is hit
+    #[must_use]
+    pub fn pick(&self, ray: &Ray) -> Option<PickHit> {
+        let mut closest: Option<(PickTarget, f32, Vec3)> = None;
+        let mut consider = |target, hit: Option<(f32, Vec3)>| {
+            if let Some((distance, normal)) = hit {
+                let closer = match closest {
+                    Some((_, best, _)) => distance < best,
+                    None => true,
+                };
+                if closer {
+                    closest = Some((target, distance, normal));
+                }
+            }
+        };
+
+        if let Some(planet) = &self.planet {
+            let hit = ray.intersect_sphere(planet).and_then(|t| {
+                let normal = (ray.at(t) - planet.center).try_normalize()?;
+                Some((t, normal))
+            });
+            consider(PickTarget::Planet, hit);
+        }
+
+        if let Some((scene, bvh)) = &self.scene {
+            let hit = |id| {
+                let mesh = scene.get(id)?.mesh()?;
+                intersect_mesh(ray, mesh, &scene.world_transform(id)?)
+            };
+            if let Some((id, hit)) = closest_hit(ray, bvh, hit) {
+                consider(PickTarget::Node(id), Some(hit));
+            }
+        }
+
+        if let Some(trees) = self.trees {
+            let instanced = trees.instanced_mesh();
+            let hit = |index: usize| {
+                let instance = instanced.instances.get(index)?;
+                intersect_mesh(ray, &instanced.base_mesh, &instance.transform)
+            };
+            if let Some((index, hit)) = closest_hit(ray, trees.bvh(), hit) {
+                consider(PickTarget::Tree(index), Some(hit));
+            }
+        }
+
+        if let Some(grass) = self.grass {
+            let blade = grass.get_lod_mesh(LodLevel::Full);
+            let hit = |index: usize| {
+                let instance = grass.instances().get(index)?;
+                intersect_mesh(ray, blade, &instance.transform)
+            };
+            if let Some((index, hit)) = closest_hit(ray, grass.bvh(), hit) {
+                consider(PickTarget::Grass(index), Some(hit));
+            }
+        }
+
+        let (target, distance, normal) = closest?;
+        let point = ray.at(distance);
+        let center = self.planet.map_or(Vec3::zero(), |planet| planet.center);
+        Some(PickHit {
+            target,
+            distance,
+            point,
+            // Face the ray, since either side of a triangle can be hit
+            normal: if normal.dot(&ray.direction) > 0.0 {
+                -normal
+            } else {
+                normal
+            },
+            lat_lon: LatLon::from_cartesian(&(point - center)),
+        })
+    }
+}
+
+/// Distance and world normal of the closest hit on `mesh` placed by `transform`
+fn intersect_mesh(ray: &Ray, mesh: &Mesh, transform: &Mat4) -> Option<(f32, Vec3)> {
+    let bounds = BoundingSphere::from(mesh.bounds()?).transform(transform);
+    ray.intersect_sphere(&bounds)?;
+
+    let positions: Vec<Vec3> = mesh
+        .vertices
+        .iter()
+        .map(|vertex| transform.transform_point(&vertex.position))
+        .collect();
+    mesh.indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|k| positions.get(triangle[k] as usize));
+            let (a, b, c) = (a?, b?, c?);
+            let t = ray.intersect_triangle(a, b, c)?;
+            let normal = (*b - *a).cross(&(*c - *a)).try_normalize()?;
+            Some((t, normal))
+        })
+        .min_by(|x, y| x.0.total_cmp(&y.0))
+}
+
+/// Key, distance and world normal of the closest item in `bvh` that `hit` reports
+/// a hit on
+fn closest_hit<K: Copy + Eq + Hash>(
+    ray: &Ray,
+    bvh: &Bvh<K>,
+    hit: impl Fn(K) -> Option<(f32, Vec3)>,
+) -> Option<(K, (f32, Vec3))> {
+    let (key, _) = bvh.closest_hit(ray, |key| hit(key).map(|(distance, _)| distance))?;
+    // Tested again for the normal, which the hierarchy does not carry
+    Some((key, hit(key)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Transform, Vec2};
+    use crate::scene::{Camera, Node};
+
+    #[test]
+    fn test_planet_hit_from_screen_center() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 60.0), Vec3::zero(), 16.0 / 9.0);
+        let viewport = Vec2::new(1280.0, 720.0);
+        let ray = camera.screen_ray(Vec2::new(640.0, 360.0), viewport);
+
+        let hit = Picker::new()
+            .with_planet(Vec3::zero(), 25.0)
+            .pick(&ray)
+            .unwrap();
+        assert_eq!(hit.target, PickTarget::Planet);
+        assert!((hit.distance - 35.0).abs() < 1e-3);
+        assert!(hit.point.distance(&Vec3::new(0.0, 0.0, 25.0)) < 1e-3);
+        assert!(hit.normal.dot(&Vec3::new(0.0, 0.0, 1.0)) > 0.9999);
+        let (lat, _) = hit.lat_lon.to_degrees();
+        assert!(lat.abs() < 1e-3);
+
+        // The corner of the screen misses
+        let corner = camera.screen_ray(Vec2::zero(), viewport);
+        assert!(Picker::new()
+            .with_planet(Vec3::zero(), 25.0)
+            .pick(&corner)
+            .is_none());
+    }
+
+    #[test]
+    fn test_closest_node_wins() {
+        let mut scene = Scene::new();
+        let mut near = Node::with_mesh("Near".to_string(), Mesh::cube());
+        near.set_position(Vec3::new(0.0, 0.0, 30.0));
+        let near = scene.add_node(near);
+        let mut far = Node::with_mesh("Far".to_string(), Mesh::cube());
+        far.set_transform(Transform::new(
+            Vec3::new(0.0, 0.0, 20.0),
+            crate::math::Quat::identity(),
+            Vec3::new(4.0, 4.0, 4.0),
+        ));
+        scene.add_node(far);
+
+        let ray = Ray::new(Vec3::new(0.2, 0.1, 50.0), Vec3::new(0.0, 0.0, -1.0));
+        let picker = Picker::new()
+            .with_planet(Vec3::zero(), 10.0)
+            .with_scene(&scene);
+        let hit = picker.pick(&ray).unwrap();
+        assert_eq!(hit.target, PickTarget::Node(near));
+        assert!((hit.distance - 19.5).abs() < 1e-4);
+        assert!(hit.normal.dot(&Vec3::new(0.0, 0.0, 1.0)) > 0.9999);
+
+        // Beside the small cube, the large one is in front of the planet
+        let ray = Ray::new(Vec3::new(1.5, 0.0, 50.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = picker.pick(&ray).unwrap();
+        assert!(matches!(hit.target, PickTarget::Node(id) if id != near));
+        assert!((hit.distance - 28.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_trees_stand_on_the_planet() {
+        let trees = TreeSystem::new(25.0, 10, 0.0, 0.0);
+        let instance = &trees.instanced_mesh().instances[0];
+        let base = instance.transform.transform_point(&Vec3::zero());
+        let up = base.normalize();
+        let side = instance
+            .transform
+            .transform_direction(&Vec3::new(1.0, 0.0, 0.0))
+            .normalize();
+
+        // Looking down at a tree, just off its axis, hits the crown before the ground
+        let ray = Ray::new(base + up * 20.0 + side * 0.3, -up);
+        let picker = Picker::new()
+            .with_planet(Vec3::zero(), 25.0)
+            .with_trees(&trees);
+        let hit = picker.pick(&ray).unwrap();
+        assert_eq!(hit.target, PickTarget::Tree(0));
+        assert!(hit.distance < 20.0 - 5.0);
+        assert!(hit.normal.dot(&up) > 0.0);
+
+        let expected = LatLon::from_cartesian(&base);
+        assert!(hit.lat_lon.angle_to(&expected) < 0.02);
+    }
+
+    #[test]
+    fn test_tree_hits_match_brute_force() {
+        let trees = TreeSystem::new(25.0, 200, 0.0, 0.0);
+        let instanced = trees.instanced_mesh();
+        let picker = Picker::new().with_trees(&trees);
+
+        let mut hits = 0;
+        for step in 0..400 {
+            // Rays aimed down at points spread over the planet, a little off center
+            let direction = LatLon::from_uniform(
+                (step as f32 * 0.618_034).fract(),
+                (step as f32 + 0.5) / 400.0,
+            )
+            .to_cartesian(1.0);
+            let target = direction * 28.0 + Vec3::new(0.3, -0.2, 0.1);
+            let ray = Ray::new(direction * 40.0, target - direction * 40.0);
+
+            let expected = instanced
+                .instances
+                .iter()
+                .enumerate()
+                .filter_map(|(index, instance)| {
+                    let (distance, _) =
+                        intersect_mesh(&ray, &instanced.base_mesh, &instance.transform)?;
+                    Some((index, distance))
+                })
+                .min_by(|x, y| x.1.total_cmp(&y.1));
+            let picked = picker.pick(&ray).map(|hit| (hit.target, hit.distance));
+            assert_eq!(
+                picked,
+                expected.map(|(index, distance)| (PickTarget::Tree(index), distance))
+            );
+            hits += usize::from(expected.is_some());
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn test_grass_blade_hit_from_the_side() {
+        let grass = GrassSystem::new(25.0, 0.2);
+        let instance = &grass.instances()[0];
+        let base = instance.transform.transform_point(&Vec3::zero());
+        let up = instance
+            .transform
+            .transform_direction(&Vec3::new(0.0, 1.0, 0.0));
+        let facing = instance
+            .transform
+            .transform_direction(&Vec3::new(0.0, 0.0, 1.0));
+
+        // Aim at the middle of the blade, where it is widest and barely curved
+        let target = base + up * 0.1;
+        let ray = Ray::new(target + facing.normalize() * 2.0, -facing);
+        let hit = Picker::new().with_grass(&grass).pick(&ray).unwrap();
+        assert!(matches!(hit.target, PickTarget::Grass(_)));
+        assert!((hit.distance - 2.0).abs() < 0.05);
+    }
+}
@@ -1,6 +1,6 @@
 //! Road system for rendering curved paths on the spherical world
 
-use crate::math::{Vec2, Vec3};
+use crate::math::{sphere, LatLon, TangentFrame, Vec2, Vec3};
 use crate::scene::{Mesh, Vertex};
 
 pub struct RoadSystem {
@@ -37,22 +37,15 @@ impl RoadSystem {
             let t = i as f32 / segments as f32;
             let angle = start_angle + (end_angle - start_angle) * t;
 
-            // Center point on sphere at this angle (assuming path on equator for now)
-            let center_x = planet_radius * angle.cos();
-            let center_y = 0.0; // Path follows equator
-            let center_z = planet_radius * angle.sin();
-            let center = Vec3::new(center_x, center_y, center_z);
-
-            // Calculate up vector (radial from planet center)
-            let up = center.normalize();
-
-            // Calculate tangent direction (perpendicular to radial and forward)
-            let forward = Vec3::new(-angle.sin(), 0.0, angle.cos());
-            let right = forward.cross(&up).normalize();
+            // Center point on the equator at this longitude; the road runs east, so
+            // its edges lie to the north and south
+            let center = LatLon::new(0.0, angle).to_cartesian(planet_radius);
+            let frame = TangentFrame::at(&center);
+            let up = frame.up;
 
             // Create vertices at road edges
-            let left_pos = center.add(&right.scale(-half_width));
-            let right_pos = center.add(&right.scale(half_width));
+            let left_pos = center - frame.north * half_width;
+            let right_pos = center + frame.north * half_width;
 
             // UV coordinates
             let u = t;
@@ -74,7 +67,7 @@ impl RoadSystem {
 
         // Generate triangles
         for i in 0..segments {
-            let base_idx = (i * 2) as u16;
+            let base_idx = (i * 2) as u32;
 
             // First triangle
             indices.push(base_idx);
@@ -107,27 +100,17 @@ impl RoadSystem {
         let end_normalized = end_pos.normalize();
 
         // Generate vertices along the great circle path
+        let point_at = |i: usize| {
+            sphere::slerp(
+                &start_normalized,
+                &end_normalized,
+                i as f32 / segments as f32,
+            )
+        };
         for i in 0..=segments {
             let t = i as f32 / segments as f32;
-
-            // Spherical linear interpolation between start and end
-            let angle = start_normalized.dot(&end_normalized).acos();
-            let sin_angle = angle.sin();
-
-            let center_normalized = if sin_angle.abs() < 0.001 {
-                // Points are too close, use linear interpolation
-                start_normalized
-                    .scale(1.0 - t)
-                    .add(&end_normalized.scale(t))
-                    .normalize()
-            } else {
-                // Standard slerp
-                let a = ((1.0 - t) * angle).sin() / sin_angle;
-                let b = (t * angle).sin() / sin_angle;
-                start_normalized.scale(a).add(&end_normalized.scale(b))
-            };
-
-            let center = center_normalized.scale(planet_radius);
+            let center_normalized = point_at(i);
+            let center = center_normalized * planet_radius;
 
             // Calculate up vector (radial from planet center)
             let up = center_normalized;
@@ -135,30 +118,17 @@ impl RoadSystem {
             // Calculate forward direction (tangent to the path)
             let forward = if i == segments {
                 // At the end, use the previous forward direction
-                let prev_t = (i - 1) as f32 / segments as f32;
-                let prev_angle = ((1.0 - prev_t) * angle).sin() / sin_angle;
-                let prev_b = (prev_t * angle).sin() / sin_angle;
-                let prev_center = start_normalized
-                    .scale(prev_angle)
-                    .add(&end_normalized.scale(prev_b));
-                center_normalized.sub(&prev_center).normalize()
+                (center_normalized - point_at(i - 1)).normalize()
             } else {
-                // Calculate forward as derivative of the slerp
-                let next_t = (i + 1) as f32 / segments as f32;
-                let next_a = ((1.0 - next_t) * angle).sin() / sin_angle;
-                let next_b = (next_t * angle).sin() / sin_angle;
-                let next_center = start_normalized
-                    .scale(next_a)
-                    .add(&end_normalized.scale(next_b));
-                next_center.sub(&center_normalized).normalize()
+                (point_at(i + 1) - center_normalized).normalize()
             };
 
             // Calculate right vector
             let right = forward.cross(&up).normalize();
 
             // Create vertices at road edges
-            let left_pos = center.add(&right.scale(-half_width));
-            let right_pos = center.add(&right.scale(half_width));
+            let left_pos = center - right * half_width;
+            let right_pos = center + right * half_width;
 
             // UV coordinates
             let u = t;
@@ -180,7 +150,7 @@ impl RoadSystem {
 
         // Generate triangles
         for i in 0..segments {
-            let base_idx = (i * 2) as u16;
+            let base_idx = (i * 2) as u32;
 
             // First triangle
             indices.push(base_idx);
@@ -196,6 +166,12 @@ impl RoadSystem {
         Mesh { vertices, indices }
     }
 
+    /// Radius of the planet the road was laid on
+    #[must_use]
+    pub fn planet_radius(&self) -> f32 {
+        self.planet_radius
+    }
+
     pub fn mesh(&self) -> &Mesh {
         &self.mesh
     }
@@ -208,7 +184,7 @@ mod tests {
     #[test]
     fn test_road_creation() {
         let road = RoadSystem::new(50.0, 0.0, std::f32::consts::PI / 2.0, 3.0);
-        assert_eq!(road.planet_radius, 50.0);
+        assert_eq!(road.planet_radius(), 50.0);
 
         // Check that mesh has vertices and indices
         assert!(!road.mesh.vertices.is_empty());
@@ -1,9 +1,13 @@
-use objc2::rc::Retained;
-use objc2::runtime::ProtocolObject;
-use objc2_metal::{
-    MTLDevice, MTLPixelFormat, MTLTexture, MTLTextureDescriptor, MTLTextureType, MTLTextureUsage,
+#[cfg(all(feature = "metal", target_os = "macos"))]
+use {
+    objc2::rc::Retained,
+    objc2::runtime::ProtocolObject,
+    objc2_metal::{
+        MTLDevice, MTLPixelFormat, MTLTexture, MTLTextureDescriptor, MTLTextureType,
+        MTLTextureUsage,
+    },
+    std::path::Path,
 };
-use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextureFormat {
@@ -12,6 +16,7 @@ pub enum TextureFormat {
 }
 
 impl TextureFormat {
+    #[cfg(all(feature = "metal", target_os = "macos"))]
     pub fn metal_format(&self) -> MTLPixelFormat {
         match self {
             Self::Rgba8 => MTLPixelFormat::RGBA8Unorm,
@@ -26,6 +31,7 @@ impl TextureFormat {
     }
 }
 
+#[cfg(all(feature = "metal", target_os = "macos"))]
 pub struct Texture {
     pub texture: Retained<ProtocolObject<dyn MTLTexture>>,
     pub width: u32,
@@ -33,6 +39,7 @@ pub struct Texture {
     pub format: TextureFormat,
 }
 
+#[cfg(all(feature = "metal", target_os = "macos"))]
 impl Texture {
     pub fn load(
         device: &ProtocolObject<dyn MTLDevice>,
@@ -150,6 +157,7 @@ impl Texture {
     }
 }
 
+#[cfg(all(feature = "metal", target_os = "macos"))]
 pub struct TextureArray {
     pub texture: Retained<ProtocolObject<dyn MTLTexture>>,
     pub width: u32,
@@ -158,6 +166,7 @@ pub struct TextureArray {
     pub format: TextureFormat,
 }
 
+#[cfg(all(feature = "metal", target_os = "macos"))]
 impl TextureArray {
     /// Creates a new texture array from raw data
     ///
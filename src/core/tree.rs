@@ -1,12 +1,14 @@
 //! Tree system for rendering low-poly trees on the spherical world
 
-use crate::math::{Mat4, Vec2, Vec3, Vec4};
-use crate::scene::{InstanceData, InstancedMesh, Mesh, Vertex};
+use crate::math::{sphere, Frustum, LatLon, Mat4, Vec3};
+use crate::scene::{mesh_ops, Bvh, InstanceData, InstancedMesh, Mesh};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 
 pub struct TreeSystem {
     instanced_mesh: InstancedMesh,
+    /// Boxes of the instances, keyed by index into `instanced_mesh.instances`
+    bvh: Bvh<usize>,
     planet_radius: f32,
 }
 
@@ -25,6 +27,7 @@ impl TreeSystem {
             road_end_angle,
         );
 
+        let bvh = Bvh::from_instances(&base_mesh, &instances);
         let instanced_mesh = InstancedMesh {
             base_mesh,
             instances,
@@ -32,110 +35,40 @@ impl TreeSystem {
 
         Self {
             instanced_mesh,
+            bvh,
             planet_radius,
         }
     }
 
-    fn create_tree_mesh() -> Mesh {
-        let mut vertices = Vec::new();
-        let mut indices = Vec::new();
-
-        // Create trunk (tapered cylinder approximation using 6 sides)
-        let trunk_sides = 6;
-        let trunk_radius_bottom = 0.4; // Increased from 0.15
-        let trunk_radius_top = 0.2; // Increased from 0.08
-        let trunk_height = 3.0; // Increased from 1.0
-
-        // Trunk vertices
-        for i in 0..trunk_sides {
-            let angle = (i as f32 / trunk_sides as f32) * 2.0 * std::f32::consts::PI;
-            let cos_angle = angle.cos();
-            let sin_angle = angle.sin();
-
-            // Bottom vertex
-            vertices.push(Vertex {
-                position: Vec3::new(
-                    trunk_radius_bottom * cos_angle,
-                    0.0,
-                    trunk_radius_bottom * sin_angle,
-                ),
-                tex_coord: Vec2::new(i as f32 / trunk_sides as f32, 1.0),
-                normal: Vec3::new(cos_angle, 0.0, sin_angle),
-            });
-
-            // Top vertex
-            vertices.push(Vertex {
-                position: Vec3::new(
-                    trunk_radius_top * cos_angle,
-                    trunk_height,
-                    trunk_radius_top * sin_angle,
-                ),
-                tex_coord: Vec2::new(i as f32 / trunk_sides as f32, 0.0),
-                normal: Vec3::new(cos_angle, 0.0, sin_angle),
-            });
-        }
-
-        // Trunk indices
-        for i in 0..trunk_sides {
-            let current_bottom = (i * 2) as u16;
-            let current_top = current_bottom + 1;
-            let next_bottom = ((i + 1) % trunk_sides * 2) as u16;
-            let next_top = next_bottom + 1;
-
-            // Two triangles per quad
-            indices.push(current_bottom);
-            indices.push(next_bottom);
-            indices.push(current_top);
-
-            indices.push(current_top);
-            indices.push(next_bottom);
-            indices.push(next_top);
-        }
-
-        // Create foliage (simple cone)
-        let foliage_base_radius = 2.5; // Increased from 0.8
-        let foliage_height = 4.0; // Increased from 1.5
-        let foliage_y_offset = trunk_height - 0.5; // Overlap slightly with trunk
-        let foliage_sides = 8;
-
-        let vertex_offset = vertices.len() as u16;
-
-        // Foliage tip vertex
-        vertices.push(Vertex {
-            position: Vec3::new(0.0, foliage_y_offset + foliage_height, 0.0),
-            tex_coord: Vec2::new(0.5, 0.0),
-            normal: Vec3::new(0.0, 1.0, 0.0),
-        });
-
-        // Foliage base vertices
-        for i in 0..foliage_sides {
-            let angle = (i as f32 / foliage_sides as f32) * 2.0 * std::f32::consts::PI;
-            let cos_angle = angle.cos();
-            let sin_angle = angle.sin();
-
-            vertices.push(Vertex {
-                position: Vec3::new(
-                    foliage_base_radius * cos_angle,
-                    foliage_y_offset,
-                    foliage_base_radius * sin_angle,
-                ),
-                tex_coord: Vec2::new(i as f32 / foliage_sides as f32, 1.0),
-                normal: Vec3::new(cos_angle, 0.5, sin_angle).normalize(),
-            });
-        }
-
-        // Foliage indices
-        for i in 0..foliage_sides {
-            let tip = vertex_offset;
-            let current_base = vertex_offset + 1 + i as u16;
-            let next_base = vertex_offset + 1 + ((i + 1) % foliage_sides) as u16;
-
-            indices.push(tip);
-            indices.push(next_base);
-            indices.push(current_base);
-        }
+    /// Trunk and foliage of a single tree, standing on the origin
+    pub(crate) fn create_tree_mesh() -> Mesh {
+        // Tapered six-sided trunk standing on the origin
+        let trunk_radius_bottom = 0.4;
+        let trunk_radius_top = 0.2;
+        let trunk_height = 3.0;
+        let trunk = Mesh::cylinder(
+            trunk_radius_bottom,
+            trunk_radius_top,
+            trunk_height,
+            6,
+            false,
+        );
 
-        Mesh { vertices, indices }
+        // Cone of foliage overlapping the top of the trunk slightly
+        let foliage_base_radius = 2.5;
+        let foliage_height = 4.0;
+        let foliage_y_offset = trunk_height - 0.5;
+        let foliage = Mesh::cone(foliage_base_radius, foliage_height, 8);
+
+        // Both parts have a handful of vertices, far below the index limit
+        mesh_ops::merge(&[
+            (&trunk, Mat4::translation(0.0, trunk_height / 2.0, 0.0)),
+            (
+                &foliage,
+                Mat4::translation(0.0, foliage_y_offset + foliage_height / 2.0, 0.0),
+            ),
+        ])
+        .unwrap_or(trunk)
     }
 
     #[allow(clippy::many_single_char_names)]
@@ -156,29 +89,14 @@ impl TreeSystem {
             // Generate random spherical coordinates
             let u: f32 = rng.gen();
             let v: f32 = rng.gen();
-
-            let theta = 2.0 * std::f32::consts::PI * u;
-            let phi = (1.0 - 2.0 * v).acos();
-
-            // Convert to cartesian coordinates on sphere surface
-            let x = planet_radius * phi.sin() * theta.cos();
-            let y = planet_radius * phi.sin() * theta.sin();
-            let z = planet_radius * phi.cos();
-
-            let position = Vec3::new(x, y, z);
-
-            // Check if tree is too close to road (simple check for equator road)
-            if y.abs() < 2.5 {
-                // Near equator (reduced from 5.0 for smaller planet)
-                let angle = x.atan2(z);
-                let normalized_angle = if angle < 0.0 {
-                    angle + 2.0 * std::f32::consts::PI
-                } else {
-                    angle
-                };
-
-                // Check if within road bounds
-                if normalized_angle >= road_start_angle && normalized_angle <= road_end_angle {
+            let coords = LatLon::from_uniform(u, v);
+            let position = coords.to_cartesian(planet_radius);
+
+            // Check if tree is too close to the road, which follows the equator
+            if coords.lat.abs() * planet_radius < 2.5 {
+                // Road angles are longitudes in [0, 2π)
+                let lon = coords.lon.rem_euclid(2.0 * std::f32::consts::PI);
+                if lon >= road_start_angle && lon <= road_end_angle {
                     continue; // Skip this position, it's on the road
                 }
             }
@@ -186,47 +104,13 @@ impl TreeSystem {
             // Calculate up vector (radial from planet center)
             let up = position.normalize();
 
-            // Create a random forward direction in the tangent plane
-            let world_up = Vec3::new(0.0, 1.0, 0.0);
-            let right = if (up.dot(&world_up).abs() - 1.0).abs() < 0.01 {
-                Vec3::new(1.0, 0.0, 0.0)
-            } else {
-                world_up.cross(&up).normalize()
-            };
-            let forward = up.cross(&right).normalize();
-
-            // Add some random rotation around the up axis
-            let angle: f32 = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
-            let cos_angle = angle.cos();
-            let sin_angle = angle.sin();
-            let rotated_forward = forward.scale(cos_angle).add(&right.scale(sin_angle));
-            let rotated_right = forward.scale(-sin_angle).add(&right.scale(cos_angle));
-
-            // Create transformation matrix
-            let mut transform = Mat4::identity();
+            // Align +Y with the surface normal, then add some random rotation around it
+            let yaw: f32 = rng.gen::<f32>() * 2.0 * std::f32::consts::PI;
 
             // Add some size variation
             let scale = 1.0 + rng.gen::<f32>() * 0.5; // 1.0 to 1.5
 
-            // Set rotation columns (Metal uses column-major)
-            transform.cols[0] = Vec4::new(
-                rotated_right.x * scale,
-                rotated_right.y * scale,
-                rotated_right.z * scale,
-                0.0,
-            );
-
-            transform.cols[1] = Vec4::new(up.x * scale, up.y * scale, up.z * scale, 0.0);
-
-            transform.cols[2] = Vec4::new(
-                rotated_forward.x * scale,
-                rotated_forward.y * scale,
-                rotated_forward.z * scale,
-                0.0,
-            );
-
-            // Set position column
-            transform.cols[3] = Vec4::new(position.x, position.y, position.z, 1.0);
+            let transform = sphere::instance_matrix(&position, &up, yaw, scale);
 
             // Color variation - trunk brown, foliage green
             // We'll use the color_variation to indicate trunk vs foliage in the shader
@@ -244,9 +128,40 @@ impl TreeSystem {
         instances
     }
 
+    /// Radius of the planet the trees stand on
+    #[must_use]
+    pub fn planet_radius(&self) -> f32 {
+        self.planet_radius
+    }
+
     pub fn instanced_mesh(&self) -> &InstancedMesh {
         &self.instanced_mesh
     }
+
+    /// Hierarchy over the trees' boxes, keyed by instance index
+    pub fn bvh(&self) -> &Bvh<usize> {
+        &self.bvh
+    }
+
+    /// Indices of the trees at least partly inside `frustum`, in ascending order
+    pub fn visible_instances(&self, frustum: &Frustum) -> Vec<usize> {
+        let mut visible = self.bvh.query_frustum(frustum);
+        visible.sort_unstable();
+        visible
+    }
+
+    /// Moves trees with `f`, given each tree's index and transform, and refits
+    /// [`Self::bvh`] to where they ended up
+    pub fn move_instances(&mut self, mut f: impl FnMut(usize, &mut Mat4)) {
+        let InstancedMesh {
+            base_mesh,
+            instances,
+        } = &mut self.instanced_mesh;
+        for (index, instance) in instances.iter_mut().enumerate() {
+            f(index, &mut instance.transform);
+        }
+        self.bvh.refit_instances(base_mesh, instances);
+    }
 }
 
 #[cfg(test)]
@@ -256,13 +171,32 @@ mod tests {
     #[test]
     fn test_tree_creation() {
         let tree_system = TreeSystem::new(50.0, 10, 0.0, std::f32::consts::PI / 2.0);
-        assert_eq!(tree_system.planet_radius, 50.0);
+        assert_eq!(tree_system.planet_radius(), 50.0);
 
         // Check that instances were created
         assert!(!tree_system.instanced_mesh.instances.is_empty());
         assert!(tree_system.instanced_mesh.instances.len() <= 10);
     }
 
+    #[test]
+    fn test_trees_avoid_road() {
+        let planet_radius = 50.0;
+        let road_end = std::f32::consts::PI / 2.0;
+        let tree_system = TreeSystem::new(planet_radius, 300, 0.0, road_end);
+
+        for instance in &tree_system.instanced_mesh.instances {
+            let position = Vec3::new(
+                instance.transform.cols[3].x,
+                instance.transform.cols[3].y,
+                instance.transform.cols[3].z,
+            );
+            let coords = LatLon::from_cartesian(&position);
+            let on_road =
+                coords.lat.abs() * planet_radius < 2.5 && (0.0..=road_end).contains(&coords.lon);
+            assert!(!on_road, "tree placed on the road at {:?}", coords);
+        }
+    }
+
     #[test]
     fn test_tree_mesh_creation() {
         let mesh = TreeSystem::create_tree_mesh();
@@ -273,5 +207,17 @@ mod tests {
 
         // Should have trunk + foliage vertices
         assert!(mesh.vertices.len() > 12); // At least trunk vertices
+
+        // Side normals point away from the trunk axis; the foliage's base faces down
+        assert!(mesh_ops::validate(&mesh).is_empty());
+        for vertex in &mesh.vertices {
+            let outward = Vec3::new(vertex.position.x, 0.0, vertex.position.z);
+            if vertex.normal.y > -0.999 && outward.length() > 1e-3 {
+                assert!(vertex.normal.dot(&outward) > 0.0);
+            }
+        }
+        let bounds = mesh.bounds().unwrap();
+        assert!(bounds.min.y.abs() < 1e-5);
+        assert!((bounds.max.y - 6.5).abs() < 1e-5);
     }
 }
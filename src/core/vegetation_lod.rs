@@ -1,7 +1,8 @@
 //! Level of Detail (LOD) system for vegetation rendering
 
+use crate::core::TreeSystem;
 use crate::math::{Vec2, Vec3};
-use crate::scene::{Mesh, Vertex};
+use crate::scene::{mesh_ops, InstanceData, LodChain, Mesh, SimplifyTarget, Vertex};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LodLevel {
@@ -12,6 +13,9 @@ pub enum LodLevel {
 }
 
 impl LodLevel {
+    /// All LOD levels in ascending distance order
+    pub const ALL: [Self; 4] = [Self::Full, Self::Reduced, Self::Billboard, Self::Fade];
+
     pub fn from_distance(distance: f32) -> Self {
         match distance {
             d if d < 10.0 => Self::Full,
@@ -50,9 +54,11 @@ pub struct GrassLodMeshes {
 
 impl GrassLodMeshes {
     pub fn generate() -> Self {
+        let full = Self::generate_full_mesh();
+        let reduced = Self::generate_reduced_mesh(&full);
         let lod_levels = [
-            Self::generate_full_mesh(),
-            Self::generate_reduced_mesh(),
+            full,
+            reduced,
             Self::generate_billboard_mesh(),
             Self::generate_fade_mesh(),
         ];
@@ -84,14 +90,14 @@ impl GrassLodMeshes {
             // Left vertex
             vertices.push(Vertex {
                 position: Vec3::new(-current_width + curve_x, y, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
+                normal: Vec3::zero(),
                 tex_coord: Vec2::new(0.0, 1.0 - t),
             });
 
             // Right vertex
             vertices.push(Vertex {
                 position: Vec3::new(current_width + curve_x, y, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
+                normal: Vec3::zero(),
                 tex_coord: Vec2::new(1.0, 1.0 - t),
             });
         }
@@ -101,59 +107,24 @@ impl GrassLodMeshes {
             let base = i * 2;
 
             // First triangle
-            indices.push(base as u16);
-            indices.push((base + 1) as u16);
-            indices.push((base + 2) as u16);
+            indices.push(base as u32);
+            indices.push((base + 1) as u32);
+            indices.push((base + 2) as u32);
 
             // Second triangle
-            indices.push((base + 1) as u16);
-            indices.push((base + 3) as u16);
-            indices.push((base + 2) as u16);
+            indices.push((base + 1) as u32);
+            indices.push((base + 3) as u32);
+            indices.push((base + 2) as u32);
         }
 
-        Mesh { vertices, indices }
+        let mut mesh = Mesh { vertices, indices };
+        mesh_ops::recompute_smooth_normals(&mut mesh);
+        mesh
     }
 
-    fn generate_reduced_mesh() -> Mesh {
-        // Simplified grass blade with 2 segments
-        let mut vertices = Vec::new();
-        let mut indices = Vec::new();
-
-        let segments = 2;
-        let width = 0.05;
-        let height = 0.6;
-        let curve_amount = 0.08;
-
-        for i in 0..=segments {
-            let t = i as f32 / segments as f32;
-            let y = t * height;
-            let curve_x = curve_amount * t * t;
-            let current_width = width * (1.0 - t * 0.8);
-
-            vertices.push(Vertex {
-                position: Vec3::new(-current_width + curve_x, y, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
-                tex_coord: Vec2::new(0.0, 1.0 - t),
-            });
-
-            vertices.push(Vertex {
-                position: Vec3::new(current_width + curve_x, y, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
-                tex_coord: Vec2::new(1.0, 1.0 - t),
-            });
-        }
-
-        for i in 0..segments {
-            let base = i * 2;
-            indices.push(base as u16);
-            indices.push((base + 1) as u16);
-            indices.push((base + 2) as u16);
-            indices.push((base + 1) as u16);
-            indices.push((base + 3) as u16);
-            indices.push((base + 2) as u16);
-        }
-
-        Mesh { vertices, indices }
+    fn generate_reduced_mesh(full: &Mesh) -> Mesh {
+        // Full blade collapsed to two segments, keeping its outline and curve
+        full.simplified(SimplifyTarget::triangles(4)).mesh
     }
 
     fn generate_billboard_mesh() -> Mesh {
@@ -164,29 +135,31 @@ impl GrassLodMeshes {
         let vertices = vec![
             Vertex {
                 position: Vec3::new(-width * 0.5, 0.0, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
+                normal: Vec3::zero(),
                 tex_coord: Vec2::new(0.0, 1.0),
             },
             Vertex {
                 position: Vec3::new(width * 0.5, 0.0, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
+                normal: Vec3::zero(),
                 tex_coord: Vec2::new(1.0, 1.0),
             },
             Vertex {
                 position: Vec3::new(-width * 0.5, height, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
+                normal: Vec3::zero(),
                 tex_coord: Vec2::new(0.0, 0.0),
             },
             Vertex {
                 position: Vec3::new(width * 0.5, height, 0.0),
-                normal: Vec3::new(0.0, 0.0, 1.0),
+                normal: Vec3::zero(),
                 tex_coord: Vec2::new(1.0, 0.0),
             },
         ];
 
         let indices = vec![0, 1, 2, 1, 3, 2];
 
-        Mesh { vertices, indices }
+        let mut mesh = Mesh { vertices, indices };
+        mesh_ops::recompute_smooth_normals(&mut mesh);
+        mesh
     }
 
     fn generate_fade_mesh() -> Mesh {
@@ -208,8 +181,24 @@ pub struct VegetationInstance {
     pub texture_index: u32,
 }
 
+impl VegetationInstance {
+    /// Per-instance data uploaded for the instanced draws
+    pub fn instance_data(&self) -> InstanceData {
+        InstanceData {
+            transform: self.transform,
+            color_variation: self.color_variation,
+            lod_level: self.lod_level as u32,
+            texture_index: self.texture_index,
+            _padding: [0; 3],
+        }
+    }
+}
+
 pub struct VegetationLodSystem {
     pub grass_lods: GrassLodMeshes,
+    /// Tree mesh simplified once per [`LodLevel`], halving the triangles each level
+    /// until no more edges can collapse
+    pub tree_lods: LodChain,
     pub view_position: Vec3,
 }
 
@@ -217,10 +206,21 @@ impl VegetationLodSystem {
     pub fn new() -> Self {
         Self {
             grass_lods: GrassLodMeshes::generate(),
+            tree_lods: LodChain::generate(
+                &TreeSystem::create_tree_mesh(),
+                LodLevel::ALL.len(),
+                0.5,
+            ),
             view_position: Vec3::zero(),
         }
     }
 
+    /// Tree mesh for `lod_level`, or the coarsest one the chain reached
+    pub fn tree_mesh(&self, lod_level: LodLevel) -> &Mesh {
+        let levels = &self.tree_lods.levels;
+        &levels[(lod_level as usize).min(levels.len() - 1)].mesh
+    }
+
     pub fn update_view_position(&mut self, position: Vec3) {
         self.view_position = position;
     }
@@ -235,3 +235,44 @@ impl VegetationLodSystem {
         (lod_level, fade_factor)
     }
 }
+
+impl Default for VegetationLodSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_lods_get_coarser() {
+        let lods = VegetationLodSystem::new();
+        let triangles: Vec<usize> = lods
+            .tree_lods
+            .levels
+            .iter()
+            .map(|level| level.mesh.indices.len() / 3)
+            .collect();
+        assert_eq!(
+            triangles[0],
+            TreeSystem::create_tree_mesh().indices.len() / 3
+        );
+        assert!(triangles.len() >= 2);
+        for pair in triangles.windows(2) {
+            assert!(pair[1] < pair[0]);
+        }
+        for level in &lods.tree_lods.levels {
+            assert!(mesh_ops::validate(&level.mesh).is_empty());
+        }
+
+        // Levels past the end of the chain reuse its coarsest mesh
+        let coarsest = triangles[triangles.len() - 1];
+        assert_eq!(
+            lods.tree_mesh(LodLevel::Full).indices.len() / 3,
+            triangles[0]
+        );
+        assert_eq!(lods.tree_mesh(LodLevel::Fade).indices.len() / 3, coarsest);
+    }
+}
@@ -0,0 +1,544 @@
+//! Camera controllers that turn [`InputState`] into camera motion
+//!
+//! Each controller keeps the state of one way of moving around: walking on the
+//! planet, orbiting a point, flying freely or looking straight down on a map.
+//! [`CameraControllers`] holds a set of them and switches between them at runtime,
+//! letting the new controller pick up from wherever the camera is.
+
+use super::InputState;
+use crate::math::Vec3;
+use crate::scene::{Camera, CameraPathPlayer, Projection};
+use std::f32::consts::FRAC_PI_2;
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// Moves a [`Camera`] from user input once per frame
+pub trait CameraController {
+    /// Short name for on-screen display
+    fn name(&self) -> &'static str;
+
+    /// Takes over `camera`, starting from its current position where possible
+    fn activate(&mut self, camera: &mut Camera);
+
+    /// Applies one frame of input
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta_time: f32);
+}
+
+fn is_running(input: &InputState) -> bool {
+    input.is_key_pressed(PhysicalKey::Code(KeyCode::ShiftLeft))
+        || input.is_key_pressed(PhysicalKey::Code(KeyCode::ShiftRight))
+}
+
+/// Unit vector along the surface towards the north pole (+Y) at `up`
+fn local_north(up: Vec3) -> Vec3 {
+    let world_up = Vec3::new(0.0, 1.0, 0.0);
+    (world_up - up * world_up.dot(&up))
+        .try_normalize()
+        .unwrap_or_else(|| Vec3::new(0.0, 0.0, -1.0))
+}
+
+/// First-person walker held at eye height above a spherical planet
+#[derive(Debug, Clone)]
+pub struct SurfaceWalker {
+    pub planet_center: Vec3,
+    pub planet_radius: f32,
+    pub eye_height: f32,
+    pub walk_speed: f32,
+    pub run_speed: f32,
+}
+
+impl SurfaceWalker {
+    #[must_use]
+    pub fn new(planet_radius: f32) -> Self {
+        Self {
+            planet_center: Vec3::zero(),
+            planet_radius,
+            eye_height: 15.0,
+            walk_speed: 5.0,
+            run_speed: 10.0,
+        }
+    }
+
+    /// Closest point at eye height to `position`
+    fn constrain(&self, position: Vec3) -> Vec3 {
+        let up = (position - self.planet_center)
+            .try_normalize()
+            .unwrap_or_else(|| Vec3::new(0.0, 1.0, 0.0));
+        self.planet_center + up * (self.planet_radius + self.eye_height)
+    }
+}
+
+impl CameraController for SurfaceWalker {
+    fn name(&self) -> &'static str {
+        "Walk"
+    }
+
+    fn activate(&mut self, camera: &mut Camera) {
+        camera.set_projection(Projection::Perspective);
+        let position = self.constrain(camera.position());
+        let up = (position - self.planet_center).normalize();
+        // Keep the heading but level the view with the ground
+        let heading = camera.forward() - up * camera.forward().dot(&up);
+        let heading = heading.try_normalize().unwrap_or_else(|| local_north(up));
+        camera.set_position(position);
+        camera.set_orientation(heading, up);
+    }
+
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta_time: f32) {
+        let (dx, dy) = input.mouse_delta();
+        if dx.abs() > 0.0 || dy.abs() > 0.0 {
+            let sensitivity = input.mouse_sensitivity();
+            camera.rotate(-dx * sensitivity, -dy * sensitivity);
+        }
+
+        let speed = if is_running(input) {
+            self.run_speed
+        } else {
+            self.walk_speed
+        };
+        let movement = camera.forward() * input.key_axis(KeyCode::KeyS, KeyCode::KeyW)
+            + camera.right() * input.key_axis(KeyCode::KeyA, KeyCode::KeyD);
+
+        // Apply movement and constrain to sphere surface
+        if movement.length() > 0.0 {
+            let position = self.constrain(camera.position() + movement * (speed * delta_time));
+            camera.set_position(position);
+            camera.set_up_vector(position - self.planet_center);
+        }
+
+        camera.update(delta_time);
+    }
+}
+
+/// Circles a target at a distance; the mouse turns around it and the scroll wheel,
+/// W and S zoom
+#[derive(Debug, Clone)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Angle around the Y axis, zero towards +Z
+    pub azimuth: f32,
+    /// Angle above the XZ plane
+    pub elevation: f32,
+    /// Fraction of the distance zoomed per scroll line, or per second of W/S
+    pub zoom_speed: f32,
+}
+
+impl OrbitController {
+    /// Highest elevation, short of straight above the target where the view's up
+    /// vector would be undefined
+    const MAX_ELEVATION: f32 = FRAC_PI_2 - 0.05;
+
+    #[must_use]
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            min_distance: distance * 0.25,
+            max_distance: distance * 4.0,
+            azimuth: 0.0,
+            elevation: 0.3,
+            zoom_speed: 0.1,
+        }
+    }
+
+    #[must_use]
+    pub fn eye(&self) -> Vec3 {
+        let horizontal = self.elevation.cos();
+        let offset = Vec3::new(
+            horizontal * self.azimuth.sin(),
+            self.elevation.sin(),
+            horizontal * self.azimuth.cos(),
+        );
+        self.target + offset * self.distance
+    }
+
+    fn apply(&self, camera: &mut Camera) {
+        let eye = self.eye();
+        camera.set_position(eye);
+        camera.set_orientation(self.target - eye, Vec3::new(0.0, 1.0, 0.0));
+    }
+}
+
+impl CameraController for OrbitController {
+    fn name(&self) -> &'static str {
+        "Orbit"
+    }
+
+    fn activate(&mut self, camera: &mut Camera) {
+        camera.set_projection(Projection::Perspective);
+        let offset = camera.position() - self.target;
+        if let Some(direction) = offset.try_normalize() {
+            self.distance = offset.length().clamp(self.min_distance, self.max_distance);
+            self.azimuth = direction.x.atan2(direction.z);
+            self.elevation = direction
+                .y
+                .asin()
+                .clamp(-Self::MAX_ELEVATION, Self::MAX_ELEVATION);
+        }
+        self.apply(camera);
+    }
+
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta_time: f32) {
+        let (dx, dy) = input.mouse_delta();
+        let sensitivity = input.mouse_sensitivity();
+        self.azimuth -= dx * sensitivity
+            + input.key_axis(KeyCode::KeyA, KeyCode::KeyD) * FRAC_PI_2 * delta_time;
+        self.elevation =
+            (self.elevation + dy * sensitivity).clamp(-Self::MAX_ELEVATION, Self::MAX_ELEVATION);
+
+        let zoom = input.scroll_delta() + input.key_axis(KeyCode::KeyS, KeyCode::KeyW) * delta_time;
+        self.distance = (self.distance * (1.0 - self.zoom_speed).powf(zoom))
+            .clamp(self.min_distance, self.max_distance);
+
+        self.apply(camera);
+    }
+}
+
+/// Unconstrained flight: WASD moves along the view, Space and Q rise and sink
+#[derive(Debug, Clone)]
+pub struct FreeFlyController {
+    pub speed: f32,
+    /// Speed multiplier while Shift is held
+    pub boost: f32,
+}
+
+impl FreeFlyController {
+    #[must_use]
+    pub fn new(speed: f32) -> Self {
+        Self { speed, boost: 4.0 }
+    }
+}
+
+impl Default for FreeFlyController {
+    fn default() -> Self {
+        Self::new(10.0)
+    }
+}
+
+impl CameraController for FreeFlyController {
+    fn name(&self) -> &'static str {
+        "Fly"
+    }
+
+    fn activate(&mut self, camera: &mut Camera) {
+        camera.set_projection(Projection::Perspective);
+        camera.set_orientation(camera.forward(), camera.up_vector());
+    }
+
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta_time: f32) {
+        let (dx, dy) = input.mouse_delta();
+        let sensitivity = input.mouse_sensitivity();
+        camera.rotate(-dx * sensitivity, -dy * sensitivity);
+
+        let speed = if is_running(input) {
+            self.speed * self.boost
+        } else {
+            self.speed
+        };
+        let movement = camera.forward() * input.key_axis(KeyCode::KeyS, KeyCode::KeyW)
+            + camera.right() * input.key_axis(KeyCode::KeyA, KeyCode::KeyD)
+            + camera.up_vector() * input.key_axis(KeyCode::KeyQ, KeyCode::Space);
+        camera.set_position(camera.position() + movement * (speed * delta_time));
+
+        camera.update(delta_time);
+    }
+}
+
+/// Orthographic map view looking straight down on a spherical planet with north
+/// up; WASD pans across the surface and the scroll wheel, Q and E zoom
+#[derive(Debug, Clone)]
+pub struct TopDownController {
+    pub planet_center: Vec3,
+    pub planet_radius: f32,
+    /// Direction from the planet center to the point below the camera
+    pub focus: Vec3,
+    /// Height of the camera above the surface, which only affects clipping
+    pub altitude: f32,
+    /// World units visible from the bottom of the screen to the top
+    pub view_height: f32,
+    pub min_view_height: f32,
+    pub max_view_height: f32,
+    /// Fraction of the view height zoomed per scroll line, or per second of Q/E
+    pub zoom_speed: f32,
+}
+
+impl TopDownController {
+    #[must_use]
+    pub fn new(planet_radius: f32) -> Self {
+        Self {
+            planet_center: Vec3::zero(),
+            planet_radius,
+            focus: Vec3::new(0.0, 1.0, 0.0),
+            altitude: planet_radius,
+            view_height: planet_radius,
+            min_view_height: planet_radius * 0.05,
+            max_view_height: planet_radius * 2.5,
+            zoom_speed: 0.1,
+        }
+    }
+
+    fn apply(&self, camera: &mut Camera) {
+        camera.set_projection(Projection::Orthographic {
+            height: self.view_height,
+        });
+        camera.set_position(self.planet_center + self.focus * (self.planet_radius + self.altitude));
+        camera.set_orientation(-self.focus, local_north(self.focus));
+    }
+}
+
+impl CameraController for TopDownController {
+    fn name(&self) -> &'static str {
+        "Map"
+    }
+
+    fn activate(&mut self, camera: &mut Camera) {
+        if let Some(focus) = (camera.position() - self.planet_center).try_normalize() {
+            self.focus = focus;
+        }
+        self.apply(camera);
+    }
+
+    fn update(&mut self, camera: &mut Camera, input: &InputState, delta_time: f32) {
+        // Pan half a screen per second, whatever the zoom
+        let north = local_north(self.focus);
+        let east = north.cross(&self.focus);
+        let pan = north * input.key_axis(KeyCode::KeyS, KeyCode::KeyW)
+            + east * input.key_axis(KeyCode::KeyA, KeyCode::KeyD);
+        let distance = self.view_height * 0.5 * delta_time;
+        if let Some(focus) = (self.focus + pan * (distance / self.planet_radius)).try_normalize() {
+            self.focus = focus;
+        }
+
+        let zoom = input.scroll_delta() + input.key_axis(KeyCode::KeyQ, KeyCode::KeyE) * delta_time;
+        self.view_height = (self.view_height * (1.0 - self.zoom_speed).powf(zoom))
+            .clamp(self.min_view_height, self.max_view_height);
+
+        self.apply(camera);
+    }
+}
+
+impl CameraController for CameraPathPlayer {
+    fn name(&self) -> &'static str {
+        "Path"
+    }
+
+    /// Starts playback from where it was left, jumping the camera onto the path
+    fn activate(&mut self, camera: &mut Camera) {
+        self.play();
+        self.apply(camera);
+    }
+
+    /// Ignores input; the path alone moves the camera
+    fn update(&mut self, camera: &mut Camera, _input: &InputState, delta_time: f32) {
+        self.advance(camera, delta_time);
+    }
+}
+
+/// A set of controllers, one of which drives the camera
+pub struct CameraControllers {
+    controllers: Vec<Box<dyn CameraController>>,
+    active: usize,
+}
+
+impl CameraControllers {
+    /// Controllers in the given order, with the first one active
+    #[must_use]
+    pub fn new(controllers: Vec<Box<dyn CameraController>>) -> Self {
+        Self {
+            controllers,
+            active: 0,
+        }
+    }
+
+    /// Walking, orbiting the whole planet, flying and the map view, in that order
+    #[must_use]
+    pub fn for_planet(planet_radius: f32) -> Self {
+        Self::new(vec![
+            Box::new(SurfaceWalker::new(planet_radius)),
+            Box::new(OrbitController::new(Vec3::zero(), planet_radius * 3.0)),
+            Box::new(FreeFlyController::default()),
+            Box::new(TopDownController::new(planet_radius)),
+        ])
+    }
+
+    /// Adds a controller after the existing ones and returns its index
+    pub fn push(&mut self, controller: Box<dyn CameraController>) -> usize {
+        self.controllers.push(controller);
+        self.controllers.len() - 1
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.controllers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.controllers.is_empty()
+    }
+
+    #[must_use]
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    #[must_use]
+    pub fn active(&self) -> Option<&dyn CameraController> {
+        self.controllers.get(self.active).map(AsRef::as_ref)
+    }
+
+    /// Hands `camera` to the controller at `index`; returns false if there is none
+    pub fn switch_to(&mut self, index: usize, camera: &mut Camera) -> bool {
+        let Some(controller) = self.controllers.get_mut(index) else {
+            return false;
+        };
+        controller.activate(camera);
+        self.active = index;
+        true
+    }
+
+    /// Hands `camera` to the next controller, wrapping around after the last
+    pub fn cycle(&mut self, camera: &mut Camera) {
+        if !self.is_empty() {
+            self.switch_to((self.active + 1) % self.len(), camera);
+        }
+    }
+
+    pub fn update(&mut self, camera: &mut Camera, input: &InputState, delta_time: f32) {
+        if let Some(controller) = self.controllers.get_mut(self.active) {
+            controller.update(camera, input, delta_time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera::new(Vec3::new(0.0, 40.0, 0.0), Vec3::new(0.0, 40.0, -10.0), 1.0)
+    }
+
+    fn holding(keys: &[KeyCode]) -> InputState {
+        let mut input = InputState::new();
+        for &key in keys {
+            input.key_pressed(PhysicalKey::Code(key));
+        }
+        input
+    }
+
+    #[test]
+    fn test_walker_stays_at_eye_height() {
+        let mut camera = camera();
+        camera.set_up_vector(Vec3::new(0.0, 1.0, 0.0));
+        let mut walker = SurfaceWalker::new(25.0);
+        walker.activate(&mut camera);
+
+        let input = holding(&[KeyCode::KeyW, KeyCode::ShiftLeft]);
+        for _ in 0..20 {
+            walker.update(&mut camera, &input, 0.1);
+        }
+        assert!((camera.position().length() - 40.0).abs() < 1e-3);
+        assert!(camera.position().distance(&Vec3::new(0.0, 40.0, 0.0)) > 15.0);
+    }
+
+    #[test]
+    fn test_orbit_looks_at_target() {
+        let mut camera = camera();
+        let mut orbit = OrbitController::new(Vec3::zero(), 75.0);
+        orbit.activate(&mut camera);
+        // Picks up the camera's direction from the target
+        assert!(orbit.elevation > 1.4);
+        assert!((camera.position().length() - 40.0).abs() < 1e-3);
+
+        let mut input = InputState::new();
+        input.set_mouse_delta(100.0, -50.0);
+        input.add_scroll(-3.0);
+        orbit.update(&mut camera, &input, 0.016);
+        assert!(camera.position().length() > 40.0);
+        let to_target = (Vec3::zero() - camera.position()).normalize();
+        assert!(camera.forward().dot(&to_target) > 0.9999);
+
+        // Zoom is limited
+        input.add_scroll(1000.0);
+        orbit.update(&mut camera, &input, 0.016);
+        assert!((camera.position().length() - orbit.min_distance).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_free_fly_moves_along_view() {
+        let mut camera = camera();
+        let mut fly = FreeFlyController::new(10.0);
+        fly.activate(&mut camera);
+        let start = camera.position();
+        fly.update(&mut camera, &holding(&[KeyCode::KeyW]), 0.5);
+        let moved = camera.position() - start;
+        assert!((moved.length() - 5.0).abs() < 1e-3);
+        assert!(moved.normalize().dot(&Vec3::new(0.0, 0.0, -1.0)) > 0.999);
+
+        fly.update(&mut camera, &holding(&[KeyCode::Space]), 1.0);
+        assert!((camera.position().y - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_top_down_is_orthographic_with_north_up() {
+        let mut camera = camera();
+        let mut map = TopDownController::new(25.0);
+        map.activate(&mut camera);
+        assert_eq!(
+            camera.projection(),
+            Projection::Orthographic { height: 25.0 }
+        );
+        assert!(camera.forward().dot(&Vec3::new(0.0, -1.0, 0.0)) > 0.9999);
+
+        // Panning north from the equator moves the focus towards the north pole
+        map.focus = Vec3::new(0.0, 0.0, 1.0);
+        map.update(&mut camera, &holding(&[KeyCode::KeyW]), 1.0);
+        assert!(map.focus.y > 0.0);
+        assert!(camera.up_vector().dot(&map.focus).abs() < 1e-4);
+        assert!(camera.up_vector().y > 0.8);
+        assert!(camera.forward().dot(&-map.focus) > 0.9999);
+        assert!((camera.position().length() - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_switching_controllers() {
+        let mut camera = camera();
+        let mut controllers = CameraControllers::for_planet(25.0);
+        assert_eq!(controllers.len(), 4);
+        assert_eq!(controllers.active().map(|c| c.name()), Some("Walk"));
+
+        assert!(controllers.switch_to(3, &mut camera));
+        assert!(matches!(
+            camera.projection(),
+            Projection::Orthographic { .. }
+        ));
+        controllers.cycle(&mut camera);
+        assert_eq!(controllers.active_index(), 0);
+        assert_eq!(camera.projection(), Projection::Perspective);
+        assert!(!controllers.switch_to(4, &mut camera));
+        assert_eq!(controllers.active_index(), 0);
+    }
+
+    #[test]
+    fn test_path_player_ignores_input() {
+        use crate::scene::{CameraKeyframe, CameraPath};
+
+        let forward = Vec3::new(0.0, 0.0, -1.0);
+        let path = CameraPath::from_keyframes(vec![
+            CameraKeyframe::new(0.0, Vec3::new(0.0, 50.0, 0.0), forward),
+            CameraKeyframe::new(1.0, Vec3::new(10.0, 50.0, 0.0), forward),
+        ]);
+        let mut camera = camera();
+        let mut controllers = CameraControllers::for_planet(25.0);
+        let index = controllers.push(Box::new(CameraPathPlayer::new(path)));
+        assert!(controllers.switch_to(index, &mut camera));
+        assert_eq!(camera.position(), Vec3::new(0.0, 50.0, 0.0));
+
+        controllers.update(&mut camera, &holding(&[KeyCode::KeyS]), 0.5);
+        assert!((camera.position().x - 5.0).abs() < 1e-4);
+        assert_eq!(camera.position().y, 50.0);
+    }
+}
@@ -1,9 +1,19 @@
+//! Keyboard and mouse state, and the camera controllers driven by it
+
+mod camera_controller;
+
+pub use camera_controller::{
+    CameraController, CameraControllers, FreeFlyController, OrbitController, SurfaceWalker,
+    TopDownController,
+};
+
 use std::collections::HashSet;
-use winit::keyboard::PhysicalKey;
+use winit::keyboard::{KeyCode, PhysicalKey};
 
 pub struct InputState {
     pressed_keys: HashSet<PhysicalKey>,
     mouse_delta: (f32, f32),
+    scroll_delta: f32,
     mouse_sensitivity: f32,
     movement_speed: f32,
 }
@@ -14,6 +24,7 @@ impl InputState {
         Self {
             pressed_keys: HashSet::new(),
             mouse_delta: (0.0, 0.0),
+            scroll_delta: 0.0,
             mouse_sensitivity: 0.003,
             movement_speed: 2.0,
         }
@@ -32,6 +43,13 @@ impl InputState {
         self.pressed_keys.contains(&key)
     }
 
+    /// -1, 0 or 1 depending on which of the two keys are held
+    #[must_use]
+    pub fn key_axis(&self, negative: KeyCode, positive: KeyCode) -> f32 {
+        let held = |code| f32::from(u8::from(self.is_key_pressed(PhysicalKey::Code(code))));
+        held(positive) - held(negative)
+    }
+
     pub fn set_mouse_delta(&mut self, delta_x: f32, delta_y: f32) {
         self.mouse_delta = (delta_x, delta_y);
     }
@@ -45,6 +63,20 @@ impl InputState {
         self.mouse_delta
     }
 
+    /// Adds wheel movement in lines, positive away from the user
+    pub fn add_scroll(&mut self, lines: f32) {
+        self.scroll_delta += lines;
+    }
+
+    pub fn reset_scroll_delta(&mut self) {
+        self.scroll_delta = 0.0;
+    }
+
+    #[must_use]
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll_delta
+    }
+
     #[must_use]
     pub fn mouse_sensitivity(&self) -> f32 {
         self.mouse_sensitivity
@@ -4,15 +4,26 @@
 //! Apple's Metal API. It includes custom math types, scene management, input handling,
 //! and a UI system.
 //!
+//! # Features
+//!
+//! - `metal` (default): the Metal renderer, UI overlay, GPU textures and the
+//!   windowed [`app::App`]. Only available on macOS; everywhere else the math,
+//!   scene, world generation, input and timing modules still build and run.
+//!
 //! # Example
 //! ```no_run
+//! # #[cfg(all(feature = "metal", target_os = "macos"))]
 //! use game_engine::app::App;
 //!
+//! # #[cfg(all(feature = "metal", target_os = "macos"))]
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     App::run()
 //! }
+//! # #[cfg(not(all(feature = "metal", target_os = "macos")))]
+//! # fn main() {}
 //! ```
 
+#[cfg(all(feature = "metal", target_os = "macos"))]
 pub mod app;
 pub mod core;
 pub mod input;
@@ -1,14 +1,12 @@
-mod app;
-mod core;
-mod input;
-mod math;
-mod renderer;
-mod scene;
-mod ui;
-
-use app::App;
-
+#[cfg(all(feature = "metal", target_os = "macos"))]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use game_engine::{app::App, log};
+
     log!("Starting 3D Graphics Engine");
     App::run()
 }
+
+#[cfg(not(all(feature = "metal", target_os = "macos")))]
+fn main() {
+    game_engine::warn!("The windowed engine requires macOS and the `metal` feature");
+}
@@ -0,0 +1,696 @@
+//! Geometric primitives and intersection tests
+//!
+//! Rays, planes, axis-aligned boxes, bounding spheres and view frustums, with the
+//! queries needed for CPU-side culling and picking. Planes use the `n·p + d = 0`
+//! convention with a unit normal, so `signed_distance` is positive in front.
+
+use crate::math::{Mat4, Vec3, Vec4};
+
+/// Result of testing a volume against a frustum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Containment {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+/// Half-line starting at `origin` and extending along a unit `direction`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Creates a ray, normalizing `direction`
+    #[must_use]
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    #[must_use]
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    /// Distance along the ray to the first hit on the sphere, or `None` on a miss.
+    /// Returns 0 when the origin is inside the sphere.
+    #[must_use]
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let to_center = sphere.center - self.origin;
+        let projection = to_center.dot(&self.direction);
+        let dist_sq = to_center.length_squared() - projection * projection;
+        let radius_sq = sphere.radius * sphere.radius;
+        if dist_sq > radius_sq {
+            return None;
+        }
+
+        let half_chord = (radius_sq - dist_sq).sqrt();
+        let near = projection - half_chord;
+        let far = projection + half_chord;
+        if far < 0.0 {
+            None
+        } else {
+            Some(near.max(0.0))
+        }
+    }
+
+    /// Möller–Trumbore ray/triangle test, hitting both faces. Returns the distance
+    /// along the ray.
+    #[must_use]
+    pub fn intersect_triangle(&self, a: &Vec3, b: &Vec3, c: &Vec3) -> Option<f32> {
+        const EPSILON: f32 = 1e-7;
+
+        let edge1 = *b - *a;
+        let edge2 = *c - *a;
+        let p = self.direction.cross(&edge2);
+        let det = edge1.dot(&p);
+        if det.abs() < EPSILON {
+            return None; // Parallel to the triangle
+        }
+
+        let inv_det = 1.0 / det;
+        let s = self.origin - *a;
+        let u = s.dot(&p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(&edge1);
+        let v = self.direction.dot(&q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(&q) * inv_det;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// Slab test against a box. Returns 0 when the origin is inside the box.
+    #[must_use]
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = 0.0_f32;
+        let mut t_max = f32::INFINITY;
+
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+            if direction.abs() < f32::EPSILON {
+                if origin < aabb.min[axis] || origin > aabb.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv = 1.0 / direction;
+            let t0 = (aabb.min[axis] - origin) * inv;
+            let t1 = (aabb.max[axis] - origin) * inv;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+
+    /// Distance along the ray to the plane, if it is hit in front of the origin
+    #[must_use]
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denom = plane.normal.dot(&self.direction);
+        if denom.abs() < f32::EPSILON {
+            return None;
+        }
+        let t = -plane.signed_distance(&self.origin) / denom;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+/// Plane satisfying `normal·p + distance = 0`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a plane, normalizing the equation so `normal` has unit length
+    #[must_use]
+    pub fn new(normal: Vec3, distance: f32) -> Self {
+        let len = normal.length();
+        if len > 0.0 {
+            Self {
+                normal: normal / len,
+                distance: distance / len,
+            }
+        } else {
+            Self { normal, distance }
+        }
+    }
+
+    #[must_use]
+    pub fn from_point_normal(point: &Vec3, normal: &Vec3) -> Self {
+        let normal = normal.normalize();
+        Self {
+            normal,
+            distance: -normal.dot(point),
+        }
+    }
+
+    /// Plane through three points, facing the side they wind counter-clockwise from
+    #[must_use]
+    pub fn from_points(a: &Vec3, b: &Vec3, c: &Vec3) -> Self {
+        let normal = (*b - *a).cross(&(*c - *a));
+        Self::from_point_normal(a, &normal)
+    }
+
+    /// Plane from `(a, b, c, d)` coefficients, as stored in shader uniforms
+    #[must_use]
+    pub fn from_vec4(v: &Vec4) -> Self {
+        Self::new(Vec3::new(v.x, v.y, v.z), v.w)
+    }
+
+    #[must_use]
+    pub fn to_vec4(&self) -> Vec4 {
+        Vec4::new(self.normal.x, self.normal.y, self.normal.z, self.distance)
+    }
+
+    /// Positive in front of the plane, negative behind
+    #[must_use]
+    pub fn signed_distance(&self, point: &Vec3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    #[must_use]
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box containing every point, or `None` if there are none
+    #[must_use]
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, p| aabb.include(p)))
+    }
+
+    #[must_use]
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    #[must_use]
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Half the size along each axis
+    #[must_use]
+    pub fn extents(&self) -> Vec3 {
+        self.size() * 0.5
+    }
+
+    #[must_use]
+    pub fn surface_area(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    #[must_use]
+    pub fn include(&self, point: &Vec3) -> Self {
+        Self::new(self.min.min(point), self.max.max(point))
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(self.min.min(&other.min), self.max.max(&other.max))
+    }
+
+    #[must_use]
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        (0..3).all(|axis| point[axis] >= self.min[axis] && point[axis] <= self.max[axis])
+    }
+
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.max[axis] && self.max[axis] >= other.min[axis])
+    }
+
+    /// Box enclosing this one after an affine transform
+    #[must_use]
+    pub fn transform(&self, matrix: &Mat4) -> Self {
+        // Transform the center and project the extents onto each world axis
+        let center = matrix.transform_point(&self.center());
+        let extents = self.extents();
+        let half = Vec3::new(
+            matrix.cols[0].x.abs() * extents.x
+                + matrix.cols[1].x.abs() * extents.y
+                + matrix.cols[2].x.abs() * extents.z,
+            matrix.cols[0].y.abs() * extents.x
+                + matrix.cols[1].y.abs() * extents.y
+                + matrix.cols[2].y.abs() * extents.z,
+            matrix.cols[0].z.abs() * extents.x
+                + matrix.cols[1].z.abs() * extents.y
+                + matrix.cols[2].z.abs() * extents.z,
+        );
+        Self::new(center - half, center + half)
+    }
+}
+
+/// Sphere used for coarse visibility and picking tests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    #[must_use]
+    pub const fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Sphere centered on the points' bounding box, or `None` if there are none
+    #[must_use]
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec3> + Clone) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius_sq = points
+            .into_iter()
+            .map(|p| (*p - center).length_squared())
+            .fold(0.0_f32, f32::max);
+        Some(Self::new(center, radius_sq.sqrt()))
+    }
+
+    #[must_use]
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        point.distance(&self.center) <= self.radius
+    }
+
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.center.distance(&other.center) <= self.radius + other.radius
+    }
+
+    /// Sphere enclosing this one after a transform, scaling the radius by the
+    /// largest axis scale
+    #[must_use]
+    pub fn transform(&self, matrix: &Mat4) -> Self {
+        let scale = (0..3)
+            .map(|axis| matrix.transform_direction(&unit_axis(axis)).length())
+            .fold(0.0_f32, f32::max);
+        Self::new(matrix.transform_point(&self.center), self.radius * scale)
+    }
+}
+
+impl From<Aabb> for BoundingSphere {
+    fn from(aabb: Aabb) -> Self {
+        Self::new(aabb.center(), aabb.extents().length())
+    }
+}
+
+/// Six inward-facing planes bounding a view volume
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a view-projection matrix (Gribb–Hartmann), assuming
+    /// clip-space z in `[-w, w]` as produced by [`Mat4::perspective`] and
+    /// [`Mat4::orthographic`]
+    ///
+    /// Reverse-Z matrices need [`Self::from_view_projection_reverse_z`], or
+    /// [`Camera::frustum`](crate::scene::Camera::frustum) to pick by depth mapping.
+    #[must_use]
+    pub fn from_view_projection(view_projection: &Mat4) -> Self {
+        let [x, y, z, w] = clip_rows(view_projection);
+        Self::from_side_planes(x, y, w, w + z, w - z)
+    }
+
+    /// Extracts the planes from a reverse-Z view-projection matrix, whose clip-space z
+    /// runs from `w` at the near plane to 0 at the far plane, as produced by
+    /// [`Mat4::perspective_reverse_z`] and [`Mat4::orthographic_reverse_z`]
+    ///
+    /// With [`Mat4::perspective_reverse_z_infinite`] the far plane has a zero normal
+    /// and rejects nothing.
+    #[must_use]
+    pub fn from_view_projection_reverse_z(view_projection: &Mat4) -> Self {
+        let [x, y, z, w] = clip_rows(view_projection);
+        Self::from_side_planes(x, y, w, w - z, z)
+    }
+
+    fn from_side_planes(x: Vec4, y: Vec4, w: Vec4, near: Vec4, far: Vec4) -> Self {
+        Self {
+            planes: [
+                Plane::from_vec4(&(w + x)),
+                Plane::from_vec4(&(w - x)),
+                Plane::from_vec4(&(w + y)),
+                Plane::from_vec4(&(w - y)),
+                Plane::from_vec4(&near),
+                Plane::from_vec4(&far),
+            ],
+        }
+    }
+
+    /// Plane coefficients in the layout the culling shader expects
+    #[must_use]
+    pub fn to_vec4s(&self) -> [Vec4; 6] {
+        self.planes.map(|plane| plane.to_vec4())
+    }
+
+    #[must_use]
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    #[must_use]
+    pub fn classify_sphere(&self, sphere: &BoundingSphere) -> Containment {
+        let mut result = Containment::Inside;
+        for plane in &self.planes {
+            let distance = plane.signed_distance(&sphere.center);
+            if distance < -sphere.radius {
+                return Containment::Outside;
+            }
+            if distance < sphere.radius {
+                result = Containment::Intersecting;
+            }
+        }
+        result
+    }
+
+    #[must_use]
+    pub fn classify_aabb(&self, aabb: &Aabb) -> Containment {
+        let center = aabb.center();
+        let extents = aabb.extents();
+        let mut result = Containment::Inside;
+        for plane in &self.planes {
+            // Projected radius of the box onto the plane normal
+            let radius = extents.dot(&plane.normal.abs());
+            let distance = plane.signed_distance(&center);
+            if distance < -radius {
+                return Containment::Outside;
+            }
+            if distance < radius {
+                result = Containment::Intersecting;
+            }
+        }
+        result
+    }
+
+    /// True unless the sphere is entirely outside, matching the GPU culling test
+    #[must_use]
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.classify_sphere(sphere) != Containment::Outside
+    }
+
+    #[must_use]
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.classify_aabb(aabb) != Containment::Outside
+    }
+}
+
+/// Rows of `matrix`, which dotted with a point give its clip-space x, y, z and w
+fn clip_rows(matrix: &Mat4) -> [Vec4; 4] {
+    [0, 1, 2, 3].map(|r| {
+        Vec4::new(
+            matrix.cols[0][r],
+            matrix.cols[1][r],
+            matrix.cols[2][r],
+            matrix.cols[3][r],
+        )
+    })
+}
+
+fn unit_axis(axis: usize) -> Vec3 {
+    let mut v = Vec3::zero();
+    v[axis] = 1.0;
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    fn test_frustum() -> Frustum {
+        // Looking down -Z from the origin with a 90° field of view
+        let projection = Mat4::perspective(FRAC_PI_2, 1.0, 1.0, 100.0);
+        let view = Mat4::look_at(
+            &Vec3::zero(),
+            &Vec3::new(0.0, 0.0, -1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        Frustum::from_view_projection(&projection.multiply(&view))
+    }
+
+    #[test]
+    fn test_ray_sphere() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -2.0));
+        let sphere = BoundingSphere::new(Vec3::zero(), 1.0);
+        assert_close(ray.intersect_sphere(&sphere).unwrap(), 9.0);
+
+        let miss = Ray::new(Vec3::new(0.0, 2.0, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(miss.intersect_sphere(&sphere), None);
+
+        let behind = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(behind.intersect_sphere(&sphere), None);
+
+        let inside = Ray::new(Vec3::zero(), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(inside.intersect_sphere(&sphere), Some(0.0));
+    }
+
+    #[test]
+    fn test_ray_triangle() {
+        let a = Vec3::new(-1.0, -1.0, 0.0);
+        let b = Vec3::new(1.0, -1.0, 0.0);
+        let c = Vec3::new(0.0, 1.0, 0.0);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_close(ray.intersect_triangle(&a, &b, &c).unwrap(), 5.0);
+
+        // Back faces are hit too
+        let back = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_close(back.intersect_triangle(&a, &b, &c).unwrap(), 5.0);
+
+        let outside = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(outside.intersect_triangle(&a, &b, &c), None);
+
+        let parallel = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(parallel.intersect_triangle(&a, &b, &c), None);
+    }
+
+    #[test]
+    fn test_ray_aabb() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+        let ray = Ray::new(Vec3::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_close(ray.intersect_aabb(&aabb).unwrap(), 4.0);
+
+        let diagonal = Ray::new(Vec3::new(3.0, 3.0, 3.0), Vec3::new(-1.0, -1.0, -1.0));
+        assert_close(
+            diagonal.intersect_aabb(&aabb).unwrap(),
+            2.0 * 3.0_f32.sqrt(),
+        );
+
+        let miss = Ray::new(Vec3::new(-5.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(miss.intersect_aabb(&aabb), None);
+
+        let away = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(away.intersect_aabb(&aabb), None);
+
+        let inside = Ray::new(Vec3::zero(), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(inside.intersect_aabb(&aabb), Some(0.0));
+    }
+
+    #[test]
+    fn test_ray_plane() {
+        let plane = Plane::from_point_normal(&Vec3::new(0.0, 2.0, 0.0), &Vec3::new(0.0, 1.0, 0.0));
+        let ray = Ray::new(Vec3::new(1.0, 5.0, 1.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_close(ray.intersect_plane(&plane).unwrap(), 3.0);
+        assert_eq!(ray.at(3.0), Vec3::new(1.0, 2.0, 1.0));
+
+        let up = Ray::new(Vec3::new(1.0, 5.0, 1.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(up.intersect_plane(&plane), None);
+    }
+
+    #[test]
+    fn test_plane() {
+        let plane = Plane::new(Vec3::new(0.0, 2.0, 0.0), -4.0);
+        assert_eq!(plane.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert_close(plane.signed_distance(&Vec3::new(3.0, 5.0, 0.0)), 3.0);
+        assert_close(plane.signed_distance(&Vec3::zero()), -2.0);
+
+        let from_points = Plane::from_points(
+            &Vec3::zero(),
+            &Vec3::new(1.0, 0.0, 0.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(from_points.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(Plane::from_vec4(&from_points.to_vec4()), from_points);
+    }
+
+    #[test]
+    fn test_aabb_from_points() {
+        let points = [
+            Vec3::new(1.0, -2.0, 3.0),
+            Vec3::new(-1.0, 4.0, 0.0),
+            Vec3::new(0.0, 0.0, -5.0),
+        ];
+        let aabb = Aabb::from_points(&points).unwrap();
+        assert_eq!(aabb.min, Vec3::new(-1.0, -2.0, -5.0));
+        assert_eq!(aabb.max, Vec3::new(1.0, 4.0, 3.0));
+        assert_eq!(aabb.center(), Vec3::new(0.0, 1.0, -1.0));
+        assert_eq!(aabb.extents(), Vec3::new(1.0, 3.0, 4.0));
+        assert!(points.iter().all(|p| aabb.contains_point(p)));
+        assert!(Aabb::from_points(&[]).is_none());
+    }
+
+    #[test]
+    fn test_aabb_overlap_and_union() {
+        let a = Aabb::new(Vec3::zero(), Vec3::new(2.0, 2.0, 2.0));
+        let b = Aabb::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(3.0, 3.0, 3.0));
+        let c = Aabb::new(Vec3::new(5.0, 0.0, 0.0), Vec3::new(6.0, 1.0, 1.0));
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&c));
+
+        let union = a.union(&c);
+        assert_eq!(union.min, Vec3::zero());
+        assert_eq!(union.max, Vec3::new(6.0, 2.0, 2.0));
+        assert_close(a.surface_area(), 24.0);
+    }
+
+    #[test]
+    fn test_aabb_transform() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let moved = aabb.transform(&Mat4::translation(5.0, 0.0, 0.0));
+        assert_eq!(moved.min, Vec3::new(4.0, -1.0, -1.0));
+        assert_eq!(moved.max, Vec3::new(6.0, 1.0, 1.0));
+
+        // A 45° rotation grows the box to enclose the rotated corners
+        let rotated = aabb.transform(&Mat4::rotation_y(std::f32::consts::FRAC_PI_4));
+        assert_close(rotated.max.x, 2.0_f32.sqrt());
+        assert_close(rotated.max.y, 1.0);
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let points = [Vec3::new(-1.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0)];
+        let sphere = BoundingSphere::from_points(&points).unwrap();
+        assert_eq!(sphere.center, Vec3::new(1.0, 0.0, 0.0));
+        assert_close(sphere.radius, 2.0);
+        assert!(sphere.contains_point(&Vec3::new(2.5, 0.0, 0.0)));
+        assert!(!sphere.contains_point(&Vec3::new(3.5, 0.0, 0.0)));
+
+        let other = BoundingSphere::new(Vec3::new(4.0, 0.0, 0.0), 1.5);
+        assert!(sphere.intersects(&other));
+
+        let scaled = sphere.transform(&Mat4::scale(1.0, 3.0, 2.0));
+        assert_close(scaled.radius, 6.0);
+
+        let from_box = BoundingSphere::from(Aabb::new(Vec3::zero(), Vec3::new(2.0, 2.0, 2.0)));
+        assert_close(from_box.radius, 3.0_f32.sqrt());
+    }
+
+    #[test]
+    fn test_frustum_planes_face_inward() {
+        let frustum = test_frustum();
+        assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, -10.0)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, 10.0)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -150.0)));
+        assert!(!frustum.contains_point(&Vec3::new(20.0, 0.0, -10.0)));
+
+        // Near and far planes sit at the projection's clip distances
+        assert_close(
+            frustum.planes[4].signed_distance(&Vec3::new(0.0, 0.0, -1.0)),
+            0.0,
+        );
+        let far = frustum.planes[5].signed_distance(&Vec3::new(0.0, 0.0, -100.0));
+        assert!(far.abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_reverse_z_frustum_keeps_the_far_plane() {
+        let view = Mat4::look_at(
+            &Vec3::zero(),
+            &Vec3::new(0.0, 0.0, -1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        let projection = Mat4::perspective_reverse_z(FRAC_PI_2, 1.0, 1.0, 100.0);
+        let frustum = Frustum::from_view_projection_reverse_z(&projection.multiply(&view));
+        assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, -10.0)));
+        assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, -99.0)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -150.0)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, 10.0)));
+        assert!(!frustum.contains_point(&Vec3::new(20.0, 0.0, -10.0)));
+        assert_close(
+            frustum.planes[4].signed_distance(&Vec3::new(0.0, 0.0, -1.0)),
+            0.0,
+        );
+        let far = frustum.planes[5].signed_distance(&Vec3::new(0.0, 0.0, -100.0));
+        assert!(far.abs() < 1e-3);
+
+        // Nothing is too far away once the far plane is at infinity
+        let projection = Mat4::perspective_reverse_z_infinite(FRAC_PI_2, 1.0, 1.0);
+        let frustum = Frustum::from_view_projection_reverse_z(&projection.multiply(&view));
+        assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, -1.0e6)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(&Vec3::new(2.0e6, 0.0, -1.0e6)));
+    }
+
+    #[test]
+    fn test_frustum_classify_sphere() {
+        let frustum = test_frustum();
+        let inside = BoundingSphere::new(Vec3::new(0.0, 0.0, -10.0), 1.0);
+        let straddling = BoundingSphere::new(Vec3::new(10.0, 0.0, -10.0), 1.0);
+        let outside = BoundingSphere::new(Vec3::new(0.0, 0.0, 10.0), 1.0);
+        assert_eq!(frustum.classify_sphere(&inside), Containment::Inside);
+        assert_eq!(
+            frustum.classify_sphere(&straddling),
+            Containment::Intersecting
+        );
+        assert_eq!(frustum.classify_sphere(&outside), Containment::Outside);
+        assert!(frustum.intersects_sphere(&straddling));
+    }
+
+    #[test]
+    fn test_frustum_classify_aabb() {
+        let frustum = test_frustum();
+        let unit = Vec3::new(1.0, 1.0, 1.0);
+        let at = |center: Vec3| Aabb::new(center - unit, center + unit);
+        assert_eq!(
+            frustum.classify_aabb(&at(Vec3::new(0.0, 0.0, -10.0))),
+            Containment::Inside
+        );
+        assert_eq!(
+            frustum.classify_aabb(&at(Vec3::new(0.0, 0.0, -100.0))),
+            Containment::Intersecting
+        );
+        assert_eq!(
+            frustum.classify_aabb(&at(Vec3::new(0.0, -30.0, -10.0))),
+            Containment::Outside
+        );
+        assert!(!frustum.intersects_aabb(&at(Vec3::new(0.0, 0.0, 5.0))));
+    }
+}
@@ -3,11 +3,24 @@
 //! This module provides mathematical types and operations optimized for 3D graphics:
 //! - SIMD-aligned vector types (Vec2, Vec3, Vec4)
 //! - 4x4 matrix operations
+//! - Operator overloading and indexing for vectors and matrices
+//! - Quaternion rotations
+//! - Rays, planes, bounding volumes and frustums with intersection tests
+//! - Latitude/longitude, tangent frames and great circles on the spherical world
 //! - Transform utilities
 //! - Camera projection matrices
 //!
 //! All types are 16-byte aligned for optimal SIMD performance.
 
+mod geometry;
+mod ops;
+mod quat;
+pub mod sphere;
+
+pub use geometry::{Aabb, BoundingSphere, Containment, Frustum, Plane, Ray};
+pub use quat::Quat;
+pub use sphere::{LatLon, TangentFrame};
+
 /// 2D vector with 16-byte alignment for SIMD operations
 #[repr(C, align(16))]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -31,6 +44,75 @@ impl Vec2 {
     pub const fn zero() -> Self {
         Self::new(0.0, 0.0)
     }
+
+    #[must_use]
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    #[must_use]
+    pub fn length_squared(&self) -> f32 {
+        self.dot(self)
+    }
+
+    #[must_use]
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    #[must_use]
+    pub fn distance(&self, other: &Self) -> f32 {
+        (*self - *other).length()
+    }
+
+    #[must_use]
+    pub fn normalize(&self) -> Self {
+        self.try_normalize().unwrap_or(*self)
+    }
+
+    /// Unit vector, or `None` if the length is zero or not finite
+    #[must_use]
+    pub fn try_normalize(&self) -> Option<Self> {
+        let len = self.length();
+        (len > 0.0 && len.is_finite()).then(|| *self / len)
+    }
+
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    #[must_use]
+    pub fn min(&self, other: &Self) -> Self {
+        Self::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    #[must_use]
+    pub fn max(&self, other: &Self) -> Self {
+        Self::new(self.x.max(other.x), self.y.max(other.y))
+    }
+
+    #[must_use]
+    pub fn abs(&self) -> Self {
+        Self::new(self.x.abs(), self.y.abs())
+    }
+
+    /// Reflects this vector off a surface with unit `normal`
+    #[must_use]
+    pub fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
+
+    /// Projection onto `other`; zero if `other` is zero
+    #[must_use]
+    pub fn project_on(&self, other: &Self) -> Self {
+        let len_sq = other.length_squared();
+        if len_sq > 0.0 {
+            *other * (self.dot(other) / len_sq)
+        } else {
+            Self::zero()
+        }
+    }
 }
 
 impl Default for Vec2 {
@@ -108,6 +190,68 @@ impl Vec3 {
     pub fn scale(&self, s: f32) -> Self {
         Self::new(self.x * s, self.y * s, self.z * s)
     }
+
+    #[must_use]
+    pub fn length_squared(&self) -> f32 {
+        self.dot(self)
+    }
+
+    #[must_use]
+    pub fn distance(&self, other: &Self) -> f32 {
+        (*self - *other).length()
+    }
+
+    /// Unit vector, or `None` if the length is zero or not finite
+    #[must_use]
+    pub fn try_normalize(&self) -> Option<Self> {
+        let len = self.length();
+        (len > 0.0 && len.is_finite()).then(|| *self / len)
+    }
+
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    #[must_use]
+    pub fn min(&self, other: &Self) -> Self {
+        Self::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    #[must_use]
+    pub fn max(&self, other: &Self) -> Self {
+        Self::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+
+    #[must_use]
+    pub fn abs(&self) -> Self {
+        Self::new(self.x.abs(), self.y.abs(), self.z.abs())
+    }
+
+    /// Reflects this vector off a surface with unit `normal`
+    #[must_use]
+    pub fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
+
+    /// Projection onto `other`; zero if `other` is zero
+    #[must_use]
+    pub fn project_on(&self, other: &Self) -> Self {
+        let len_sq = other.length_squared();
+        if len_sq > 0.0 {
+            *other * (self.dot(other) / len_sq)
+        } else {
+            Self::zero()
+        }
+    }
 }
 
 impl Default for Vec3 {
@@ -142,6 +286,52 @@ impl Vec4 {
         self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
     }
 
+    #[must_use]
+    pub fn length_squared(&self) -> f32 {
+        self.dot(self)
+    }
+
+    #[must_use]
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    #[must_use]
+    pub fn min(&self, other: &Self) -> Self {
+        Self::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+            self.w.min(other.w),
+        )
+    }
+
+    #[must_use]
+    pub fn max(&self, other: &Self) -> Self {
+        Self::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+            self.w.max(other.w),
+        )
+    }
+
+    #[must_use]
+    pub fn abs(&self) -> Self {
+        Self::new(self.x.abs(), self.y.abs(), self.z.abs(), self.w.abs())
+    }
+
+    /// The xyz components, dropping w
+    #[must_use]
+    pub fn truncate(&self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
     #[must_use]
     pub fn normalize_plane(&self) -> Self {
         let len = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
@@ -255,96 +445,40 @@ impl Mat4 {
         )
     }
 
+    /// Transforms a point (w = 1), dividing through by w for projective matrices
     #[must_use]
+    pub fn transform_point(&self, p: &Vec3) -> Vec3 {
+        let v = self.multiply_vec4(&Vec4::new(p.x, p.y, p.z, 1.0));
+        if v.w != 0.0 && v.w != 1.0 {
+            v.truncate() / v.w
+        } else {
+            v.truncate()
+        }
+    }
+
+    /// Transforms a direction (w = 0), ignoring translation
+    #[must_use]
+    pub fn transform_direction(&self, d: &Vec3) -> Vec3 {
+        self.multiply_vec4(&Vec4::new(d.x, d.y, d.z, 0.0))
+            .truncate()
+    }
+
+    #[must_use]
+    #[allow(clippy::double_must_use)]
     pub fn get(&self, row: usize, col: usize) -> Result<f32, String> {
-        match (row, col) {
-            (0, 0) => Ok(self.cols[0].x),
-            (1, 0) => Ok(self.cols[0].y),
-            (2, 0) => Ok(self.cols[0].z),
-            (3, 0) => Ok(self.cols[0].w),
-            (0, 1) => Ok(self.cols[1].x),
-            (1, 1) => Ok(self.cols[1].y),
-            (2, 1) => Ok(self.cols[1].z),
-            (3, 1) => Ok(self.cols[1].w),
-            (0, 2) => Ok(self.cols[2].x),
-            (1, 2) => Ok(self.cols[2].y),
-            (2, 2) => Ok(self.cols[2].z),
-            (3, 2) => Ok(self.cols[2].w),
-            (0, 3) => Ok(self.cols[3].x),
-            (1, 3) => Ok(self.cols[3].y),
-            (2, 3) => Ok(self.cols[3].z),
-            (3, 3) => Ok(self.cols[3].w),
-            _ => Err(format!("Matrix index out of bounds: ({}, {})", row, col)),
+        if row < 4 && col < 4 {
+            Ok(self[col][row])
+        } else {
+            Err(format!("Matrix index out of bounds: ({}, {})", row, col))
         }
     }
 
     pub fn set(&mut self, row: usize, col: usize, value: f32) -> Result<(), String> {
-        match (row, col) {
-            (0, 0) => {
-                self.cols[0].x = value;
-                Ok(())
-            }
-            (1, 0) => {
-                self.cols[0].y = value;
-                Ok(())
-            }
-            (2, 0) => {
-                self.cols[0].z = value;
-                Ok(())
-            }
-            (3, 0) => {
-                self.cols[0].w = value;
-                Ok(())
-            }
-            (0, 1) => {
-                self.cols[1].x = value;
-                Ok(())
-            }
-            (1, 1) => {
-                self.cols[1].y = value;
-                Ok(())
-            }
-            (2, 1) => {
-                self.cols[1].z = value;
-                Ok(())
-            }
-            (3, 1) => {
-                self.cols[1].w = value;
-                Ok(())
-            }
-            (0, 2) => {
-                self.cols[2].x = value;
-                Ok(())
-            }
-            (1, 2) => {
-                self.cols[2].y = value;
-                Ok(())
-            }
-            (2, 2) => {
-                self.cols[2].z = value;
-                Ok(())
-            }
-            (3, 2) => {
-                self.cols[2].w = value;
-                Ok(())
-            }
-            (0, 3) => {
-                self.cols[3].x = value;
-                Ok(())
-            }
-            (1, 3) => {
-                self.cols[3].y = value;
-                Ok(())
-            }
-            (2, 3) => {
-                self.cols[3].z = value;
-                Ok(())
-            }
-            (3, 3) => {
-                self.cols[3].w = value;
-                Ok(())
-            }
-            _ => Err(format!("Matrix index out of bounds: ({}, {})", row, col)),
+        if row < 4 && col < 4 {
+            self[col][row] = value;
+            Ok(())
+        } else {
+            Err(format!("Matrix index out of bounds: ({}, {})", row, col))
         }
     }
 
@@ -416,6 +550,36 @@ impl Mat4 {
         )
     }
 
+    /// Perspective projection with reversed depth: the near plane maps to depth 1 and
+    /// the far plane to 0, in the `[0, 1]` clip depth range used by Metal
+    ///
+    /// Floating-point depth is most precise near 0, so reversing the range spreads
+    /// precision far more evenly over distance than [`Self::perspective`].
+    #[must_use]
+    pub fn perspective_reverse_z(fov_y_rad: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (fov_y_rad / 2.0).tan();
+        let range = far - near;
+        Self::new(
+            Vec4::new(f / aspect, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, f, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, near / range, -1.0),
+            Vec4::new(0.0, 0.0, far * near / range, 0.0),
+        )
+    }
+
+    /// [`Self::perspective_reverse_z`] with the far plane at infinity, where depth
+    /// approaches 0
+    #[must_use]
+    pub fn perspective_reverse_z_infinite(fov_y_rad: f32, aspect: f32, near: f32) -> Self {
+        let f = 1.0 / (fov_y_rad / 2.0).tan();
+        Self::new(
+            Vec4::new(f / aspect, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, f, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, -1.0),
+            Vec4::new(0.0, 0.0, near, 0.0),
+        )
+    }
+
     #[must_use]
     pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
         let width = right - left;
@@ -435,6 +599,34 @@ impl Mat4 {
         )
     }
 
+    /// Orthographic projection mapping the near plane to depth 1 and the far plane
+    /// to 0, matching [`Self::perspective_reverse_z`]
+    #[must_use]
+    pub fn orthographic_reverse_z(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) -> Self {
+        let width = right - left;
+        let height = top - bottom;
+        let depth = far - near;
+
+        Self::new(
+            Vec4::new(2.0 / width, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 / height, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 1.0 / depth, 0.0),
+            Vec4::new(
+                -(right + left) / width,
+                -(top + bottom) / height,
+                far / depth,
+                1.0,
+            ),
+        )
+    }
+
     #[must_use]
     pub fn look_at(eye: &Vec3, center: &Vec3, up: &Vec3) -> Self {
         let f = center.sub(eye).normalize();
@@ -448,6 +640,214 @@ impl Mat4 {
             Vec4::new(-s.dot(eye), -u.dot(eye), f.dot(eye), 1.0),
         )
     }
+
+    #[must_use]
+    pub fn transpose(&self) -> Self {
+        let [c0, c1, c2, c3] = self.cols;
+        Self::new(
+            Vec4::new(c0.x, c1.x, c2.x, c3.x),
+            Vec4::new(c0.y, c1.y, c2.y, c3.y),
+            Vec4::new(c0.z, c1.z, c2.z, c3.z),
+            Vec4::new(c0.w, c1.w, c2.w, c3.w),
+        )
+    }
+
+    #[must_use]
+    pub fn determinant(&self) -> f32 {
+        self.cofactors().1
+    }
+
+    /// General inverse, or `None` if the matrix is singular
+    #[must_use]
+    pub fn inverse(&self) -> Option<Self> {
+        let (cofactors, det) = self.cofactors();
+        if det.abs() < f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        Some(Self::from_flat(cofactors.map(|c| c * inv_det)))
+    }
+
+    /// Inverse of a matrix whose bottom row is `(0, 0, 0, 1)` (rotation, scale and
+    /// translation only), cheaper than [`inverse`](Self::inverse)
+    #[must_use]
+    pub fn inverse_affine(&self) -> Option<Self> {
+        let linear = self.inverse_linear()?;
+        let t = self.cols[3];
+        let translation = linear.multiply_vec4(&Vec4::new(t.x, t.y, t.z, 0.0));
+
+        let mut result = linear;
+        result.cols[3] = Vec4::new(-translation.x, -translation.y, -translation.z, 1.0);
+        Some(result)
+    }
+
+    /// Matrix for transforming normals: the inverse-transpose of the upper 3x3 with no
+    /// translation. Falls back to the upper 3x3 itself when it is singular.
+    #[must_use]
+    pub fn normal_matrix(&self) -> Self {
+        self.inverse_linear()
+            .map_or_else(|| self.linear(), |inverse| inverse.transpose())
+    }
+
+    /// Splits an affine matrix into translation, rotation and scale; a mirrored matrix
+    /// gets a negative X scale
+    #[must_use]
+    pub fn decompose(&self) -> (Vec3, Quat, Vec3) {
+        let column = |v: &Vec4| Vec3::new(v.x, v.y, v.z);
+        let translation = column(&self.cols[3]);
+
+        let mut axes = [
+            column(&self.cols[0]),
+            column(&self.cols[1]),
+            column(&self.cols[2]),
+        ];
+        let mut scale = Vec3::new(axes[0].length(), axes[1].length(), axes[2].length());
+        if axes[0].cross(&axes[1]).dot(&axes[2]) < 0.0 {
+            scale.x = -scale.x;
+            axes[0] = axes[0].scale(-1.0);
+        }
+
+        let rotation = if scale.x == 0.0 || scale.y == 0.0 || scale.z == 0.0 {
+            Quat::identity()
+        } else {
+            let axis = |v: Vec3| Vec4::new(v.x, v.y, v.z, 0.0);
+            Quat::from_mat4(&Self::new(
+                axis(axes[0]),
+                axis(axes[1]),
+                axis(axes[2]),
+                Vec4::new(0.0, 0.0, 0.0, 1.0),
+            ))
+        };
+
+        (translation, rotation, scale)
+    }
+
+    /// Upper 3x3 with the translation and projection parts cleared
+    fn linear(&self) -> Self {
+        let axis = |v: &Vec4| Vec4::new(v.x, v.y, v.z, 0.0);
+        Self::new(
+            axis(&self.cols[0]),
+            axis(&self.cols[1]),
+            axis(&self.cols[2]),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Inverse of the upper 3x3, embedded in a 4x4 with no translation
+    fn inverse_linear(&self) -> Option<Self> {
+        let column = |v: &Vec4| Vec3::new(v.x, v.y, v.z);
+        let a = column(&self.cols[0]);
+        let b = column(&self.cols[1]);
+        let c = column(&self.cols[2]);
+
+        let det = a.cross(&b).dot(&c);
+        if det.abs() < f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        // Rows of the inverse are the cross products of the columns
+        let r0 = b.cross(&c).scale(inv_det);
+        let r1 = c.cross(&a).scale(inv_det);
+        let r2 = a.cross(&b).scale(inv_det);
+        Some(Self::new(
+            Vec4::new(r0.x, r1.x, r2.x, 0.0),
+            Vec4::new(r0.y, r1.y, r2.y, 0.0),
+            Vec4::new(r0.z, r1.z, r2.z, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        ))
+    }
+
+    fn to_flat(self) -> [f32; 16] {
+        let [c0, c1, c2, c3] = self.cols;
+        [
+            c0.x, c0.y, c0.z, c0.w, c1.x, c1.y, c1.z, c1.w, c2.x, c2.y, c2.z, c2.w, c3.x, c3.y,
+            c3.z, c3.w,
+        ]
+    }
+
+    fn from_flat(m: [f32; 16]) -> Self {
+        Self::new(
+            Vec4::new(m[0], m[1], m[2], m[3]),
+            Vec4::new(m[4], m[5], m[6], m[7]),
+            Vec4::new(m[8], m[9], m[10], m[11]),
+            Vec4::new(m[12], m[13], m[14], m[15]),
+        )
+    }
+
+    /// Adjugate (transposed cofactor matrix) in column-major order, plus the determinant
+    fn cofactors(&self) -> ([f32; 16], f32) {
+        let m = self.to_flat();
+        let mut inv = [0.0; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14]
+            + m[13] * m[6] * m[11]
+            - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14]
+            - m[12] * m[6] * m[11]
+            + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13]
+            + m[12] * m[5] * m[11]
+            - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13]
+            - m[12] * m[5] * m[10]
+            + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14]
+            - m[13] * m[2] * m[11]
+            + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14]
+            + m[12] * m[2] * m[11]
+            - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13]
+            - m[12] * m[1] * m[11]
+            + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13]
+            + m[12] * m[1] * m[10]
+            - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14]
+            + m[13] * m[2] * m[7]
+            - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14]
+            - m[12] * m[2] * m[7]
+            + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13]
+            + m[12] * m[1] * m[7]
+            - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13]
+            - m[12] * m[1] * m[6]
+            + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10]
+            - m[9] * m[2] * m[7]
+            + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10]
+            + m[8] * m[2] * m[7]
+            - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9]
+            - m[8] * m[1] * m[7]
+            + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9]
+            + m[8] * m[1] * m[6]
+            - m[8] * m[2] * m[5];
+
+        let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+        (inv, det)
+    }
 }
 
 impl Default for Mat4 {
@@ -456,16 +856,17 @@ impl Default for Mat4 {
     }
 }
 
+/// Translation, rotation and scale applied as scale, then rotation, then translation
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Transform {
     pub position: Vec3,
-    pub rotation: Vec3,
+    pub rotation: Quat,
     pub scale: Vec3,
 }
 
 impl Transform {
     #[must_use]
-    pub const fn new(position: Vec3, rotation: Vec3, scale: Vec3) -> Self {
+    pub const fn new(position: Vec3, rotation: Quat, scale: Vec3) -> Self {
         Self {
             position,
             rotation,
@@ -473,11 +874,24 @@ impl Transform {
         }
     }
 
+    /// Creates a transform from Euler angles in radians, applied X, then Y, then Z
+    #[must_use]
+    pub fn from_euler(position: Vec3, euler: Vec3, scale: Vec3) -> Self {
+        Self::new(position, Quat::from_euler(&euler), scale)
+    }
+
+    /// Recovers position, rotation and scale from an affine matrix
+    #[must_use]
+    pub fn from_matrix(matrix: &Mat4) -> Self {
+        let (position, rotation, scale) = matrix.decompose();
+        Self::new(position, rotation, scale)
+    }
+
     #[must_use]
     pub const fn identity() -> Self {
         Self {
             position: Vec3::zero(),
-            rotation: Vec3::zero(),
+            rotation: Quat::identity(),
             scale: Vec3::new(1.0, 1.0, 1.0),
         }
     }
@@ -485,13 +899,10 @@ impl Transform {
     #[must_use]
     pub fn to_matrix(&self) -> Mat4 {
         let translation = Mat4::translation(self.position.x, self.position.y, self.position.z);
-        let rotation_x = Mat4::rotation_x(self.rotation.x);
-        let rotation_y = Mat4::rotation_y(self.rotation.y);
-        let rotation_z = Mat4::rotation_z(self.rotation.z);
+        let rotation = self.rotation.to_mat4();
         let scale = Mat4::scale(self.scale.x, self.scale.y, self.scale.z);
 
-        // Order: Scale -> Rotation X -> Rotation Y -> Rotation Z -> Translation
-        let rotation = rotation_z.multiply(&rotation_y).multiply(&rotation_x);
+        // Order: Scale -> Rotation -> Translation
         translation.multiply(&rotation).multiply(&scale)
     }
 }
@@ -538,6 +949,24 @@ mod tests {
             assert_eq!(v1, v2);
             assert_ne!(v1, v3);
         }
+
+        #[test]
+        fn test_length_and_normalize() {
+            let v = Vec2::new(3.0, 4.0);
+            assert_eq!(v.length_squared(), 25.0);
+            assert_eq!(v.length(), 5.0);
+            assert_eq!(v.normalize(), Vec2::new(0.6, 0.8));
+            assert_eq!(Vec2::zero().try_normalize(), None);
+            assert_eq!(v.distance(&Vec2::zero()), 5.0);
+        }
+
+        #[test]
+        fn test_reflect_and_project() {
+            let v = Vec2::new(1.0, -1.0);
+            let normal = Vec2::new(0.0, 1.0);
+            assert_eq!(v.reflect(&normal), Vec2::new(1.0, 1.0));
+            assert_eq!(v.project_on(&Vec2::new(2.0, 0.0)), Vec2::new(1.0, 0.0));
+        }
     }
 
     mod vec3_tests {
@@ -645,6 +1074,57 @@ mod tests {
             let normalized = v.normalize();
             assert_eq!(normalized, v); // Zero vector should remain zero
         }
+
+        #[test]
+        fn test_length_squared_and_distance() {
+            let v = Vec3::new(1.0, 2.0, 2.0);
+            assert_eq!(v.length_squared(), 9.0);
+            assert_eq!(v.distance(&Vec3::new(1.0, 2.0, 5.0)), 3.0);
+        }
+
+        #[test]
+        fn test_try_normalize() {
+            let v = Vec3::new(0.0, 0.0, 2.0);
+            assert_eq!(v.try_normalize(), Some(Vec3::new(0.0, 0.0, 1.0)));
+            assert_eq!(Vec3::zero().try_normalize(), None);
+            assert_eq!(Vec3::new(f32::INFINITY, 0.0, 0.0).try_normalize(), None);
+            assert_eq!(Vec3::new(f32::NAN, 0.0, 0.0).try_normalize(), None);
+        }
+
+        #[test]
+        fn test_lerp() {
+            let a = Vec3::new(0.0, 2.0, -4.0);
+            let b = Vec3::new(2.0, 4.0, 4.0);
+            assert_eq!(a.lerp(&b, 0.0), a);
+            assert_eq!(a.lerp(&b, 1.0), b);
+            assert_eq!(a.lerp(&b, 0.5), Vec3::new(1.0, 3.0, 0.0));
+        }
+
+        #[test]
+        fn test_min_max_abs() {
+            let a = Vec3::new(-1.0, 5.0, 2.0);
+            let b = Vec3::new(3.0, -2.0, 2.0);
+            assert_eq!(a.min(&b), Vec3::new(-1.0, -2.0, 2.0));
+            assert_eq!(a.max(&b), Vec3::new(3.0, 5.0, 2.0));
+            assert_eq!(a.abs(), Vec3::new(1.0, 5.0, 2.0));
+        }
+
+        #[test]
+        fn test_reflect() {
+            let incoming = Vec3::new(1.0, -1.0, 0.0);
+            let normal = Vec3::new(0.0, 1.0, 0.0);
+            assert_eq!(incoming.reflect(&normal), Vec3::new(1.0, 1.0, 0.0));
+        }
+
+        #[test]
+        fn test_project_on() {
+            let v = Vec3::new(2.0, 3.0, 4.0);
+            assert_eq!(
+                v.project_on(&Vec3::new(0.0, 5.0, 0.0)),
+                Vec3::new(0.0, 3.0, 0.0)
+            );
+            assert_eq!(v.project_on(&Vec3::zero()), Vec3::zero());
+        }
     }
 
     mod vec4_tests {
@@ -694,6 +1174,18 @@ mod tests {
             let result = v1.dot(&v2);
             assert_eq!(result, 70.0); // 1*5 + 2*6 + 3*7 + 4*8 = 5 + 12 + 21 + 32 = 70
         }
+
+        #[test]
+        fn test_length_lerp_and_bounds() {
+            let v = Vec4::new(1.0, -1.0, 1.0, -1.0);
+            assert_eq!(v.length_squared(), 4.0);
+            assert_eq!(v.length(), 2.0);
+            assert_eq!(v.abs(), Vec4::new(1.0, 1.0, 1.0, 1.0));
+            assert_eq!(v.lerp(&Vec4::zero(), 0.5), v * 0.5);
+            assert_eq!(v.min(&Vec4::zero()), Vec4::new(0.0, -1.0, 0.0, -1.0));
+            assert_eq!(v.max(&Vec4::zero()), Vec4::new(1.0, 0.0, 1.0, 0.0));
+            assert_eq!(v.truncate(), Vec3::new(1.0, -1.0, 1.0));
+        }
     }
 
     mod mat4_tests {
@@ -818,6 +1310,45 @@ mod tests {
             assert!(m.get(1, 1).unwrap() > 0.0);
         }
 
+        /// Depth after the perspective divide of a point `distance` in front of the eye
+        fn clip_depth(m: &Mat4, distance: f32) -> f32 {
+            let clip = m.multiply_vec4(&Vec4::new(0.3, -0.2, -distance, 1.0));
+            clip.z / clip.w
+        }
+
+        #[test]
+        fn test_perspective_reverse_z() {
+            let m = Mat4::perspective_reverse_z(1.0, 1.5, 0.5, 200.0);
+            let standard = Mat4::perspective(1.0, 1.5, 0.5, 200.0);
+            assert_eq!(m.get(0, 0), standard.get(0, 0));
+            assert_eq!(m.get(1, 1), standard.get(1, 1));
+
+            assert!((clip_depth(&m, 0.5) - 1.0).abs() < 1e-6);
+            assert!(clip_depth(&m, 200.0).abs() < 1e-6);
+            assert!(clip_depth(&m, 10.0) > clip_depth(&m, 11.0));
+        }
+
+        #[test]
+        fn test_perspective_reverse_z_infinite() {
+            let m = Mat4::perspective_reverse_z_infinite(1.0, 1.5, 0.5);
+            assert!((clip_depth(&m, 0.5) - 1.0).abs() < 1e-6);
+            assert!(clip_depth(&m, 1e6) > 0.0);
+            assert!(clip_depth(&m, 1e6) < 1e-6);
+
+            // Converges to the finite version as the far plane recedes
+            let finite = Mat4::perspective_reverse_z(1.0, 1.5, 0.5, 1e7);
+            assert!((clip_depth(&m, 50.0) - clip_depth(&finite, 50.0)).abs() < 1e-6);
+        }
+
+        #[test]
+        fn test_orthographic_reverse_z() {
+            let m = Mat4::orthographic_reverse_z(-1.0, 1.0, -1.0, 1.0, 0.5, 20.0);
+            assert!((clip_depth(&m, 0.5) - 1.0).abs() < 1e-6);
+            assert!(clip_depth(&m, 20.0).abs() < 1e-6);
+            let corner = m.transform_point(&Vec3::new(1.0, -1.0, -3.0));
+            assert!((corner.x - 1.0).abs() < 1e-6 && (corner.y + 1.0).abs() < 1e-6);
+        }
+
         #[test]
         fn test_orthographic() {
             let m = Mat4::orthographic(-1.0, 1.0, -1.0, 1.0, 0.1, 100.0);
@@ -853,6 +1384,23 @@ mod tests {
             assert_eq!(m.get(3, 3).unwrap(), 10.0);
         }
 
+        #[test]
+        fn test_transform_point_and_direction() {
+            let m = Mat4::translation(1.0, 2.0, 3.0).multiply(&Mat4::scale(2.0, 2.0, 2.0));
+            let v = Vec3::new(1.0, 0.0, -1.0);
+            assert_eq!(m.transform_point(&v), Vec3::new(3.0, 2.0, 1.0));
+            assert_eq!(m.transform_direction(&v), Vec3::new(2.0, 0.0, -2.0));
+        }
+
+        #[test]
+        fn test_transform_point_divides_by_w() {
+            let proj = Mat4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 10.0);
+            let near = proj.transform_point(&Vec3::new(0.0, 0.0, -1.0));
+            let far = proj.transform_point(&Vec3::new(0.0, 0.0, -10.0));
+            assert!((near.z + 1.0).abs() < 1e-5);
+            assert!((far.z - 1.0).abs() < 1e-5);
+        }
+
         #[test]
         fn test_get_out_of_bounds() {
             let m = Mat4::identity();
@@ -868,6 +1416,111 @@ mod tests {
             assert!(m.set(4, 0, 1.0).is_err());
             assert!(m.set(5, 5, 1.0).is_err());
         }
+
+        fn assert_mat4_near(a: &Mat4, b: &Mat4) {
+            for (ca, cb) in a.cols.iter().zip(&b.cols) {
+                assert!(
+                    (ca.x - cb.x).abs() < 1e-4
+                        && (ca.y - cb.y).abs() < 1e-4
+                        && (ca.z - cb.z).abs() < 1e-4
+                        && (ca.w - cb.w).abs() < 1e-4,
+                    "{a:?} != {b:?}"
+                );
+            }
+        }
+
+        fn sample_affine() -> Mat4 {
+            Transform::from_euler(
+                Vec3::new(3.0, -2.0, 5.0),
+                Vec3::new(0.3, 1.1, -0.7),
+                Vec3::new(2.0, 0.5, 3.0),
+            )
+            .to_matrix()
+        }
+
+        #[test]
+        fn test_transpose() {
+            let m = sample_affine();
+            let t = m.transpose();
+            assert_eq!(t.cols[0].y, m.cols[1].x);
+            assert_eq!(t.cols[3].x, m.cols[0].w);
+            assert_eq!(t.transpose(), m);
+        }
+
+        #[test]
+        fn test_determinant() {
+            assert_eq!(Mat4::identity().determinant(), 1.0);
+            assert!((Mat4::scale(2.0, 3.0, 4.0).determinant() - 24.0).abs() < 1e-5);
+            assert!((sample_affine().determinant() - 3.0).abs() < 1e-4);
+            assert!(Mat4::rotation_y(0.8).determinant() > 0.999);
+        }
+
+        #[test]
+        fn test_inverse() {
+            let m = sample_affine();
+            let inv = m.inverse().unwrap();
+            assert_mat4_near(&m.multiply(&inv), &Mat4::identity());
+            assert_mat4_near(&inv.multiply(&m), &Mat4::identity());
+
+            let p = Mat4::perspective(1.0, 1.5, 0.1, 100.0);
+            assert_mat4_near(&p.multiply(&p.inverse().unwrap()), &Mat4::identity());
+        }
+
+        #[test]
+        fn test_inverse_singular() {
+            assert!(Mat4::zero().inverse().is_none());
+            assert!(Mat4::scale(1.0, 0.0, 1.0).inverse().is_none());
+            assert!(Mat4::scale(1.0, 0.0, 1.0).inverse_affine().is_none());
+        }
+
+        #[test]
+        fn test_inverse_affine_matches_inverse() {
+            let m = sample_affine();
+            assert_mat4_near(&m.inverse_affine().unwrap(), &m.inverse().unwrap());
+        }
+
+        #[test]
+        fn test_normal_matrix_non_uniform_scale() {
+            let model = Mat4::scale(4.0, 1.0, 1.0).multiply(&Mat4::rotation_z(0.5));
+            let normal_matrix = model.normal_matrix();
+
+            // A surface with normal (1, 1, 0) contains the tangent (1, -1, 0)
+            let tangent = model.multiply_vec4(&Vec4::new(1.0, -1.0, 0.0, 0.0));
+            let normal = normal_matrix.multiply_vec4(&Vec4::new(1.0, 1.0, 0.0, 0.0));
+            assert!(tangent.dot(&normal).abs() < 1e-5);
+            assert_eq!(normal_matrix.cols[3], Vec4::new(0.0, 0.0, 0.0, 1.0));
+        }
+
+        #[test]
+        fn test_normal_matrix_rotation_is_unchanged() {
+            let rotation = Mat4::rotation_x(0.4).multiply(&Mat4::translation(1.0, 2.0, 3.0));
+            let mut expected = rotation;
+            expected.cols[3] = Vec4::new(0.0, 0.0, 0.0, 1.0);
+            assert_mat4_near(&rotation.normal_matrix(), &expected);
+        }
+
+        #[test]
+        fn test_decompose_round_trip() {
+            let m = sample_affine();
+            let (translation, rotation, scale) = m.decompose();
+            assert_eq!(translation, Vec3::new(3.0, -2.0, 5.0));
+            assert!((scale.x - 2.0).abs() < 1e-5);
+            assert!((scale.y - 0.5).abs() < 1e-5);
+            assert!((scale.z - 3.0).abs() < 1e-5);
+            assert_mat4_near(
+                &Transform::new(translation, rotation, scale).to_matrix(),
+                &m,
+            );
+            assert_mat4_near(&Transform::from_matrix(&m).to_matrix(), &m);
+        }
+
+        #[test]
+        fn test_decompose_mirrored() {
+            let m = Mat4::scale(-2.0, 1.0, 1.0);
+            let (_, rotation, scale) = m.decompose();
+            assert_eq!(scale, Vec3::new(-2.0, 1.0, 1.0));
+            assert_mat4_near(&rotation.to_mat4(), &Mat4::identity());
+        }
     }
 
     mod transform_tests {
@@ -876,7 +1529,7 @@ mod tests {
         #[test]
         fn test_new() {
             let pos = Vec3::new(1.0, 2.0, 3.0);
-            let rot = Vec3::new(0.1, 0.2, 0.3);
+            let rot = Quat::from_euler(&Vec3::new(0.1, 0.2, 0.3));
             let scale = Vec3::new(2.0, 2.0, 2.0);
             let t = Transform::new(pos, rot, scale);
             assert_eq!(t.position, pos);
@@ -888,7 +1541,7 @@ mod tests {
         fn test_identity() {
             let t = Transform::identity();
             assert_eq!(t.position, Vec3::zero());
-            assert_eq!(t.rotation, Vec3::zero());
+            assert_eq!(t.rotation, Quat::identity());
             assert_eq!(t.scale, Vec3::new(1.0, 1.0, 1.0));
         }
 
@@ -909,7 +1562,7 @@ mod tests {
         fn test_to_matrix_translation_only() {
             let t = Transform {
                 position: Vec3::new(1.0, 2.0, 3.0),
-                rotation: Vec3::zero(),
+                rotation: Quat::identity(),
                 scale: Vec3::new(1.0, 1.0, 1.0),
             };
             let m = t.to_matrix();
@@ -921,7 +1574,7 @@ mod tests {
         fn test_to_matrix_scale_only() {
             let t = Transform {
                 position: Vec3::zero(),
-                rotation: Vec3::zero(),
+                rotation: Quat::identity(),
                 scale: Vec3::new(2.0, 3.0, 4.0),
             };
             let m = t.to_matrix();
@@ -933,7 +1586,7 @@ mod tests {
         fn test_to_matrix_combined() {
             let t = Transform {
                 position: Vec3::new(1.0, 0.0, 0.0),
-                rotation: Vec3::zero(),
+                rotation: Quat::identity(),
                 scale: Vec3::new(2.0, 2.0, 2.0),
             };
             let m = t.to_matrix();
@@ -946,5 +1599,25 @@ mod tests {
             assert_eq!(result.z, 0.0);
             assert_eq!(result.w, 1.0);
         }
+
+        #[test]
+        fn test_to_matrix_euler_matches_mat4_rotations() {
+            let euler = Vec3::new(0.3, 0.6, -0.9);
+            let t =
+                Transform::from_euler(Vec3::new(1.0, 2.0, 3.0), euler, Vec3::new(2.0, 1.0, 0.5));
+            let expected = Mat4::translation(1.0, 2.0, 3.0)
+                .multiply(&Mat4::rotation_z(euler.z))
+                .multiply(&Mat4::rotation_y(euler.y))
+                .multiply(&Mat4::rotation_x(euler.x))
+                .multiply(&Mat4::scale(2.0, 1.0, 0.5));
+
+            let m = t.to_matrix();
+            for (a, b) in m.cols.iter().zip(&expected.cols) {
+                assert!((a.x - b.x).abs() < 1e-5);
+                assert!((a.y - b.y).abs() < 1e-5);
+                assert!((a.z - b.z).abs() < 1e-5);
+                assert!((a.w - b.w).abs() < 1e-5);
+            }
+        }
     }
 }
@@ -0,0 +1,304 @@
+//! Operator overloads for the math types
+//!
+//! Vectors support component-wise `+`, `-`, `*` and `/`, scaling by `f32` from either
+//! side, negation, the assign variants and indexing by component. `Mat4 * Mat4`,
+//! `Mat4 * Vec4` and `Mat4 * Vec3` (a point) follow the matching methods, as do
+//! `Quat * Quat` and `Quat * Vec3`.
+
+use crate::math::{Mat4, Quat, Vec2, Vec3, Vec4};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+macro_rules! impl_vector_ops {
+    ($type:ident { $($field:ident),+ }) => {
+        impl Add for $type {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self::new($(self.$field + rhs.$field),+)
+            }
+        }
+
+        impl Sub for $type {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self::new($(self.$field - rhs.$field),+)
+            }
+        }
+
+        impl Mul for $type {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                Self::new($(self.$field * rhs.$field),+)
+            }
+        }
+
+        impl Div for $type {
+            type Output = Self;
+
+            fn div(self, rhs: Self) -> Self {
+                Self::new($(self.$field / rhs.$field),+)
+            }
+        }
+
+        impl Mul<f32> for $type {
+            type Output = Self;
+
+            fn mul(self, rhs: f32) -> Self {
+                Self::new($(self.$field * rhs),+)
+            }
+        }
+
+        impl Mul<$type> for f32 {
+            type Output = $type;
+
+            fn mul(self, rhs: $type) -> $type {
+                rhs * self
+            }
+        }
+
+        impl Div<f32> for $type {
+            type Output = Self;
+
+            fn div(self, rhs: f32) -> Self {
+                Self::new($(self.$field / rhs),+)
+            }
+        }
+
+        impl Neg for $type {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self::new($(-self.$field),+)
+            }
+        }
+
+        impl AddAssign for $type {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $type {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl MulAssign<f32> for $type {
+            fn mul_assign(&mut self, rhs: f32) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl DivAssign<f32> for $type {
+            fn div_assign(&mut self, rhs: f32) {
+                *self = *self / rhs;
+            }
+        }
+    };
+}
+
+impl_vector_ops!(Vec2 { x, y });
+impl_vector_ops!(Vec3 { x, y, z });
+impl_vector_ops!(Vec4 { x, y, z, w });
+
+macro_rules! impl_vector_index {
+    ($type:ident { $($index:literal => $field:ident),+ }) => {
+        impl Index<usize> for $type {
+            type Output = f32;
+
+            // Out-of-range components panic, like slice indexing
+            #[allow(clippy::panic)]
+            fn index(&self, index: usize) -> &f32 {
+                match index {
+                    $($index => &self.$field,)+
+                    _ => panic!(concat!(stringify!($type), " index out of bounds: {}"), index),
+                }
+            }
+        }
+
+        impl IndexMut<usize> for $type {
+            #[allow(clippy::panic)]
+            fn index_mut(&mut self, index: usize) -> &mut f32 {
+                match index {
+                    $($index => &mut self.$field,)+
+                    _ => panic!(concat!(stringify!($type), " index out of bounds: {}"), index),
+                }
+            }
+        }
+    };
+}
+
+impl_vector_index!(Vec2 { 0 => x, 1 => y });
+impl_vector_index!(Vec3 { 0 => x, 1 => y, 2 => z });
+impl_vector_index!(Vec4 { 0 => x, 1 => y, 2 => z, 3 => w });
+
+/// Indexes columns, so `m[col][row]` addresses a single element
+impl Index<usize> for Mat4 {
+    type Output = Vec4;
+
+    fn index(&self, col: usize) -> &Vec4 {
+        &self.cols[col]
+    }
+}
+
+impl IndexMut<usize> for Mat4 {
+    fn index_mut(&mut self, col: usize) -> &mut Vec4 {
+        &mut self.cols[col]
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.multiply(&rhs)
+    }
+}
+
+impl MulAssign for Mat4 {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = self.multiply(&rhs);
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, rhs: Vec4) -> Vec4 {
+        self.multiply_vec4(&rhs)
+    }
+}
+
+/// Transforms a point; use [`Mat4::transform_direction`] for directions
+impl Mul<Vec3> for Mat4 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        self.transform_point(&rhs)
+    }
+}
+
+impl Mul for Quat {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.multiply(&rhs)
+    }
+}
+
+impl MulAssign for Quat {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = self.multiply(&rhs);
+    }
+}
+
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        self.rotate_vec3(&rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vec3_arithmetic() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, 5.0, 6.0);
+        assert_eq!(a + b, Vec3::new(5.0, 7.0, 9.0));
+        assert_eq!(b - a, Vec3::new(3.0, 3.0, 3.0));
+        assert_eq!(a * b, Vec3::new(4.0, 10.0, 18.0));
+        assert_eq!(b / a, Vec3::new(4.0, 2.5, 2.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(b / 2.0, Vec3::new(2.0, 2.5, 3.0));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+    }
+
+    #[test]
+    fn test_operators_match_methods() {
+        let a = Vec3::new(0.5, -1.5, 2.0);
+        let b = Vec3::new(3.0, 0.25, -4.0);
+        // Path syntax picks the inherent methods over the std::ops traits
+        assert_eq!(a + b, Vec3::add(&a, &b));
+        assert_eq!(a - b, Vec3::sub(&a, &b));
+        assert_eq!(a * 3.0, Vec3::scale(&a, 3.0));
+    }
+
+    #[test]
+    fn test_assign_operators() {
+        let mut v = Vec2::new(1.0, 2.0);
+        v += Vec2::new(1.0, 1.0);
+        assert_eq!(v, Vec2::new(2.0, 3.0));
+        v -= Vec2::new(0.5, 0.5);
+        assert_eq!(v, Vec2::new(1.5, 2.5));
+        v *= 2.0;
+        assert_eq!(v, Vec2::new(3.0, 5.0));
+        v /= 4.0;
+        assert_eq!(v, Vec2::new(0.75, 1.25));
+
+        let mut w = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        w += Vec4::new(1.0, 1.0, 1.0, 1.0);
+        assert_eq!(w, Vec4::new(2.0, 3.0, 4.0, 5.0));
+    }
+
+    #[test]
+    fn test_vector_index() {
+        let mut v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!((v[0], v[1], v[2]), (1.0, 2.0, 3.0));
+        v[1] = 7.0;
+        assert_eq!(v.y, 7.0);
+
+        let mut w = Vec4::zero();
+        w[3] = 1.0;
+        assert_eq!(w.w, 1.0);
+        assert_eq!(Vec2::new(5.0, 6.0)[1], 6.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vec3 index out of bounds: 3")]
+    fn test_vector_index_out_of_bounds() {
+        let v = Vec3::zero();
+        let _ = v[3];
+    }
+
+    #[test]
+    fn test_mat4_index() {
+        let mut m = Mat4::translation(1.0, 2.0, 3.0);
+        assert_eq!(m[3][1], 2.0);
+        m[0][0] = 5.0;
+        assert_eq!(m.cols[0].x, 5.0);
+    }
+
+    #[test]
+    fn test_mat4_operators() {
+        let a = Mat4::translation(1.0, 0.0, 0.0);
+        let b = Mat4::scale(2.0, 2.0, 2.0);
+        assert_eq!(a * b, a.multiply(&b));
+
+        let mut c = a;
+        c *= b;
+        assert_eq!(c, a * b);
+
+        let v = Vec4::new(1.0, 1.0, 1.0, 1.0);
+        assert_eq!(a * v, a.multiply_vec4(&v));
+        assert_eq!(a * b * Vec3::new(1.0, 1.0, 1.0), Vec3::new(3.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn test_quat_operators() {
+        let q = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 0.7);
+        let r = Quat::from_axis_angle(&Vec3::new(1.0, 0.0, 0.0), -0.2);
+        assert_eq!(q * r, q.multiply(&r));
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(q * v, q.rotate_vec3(&v));
+    }
+}
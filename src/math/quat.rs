@@ -0,0 +1,468 @@
+//! Unit quaternions for rotations
+//!
+//! [`Quat`] uses the right-handed convention: a positive angle rotates counter-clockwise
+//! when looking down the axis towards the origin. Note that [`Mat4::rotation_x`] and
+//! friends rotate the other way; [`Quat::from_euler`] follows those helpers so Euler
+//! angles mean the same thing everywhere.
+
+use crate::math::{Mat4, Vec3, Vec4};
+
+/// Rotation quaternion `xi + yj + zk + w` with 16-byte alignment
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quat {
+    #[must_use]
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    #[must_use]
+    pub const fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Rotation of `angle_rad` around `axis`; a zero axis gives the identity
+    #[must_use]
+    pub fn from_axis_angle(axis: &Vec3, angle_rad: f32) -> Self {
+        let Some(axis) = axis.try_normalize() else {
+            return Self::identity();
+        };
+        let (s, c) = (angle_rad * 0.5).sin_cos();
+        Self::new(axis.x * s, axis.y * s, axis.z * s, c)
+    }
+
+    /// Rotation equivalent to `Mat4::rotation_z(z) * Mat4::rotation_y(y) * Mat4::rotation_x(x)`
+    #[must_use]
+    pub fn from_euler(euler: &Vec3) -> Self {
+        // The Mat4 helpers rotate clockwise, hence the negated angles
+        let qx = Self::from_axis_angle(&Vec3::new(1.0, 0.0, 0.0), -euler.x);
+        let qy = Self::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), -euler.y);
+        let qz = Self::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), -euler.z);
+        qz.multiply(&qy).multiply(&qx)
+    }
+
+    /// Shortest rotation taking direction `from` onto direction `to`
+    #[must_use]
+    pub fn from_rotation_arc(from: &Vec3, to: &Vec3) -> Self {
+        let from = from.normalize();
+        let to = to.normalize();
+        let d = from.dot(&to);
+
+        if d < -0.999_999 {
+            // Opposite directions: half turn around any perpendicular axis
+            let axis = if from.x.abs() < 0.9 {
+                Vec3::new(1.0, 0.0, 0.0).cross(&from)
+            } else {
+                Vec3::new(0.0, 1.0, 0.0).cross(&from)
+            };
+            return Self::from_axis_angle(&axis, std::f32::consts::PI);
+        }
+
+        let c = from.cross(&to);
+        Self::new(c.x, c.y, c.z, 1.0 + d).normalize()
+    }
+
+    /// Orientation whose -Z axis points along `forward` and whose +Y axis is as close to
+    /// `up` as possible, matching the camera convention
+    #[must_use]
+    pub fn look_rotation(forward: &Vec3, up: &Vec3) -> Self {
+        let back = forward.normalize().scale(-1.0);
+        let mut right = up.cross(&back);
+        if right.length() < 1e-6 {
+            // up is parallel to forward; pick any perpendicular right vector
+            let fallback = if back.y.abs() < 0.9 {
+                Vec3::new(0.0, 1.0, 0.0)
+            } else {
+                Vec3::new(1.0, 0.0, 0.0)
+            };
+            right = fallback.cross(&back);
+        }
+        let right = right.normalize();
+        let up = back.cross(&right);
+
+        Self::from_basis(&right, &up, &back)
+    }
+
+    /// Rotation part of `matrix`; any scale in the upper 3x3 is removed first
+    #[must_use]
+    pub fn from_mat4(matrix: &Mat4) -> Self {
+        let axis = |v: &Vec4| Vec3::new(v.x, v.y, v.z).normalize();
+        Self::from_basis(
+            &axis(&matrix.cols[0]),
+            &axis(&matrix.cols[1]),
+            &axis(&matrix.cols[2]),
+        )
+    }
+
+    /// Quaternion for the orthonormal basis with the given images of X, Y and Z
+    fn from_basis(x_axis: &Vec3, y_axis: &Vec3, z_axis: &Vec3) -> Self {
+        let (m00, m11, m22) = (x_axis.x, y_axis.y, z_axis.z);
+        let trace = m00 + m11 + m22;
+
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new(
+                (y_axis.z - z_axis.y) / s,
+                (z_axis.x - x_axis.z) / s,
+                (x_axis.y - y_axis.x) / s,
+                0.25 * s,
+            )
+        } else if m00 > m11 && m00 > m22 {
+            let s = (1.0 + m00 - m11 - m22).sqrt() * 2.0;
+            Self::new(
+                0.25 * s,
+                (y_axis.x + x_axis.y) / s,
+                (z_axis.x + x_axis.z) / s,
+                (y_axis.z - z_axis.y) / s,
+            )
+        } else if m11 > m22 {
+            let s = (1.0 + m11 - m00 - m22).sqrt() * 2.0;
+            Self::new(
+                (y_axis.x + x_axis.y) / s,
+                0.25 * s,
+                (z_axis.y + y_axis.z) / s,
+                (z_axis.x - x_axis.z) / s,
+            )
+        } else {
+            let s = (1.0 + m22 - m00 - m11).sqrt() * 2.0;
+            Self::new(
+                (z_axis.x + x_axis.z) / s,
+                (z_axis.y + y_axis.z) / s,
+                0.25 * s,
+                (x_axis.y - y_axis.x) / s,
+            )
+        };
+        q.normalize()
+    }
+
+    /// Axis and angle in radians; the identity returns the X axis and zero
+    #[must_use]
+    pub fn to_axis_angle(&self) -> (Vec3, f32) {
+        let q = if self.w < 0.0 {
+            self.scale(-1.0)
+        } else {
+            *self
+        };
+        let sin_half = Vec3::new(q.x, q.y, q.z).length();
+        if sin_half < 1e-6 {
+            return (Vec3::new(1.0, 0.0, 0.0), 0.0);
+        }
+        let axis = Vec3::new(q.x / sin_half, q.y / sin_half, q.z / sin_half);
+        (axis, 2.0 * sin_half.atan2(q.w))
+    }
+
+    /// Rotation matrix with no translation
+    #[must_use]
+    pub fn to_mat4(&self) -> Mat4 {
+        let Self { x, y, z, w } = *self;
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+
+        Mat4::new(
+            Vec4::new(1.0 - 2.0 * (yy + zz), 2.0 * (xy + wz), 2.0 * (xz - wy), 0.0),
+            Vec4::new(2.0 * (xy - wz), 1.0 - 2.0 * (xx + zz), 2.0 * (yz + wx), 0.0),
+            Vec4::new(2.0 * (xz + wy), 2.0 * (yz - wx), 1.0 - 2.0 * (xx + yy), 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Hamilton product: the result applies `other` first, then `self`
+    #[must_use]
+    pub fn multiply(&self, other: &Self) -> Self {
+        Self::new(
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        )
+    }
+
+    #[must_use]
+    pub fn rotate_vec3(&self, v: &Vec3) -> Vec3 {
+        // v' = v + 2w(q x v) + 2q x (q x v)
+        let q = Vec3::new(self.x, self.y, self.z);
+        let t = q.cross(v).scale(2.0);
+        v.add(&t.scale(self.w)).add(&q.cross(&t))
+    }
+
+    #[must_use]
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    #[must_use]
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Unit quaternion; a zero quaternion gives the identity
+    #[must_use]
+    pub fn normalize(&self) -> Self {
+        let len = self.length();
+        if len > 0.0 {
+            self.scale(1.0 / len)
+        } else {
+            Self::identity()
+        }
+    }
+
+    #[must_use]
+    pub fn conjugate(&self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// Inverse rotation; equal to the conjugate for unit quaternions
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        let len_sq = self.dot(self);
+        if len_sq > 0.0 {
+            self.conjugate().scale(1.0 / len_sq)
+        } else {
+            Self::identity()
+        }
+    }
+
+    /// Normalized linear interpolation along the shortest path
+    #[must_use]
+    pub fn nlerp(&self, other: &Self, t: f32) -> Self {
+        let other = if self.dot(other) < 0.0 {
+            other.scale(-1.0)
+        } else {
+            *other
+        };
+        Self::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+            self.z + (other.z - self.z) * t,
+            self.w + (other.w - self.w) * t,
+        )
+        .normalize()
+    }
+
+    /// Spherical linear interpolation along the shortest path at constant angular speed
+    #[must_use]
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        let mut cos_theta = self.dot(other);
+        let other = if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            other.scale(-1.0)
+        } else {
+            *other
+        };
+
+        // Nearly parallel: fall back to nlerp to avoid dividing by sin(theta) ~ 0
+        if cos_theta > 0.9995 {
+            return self.nlerp(&other, t);
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+        Self::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+    }
+
+    fn scale(&self, s: f32) -> Self {
+        Self::new(self.x * s, self.y * s, self.z * s, self.w * s)
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_vec3_near(a: &Vec3, b: &Vec3) {
+        assert!(
+            (a.x - b.x).abs() < EPSILON
+                && (a.y - b.y).abs() < EPSILON
+                && (a.z - b.z).abs() < EPSILON,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    fn assert_mat4_near(a: &Mat4, b: &Mat4) {
+        for (ca, cb) in a.cols.iter().zip(&b.cols) {
+            assert!(
+                (ca.x - cb.x).abs() < EPSILON
+                    && (ca.y - cb.y).abs() < EPSILON
+                    && (ca.z - cb.z).abs() < EPSILON
+                    && (ca.w - cb.w).abs() < EPSILON,
+                "{a:?} != {b:?}"
+            );
+        }
+    }
+
+    fn assert_same_rotation(a: &Quat, b: &Quat) {
+        // q and -q are the same rotation
+        assert!((a.dot(b).abs() - 1.0).abs() < EPSILON, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_identity() {
+        let q = Quat::identity();
+        assert_eq!(q, Quat::default());
+        assert_eq!(q.to_mat4(), Mat4::identity());
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(q.rotate_vec3(&v), v);
+    }
+
+    #[test]
+    fn test_axis_angle_rotates_counter_clockwise() {
+        let q = Quat::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2);
+        let rotated = q.rotate_vec3(&Vec3::new(1.0, 0.0, 0.0));
+        assert_vec3_near(&rotated, &Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_zero_axis_gives_identity() {
+        let q = Quat::from_axis_angle(&Vec3::zero(), 1.2);
+        assert_eq!(q, Quat::identity());
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(q.rotate_vec3(&v), v);
+    }
+
+    #[test]
+    fn test_axis_angle_round_trip() {
+        let axis = Vec3::new(1.0, 2.0, -0.5).normalize();
+        let q = Quat::from_axis_angle(&axis, 1.2);
+        let (out_axis, angle) = q.to_axis_angle();
+        assert_vec3_near(&out_axis, &axis);
+        assert!((angle - 1.2).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_to_mat4_matches_rotate_vec3() {
+        let q = Quat::from_axis_angle(&Vec3::new(0.3, -1.0, 0.4), 2.1);
+        let v = Vec3::new(0.5, -2.0, 1.5);
+        let m = q.to_mat4().multiply_vec4(&Vec4::from(v));
+        assert_vec3_near(&Vec3::new(m.x, m.y, m.z), &q.rotate_vec3(&v));
+    }
+
+    #[test]
+    fn test_mat4_round_trip() {
+        for (axis, angle) in [
+            (Vec3::new(1.0, 0.0, 0.0), 0.7),
+            (Vec3::new(0.0, 1.0, 0.0), PI),
+            (Vec3::new(0.2, 0.3, -1.0), 2.9),
+            (Vec3::new(-1.0, 1.0, 1.0), -1.3),
+        ] {
+            let q = Quat::from_axis_angle(&axis, angle);
+            assert_same_rotation(&Quat::from_mat4(&q.to_mat4()), &q);
+        }
+    }
+
+    #[test]
+    fn test_from_mat4_ignores_scale() {
+        let q = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 0.9);
+        let m = q.to_mat4().multiply(&Mat4::scale(2.0, 3.0, 0.5));
+        assert_same_rotation(&Quat::from_mat4(&m), &q);
+    }
+
+    #[test]
+    fn test_from_euler_matches_mat4_rotations() {
+        let euler = Vec3::new(0.4, -1.1, 2.3);
+        let expected = Mat4::rotation_z(euler.z)
+            .multiply(&Mat4::rotation_y(euler.y))
+            .multiply(&Mat4::rotation_x(euler.x));
+        assert_mat4_near(&Quat::from_euler(&euler).to_mat4(), &expected);
+    }
+
+    #[test]
+    fn test_rotation_arc() {
+        let from = Vec3::new(0.0, 1.0, 0.0);
+        for to in [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.3, -0.2, 0.9).normalize(),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+        ] {
+            let q = Quat::from_rotation_arc(&from, &to);
+            assert_vec3_near(&q.rotate_vec3(&from), &to);
+        }
+    }
+
+    #[test]
+    fn test_look_rotation() {
+        let forward = Vec3::new(1.0, 0.0, -1.0).normalize();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let q = Quat::look_rotation(&forward, &up);
+        assert_vec3_near(&q.rotate_vec3(&Vec3::new(0.0, 0.0, -1.0)), &forward);
+        assert_vec3_near(&q.rotate_vec3(&up), &up);
+
+        // Degenerate up still yields a valid orientation
+        let q = Quat::look_rotation(&up, &up);
+        assert_vec3_near(&q.rotate_vec3(&Vec3::new(0.0, 0.0, -1.0)), &up);
+    }
+
+    #[test]
+    fn test_multiply_composes_rotations() {
+        let a = Quat::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2);
+        let b = Quat::from_axis_angle(&Vec3::new(1.0, 0.0, 0.0), FRAC_PI_2);
+        let v = Vec3::new(0.0, 1.0, 0.0);
+        // b first (Y -> Z), then a (Z stays Z)
+        assert_vec3_near(
+            &a.multiply(&b).rotate_vec3(&v),
+            &a.rotate_vec3(&b.rotate_vec3(&v)),
+        );
+        assert_vec3_near(&a.multiply(&b).rotate_vec3(&v), &Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_inverse() {
+        let q = Quat::from_axis_angle(&Vec3::new(1.0, 1.0, 0.0), 0.8);
+        assert_same_rotation(&q.multiply(&q.inverse()), &Quat::identity());
+        assert_eq!(q.conjugate().w, q.w);
+    }
+
+    #[test]
+    fn test_slerp() {
+        let a = Quat::identity();
+        let b = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), FRAC_PI_2);
+        assert_same_rotation(&a.slerp(&b, 0.0), &a);
+        assert_same_rotation(&a.slerp(&b, 1.0), &b);
+
+        let (axis, angle) = a.slerp(&b, 0.25).to_axis_angle();
+        assert_vec3_near(&axis, &Vec3::new(0.0, 1.0, 0.0));
+        assert!((angle - FRAC_PI_2 * 0.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_slerp_takes_shortest_path() {
+        let a = Quat::identity();
+        let b = Quat::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), 0.5);
+        let negated = Quat::new(-b.x, -b.y, -b.z, -b.w);
+        let (_, angle) = a.slerp(&negated, 0.5).to_axis_angle();
+        assert!((angle - 0.25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_nlerp_is_normalized() {
+        let a = Quat::from_axis_angle(&Vec3::new(1.0, 0.0, 0.0), 0.2);
+        let b = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 2.0);
+        let q = a.nlerp(&b, 0.4);
+        assert!((q.length() - 1.0).abs() < EPSILON);
+        assert_same_rotation(&a.nlerp(&b, 1.0), &b);
+    }
+}
@@ -0,0 +1,389 @@
+//! Coordinates on the spherical world
+//!
+//! The planet is centered at the origin with +Y through the north pole. Latitude is
+//! measured from the equator towards +Y and longitude from +X towards +Z, so a point
+//! is `(cos lat cos lon, sin lat, cos lat sin lon) * radius`. Angles are in radians.
+
+use crate::math::{Mat4, Vec2, Vec3, Vec4};
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// Latitude and longitude in radians
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLon {
+    pub lat: f32,
+    pub lon: f32,
+}
+
+impl LatLon {
+    #[must_use]
+    pub const fn new(lat: f32, lon: f32) -> Self {
+        Self { lat, lon }
+    }
+
+    #[must_use]
+    pub fn from_degrees(lat: f32, lon: f32) -> Self {
+        Self::new(lat.to_radians(), lon.to_radians())
+    }
+
+    /// Latitude and longitude of the direction from the planet center to `position`.
+    /// The origin maps to (0, 0).
+    #[must_use]
+    pub fn from_cartesian(position: &Vec3) -> Self {
+        let Some(dir) = position.try_normalize() else {
+            return Self::new(0.0, 0.0);
+        };
+        Self::new(dir.y.clamp(-1.0, 1.0).asin(), dir.z.atan2(dir.x))
+    }
+
+    /// Maps two uniform samples in `[0, 1)` to a point uniformly distributed over the
+    /// sphere's surface
+    #[must_use]
+    pub fn from_uniform(u: f32, v: f32) -> Self {
+        Self::new((2.0 * v - 1.0).clamp(-1.0, 1.0).asin(), TAU * u - PI)
+    }
+
+    #[must_use]
+    pub fn to_unit_vector(&self) -> Vec3 {
+        let (sin_lat, cos_lat) = self.lat.sin_cos();
+        let (sin_lon, cos_lon) = self.lon.sin_cos();
+        Vec3::new(cos_lat * cos_lon, sin_lat, cos_lat * sin_lon)
+    }
+
+    #[must_use]
+    pub fn to_cartesian(&self, radius: f32) -> Vec3 {
+        self.to_unit_vector() * radius
+    }
+
+    /// Equirectangular texture coordinates, with u increasing with longitude from
+    /// -π and v from 0 at the south pole to 1 at the north pole
+    #[must_use]
+    pub fn to_uv(&self) -> Vec2 {
+        Vec2::new((self.lon + PI) / TAU, (self.lat + FRAC_PI_2) / PI)
+    }
+
+    /// Same point with latitude clamped to the poles and longitude wrapped into `[-π, π)`
+    #[must_use]
+    pub fn normalized(&self) -> Self {
+        Self::new(
+            self.lat.clamp(-FRAC_PI_2, FRAC_PI_2),
+            (self.lon + PI).rem_euclid(TAU) - PI,
+        )
+    }
+
+    #[must_use]
+    pub fn to_degrees(&self) -> (f32, f32) {
+        (self.lat.to_degrees(), self.lon.to_degrees())
+    }
+
+    /// Central angle to `other` in radians
+    #[must_use]
+    pub fn angle_to(&self, other: &Self) -> f32 {
+        central_angle(&self.to_unit_vector(), &other.to_unit_vector())
+    }
+
+    /// Great-circle distance to `other` on a sphere of `radius`
+    #[must_use]
+    pub fn distance_to(&self, other: &Self, radius: f32) -> f32 {
+        self.angle_to(other) * radius
+    }
+
+    /// Initial bearing towards `other`, clockwise from north towards east in `[0, 2π)`
+    #[must_use]
+    pub fn bearing_to(&self, other: &Self) -> f32 {
+        let delta_lon = other.lon - self.lon;
+        let y = delta_lon.sin() * other.lat.cos();
+        let x =
+            self.lat.cos() * other.lat.sin() - self.lat.sin() * other.lat.cos() * delta_lon.cos();
+        y.atan2(x).rem_euclid(TAU)
+    }
+
+    /// Point a fraction `t` of the way along the great circle to `other`
+    #[must_use]
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        Self::from_cartesian(&slerp(&self.to_unit_vector(), &other.to_unit_vector(), t))
+    }
+}
+
+/// Orthonormal basis on the sphere's surface: `east` and `north` span the tangent
+/// plane and `up` points away from the center
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TangentFrame {
+    pub east: Vec3,
+    pub up: Vec3,
+    pub north: Vec3,
+}
+
+impl TangentFrame {
+    /// Frame whose `up` is the normalized `up`. At the poles, where east is undefined,
+    /// the frame for longitude 0 is used so nearby frames stay continuous.
+    #[must_use]
+    pub fn from_up(up: &Vec3) -> Self {
+        let up = up.try_normalize().unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+        let east = up
+            .cross(&Vec3::new(0.0, 1.0, 0.0))
+            .try_normalize()
+            .filter(|_| up.y.abs() < 0.9999)
+            .unwrap_or(Vec3::new(0.0, 0.0, 1.0));
+        let north = east.cross(&up);
+        Self { east, up, north }
+    }
+
+    /// Frame at `position` on a sphere centered at the origin
+    #[must_use]
+    pub fn at(position: &Vec3) -> Self {
+        Self::from_up(position)
+    }
+
+    /// Frame turned by `yaw` radians around `up`, taking `north` towards `east`
+    #[must_use]
+    pub fn rotated(&self, yaw: f32) -> Self {
+        let (s, c) = yaw.sin_cos();
+        Self {
+            east: self.east * c - self.north * s,
+            up: self.up,
+            north: self.east * s + self.north * c,
+        }
+    }
+
+    /// Rotation matrix taking local X, Y and Z onto east, up and north
+    #[must_use]
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::new(
+            Vec4::new(self.east.x, self.east.y, self.east.z, 0.0),
+            Vec4::new(self.up.x, self.up.y, self.up.z, 0.0),
+            Vec4::new(self.north.x, self.north.y, self.north.z, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+}
+
+/// Model matrix for an object standing on the surface at `position`: local +Y is
+/// aligned with `up`, turned by `yaw` around it and uniformly scaled
+#[must_use]
+pub fn instance_matrix(position: &Vec3, up: &Vec3, yaw: f32, scale: f32) -> Mat4 {
+    let frame = TangentFrame::from_up(up).rotated(yaw);
+    let column = |axis: Vec3| Vec4::new(axis.x * scale, axis.y * scale, axis.z * scale, 0.0);
+    Mat4::new(
+        column(frame.east),
+        column(frame.up),
+        column(frame.north),
+        Vec4::new(position.x, position.y, position.z, 1.0),
+    )
+}
+
+/// Angle in radians between two directions, accurate for nearly equal and nearly
+/// opposite vectors
+#[must_use]
+pub fn central_angle(a: &Vec3, b: &Vec3) -> f32 {
+    a.cross(b).length().atan2(a.dot(b))
+}
+
+/// Great-circle distance between the surface points above `a` and `b`
+#[must_use]
+pub fn great_circle_distance(a: &Vec3, b: &Vec3, radius: f32) -> f32 {
+    central_angle(a, b) * radius
+}
+
+/// Spherical interpolation between two directions, returning a unit vector. Falls
+/// back to a normalized lerp when they are nearly parallel, and turns about an
+/// arbitrary perpendicular axis when they are nearly opposite.
+#[must_use]
+pub fn slerp(a: &Vec3, b: &Vec3, t: f32) -> Vec3 {
+    let a = a.normalize();
+    let b = b.normalize();
+    let angle = central_angle(&a, &b);
+    let sin_angle = angle.sin();
+    if sin_angle.abs() < 1e-3 {
+        if angle < FRAC_PI_2 {
+            return a.lerp(&b, t).normalize();
+        }
+        // No unique great circle joins opposite points; pick one through `a`
+        let (sin_t, cos_t) = (t * PI).sin_cos();
+        return a * cos_t + perpendicular(&a) * sin_t;
+    }
+    (a * ((1.0 - t) * angle).sin() + b * (t * angle).sin()) / sin_angle
+}
+
+/// Unit vector perpendicular to the unit vector `v`
+fn perpendicular(v: &Vec3) -> Vec3 {
+    let axis = if v.x.abs() < 0.9 {
+        Vec3::new(1.0, 0.0, 0.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    };
+    v.cross(&axis).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    fn assert_vec_close(a: &Vec3, b: &Vec3) {
+        assert!(a.distance(b) < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_cartesian_round_trip() {
+        let coords = LatLon::from_degrees(35.0, -120.0);
+        let position = coords.to_cartesian(50.0);
+        assert_close(position.length(), 50.0);
+
+        let back = LatLon::from_cartesian(&position);
+        assert_close(back.lat, coords.lat);
+        assert_close(back.lon, coords.lon);
+    }
+
+    #[test]
+    fn test_axes() {
+        assert_vec_close(
+            &LatLon::new(0.0, 0.0).to_unit_vector(),
+            &Vec3::new(1.0, 0.0, 0.0),
+        );
+        assert_vec_close(
+            &LatLon::new(0.0, FRAC_PI_2).to_unit_vector(),
+            &Vec3::new(0.0, 0.0, 1.0),
+        );
+        assert_vec_close(
+            &LatLon::new(FRAC_PI_2, 0.0).to_unit_vector(),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(LatLon::from_cartesian(&Vec3::zero()), LatLon::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_uv() {
+        let equator = LatLon::new(0.0, 0.0).to_uv();
+        assert_close(equator.x, 0.5);
+        assert_close(equator.y, 0.5);
+        assert_close(LatLon::new(FRAC_PI_2, 0.0).to_uv().y, 1.0);
+        assert_close(LatLon::new(-FRAC_PI_2, -PI).to_uv().x, 0.0);
+    }
+
+    #[test]
+    fn test_normalized_wraps_longitude() {
+        let coords = LatLon::new(2.0, 3.0 * PI).normalized();
+        assert_close(coords.lat, FRAC_PI_2);
+        assert_close(coords.lon, -PI);
+    }
+
+    #[test]
+    fn test_from_uniform_covers_sphere() {
+        assert_close(LatLon::from_uniform(0.0, 0.0).lat, -FRAC_PI_2);
+        assert_close(LatLon::from_uniform(0.5, 0.5).lat, 0.0);
+        assert_close(LatLon::from_uniform(0.5, 0.5).lon, 0.0);
+        assert_close(LatLon::from_uniform(0.0, 1.0).lat, FRAC_PI_2);
+    }
+
+    #[test]
+    fn test_great_circle_distance() {
+        let a = LatLon::new(0.0, 0.0);
+        let b = LatLon::new(0.0, FRAC_PI_2);
+        assert_close(a.distance_to(&b, 10.0), 5.0 * PI);
+        assert_close(
+            great_circle_distance(&Vec3::new(0.0, 1.0, 0.0), &Vec3::new(0.0, -2.0, 0.0), 1.0),
+            PI,
+        );
+        assert_close(a.angle_to(&a), 0.0);
+    }
+
+    #[test]
+    fn test_bearing() {
+        let origin = LatLon::new(0.0, 0.0);
+        assert_close(origin.bearing_to(&LatLon::new(0.5, 0.0)), 0.0);
+        assert_close(origin.bearing_to(&LatLon::new(0.0, 0.5)), FRAC_PI_2);
+        assert_close(origin.bearing_to(&LatLon::new(-0.5, 0.0)), PI);
+        assert_close(origin.bearing_to(&LatLon::new(0.0, -0.5)), 3.0 * FRAC_PI_2);
+    }
+
+    #[test]
+    fn test_slerp() {
+        let a = Vec3::new(1.0, 0.0, 0.0);
+        let b = Vec3::new(0.0, 0.0, 3.0);
+        let mid = slerp(&a, &b, 0.5);
+        assert_vec_close(&mid, &Vec3::new(FRAC_PI_4.cos(), 0.0, FRAC_PI_4.sin()));
+        assert_vec_close(&slerp(&a, &b, 0.0), &a);
+        assert_vec_close(&slerp(&a, &b, 1.0), &Vec3::new(0.0, 0.0, 1.0));
+        assert_vec_close(&slerp(&a, &a, 0.3), &a);
+
+        let start = LatLon::new(0.0, 0.0);
+        let end = LatLon::new(0.0, 1.0);
+        assert_close(start.slerp(&end, 0.25).lon, 0.25);
+    }
+
+    #[test]
+    fn test_slerp_between_opposite_directions() {
+        let a = Vec3::new(0.0, 1.0, 0.0);
+        let b = Vec3::new(0.0, -1.0, 0.0);
+        assert_vec_close(&slerp(&a, &b, 0.0), &a);
+        assert_vec_close(&slerp(&a, &b, 1.0), &b);
+        for t in [0.25, 0.5, 0.75] {
+            let point = slerp(&a, &b, t);
+            assert_close(point.length(), 1.0);
+            assert_close(central_angle(&a, &point), t * PI);
+        }
+    }
+
+    #[test]
+    fn test_tangent_frame_is_orthonormal() {
+        for up in [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.3, -0.8, 0.5),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(1e-6, 1.0, 0.0),
+        ] {
+            let frame = TangentFrame::from_up(&up);
+            assert_vec_close(&frame.up, &up.normalize());
+            assert_close(frame.east.length(), 1.0);
+            assert_close(frame.north.length(), 1.0);
+            assert_close(frame.east.dot(&frame.up), 0.0);
+            assert_close(frame.north.dot(&frame.up), 0.0);
+            assert_vec_close(&frame.east.cross(&frame.up), &frame.north);
+        }
+    }
+
+    #[test]
+    fn test_tangent_frame_directions() {
+        let coords = LatLon::new(0.3, 1.2);
+        let frame = TangentFrame::at(&coords.to_cartesian(20.0));
+        let step = 1e-3;
+        let east =
+            LatLon::new(coords.lat, coords.lon + step).to_unit_vector() - coords.to_unit_vector();
+        let north =
+            LatLon::new(coords.lat + step, coords.lon).to_unit_vector() - coords.to_unit_vector();
+        assert!(east.normalize().dot(&frame.east) > 0.999);
+        assert!(north.normalize().dot(&frame.north) > 0.999);
+    }
+
+    #[test]
+    fn test_frame_rotation() {
+        let frame = TangentFrame::from_up(&Vec3::new(1.0, 0.0, 0.0));
+        let turned = frame.rotated(FRAC_PI_2);
+        assert_vec_close(&turned.east, &(-frame.north));
+        assert_vec_close(&turned.north, &frame.east);
+        assert_eq!(turned.up, frame.up);
+    }
+
+    #[test]
+    fn test_instance_matrix() {
+        let position = Vec3::new(0.0, 0.0, 10.0);
+        let up = position.normalize();
+        let m = instance_matrix(&position, &up, 0.7, 2.0);
+
+        assert_vec_close(&m.transform_point(&Vec3::zero()), &position);
+        assert_vec_close(
+            &m.transform_direction(&Vec3::new(0.0, 1.0, 0.0)),
+            &(up * 2.0),
+        );
+        assert_close(
+            m.transform_direction(&Vec3::new(1.0, 0.0, 0.0)).length(),
+            2.0,
+        );
+        assert_close(m.determinant(), 8.0);
+    }
+}
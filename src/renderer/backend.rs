@@ -0,0 +1,431 @@
+//! Backend-agnostic rendering interface
+//!
+//! [`RenderBackend`] is the narrow set of GPU operations the [`Renderer`](super::Renderer)
+//! needs: creating buffers, textures and pipelines, writing uniform blocks and issuing
+//! indexed (optionally instanced) draws. Resources are referred to by opaque handles so
+//! that scene traversal, LOD selection and uniform construction stay independent of the
+//! graphics API.
+
+use crate::core::TextureFormat;
+use crate::math::Vec4;
+use crate::renderer::UniformBlock;
+use crate::scene::{DepthMapping, IndexFormat, InstanceData, Vertex};
+use image::RgbaImage;
+
+/// Handle to a buffer owned by a [`RenderBackend`]
+///
+/// Handles carry the generation of their slot, so one kept after
+/// [`RenderBackend::destroy_buffer`] stops resolving instead of aliasing the next
+/// buffer stored in that slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferHandle {
+    index: u32,
+    generation: u32,
+}
+
+/// Handle to a texture owned by a [`RenderBackend`], generational like
+/// [`BufferHandle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+    index: u32,
+    generation: u32,
+}
+
+/// Handle to a render pipeline owned by a [`RenderBackend`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineHandle {
+    index: u32,
+    generation: u32,
+}
+
+macro_rules! impl_handle {
+    ($($handle:ident),*) => {
+        $(
+            impl $handle {
+                /// Creates a first-generation handle from a backend-specific resource
+                /// index
+                #[must_use]
+                pub const fn from_index(index: u32) -> Self {
+                    Self::from_parts(index, 0)
+                }
+
+                /// Creates a handle from a resource index and the generation of its slot
+                #[must_use]
+                pub const fn from_parts(index: u32, generation: u32) -> Self {
+                    Self { index, generation }
+                }
+
+                /// Returns the backend-specific resource index
+                #[must_use]
+                pub const fn index(self) -> u32 {
+                    self.index
+                }
+
+                /// How many resources used this handle's slot before it
+                #[must_use]
+                pub const fn generation(self) -> u32 {
+                    self.generation
+                }
+            }
+
+            impl SlotHandle for $handle {
+                fn from_parts(index: u32, generation: u32) -> Self {
+                    Self::from_parts(index, generation)
+                }
+
+                fn parts(self) -> (u32, u32) {
+                    (self.index, self.generation)
+                }
+            }
+        )*
+    };
+}
+
+/// Handles that [`ResourceSlots`] hands out and resolves
+pub(crate) trait SlotHandle: Copy {
+    fn from_parts(index: u32, generation: u32) -> Self;
+    fn parts(self) -> (u32, u32);
+}
+
+impl_handle!(BufferHandle, TextureHandle, PipelineHandle);
+
+/// Initial contents of a buffer
+#[derive(Debug, Clone, Copy)]
+pub enum BufferData<'a> {
+    Vertices(&'a [Vertex]),
+    /// Indices drawn with [`IndexFormat::U16`]
+    Indices16(&'a [u16]),
+    /// Indices drawn with [`IndexFormat::U32`]
+    Indices32(&'a [u32]),
+    Instances(&'a [InstanceData]),
+    /// Storage of the given size in bytes, written later through
+    /// [`RenderBackend::update_buffer`] or [`RenderBackend::set_uniforms`]
+    Empty(usize),
+}
+
+impl BufferData<'_> {
+    /// Size of the buffer in bytes
+    #[must_use]
+    pub fn byte_len(&self) -> usize {
+        match self {
+            Self::Vertices(data) => std::mem::size_of_val(*data),
+            Self::Indices16(data) => std::mem::size_of_val(*data),
+            Self::Indices32(data) => std::mem::size_of_val(*data),
+            Self::Instances(data) => std::mem::size_of_val(*data),
+            Self::Empty(bytes) => *bytes,
+        }
+    }
+}
+
+/// Size and layout of a texture or texture array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureDescriptor {
+    pub width: u32,
+    pub height: u32,
+    /// Number of array slices; `1` creates a plain 2D texture
+    pub layers: u32,
+    pub format: TextureFormat,
+}
+
+impl TextureDescriptor {
+    /// Number of bytes expected by [`RenderBackend::create_texture`]
+    #[must_use]
+    pub fn byte_len(&self) -> usize {
+        self.width as usize
+            * self.height as usize
+            * self.layers as usize
+            * self.format.bytes_per_pixel()
+    }
+}
+
+/// Shader programs known to every backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderProgram {
+    /// Phong-lit scene geometry (`cube.metal`)
+    Scene,
+    Skybox,
+    /// Instanced grass blades with LOD billboards
+    Grass,
+    Road,
+    /// Instanced trees with wind animation
+    Tree,
+    /// Screen-space UI overlay using `UIVertex`
+    Ui,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Opaque,
+    /// Source-alpha over blending
+    Alpha,
+}
+
+/// Depth test of a pipeline; the comparisons flip to `Greater` and `GreaterEqual`
+/// in frames begun with a reversed [`DepthMapping`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthMode {
+    /// Depth test with `Less` and write depth
+    ReadWrite,
+    /// Depth test with `LessEqual` without writing depth
+    ReadOnly,
+    /// Always pass and never write depth
+    Disabled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineDescriptor {
+    pub program: ShaderProgram,
+    pub blend: BlendMode,
+    pub depth: DepthMode,
+}
+
+impl PipelineDescriptor {
+    /// Returns the blend and depth configuration the engine uses for `program`
+    #[must_use]
+    pub const fn for_program(program: ShaderProgram) -> Self {
+        let (blend, depth) = match program {
+            ShaderProgram::Scene | ShaderProgram::Road | ShaderProgram::Tree => {
+                (BlendMode::Opaque, DepthMode::ReadWrite)
+            }
+            ShaderProgram::Skybox => (BlendMode::Opaque, DepthMode::ReadOnly),
+            ShaderProgram::Grass => (BlendMode::Alpha, DepthMode::ReadWrite),
+            ShaderProgram::Ui => (BlendMode::Alpha, DepthMode::Disabled),
+        };
+        Self {
+            program,
+            blend,
+            depth,
+        }
+    }
+}
+
+/// A single indexed draw with the engine's fixed binding layout
+///
+/// Vertices are bound at vertex buffer 0, uniforms at vertex and fragment buffer 1,
+/// per-instance data at vertex buffer 2 and the texture at fragment texture 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawCall {
+    pub vertex_buffer: BufferHandle,
+    pub index_buffer: BufferHandle,
+    /// Format the index buffer was created with
+    pub index_format: IndexFormat,
+    pub index_count: usize,
+    pub uniforms: BufferHandle,
+    pub instances: Option<BufferHandle>,
+    /// Number of instances drawn; `1` for non-instanced draws
+    pub instance_count: usize,
+    pub texture: Option<TextureHandle>,
+}
+
+/// GPU operations required to render a frame
+pub trait RenderBackend {
+    /// Creates a buffer initialized with `data`
+    ///
+    /// # Errors
+    /// Returns an error if the backend cannot allocate the buffer
+    fn create_buffer(&mut self, data: BufferData<'_>) -> Result<BufferHandle, String>;
+
+    /// Overwrites the start of an existing buffer with `data`; `Empty` writes nothing
+    ///
+    /// # Errors
+    /// Returns an error if the handle is unknown or `data` does not fit
+    fn update_buffer(&mut self, buffer: BufferHandle, data: BufferData<'_>) -> Result<(), String>;
+
+    /// Creates a texture (or texture array when `layers > 1`) from tightly packed pixels
+    ///
+    /// # Errors
+    /// Returns an error if the data size does not match the descriptor or allocation fails
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureHandle, String>;
+
+    /// Releases a buffer; the handle must not be used again. Unknown handles are ignored.
+    fn destroy_buffer(&mut self, buffer: BufferHandle);
+
+    /// Releases a texture; the handle must not be used again. Unknown handles are ignored.
+    fn destroy_texture(&mut self, texture: TextureHandle);
+
+    /// Creates a render pipeline
+    ///
+    /// # Errors
+    /// Returns an error if shader compilation or pipeline creation fails
+    fn create_pipeline(
+        &mut self,
+        descriptor: &PipelineDescriptor,
+    ) -> Result<PipelineHandle, String>;
+
+    /// Resizes the render target
+    fn resize(&mut self, width: u32, height: u32);
+
+    /// Starts a frame, clearing color to `clear_color` and depth to
+    /// [`DepthMapping::clear_depth`]; depth tests in the frame follow `depth`
+    ///
+    /// # Errors
+    /// Returns an error if no render target is available
+    fn begin_frame(&mut self, clear_color: Vec4, depth: DepthMapping) -> Result<(), String>;
+
+    fn set_pipeline(&mut self, pipeline: PipelineHandle);
+
+    /// Writes a uniform block into `buffer`
+    fn set_uniforms(&mut self, buffer: BufferHandle, block: &UniformBlock);
+
+    fn draw(&mut self, call: &DrawCall);
+
+    /// Submits the frame
+    ///
+    /// # Errors
+    /// Returns an error if the frame was never started
+    fn end_frame(&mut self) -> Result<(), String>;
+
+    /// Asks the backend to keep a CPU copy of the next frame for
+    /// [`capture_frame`](Self::capture_frame); offscreen backends always keep one
+    fn request_capture(&mut self) {}
+
+    /// Returns the color target of the most recently captured frame
+    ///
+    /// Offscreen backends return their color target as it stands, which is the
+    /// cleared image before the first frame; onscreen backends only have a frame
+    /// once one was rendered after [`request_capture`](Self::request_capture).
+    ///
+    /// # Errors
+    /// Returns an error if called mid-frame, if an onscreen backend has not captured a
+    /// frame yet, or if the backend has no color target
+    fn capture_frame(&self) -> Result<RgbaImage, String>;
+}
+
+impl<B: RenderBackend + ?Sized> RenderBackend for Box<B> {
+    fn create_buffer(&mut self, data: BufferData<'_>) -> Result<BufferHandle, String> {
+        (**self).create_buffer(data)
+    }
+
+    fn update_buffer(&mut self, buffer: BufferHandle, data: BufferData<'_>) -> Result<(), String> {
+        (**self).update_buffer(buffer, data)
+    }
+
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureHandle, String> {
+        (**self).create_texture(descriptor, data)
+    }
+
+    fn destroy_buffer(&mut self, buffer: BufferHandle) {
+        (**self).destroy_buffer(buffer);
+    }
+
+    fn destroy_texture(&mut self, texture: TextureHandle) {
+        (**self).destroy_texture(texture);
+    }
+
+    fn create_pipeline(
+        &mut self,
+        descriptor: &PipelineDescriptor,
+    ) -> Result<PipelineHandle, String> {
+        (**self).create_pipeline(descriptor)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        (**self).resize(width, height);
+    }
+
+    fn begin_frame(&mut self, clear_color: Vec4, depth: DepthMapping) -> Result<(), String> {
+        (**self).begin_frame(clear_color, depth)
+    }
+
+    fn set_pipeline(&mut self, pipeline: PipelineHandle) {
+        (**self).set_pipeline(pipeline);
+    }
+
+    fn set_uniforms(&mut self, buffer: BufferHandle, block: &UniformBlock) {
+        (**self).set_uniforms(buffer, block);
+    }
+
+    fn draw(&mut self, call: &DrawCall) {
+        (**self).draw(call);
+    }
+
+    fn end_frame(&mut self) -> Result<(), String> {
+        (**self).end_frame()
+    }
+
+    fn request_capture(&mut self) {
+        (**self).request_capture();
+    }
+
+    fn capture_frame(&self) -> Result<RgbaImage, String> {
+        (**self).capture_frame()
+    }
+}
+
+/// Resources of one kind stored by handle; slots of destroyed resources are reused
+/// by later ones under a new generation, so stale handles resolve to nothing
+#[derive(Debug, Clone)]
+pub(crate) struct ResourceSlots<T> {
+    slots: Vec<ResourceSlot<T>>,
+    free: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+struct ResourceSlot<T> {
+    generation: u32,
+    resource: Option<T>,
+}
+
+impl<T> ResourceSlots<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Stores `resource`, returning its handle
+    pub(crate) fn insert<H: SlotHandle>(&mut self, resource: T) -> Result<H, String> {
+        if let Some(index) = self.free.pop() {
+            if let Some(slot) = self.slots.get_mut(index as usize) {
+                slot.resource = Some(resource);
+                return Ok(H::from_parts(index, slot.generation));
+            }
+        }
+        let index =
+            u32::try_from(self.slots.len()).map_err(|_| "Too many resources".to_string())?;
+        self.slots.push(ResourceSlot {
+            generation: 0,
+            resource: Some(resource),
+        });
+        Ok(H::from_parts(index, 0))
+    }
+
+    pub(crate) fn get<H: SlotHandle>(&self, handle: H) -> Option<&T> {
+        let (index, generation) = handle.parts();
+        let slot = self.slots.get(index as usize)?;
+        (slot.generation == generation)
+            .then_some(slot.resource.as_ref())
+            .flatten()
+    }
+
+    pub(crate) fn get_mut<H: SlotHandle>(&mut self, handle: H) -> Option<&mut T> {
+        let (index, generation) = handle.parts();
+        let slot = self.slots.get_mut(index as usize)?;
+        (slot.generation == generation)
+            .then_some(slot.resource.as_mut())
+            .flatten()
+    }
+
+    /// Takes the resource out, leaving its slot to the next insert; stale handles
+    /// remove nothing
+    pub(crate) fn remove<H: SlotHandle>(&mut self, handle: H) -> Option<T> {
+        let (index, generation) = handle.parts();
+        let slot = self.slots.get_mut(index as usize)?;
+        if slot.generation != generation {
+            return None;
+        }
+        let resource = slot.resource.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index);
+        Some(resource)
+    }
+}
@@ -0,0 +1,572 @@
+//! CPU ports of the Metal shaders in `src/shaders`
+//!
+//! Each function mirrors the vertex or fragment function of the same name so the
+//! software rasterizer produces the same lighting, fog and animation as the GPU path.
+
+use crate::math::{Vec2, Vec3, Vec4};
+use crate::renderer::{SkyboxUniforms, TreeUniforms, Uniforms};
+use crate::scene::{InstanceData, Vertex};
+
+/// Values interpolated across a triangle
+///
+/// Shaders only fill the fields their Metal counterpart declares in `VertexOut`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Varyings {
+    pub world_pos: Vec3,
+    pub normal: Vec3,
+    pub tex_coord: Vec2,
+    pub color: Vec3,
+    pub fade_alpha: f32,
+    pub distance: f32,
+}
+
+impl Varyings {
+    /// Weighted sum of three vertices' varyings
+    pub fn blend(v: [&Self; 3], w: [f32; 3]) -> Self {
+        let vec3 = |f: fn(&Self) -> Vec3| {
+            f(v[0])
+                .scale(w[0])
+                .add(&f(v[1]).scale(w[1]))
+                .add(&f(v[2]).scale(w[2]))
+        };
+        let scalar = |f: fn(&Self) -> f32| f(v[0]) * w[0] + f(v[1]) * w[1] + f(v[2]) * w[2];
+        Self {
+            world_pos: vec3(|v| v.world_pos),
+            normal: vec3(|v| v.normal),
+            tex_coord: Vec2::new(scalar(|v| v.tex_coord.x), scalar(|v| v.tex_coord.y)),
+            color: vec3(|v| v.color),
+            fade_alpha: scalar(|v| v.fade_alpha),
+            distance: scalar(|v| v.distance),
+        }
+    }
+
+    /// Linear interpolation from `a` to `b`
+    pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        Self::blend([a, b, b], [1.0 - t, t, 0.0])
+    }
+}
+
+/// Clip-space position and varyings produced by a vertex shader
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VertexOut {
+    pub position: Vec4,
+    pub varyings: Varyings,
+}
+
+fn xyz(v: Vec4) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+
+fn mix3(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    a.add(&b.sub(&a).scale(t))
+}
+
+fn mul3(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3::new(a.x * b.x, a.y * b.y, a.z * b.z)
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn reflect(incident: Vec3, normal: Vec3) -> Vec3 {
+    incident.sub(&normal.scale(2.0 * normal.dot(&incident)))
+}
+
+fn fract(x: f32) -> f32 {
+    x - x.floor()
+}
+
+fn rgba(rgb: Vec3, a: f32) -> Vec4 {
+    Vec4::new(rgb.x, rgb.y, rgb.z, a)
+}
+
+fn point(v: Vec3) -> Vec4 {
+    Vec4::new(v.x, v.y, v.z, 1.0)
+}
+
+fn direction(v: Vec3) -> Vec4 {
+    Vec4::new(v.x, v.y, v.z, 0.0)
+}
+
+fn fog_factor(density: f32, start: f32, distance: f32) -> f32 {
+    (1.0 - (-density * (distance - start).max(0.0)).exp()).clamp(0.0, 1.0)
+}
+
+/// Fog color blended between horizon and zenith by view elevation
+fn sky_fog_color(uniforms: &Uniforms, world_pos: Vec3) -> Vec3 {
+    let normalized_view = world_pos.sub(&uniforms.view_pos).normalize();
+    let sky_gradient = (normalized_view.y + 1.0) * 0.5;
+    mix3(uniforms.horizon_color, uniforms.zenith_color, sky_gradient)
+}
+
+/// `cube_vertex` and `road_vertex`
+pub(crate) fn scene_vertex(uniforms: &Uniforms, vertex: &Vertex) -> VertexOut {
+    let position = point(vertex.position);
+    VertexOut {
+        position: uniforms.mvp_matrix.multiply_vec4(&position),
+        varyings: Varyings {
+            world_pos: xyz(uniforms.model_matrix.multiply_vec4(&position)),
+            normal: xyz(uniforms
+                .normal_matrix
+                .multiply_vec4(&direction(vertex.normal)))
+            .normalize(),
+            tex_coord: vertex.tex_coord,
+            ..Varyings::default()
+        },
+    }
+}
+
+/// `cube_fragment`
+pub(crate) fn scene_fragment(uniforms: &Uniforms, input: &Varyings) -> Vec4 {
+    let object_color = Vec3::new(0.4, 0.55, 0.3);
+
+    let ambient = uniforms.light_color.scale(uniforms.ambient_strength);
+
+    let light_dir = uniforms.light_pos.sub(&input.world_pos).normalize();
+    let diff = input.normal.dot(&light_dir).max(0.0);
+    let diffuse = uniforms.light_color.scale(uniforms.diffuse_strength * diff);
+
+    let view_dir = uniforms.view_pos.sub(&input.world_pos).normalize();
+    let reflect_dir = reflect(light_dir.scale(-1.0), input.normal);
+    let spec = view_dir.dot(&reflect_dir).max(0.0).powf(32.0);
+    let specular = uniforms
+        .light_color
+        .scale(uniforms.specular_strength * spec);
+
+    let result = mul3(ambient.add(&diffuse).add(&specular), object_color);
+
+    let distance = uniforms.view_pos.sub(&input.world_pos).length();
+    let fog = fog_factor(uniforms.fog_density, uniforms.fog_start, distance);
+    rgba(
+        mix3(result, sky_fog_color(uniforms, input.world_pos), fog),
+        1.0,
+    )
+}
+
+/// `road_fragment`
+pub(crate) fn road_fragment(uniforms: &Uniforms, input: &Varyings) -> Vec4 {
+    let variation =
+        (input.tex_coord.x * 20.0).sin() * 0.05 + (input.tex_coord.y * 15.0).sin() * 0.05;
+    let texture_color = Vec3::new(0.5 + variation, 0.35 + variation, 0.2 + variation);
+
+    let normal = input.normal.normalize();
+    let light_dir = uniforms.light_pos.sub(&input.world_pos).normalize();
+    let view_dir = uniforms.view_pos.sub(&input.world_pos).normalize();
+
+    let ambient = uniforms.light_color.scale(uniforms.ambient_strength);
+
+    let diff = normal.dot(&light_dir).max(0.0);
+    let diffuse = uniforms.light_color.scale(uniforms.diffuse_strength * diff);
+
+    let reflect_dir = reflect(light_dir.scale(-1.0), normal);
+    let spec = view_dir.dot(&reflect_dir).max(0.0).powf(16.0);
+    let specular = uniforms
+        .light_color
+        .scale(uniforms.specular_strength * 0.2 * spec);
+
+    let result = mul3(texture_color, ambient.add(&diffuse).add(&specular));
+
+    let distance = input.world_pos.sub(&uniforms.view_pos).length();
+    let fog = fog_factor(uniforms.fog_density, uniforms.fog_start, distance);
+    rgba(mix3(result, uniforms.fog_color, fog), 1.0)
+}
+
+/// `tree_vertex`
+pub(crate) fn tree_vertex(
+    uniforms: &TreeUniforms,
+    instance: &InstanceData,
+    vertex: &Vertex,
+) -> VertexOut {
+    let mut world_pos = instance.transform.multiply_vec4(&point(vertex.position));
+
+    let wind_strength = 0.3;
+    let wind_frequency = 1.0;
+
+    // Only apply wind to vertices above ground
+    if vertex.position.y > 0.1 {
+        let wind_phase = world_pos.x * 0.1 + world_pos.z * 0.1 + uniforms.time * wind_frequency;
+        let height_factor = vertex.position.y / 2.5;
+
+        let mut wind_x = wind_phase.sin() * wind_strength * height_factor;
+        let mut wind_z = (wind_phase * 0.7).cos() * wind_strength * height_factor;
+
+        let sway = if vertex.position.y < 1.0 {
+            // Trunk: gentle bend
+            0.3
+        } else {
+            // Foliage: more pronounced wobble
+            1.0 + (uniforms.time * 3.0 + world_pos.x).sin() * 0.2
+        };
+        wind_x *= sway;
+        wind_z *= sway;
+
+        world_pos.x += wind_x;
+        world_pos.z += wind_z;
+    }
+
+    let world_position = xyz(world_pos);
+    let position = uniforms
+        .projection_matrix
+        .multiply(&uniforms.view_matrix)
+        .multiply_vec4(&world_pos);
+
+    // Upper-left 3x3 of the instance transform
+    let normal = xyz(instance.transform.multiply_vec4(&direction(vertex.normal))).normalize();
+
+    let color = if vertex.position.y < 1.0 {
+        Vec3::new(0.4, 0.25, 0.1).add(&instance.color_variation.scale(0.1))
+    } else {
+        Vec3::new(0.1, 0.5 + instance.color_variation.y * 0.2, 0.1)
+    };
+
+    VertexOut {
+        position,
+        varyings: Varyings {
+            world_pos: world_position,
+            normal,
+            tex_coord: vertex.tex_coord,
+            color,
+            distance: uniforms.view_position.sub(&world_position).length(),
+            ..Varyings::default()
+        },
+    }
+}
+
+/// `tree_fragment`
+pub(crate) fn tree_fragment(uniforms: &TreeUniforms, input: &Varyings) -> Vec4 {
+    let light_dir = uniforms.light_position.sub(&input.world_pos).normalize();
+    let normal = input.normal.normalize();
+
+    let diffuse = normal.dot(&light_dir).max(0.0);
+    let ambient = Vec3::new(0.3, 0.3, 0.3);
+
+    let lighting = ambient.add(&Vec3::new(1.0, 1.0, 1.0).scale(diffuse * 0.7));
+    let final_color = mul3(input.color, lighting);
+
+    let fog = fog_factor(uniforms.fog_density, uniforms.fog_start, input.distance);
+    let fog_color = xyz(uniforms.sky_gradient_bottom);
+    rgba(mix3(final_color, fog_color, fog), 1.0)
+}
+
+/// `grass_vertex`
+pub(crate) fn grass_vertex(
+    uniforms: &Uniforms,
+    instance: &InstanceData,
+    vertex: &Vertex,
+) -> VertexOut {
+    let instance_pos = xyz(instance.transform.cols[3]);
+    let mut local_pos = vertex.position;
+
+    let (world_pos, normal) = if instance.lod_level >= 2 {
+        // Billboard: face the camera while staying upright
+        let mut to_camera = uniforms.view_pos.sub(&instance_pos);
+        to_camera.y = 0.0;
+        let to_camera = to_camera.normalize();
+
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let right = up.cross(&to_camera).normalize();
+
+        let billboard_pos = instance_pos
+            .add(&right.scale(local_pos.x))
+            .add(&up.scale(local_pos.y));
+        (billboard_pos, to_camera)
+    } else {
+        if vertex.tex_coord.y < 0.8 {
+            let wind_strength = 0.1;
+            let wind_speed = 1.5;
+
+            let wind_offset = instance_pos.x * 0.1 + instance_pos.z * 0.1;
+            let wind_time = uniforms.time * wind_speed + wind_offset;
+            let wind_time2 = uniforms.time * wind_speed * 0.37 + wind_offset * 1.3;
+
+            let mut wind_x = wind_time.sin() * wind_strength;
+            let mut wind_z = (wind_time * 0.7).cos() * wind_strength * 0.5;
+            wind_x += (wind_time2 * 2.3).sin() * wind_strength * 0.3;
+            wind_z += (wind_time2 * 1.9).cos() * wind_strength * 0.2;
+
+            let height_factor = (1.0 - vertex.tex_coord.y).powi(2);
+            local_pos.x += wind_x * height_factor;
+            local_pos.z += wind_z * height_factor;
+        }
+
+        let world_pos = xyz(instance.transform.multiply_vec4(&point(local_pos)));
+
+        // Two-sided lighting: flip the normal towards the camera
+        let mut normal =
+            xyz(instance.transform.multiply_vec4(&direction(vertex.normal))).normalize();
+        if normal.dot(&uniforms.view_pos.sub(&world_pos)) < 0.0 {
+            normal = normal.scale(-1.0);
+        }
+        (world_pos, normal)
+    };
+
+    let fade_alpha = if instance.lod_level == 3 {
+        let distance = uniforms.view_pos.sub(&world_pos).length();
+        1.0 - ((distance - 50.0) / 10.0).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+
+    VertexOut {
+        position: uniforms.mvp_matrix.multiply_vec4(&point(world_pos)),
+        varyings: Varyings {
+            world_pos,
+            normal,
+            tex_coord: vertex.tex_coord,
+            color: instance.color_variation,
+            fade_alpha,
+            ..Varyings::default()
+        },
+    }
+}
+
+/// `grass_fragment`; `tex_color` is the sampled texture array color
+///
+/// Returns `None` where the Metal shader discards the fragment.
+pub(crate) fn grass_fragment(
+    uniforms: &Uniforms,
+    input: &Varyings,
+    tex_color: Vec4,
+) -> Option<Vec4> {
+    if tex_color.w < 0.1 {
+        return None;
+    }
+
+    let mut grass_color = mul3(
+        xyz(tex_color),
+        Vec3::new(1.0, 1.0, 1.0).add(&input.color.scale(0.3)),
+    );
+    let gradient = mix(0.5, 1.0, input.tex_coord.y.max(0.0).sqrt());
+    grass_color = grass_color.scale(gradient);
+
+    let light_dir = uniforms.light_pos.sub(&input.world_pos).normalize();
+    let view_dir = uniforms.view_pos.sub(&input.world_pos).normalize();
+    let half_dir = light_dir.add(&view_dir).normalize();
+
+    // Subsurface scattering
+    let light_to_point = input.world_pos.sub(&uniforms.light_pos).normalize();
+    let back_light = view_dir.dot(&light_to_point.scale(-1.0)).max(0.0);
+    let subsurface_wrap = (input.normal.dot(&light_dir) + 0.5).max(0.0) * 0.7;
+
+    let thickness = 1.0 - input.tex_coord.y;
+    let translucency = back_light.powi(3) * 0.8 * (1.0 - thickness * 0.5);
+
+    let subsurface_color = mul3(Vec3::new(0.4, 0.7, 0.2), uniforms.light_color);
+    let subsurface_contribution = subsurface_color.scale(subsurface_wrap + translucency * 0.6);
+
+    // Wrapped diffuse
+    let n_dot_l = input.normal.dot(&light_dir);
+    let wrapped_diffuse = ((n_dot_l + 0.3) / 1.3).max(0.0);
+    let diffuse = uniforms
+        .light_color
+        .scale(uniforms.diffuse_strength * wrapped_diffuse);
+
+    // Soft specular, weaker at the tips
+    let n_dot_h = input.normal.dot(&half_dir).max(0.0);
+    let specular = n_dot_h.powf(32.0) * 0.2 * (1.0 - input.tex_coord.y);
+
+    let ambient = uniforms
+        .light_color
+        .scale(uniforms.ambient_strength)
+        .add(&Vec3::new(0.05, 0.08, 0.02).scale(1.0 - input.tex_coord.y));
+
+    let lighting = ambient.add(&diffuse).add(&subsurface_contribution);
+    let mut result = mul3(grass_color, lighting).add(&uniforms.light_color.scale(specular));
+
+    // Rim lighting
+    let rim = (1.0 - view_dir.dot(&input.normal).max(0.0)).powi(2) * 0.15;
+    result = result.add(&mul3(uniforms.light_color, grass_color).scale(rim));
+
+    let distance = uniforms.view_pos.sub(&input.world_pos).length();
+    let fog = fog_factor(uniforms.fog_density, uniforms.fog_start, distance);
+    result = mix3(result, sky_fog_color(uniforms, input.world_pos), fog);
+
+    Some(rgba(result, input.fade_alpha))
+}
+
+/// `skybox_vertex`
+pub(crate) fn skybox_vertex(uniforms: &SkyboxUniforms, vertex: &Vertex) -> VertexOut {
+    let sky_pos = vertex.position.add(&uniforms.camera_pos);
+    VertexOut {
+        position: uniforms
+            .view_projection_matrix
+            .multiply_vec4(&point(sky_pos)),
+        varyings: Varyings {
+            // Local position is used for sky calculations
+            world_pos: vertex.position,
+            tex_coord: vertex.tex_coord,
+            ..Varyings::default()
+        },
+    }
+}
+
+fn hash(x: f32, y: f32) -> f32 {
+    let mut p3 = [fract(x * 0.13), fract(y * 0.13), fract(x * 0.13)];
+    let d = p3[0] * (p3[1] + 3.333) + p3[1] * (p3[2] + 3.333) + p3[2] * (p3[0] + 3.333);
+    for c in &mut p3 {
+        *c += d;
+    }
+    fract((p3[0] + p3[1]) * p3[2])
+}
+
+fn value_noise(x: f32, y: f32) -> f32 {
+    let (ix, iy) = (x.floor(), y.floor());
+    let (fx, fy) = (fract(x), fract(y));
+
+    let a = hash(ix, iy);
+    let b = hash(ix + 1.0, iy);
+    let c = hash(ix, iy + 1.0);
+    let d = hash(ix + 1.0, iy + 1.0);
+
+    let ux = fx * fx * (3.0 - 2.0 * fx);
+    let uy = fy * fy * (3.0 - 2.0 * fy);
+    mix(a, b, ux) + (c - a) * uy * (1.0 - ux) + (d - b) * ux * uy
+}
+
+fn fbm(x: f32, y: f32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for _ in 0..4 {
+        value += amplitude * value_noise(x * frequency, y * frequency);
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    value
+}
+
+/// `skybox_fragment`
+pub(crate) fn skybox_fragment(uniforms: &SkyboxUniforms, input: &Varyings) -> Vec4 {
+    let sky_dir = input.world_pos.normalize();
+    let up_dot = sky_dir.y;
+    let horizon_blend = smoothstep(-0.1, 0.3, up_dot);
+
+    let horizon_color = Vec3::new(0.7, 0.8, 0.9);
+    let zenith_color = Vec3::new(0.2, 0.4, 0.8);
+    let ground_color = Vec3::new(0.15, 0.2, 0.25);
+
+    let mut base_color = if up_dot < 0.0 {
+        mix3(ground_color, horizon_color, smoothstep(-0.5, 0.0, up_dot))
+    } else {
+        mix3(horizon_color, zenith_color, horizon_blend)
+    };
+
+    // Sun glow
+    let sun_dot = sky_dir.dot(&uniforms.sun_direction).max(0.0);
+    let sun_glow = sun_dot.powf(128.0) * 2.0 + sun_dot.powf(8.0) * 0.5;
+    base_color = base_color.add(&Vec3::new(1.0, 0.9, 0.7).scale(sun_glow));
+
+    // Procedural clouds
+    if up_dot > 0.0 {
+        let projection = 3.0 / (1.0 + sky_dir.y * 0.5);
+        let drift = uniforms.time * 0.02;
+        let u = sky_dir.x * projection + drift;
+        let v = sky_dir.z * projection + drift;
+
+        let detail_drift = uniforms.time * 0.05;
+        let cloud_base = fbm(u, v);
+        let cloud_detail = fbm(u * 3.0 + detail_drift, v * 3.0 + detail_drift);
+        let clouds = smoothstep(0.4, 0.6, cloud_base * 0.7 + cloud_detail * 0.3)
+            * smoothstep(0.0, 0.3, up_dot);
+
+        base_color = mix3(base_color, Vec3::new(1.0, 1.0, 1.0), clouds * 0.7);
+    }
+
+    rgba(base_color, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Mat4;
+
+    fn uniforms(view_pos: Vec3) -> Uniforms {
+        Uniforms {
+            mvp_matrix: Mat4::identity(),
+            model_matrix: Mat4::identity(),
+            normal_matrix: Mat4::identity(),
+            view_pos,
+            time: 0.0,
+            light_pos: Vec3::new(0.0, 10.0, 0.0),
+            _padding1: 0.0,
+            light_color: Vec3::new(1.0, 1.0, 1.0),
+            ambient_strength: 0.1,
+            diffuse_strength: 0.8,
+            specular_strength: 0.5,
+            fog_density: 0.02,
+            fog_color: Vec3::new(0.7, 0.8, 0.9),
+            fog_start: 10.0,
+            horizon_color: Vec3::new(0.7, 0.8, 0.9),
+            _padding2: 0.0,
+            zenith_color: Vec3::new(0.2, 0.4, 0.8),
+            _padding3: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_scene_fragment_lit_from_above() {
+        let uniforms = uniforms(Vec3::new(0.0, 5.0, 5.0));
+        let lit = Varyings {
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            ..Varyings::default()
+        };
+        let unlit = Varyings {
+            normal: Vec3::new(0.0, -1.0, 0.0),
+            ..Varyings::default()
+        };
+
+        let lit_color = scene_fragment(&uniforms, &lit);
+        let unlit_color = scene_fragment(&uniforms, &unlit);
+        assert!(lit_color.y > unlit_color.y);
+        // Ambient only: 0.1 * object color
+        assert!((unlit_color.y - 0.055).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fog_approaches_sky_color_with_distance() {
+        let uniforms = uniforms(Vec3::new(0.0, 0.0, 1000.0));
+        let input = Varyings {
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            ..Varyings::default()
+        };
+        let color = scene_fragment(&uniforms, &input);
+        // Looking horizontally, the fog color is halfway between horizon and zenith
+        assert!((color.x - 0.45).abs() < 1e-3);
+        assert!((color.z - 0.85).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_grass_fragment_discards_transparent_texels() {
+        let uniforms = uniforms(Vec3::new(0.0, 1.0, 1.0));
+        let input = Varyings {
+            fade_alpha: 1.0,
+            ..Varyings::default()
+        };
+        assert!(grass_fragment(&uniforms, &input, Vec4::new(0.0, 1.0, 0.0, 0.05)).is_none());
+        assert!(grass_fragment(&uniforms, &input, Vec4::new(0.0, 1.0, 0.0, 1.0)).is_some());
+    }
+
+    #[test]
+    fn test_skybox_gradient() {
+        let uniforms = SkyboxUniforms {
+            view_projection_matrix: Mat4::identity(),
+            camera_pos: Vec3::zero(),
+            time: 0.0,
+            sun_direction: Vec3::new(1.0, 0.0, 0.0),
+            _padding: 0.0,
+        };
+        let below = Varyings {
+            world_pos: Vec3::new(0.0, -1.0, 0.0),
+            ..Varyings::default()
+        };
+        let color = skybox_fragment(&uniforms, &below);
+        assert!((color.x - 0.15).abs() < 1e-4);
+        assert!((color.z - 0.25).abs() < 1e-4);
+    }
+}
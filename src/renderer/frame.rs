@@ -0,0 +1,737 @@
+//! Backend-agnostic frame rendering
+//!
+//! [`Renderer`] owns the camera and the GPU resources for the skybox, grass, road,
+//! trees and scene meshes, and records each frame through a [`RenderBackend`].
+
+use crate::core::{GrassSystem, GrassTextureGenerator, LodLevel, RoadSystem, Skybox, TreeSystem};
+use crate::math::{BoundingSphere, Mat4, Vec3, Vec4};
+use crate::renderer::{
+    BufferData, BufferHandle, DrawCall, PipelineDescriptor, PipelineHandle, RenderBackend,
+    ShaderProgram, SkyboxUniforms, TextureDescriptor, TextureHandle, TreeUniforms, UniformBlock,
+    Uniforms,
+};
+use crate::scene::{Camera, IndexFormat, InstanceData, Light, Mesh, NodeId, Scene};
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const HORIZON_COLOR: Vec3 = Vec3::new(0.7, 0.8, 0.9);
+const ZENITH_COLOR: Vec3 = Vec3::new(0.2, 0.4, 0.8);
+const FOG_COLOR: Vec3 = Vec3::new(0.7, 0.8, 0.9);
+const FOG_DENSITY: f32 = 0.02;
+const FOG_START: f32 = 10.0;
+
+const GRASS_TEXTURE_SIZE: u32 = 256;
+const GRASS_TEXTURE_LAYERS: u32 = 8;
+
+struct MeshBuffers {
+    vertex_buffer: BufferHandle,
+    index_buffer: BufferHandle,
+    uniform_buffer: BufferHandle,
+    index_format: IndexFormat,
+    index_count: usize,
+    bounds: Option<BoundingSphere>,
+}
+
+impl MeshBuffers {
+    /// Uploads `mesh` with the narrowest index format that addresses all of its vertices
+    fn new<B: RenderBackend>(backend: &mut B, mesh: &Mesh) -> Result<Self, String> {
+        mesh.check_indices()?;
+        let index_format = mesh.index_format()?;
+        let vertex_buffer = backend.create_buffer(BufferData::Vertices(&mesh.vertices))?;
+        let index_buffer = match index_format {
+            IndexFormat::U16 => {
+                backend.create_buffer(BufferData::Indices16(&mesh.indices_u16()?))?
+            }
+            IndexFormat::U32 => backend.create_buffer(BufferData::Indices32(&mesh.indices))?,
+        };
+        Ok(Self {
+            vertex_buffer,
+            index_buffer,
+            uniform_buffer: backend.create_buffer(BufferData::Empty(UniformBlock::MAX_SIZE))?,
+            index_format,
+            index_count: mesh.indices.len(),
+            bounds: mesh.bounds().map(BoundingSphere::from),
+        })
+    }
+
+    fn destroy<B: RenderBackend>(self, backend: &mut B) {
+        backend.destroy_buffer(self.vertex_buffer);
+        backend.destroy_buffer(self.index_buffer);
+        backend.destroy_buffer(self.uniform_buffer);
+    }
+
+    fn draw_call(&self, texture: Option<TextureHandle>) -> DrawCall {
+        DrawCall {
+            vertex_buffer: self.vertex_buffer,
+            index_buffer: self.index_buffer,
+            index_format: self.index_format,
+            index_count: self.index_count,
+            uniforms: self.uniform_buffer,
+            instances: None,
+            instance_count: 1,
+            texture,
+        }
+    }
+
+    fn instanced_draw_call(
+        &self,
+        instances: &InstanceBuffer,
+        texture: Option<TextureHandle>,
+    ) -> DrawCall {
+        DrawCall {
+            instances: Some(instances.buffer),
+            instance_count: instances.count,
+            ..self.draw_call(texture)
+        }
+    }
+}
+
+struct InstanceBuffer {
+    buffer: BufferHandle,
+    count: usize,
+}
+
+/// GPU resources of a scene node, built from [`Node::revision`](crate::scene::Node::revision)
+struct NodeBuffers {
+    revision: u32,
+    mesh: MeshBuffers,
+    /// The node's image, which keys its entry in `Renderer::node_textures`, and the
+    /// texture uploaded for it
+    texture: Option<(Arc<RgbaImage>, TextureHandle)>,
+}
+
+/// Texture uploaded for one `Arc<RgbaImage>` and shared by every node holding it
+struct SharedTexture {
+    handle: TextureHandle,
+    users: usize,
+}
+
+struct GrassLodBuffers {
+    /// Mesh and instance buffers for each entry of [`LodLevel::ALL`]
+    lods: [(MeshBuffers, InstanceBuffer); 4],
+    /// Uniforms shared across all LOD draws
+    uniform_buffer: BufferHandle,
+}
+
+impl GrassLodBuffers {
+    fn destroy<B: RenderBackend>(self, backend: &mut B) {
+        for (mesh_buffers, instances) in self.lods {
+            mesh_buffers.destroy(backend);
+            backend.destroy_buffer(instances.buffer);
+        }
+        backend.destroy_buffer(self.uniform_buffer);
+    }
+}
+
+/// Draws a [`Scene`] and the world systems through a [`RenderBackend`]
+///
+/// Frames are drawn in a fixed order: skybox, grass (one instanced draw per LOD
+/// level), road, trees, scene nodes and finally an optional overlay.
+pub struct Renderer<B: RenderBackend> {
+    backend: B,
+    camera: Camera,
+    scene_pipeline: PipelineHandle,
+    skybox_pipeline: PipelineHandle,
+    grass_pipeline: Option<PipelineHandle>,
+    road_pipeline: Option<PipelineHandle>,
+    tree_pipeline: Option<PipelineHandle>,
+    default_texture: TextureHandle,
+    grass_texture_array: Option<TextureHandle>,
+    /// [`Scene::id`] of the scene `node_buffers` were built for
+    node_scene: Option<u64>,
+    node_buffers: HashMap<NodeId, NodeBuffers>,
+    /// Node textures keyed by the address of their `Arc<RgbaImage>`
+    node_textures: HashMap<usize, SharedTexture>,
+    skybox_buffers: Option<MeshBuffers>,
+    grass_buffers: Option<GrassLodBuffers>,
+    road_buffers: Option<MeshBuffers>,
+    tree_buffers: Option<(MeshBuffers, InstanceBuffer)>,
+    time: f32,
+}
+
+impl<B: RenderBackend> Renderer<B> {
+    /// Creates a renderer drawing into a `width` x `height` target
+    ///
+    /// # Errors
+    /// Returns an error if the backend fails to create the scene and skybox pipelines
+    /// or the default texture
+    pub fn new(mut backend: B, width: u32, height: u32) -> Result<Self, String> {
+        let scene_pipeline =
+            backend.create_pipeline(&PipelineDescriptor::for_program(ShaderProgram::Scene))?;
+        let skybox_pipeline =
+            backend.create_pipeline(&PipelineDescriptor::for_program(ShaderProgram::Skybox))?;
+        let default_texture = Self::create_checkerboard_texture(&mut backend)?;
+
+        let aspect_ratio = width as f32 / height as f32;
+        let camera = Camera::new(
+            Vec3::new(3.0, 3.0, 3.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            aspect_ratio,
+        );
+
+        Ok(Self {
+            backend,
+            camera,
+            scene_pipeline,
+            skybox_pipeline,
+            grass_pipeline: None,
+            road_pipeline: None,
+            tree_pipeline: None,
+            default_texture,
+            grass_texture_array: None,
+            node_scene: None,
+            node_buffers: HashMap::new(),
+            node_textures: HashMap::new(),
+            skybox_buffers: None,
+            grass_buffers: None,
+            road_buffers: None,
+            tree_buffers: None,
+            time: 0.0,
+        })
+    }
+
+    fn create_checkerboard_texture(backend: &mut B) -> Result<TextureHandle, String> {
+        const SIZE: u32 = 64;
+        const CHECKER_SIZE: u32 = 8;
+        let mut data = vec![0u8; (SIZE * SIZE * 4) as usize];
+
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let is_white = ((x / CHECKER_SIZE) + (y / CHECKER_SIZE)).is_multiple_of(2);
+                let color = if is_white { 255u8 } else { 0u8 };
+                let idx = ((y * SIZE + x) * 4) as usize;
+                data[idx] = color; // R
+                data[idx + 1] = color; // G
+                data[idx + 2] = color; // B
+                data[idx + 3] = 255; // A
+            }
+        }
+
+        let descriptor = TextureDescriptor {
+            width: SIZE,
+            height: SIZE,
+            layers: 1,
+            format: crate::core::TextureFormat::Rgba8,
+        };
+        backend.create_texture(&descriptor, &data)
+    }
+
+    #[must_use]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Consumes the renderer, returning its backend
+    #[must_use]
+    pub fn into_backend(self) -> B {
+        self.backend
+    }
+
+    #[must_use]
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    pub fn update_time(&mut self, delta_time: f32) {
+        self.time += delta_time;
+    }
+
+    /// Resizes the render target and updates the camera aspect ratio
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.backend.resize(width, height);
+        self.camera.set_aspect_ratio(width as f32 / height as f32);
+    }
+
+    /// Keeps a CPU copy of the next rendered frame for [`capture_frame`](Self::capture_frame)
+    pub fn request_capture(&mut self) {
+        self.backend.request_capture();
+    }
+
+    /// Returns the most recently captured frame as an RGBA image; see
+    /// [`RenderBackend::capture_frame`] for what each backend returns before the first frame
+    ///
+    /// # Errors
+    /// Returns an error if the backend has no frame to capture
+    pub fn capture_frame(&self) -> Result<RgbaImage, String> {
+        self.backend.capture_frame()
+    }
+
+    /// Writes the most recently captured frame to `path`; the format follows the extension
+    ///
+    /// # Errors
+    /// Returns an error if no frame has been captured or the file cannot be written
+    pub fn save_screenshot(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        self.capture_frame()?
+            .save(path)
+            .map_err(|e| format!("Failed to save screenshot to {}: {e}", path.display()))
+    }
+
+    /// Creates the skybox buffers
+    ///
+    /// # Errors
+    /// Returns an error if buffer creation fails
+    pub fn initialize_skybox(&mut self, skybox: &Skybox) -> Result<(), String> {
+        let buffers = MeshBuffers::new(&mut self.backend, &skybox.mesh)?;
+        if let Some(old) = self.skybox_buffers.replace(buffers) {
+            old.destroy(&mut self.backend);
+        }
+        Ok(())
+    }
+
+    /// Creates the grass pipeline, texture array and per-LOD buffers, then uploads
+    /// the blades the camera sees
+    ///
+    /// Each LOD instance buffer is sized for every grass instance so that
+    /// [`update_grass`](Self::update_grass) never needs to reallocate.
+    ///
+    /// # Errors
+    /// Returns an error if pipeline, texture or buffer creation fails
+    pub fn initialize_grass(&mut self, grass_system: &GrassSystem) -> Result<(), String> {
+        if self.grass_pipeline.is_none() {
+            self.grass_pipeline = Some(
+                self.backend
+                    .create_pipeline(&PipelineDescriptor::for_program(ShaderProgram::Grass))?,
+            );
+        }
+
+        if self.grass_texture_array.is_none() {
+            let generator = GrassTextureGenerator::new(
+                GRASS_TEXTURE_SIZE,
+                GRASS_TEXTURE_SIZE,
+                GRASS_TEXTURE_LAYERS,
+            );
+            let descriptor = TextureDescriptor {
+                width: GRASS_TEXTURE_SIZE,
+                height: GRASS_TEXTURE_SIZE,
+                layers: GRASS_TEXTURE_LAYERS,
+                format: GrassTextureGenerator::format(),
+            };
+            self.grass_texture_array = Some(
+                self.backend
+                    .create_texture(&descriptor, &generator.generate_texture_array_data())?,
+            );
+        }
+
+        let capacity = grass_system.instanced_mesh().instances.len().max(1)
+            * std::mem::size_of::<InstanceData>();
+        let mut create_lod = |lod_level| -> Result<(MeshBuffers, InstanceBuffer), String> {
+            let mesh_buffers =
+                MeshBuffers::new(&mut self.backend, grass_system.get_lod_mesh(lod_level))?;
+            let buffer = self.backend.create_buffer(BufferData::Empty(capacity))?;
+            Ok((mesh_buffers, InstanceBuffer { buffer, count: 0 }))
+        };
+
+        let [full, reduced, billboard, fade] = LodLevel::ALL;
+        let lods = [
+            create_lod(full)?,
+            create_lod(reduced)?,
+            create_lod(billboard)?,
+            create_lod(fade)?,
+        ];
+        let uniform_buffer = self
+            .backend
+            .create_buffer(BufferData::Empty(UniformBlock::MAX_SIZE))?;
+
+        let buffers = GrassLodBuffers {
+            lods,
+            uniform_buffer,
+        };
+        if let Some(old) = self.grass_buffers.replace(buffers) {
+            old.destroy(&mut self.backend);
+        }
+
+        self.update_grass(grass_system)
+    }
+
+    /// Uploads the current per-LOD grass instances, skipping blades outside the
+    /// camera's frustum
+    ///
+    /// # Errors
+    /// Returns an error if an instance buffer cannot be written
+    pub fn update_grass(&mut self, grass_system: &GrassSystem) -> Result<(), String> {
+        let Some(grass_buffers) = &mut self.grass_buffers else {
+            return Ok(());
+        };
+
+        let frustum = self.camera.frustum();
+        let mut lods: [Vec<InstanceData>; 4] = Default::default();
+        for index in grass_system.visible_instances(&frustum) {
+            if let Some(instance) = grass_system.instances().get(index) {
+                lods[instance.lod_level as usize].push(instance.instance_data());
+            }
+        }
+
+        for ((_, instance_buffer), instances) in grass_buffers.lods.iter_mut().zip(&lods) {
+            self.backend
+                .update_buffer(instance_buffer.buffer, BufferData::Instances(instances))?;
+            instance_buffer.count = instances.len();
+        }
+
+        Ok(())
+    }
+
+    /// Creates the road pipeline and buffers
+    ///
+    /// # Errors
+    /// Returns an error if pipeline or buffer creation fails
+    pub fn initialize_road(&mut self, road_system: &RoadSystem) -> Result<(), String> {
+        if self.road_pipeline.is_none() {
+            self.road_pipeline = Some(
+                self.backend
+                    .create_pipeline(&PipelineDescriptor::for_program(ShaderProgram::Road))?,
+            );
+        }
+
+        let buffers = MeshBuffers::new(&mut self.backend, road_system.mesh())?;
+        if let Some(old) = self.road_buffers.replace(buffers) {
+            old.destroy(&mut self.backend);
+        }
+        Ok(())
+    }
+
+    /// Creates the tree pipeline and instanced buffers, then uploads the trees the
+    /// camera sees
+    ///
+    /// The instance buffer is sized for every tree so that
+    /// [`update_trees`](Self::update_trees) never needs to reallocate.
+    ///
+    /// # Errors
+    /// Returns an error if pipeline or buffer creation fails
+    pub fn initialize_tree(&mut self, tree_system: &TreeSystem) -> Result<(), String> {
+        if self.tree_pipeline.is_none() {
+            self.tree_pipeline = Some(
+                self.backend
+                    .create_pipeline(&PipelineDescriptor::for_program(ShaderProgram::Tree))?,
+            );
+        }
+
+        let instanced_mesh = tree_system.instanced_mesh();
+        let mesh_buffers = MeshBuffers::new(&mut self.backend, &instanced_mesh.base_mesh)?;
+        let capacity = instanced_mesh.instances.len().max(1) * std::mem::size_of::<InstanceData>();
+        let buffer = self.backend.create_buffer(BufferData::Empty(capacity))?;
+
+        let buffers = (mesh_buffers, InstanceBuffer { buffer, count: 0 });
+        if let Some((old_mesh, old_instances)) = self.tree_buffers.replace(buffers) {
+            old_mesh.destroy(&mut self.backend);
+            self.backend.destroy_buffer(old_instances.buffer);
+        }
+        self.update_trees(tree_system)
+    }
+
+    /// Uploads the trees inside the camera's frustum
+    ///
+    /// # Errors
+    /// Returns an error if the instance buffer cannot be written
+    pub fn update_trees(&mut self, tree_system: &TreeSystem) -> Result<(), String> {
+        let Some((_, instance_buffer)) = &mut self.tree_buffers else {
+            return Ok(());
+        };
+
+        let frustum = self.camera.frustum();
+        let all = &tree_system.instanced_mesh().instances;
+        let instances: Vec<InstanceData> = tree_system
+            .visible_instances(&frustum)
+            .into_iter()
+            .filter_map(|index| all.get(index).copied())
+            .collect();
+        self.backend
+            .update_buffer(instance_buffer.buffer, BufferData::Instances(&instances))?;
+        instance_buffer.count = instances.len();
+        Ok(())
+    }
+
+    fn ensure_node_buffers(&mut self, scene: &Scene) -> Result<(), String> {
+        // Ids of another scene can match cached ones, so a new scene starts over
+        if self.node_scene != Some(scene.id()) {
+            for (_, buffers) in std::mem::take(&mut self.node_buffers) {
+                self.release_node_buffers(buffers);
+            }
+            self.node_scene = Some(scene.id());
+        }
+
+        // Release buffers of removed nodes and of nodes that no longer have a mesh
+        let stale: Vec<NodeId> = self
+            .node_buffers
+            .keys()
+            .filter(|&&id| scene.get(id).is_none_or(|node| node.mesh().is_none()))
+            .copied()
+            .collect();
+        for id in stale {
+            if let Some(buffers) = self.node_buffers.remove(&id) {
+                self.release_node_buffers(buffers);
+            }
+        }
+
+        for (id, node) in scene {
+            let Some(mesh) = node.mesh() else {
+                continue;
+            };
+            let revision = node.revision();
+            if self
+                .node_buffers
+                .get(&id)
+                .is_some_and(|buffers| buffers.revision == revision)
+            {
+                continue;
+            }
+
+            let mesh = MeshBuffers::new(&mut self.backend, mesh)?;
+            let texture = match node.texture() {
+                Some(image) => match self.acquire_node_texture(image) {
+                    Ok(handle) => Some((Arc::clone(image), handle)),
+                    Err(e) => {
+                        mesh.destroy(&mut self.backend);
+                        return Err(e);
+                    }
+                },
+                None => None,
+            };
+            let buffers = NodeBuffers {
+                revision,
+                mesh,
+                texture,
+            };
+            // Acquired before releasing the old buffers, so an unchanged texture is kept
+            if let Some(old) = self.node_buffers.insert(id, buffers) {
+                self.release_node_buffers(old);
+            }
+        }
+        Ok(())
+    }
+
+    /// Texture for `image`, uploaded the first time any node uses that `Arc`
+    fn acquire_node_texture(&mut self, image: &Arc<RgbaImage>) -> Result<TextureHandle, String> {
+        let key = texture_key(image);
+        if let Some(shared) = self.node_textures.get_mut(&key) {
+            shared.users += 1;
+            return Ok(shared.handle);
+        }
+
+        let descriptor = TextureDescriptor {
+            width: image.width(),
+            height: image.height(),
+            layers: 1,
+            format: crate::core::TextureFormat::Rgba8,
+        };
+        let handle = self.backend.create_texture(&descriptor, image.as_raw())?;
+        self.node_textures
+            .insert(key, SharedTexture { handle, users: 1 });
+        Ok(handle)
+    }
+
+    /// Destroys a node's mesh buffers and its texture once no other node uses it
+    fn release_node_buffers(&mut self, buffers: NodeBuffers) {
+        buffers.mesh.destroy(&mut self.backend);
+        let Some((image, handle)) = buffers.texture else {
+            return;
+        };
+        let key = texture_key(&image);
+        if let Some(shared) = self.node_textures.get_mut(&key) {
+            shared.users -= 1;
+            if shared.users == 0 {
+                self.node_textures.remove(&key);
+                self.backend.destroy_texture(handle);
+            }
+        }
+    }
+
+    /// Clear color blending from horizon to zenith based on the camera up direction
+    #[must_use]
+    pub fn clear_color(&self) -> Vec4 {
+        let up_y = self.camera.up_vector().y.clamp(-1.0, 1.0);
+        let t = (up_y + 1.0) * 0.5; // Map from [-1, 1] to [0, 1]
+        let color = HORIZON_COLOR.add(&ZENITH_COLOR.sub(&HORIZON_COLOR).scale(t));
+        Vec4::new(color.x, color.y, color.z, 1.0)
+    }
+
+    /// Renders one frame
+    ///
+    /// # Errors
+    /// Returns an error if the backend fails to start or submit the frame
+    pub fn render(&mut self, scene: &Scene) -> Result<(), String> {
+        self.render_with_overlay(scene, |_| Ok(()))
+    }
+
+    /// Renders one frame, calling `overlay` after the scene has been drawn
+    ///
+    /// The overlay receives the backend mid-frame, so it can issue its own draws on top
+    /// of the scene (e.g. the UI). The frame is submitted even if the overlay fails.
+    ///
+    /// # Errors
+    /// Returns an error if the backend fails to start or submit the frame, or if the
+    /// overlay fails
+    pub fn render_with_overlay<F>(&mut self, scene: &Scene, overlay: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut B) -> Result<(), String>,
+    {
+        self.ensure_node_buffers(scene)?;
+
+        self.backend
+            .begin_frame(self.clear_color(), self.camera.depth_mapping())?;
+
+        self.draw_skybox();
+        self.draw_grass(&scene.light);
+        self.draw_road(&scene.light);
+        self.draw_trees(&scene.light);
+        self.draw_nodes(scene);
+
+        let overlay_result = overlay(&mut self.backend);
+        self.backend.end_frame()?;
+        overlay_result
+    }
+
+    fn draw_skybox(&mut self) {
+        let Some(buffers) = &self.skybox_buffers else {
+            return;
+        };
+
+        let uniforms = SkyboxUniforms {
+            view_projection_matrix: self.camera.view_projection_matrix(),
+            camera_pos: self.camera.position(),
+            time: self.time,
+            sun_direction: Vec3::new(0.5, 0.8, 0.3).normalize(),
+            _padding: 0.0,
+        };
+
+        self.backend.set_pipeline(self.skybox_pipeline);
+        self.backend
+            .set_uniforms(buffers.uniform_buffer, &UniformBlock::Skybox(uniforms));
+        self.backend.draw(&buffers.draw_call(None));
+    }
+
+    fn draw_grass(&mut self, light: &Light) {
+        let (Some(grass_buffers), Some(pipeline)) = (&self.grass_buffers, self.grass_pipeline)
+        else {
+            return;
+        };
+
+        let uniforms = lit_uniforms(&self.camera, light, self.time, &Mat4::identity());
+        self.backend.set_pipeline(pipeline);
+        self.backend
+            .set_uniforms(grass_buffers.uniform_buffer, &UniformBlock::Scene(uniforms));
+
+        for (mesh_buffers, instances) in &grass_buffers.lods {
+            if instances.count == 0 {
+                continue;
+            }
+            let call = DrawCall {
+                uniforms: grass_buffers.uniform_buffer,
+                ..mesh_buffers.instanced_draw_call(instances, self.grass_texture_array)
+            };
+            self.backend.draw(&call);
+        }
+    }
+
+    fn draw_road(&mut self, light: &Light) {
+        let (Some(buffers), Some(pipeline)) = (&self.road_buffers, self.road_pipeline) else {
+            return;
+        };
+
+        let uniforms = lit_uniforms(&self.camera, light, self.time, &Mat4::identity());
+        self.backend.set_pipeline(pipeline);
+        self.backend
+            .set_uniforms(buffers.uniform_buffer, &UniformBlock::Scene(uniforms));
+        // Default texture until the road gets its own
+        self.backend
+            .draw(&buffers.draw_call(Some(self.default_texture)));
+    }
+
+    fn draw_trees(&mut self, light: &Light) {
+        let (Some((buffers, instances)), Some(pipeline)) = (&self.tree_buffers, self.tree_pipeline)
+        else {
+            return;
+        };
+        if instances.count == 0 {
+            return;
+        }
+
+        let uniforms = TreeUniforms {
+            view_matrix: self.camera.view_matrix(),
+            projection_matrix: self.camera.projection_matrix(),
+            light_position: light.position,
+            time: self.time,
+            view_position: self.camera.position(),
+            _padding: 0.0,
+            sky_gradient_bottom: Vec4::new(HORIZON_COLOR.x, HORIZON_COLOR.y, HORIZON_COLOR.z, 1.0),
+            sky_gradient_top: Vec4::new(ZENITH_COLOR.x, ZENITH_COLOR.y, ZENITH_COLOR.z, 1.0),
+            sun_direction: Vec3::new(0.5, 0.8, 0.3).normalize(),
+            fog_density: FOG_DENSITY,
+            fog_start: FOG_START,
+            _padding2: [0.0, 0.0, 0.0],
+        };
+
+        self.backend.set_pipeline(pipeline);
+        self.backend
+            .set_uniforms(buffers.uniform_buffer, &UniformBlock::Tree(uniforms));
+        self.backend
+            .draw(&buffers.instanced_draw_call(instances, None));
+    }
+
+    fn draw_nodes(&mut self, scene: &Scene) {
+        self.backend.set_pipeline(self.scene_pipeline);
+        let frustum = self.camera.frustum();
+
+        scene.traverse(|id, _, world_transform| {
+            let Some(buffers) = self.node_buffers.get(&id) else {
+                return;
+            };
+            let visible = buffers
+                .mesh
+                .bounds
+                .is_none_or(|bounds| frustum.intersects_sphere(&bounds.transform(world_transform)));
+            if !visible {
+                return;
+            }
+
+            let uniforms = lit_uniforms(&self.camera, &scene.light, self.time, world_transform);
+            self.backend
+                .set_uniforms(buffers.mesh.uniform_buffer, &UniformBlock::Scene(uniforms));
+            let texture = buffers
+                .texture
+                .as_ref()
+                .map_or(self.default_texture, |(_, handle)| *handle);
+            self.backend.draw(&buffers.mesh.draw_call(Some(texture)));
+        });
+    }
+}
+
+/// Builds the Phong + fog uniforms shared by the scene, grass and road shaders
+fn lit_uniforms(camera: &Camera, light: &Light, time: f32, model: &Mat4) -> Uniforms {
+    Uniforms {
+        mvp_matrix: camera.view_projection_matrix().multiply(model),
+        model_matrix: *model,
+        normal_matrix: model.normal_matrix(),
+        view_pos: camera.position(),
+        time,
+        light_pos: light.position,
+        _padding1: 0.0,
+        light_color: light.color,
+        ambient_strength: light.ambient,
+        diffuse_strength: light.diffuse,
+        specular_strength: light.specular,
+        fog_density: FOG_DENSITY,
+        fog_color: FOG_COLOR,
+        fog_start: FOG_START,
+        horizon_color: HORIZON_COLOR,
+        _padding2: 0.0,
+        zenith_color: ZENITH_COLOR,
+        _padding3: 0.0,
+    }
+}
+
+/// Key of a node texture in `Renderer::node_textures`; the node buffers holding the
+/// `Arc` keep the address from being reused while the entry exists
+fn texture_key(image: &Arc<RgbaImage>) -> usize {
+    Arc::as_ptr(image) as usize
+}
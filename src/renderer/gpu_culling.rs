@@ -1,6 +1,7 @@
 //! GPU-driven culling system for efficient vegetation rendering
 
 use crate::math::{Mat4, Vec3, Vec4};
+use crate::scene::Camera;
 use objc2::rc::Retained;
 use objc2::runtime::ProtocolObject;
 use objc2_foundation::NSString;
@@ -113,18 +114,12 @@ impl GpuCullingSystem {
         Ok(pipeline_state)
     }
 
-    pub fn update_culling_uniforms(
-        &self,
-        view_projection: &Mat4,
-        camera_position: &Vec3,
-        cull_distance: f32,
-    ) {
-        // Extract frustum planes from view-projection matrix
-        let frustum_planes = Self::extract_frustum_planes(view_projection);
+    pub fn update_culling_uniforms(&self, camera: &Camera, cull_distance: f32) {
+        let frustum_planes = camera.frustum().to_vec4s();
 
         let uniforms = CullingUniforms {
-            view_projection_matrix: *view_projection,
-            camera_position: *camera_position,
+            view_projection_matrix: camera.view_projection_matrix(),
+            camera_position: camera.position(),
             cull_distance,
             frustum_planes,
         };
@@ -139,66 +134,6 @@ impl GpuCullingSystem {
         }
     }
 
-    fn extract_frustum_planes(view_projection: &Mat4) -> [Vec4; 6] {
-        let m = view_projection;
-
-        // Left plane
-        let left = Vec4::new(
-            m.cols[3].x + m.cols[0].x,
-            m.cols[3].y + m.cols[0].y,
-            m.cols[3].z + m.cols[0].z,
-            m.cols[3].w + m.cols[0].w,
-        )
-        .normalize_plane();
-
-        // Right plane
-        let right = Vec4::new(
-            m.cols[3].x - m.cols[0].x,
-            m.cols[3].y - m.cols[0].y,
-            m.cols[3].z - m.cols[0].z,
-            m.cols[3].w - m.cols[0].w,
-        )
-        .normalize_plane();
-
-        // Bottom plane
-        let bottom = Vec4::new(
-            m.cols[3].x + m.cols[1].x,
-            m.cols[3].y + m.cols[1].y,
-            m.cols[3].z + m.cols[1].z,
-            m.cols[3].w + m.cols[1].w,
-        )
-        .normalize_plane();
-
-        // Top plane
-        let top = Vec4::new(
-            m.cols[3].x - m.cols[1].x,
-            m.cols[3].y - m.cols[1].y,
-            m.cols[3].z - m.cols[1].z,
-            m.cols[3].w - m.cols[1].w,
-        )
-        .normalize_plane();
-
-        // Near plane
-        let near = Vec4::new(
-            m.cols[3].x + m.cols[2].x,
-            m.cols[3].y + m.cols[2].y,
-            m.cols[3].z + m.cols[2].z,
-            m.cols[3].w + m.cols[2].w,
-        )
-        .normalize_plane();
-
-        // Far plane
-        let far = Vec4::new(
-            m.cols[3].x - m.cols[2].x,
-            m.cols[3].y - m.cols[2].y,
-            m.cols[3].z - m.cols[2].z,
-            m.cols[3].w - m.cols[2].w,
-        )
-        .normalize_plane();
-
-        [left, right, bottom, top, near, far]
-    }
-
     /// Executes GPU culling on the provided instances
     ///
     /// # Errors
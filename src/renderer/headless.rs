@@ -0,0 +1,93 @@
+//! Headless offscreen rendering
+//!
+//! [`HeadlessRenderer`] draws into an offscreen target instead of a window: a Metal
+//! texture when a GPU is available, otherwise the CPU [`SoftwareBackend`]. Every frame is
+//! kept for [`Renderer::capture_frame`], so screenshots and golden images work the same
+//! way on both paths.
+
+use crate::renderer::{RenderBackend, Renderer, SoftwareBackend};
+
+/// [`Renderer`] over whichever offscreen backend is available
+pub type HeadlessRenderer = Renderer<Box<dyn RenderBackend>>;
+
+impl HeadlessRenderer {
+    /// Creates an offscreen renderer, preferring the GPU and falling back to the CPU
+    ///
+    /// # Errors
+    /// Returns an error if neither backend can create the default pipelines
+    pub fn headless(width: u32, height: u32) -> Result<Self, String> {
+        #[cfg(all(feature = "metal", target_os = "macos"))]
+        match crate::renderer::MetalBackend::new_offscreen(width, height) {
+            Ok(backend) => return Renderer::new(Box::new(backend), width, height),
+            Err(e) => crate::warn!("Falling back to software rendering: {}", e),
+        }
+
+        Self::software(width, height)
+    }
+
+    /// Creates an offscreen renderer that always rasterizes on the CPU
+    ///
+    /// # Errors
+    /// Returns an error if the default pipelines cannot be created
+    pub fn software(width: u32, height: u32) -> Result<Self, String> {
+        Renderer::new(Box::new(SoftwareBackend::new(width, height)), width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+    use crate::renderer::{rasterize, WorldLayers};
+    use crate::scene::{Mesh, Node, Scene};
+
+    fn cube_scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.add_node(Node::with_mesh("cube".to_string(), Mesh::cube()));
+        scene
+    }
+
+    #[test]
+    fn test_capture_before_render_is_blank() {
+        let renderer = HeadlessRenderer::software(8, 4).unwrap();
+        let image = renderer.capture_frame().unwrap();
+        assert_eq!(image.dimensions(), (8, 4));
+    }
+
+    #[test]
+    fn test_capture_matches_rasterize() {
+        let scene = cube_scene();
+        let mut renderer = HeadlessRenderer::software(32, 24).unwrap();
+        renderer.camera_mut().set_position(Vec3::new(0.0, 0.0, 4.0));
+        let camera = renderer.camera().clone();
+
+        renderer.request_capture();
+        renderer.render(&scene).unwrap();
+        let image = renderer.capture_frame().unwrap();
+
+        let expected = rasterize(&scene, &camera, WorldLayers::default(), 32, 24).unwrap();
+        assert_eq!(image.dimensions(), (32, 24));
+        assert_eq!(image.as_raw().as_slice(), expected.color());
+    }
+
+    #[test]
+    fn test_capture_follows_resize() {
+        let mut renderer = HeadlessRenderer::software(16, 16).unwrap();
+        renderer.resize(40, 10);
+        renderer.render(&cube_scene()).unwrap();
+        assert_eq!(renderer.capture_frame().unwrap().dimensions(), (40, 10));
+    }
+
+    #[test]
+    fn test_save_screenshot_writes_png() {
+        let mut renderer = HeadlessRenderer::software(16, 12).unwrap();
+        renderer.render(&cube_scene()).unwrap();
+
+        let path = std::env::temp_dir().join(format!("headless-{}.png", std::process::id()));
+        renderer.save_screenshot(&path).unwrap();
+        let saved = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(saved, renderer.capture_frame().unwrap());
+    }
+}
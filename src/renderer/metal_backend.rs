@@ -0,0 +1,868 @@
+//! Metal implementation of [`RenderBackend`]
+//!
+//! [`MetalBackend`] draws either into a window's `CAMetalLayer` or into an offscreen
+//! texture. Captured frames are blitted into a shared buffer and read back once the
+//! command buffer completes.
+
+use crate::core::{Texture, TextureArray};
+use crate::math::Vec4;
+use crate::renderer::backend::ResourceSlots;
+use crate::renderer::{
+    BlendMode, BufferData, BufferHandle, DepthMode, DrawCall, PipelineDescriptor, PipelineHandle,
+    RenderBackend, ShaderProgram, SkyboxUniforms, TextureDescriptor, TextureHandle, TreeUniforms,
+    UniformBlock, Uniforms,
+};
+use crate::scene::{DepthMapping, IndexFormat, Vertex};
+use crate::ui::{UIRenderer, UIVertex};
+use image::RgbaImage;
+use objc2::msg_send;
+use objc2::rc::Retained;
+use objc2::runtime::ProtocolObject;
+use objc2_core_foundation::CGSize;
+use objc2_foundation::NSString;
+use objc2_metal::{
+    MTLBlendFactor, MTLBlendOperation, MTLBlitCommandEncoder, MTLBuffer, MTLClearColor,
+    MTLCommandBuffer, MTLCommandEncoder, MTLCommandQueue, MTLCompareFunction, MTLCompileOptions,
+    MTLCreateSystemDefaultDevice, MTLDepthStencilDescriptor, MTLDepthStencilState, MTLDevice,
+    MTLDrawable, MTLIndexType, MTLLibrary, MTLLoadAction, MTLOrigin, MTLPixelFormat,
+    MTLPrimitiveType, MTLRenderCommandEncoder, MTLRenderPassDescriptor,
+    MTLRenderPipelineDescriptor, MTLRenderPipelineState, MTLResourceOptions, MTLSamplerDescriptor,
+    MTLSamplerMinMagFilter, MTLSamplerState, MTLSize, MTLStorageMode, MTLStoreAction, MTLTexture,
+    MTLTextureDescriptor, MTLTextureUsage, MTLVertexDescriptor,
+};
+use objc2_quartz_core::{CAMetalDrawable, CAMetalLayer};
+use winit::raw_window_handle::RawWindowHandle;
+
+struct MetalPipeline {
+    state: Retained<ProtocolObject<dyn MTLRenderPipelineState>>,
+    depth_state: Retained<ProtocolObject<dyn MTLDepthStencilState>>,
+    /// Depth state with flipped comparisons, for reverse-Z frames
+    reversed_depth_state: Retained<ProtocolObject<dyn MTLDepthStencilState>>,
+}
+
+/// Where frames are drawn
+enum RenderTarget {
+    /// A window's layer; frames are presented
+    Layer(Retained<CAMetalLayer>),
+    /// An offscreen color texture; every frame is read back
+    Offscreen(Retained<ProtocolObject<dyn MTLTexture>>),
+}
+
+struct MetalFrame {
+    command_buffer: Retained<ProtocolObject<dyn MTLCommandBuffer>>,
+    encoder: Retained<ProtocolObject<dyn MTLRenderCommandEncoder>>,
+    color_texture: Retained<ProtocolObject<dyn MTLTexture>>,
+    drawable: Option<Retained<ProtocolObject<dyn CAMetalDrawable>>>,
+    reversed_depth: bool,
+}
+
+/// Renders into a window's `CAMetalLayer` or an offscreen texture
+pub struct MetalBackend {
+    device: Retained<ProtocolObject<dyn MTLDevice>>,
+    command_queue: Retained<ProtocolObject<dyn MTLCommandQueue>>,
+    target: RenderTarget,
+    depth_texture: Option<Retained<ProtocolObject<dyn MTLTexture>>>,
+    sampler_state: Retained<ProtocolObject<dyn MTLSamplerState>>,
+    buffers: ResourceSlots<Retained<ProtocolObject<dyn MTLBuffer>>>,
+    textures: ResourceSlots<Retained<ProtocolObject<dyn MTLTexture>>>,
+    pipelines: Vec<MetalPipeline>,
+    frame: Option<MetalFrame>,
+    capture_requested: bool,
+    captured: Option<RgbaImage>,
+}
+
+impl MetalBackend {
+    /// Creates a backend rendering into the view behind `window_handle`
+    ///
+    /// # Errors
+    /// Returns an error if no Metal device is available or setup fails
+    pub fn new(window_handle: RawWindowHandle, width: u32, height: u32) -> Result<Self, String> {
+        let device = MTLCreateSystemDefaultDevice()
+            .ok_or_else(|| "Failed to get default Metal device".to_string())?;
+        let layer = Self::create_metal_layer(&device, window_handle)?;
+        Self::with_target(device, RenderTarget::Layer(layer), width, height)
+    }
+
+    /// Creates a backend rendering into a `width` x `height` offscreen texture
+    ///
+    /// # Errors
+    /// Returns an error if no Metal device is available or setup fails
+    pub fn new_offscreen(width: u32, height: u32) -> Result<Self, String> {
+        let device = MTLCreateSystemDefaultDevice()
+            .ok_or_else(|| "Failed to get default Metal device".to_string())?;
+        let color_texture = Self::create_color_texture(&device, width, height)?;
+        Self::with_target(
+            device,
+            RenderTarget::Offscreen(color_texture),
+            width,
+            height,
+        )
+    }
+
+    fn with_target(
+        device: Retained<ProtocolObject<dyn MTLDevice>>,
+        target: RenderTarget,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, String> {
+        let command_queue = device
+            .newCommandQueue()
+            .ok_or_else(|| "Failed to create command queue".to_string())?;
+
+        let depth_texture = Self::create_depth_texture(&device, width, height)?;
+        let sampler_state = Self::create_sampler_state(&device)?;
+
+        Ok(Self {
+            device,
+            command_queue,
+            target,
+            depth_texture: Some(depth_texture),
+            sampler_state,
+            buffers: ResourceSlots::new(),
+            textures: ResourceSlots::new(),
+            pipelines: Vec::new(),
+            frame: None,
+            capture_requested: false,
+            captured: None,
+        })
+    }
+
+    pub fn device(&self) -> &ProtocolObject<dyn MTLDevice> {
+        &self.device
+    }
+
+    fn create_metal_layer(
+        device: &ProtocolObject<dyn MTLDevice>,
+        window_handle: RawWindowHandle,
+    ) -> Result<Retained<CAMetalLayer>, String> {
+        // Safety: CAMetalLayer::new() is a valid constructor
+        let layer = unsafe { CAMetalLayer::new() };
+
+        // Safety: Setting layer properties with valid values
+        unsafe {
+            layer.setDevice(Some(device));
+            layer.setPixelFormat(MTLPixelFormat::BGRA8Unorm);
+            layer.setOpaque(true);
+            // Drawables must be blit sources for screenshots
+            layer.setFramebufferOnly(false);
+        }
+
+        if let RawWindowHandle::AppKit(handle) = window_handle {
+            let ns_view = handle.ns_view.as_ptr();
+            let ns_view = ns_view.cast::<objc2::runtime::NSObject>();
+            // Safety: ns_view is a valid NSView pointer from the window handle,
+            // and we're setting standard layer properties
+            let _: () = unsafe { msg_send![ns_view, setWantsLayer: true] };
+            let _: () = unsafe { msg_send![ns_view, setLayer: &*layer] };
+        }
+
+        Ok(layer)
+    }
+
+    fn create_color_texture(
+        device: &ProtocolObject<dyn MTLDevice>,
+        width: u32,
+        height: u32,
+    ) -> Result<Retained<ProtocolObject<dyn MTLTexture>>, String> {
+        let descriptor = unsafe { MTLTextureDescriptor::new() };
+        descriptor.setTextureType(objc2_metal::MTLTextureType::Type2D);
+        descriptor.setPixelFormat(MTLPixelFormat::BGRA8Unorm);
+        unsafe {
+            descriptor.setWidth(width as usize);
+            descriptor.setHeight(height as usize);
+        }
+        descriptor.setUsage(MTLTextureUsage::RenderTarget | MTLTextureUsage::ShaderRead);
+        descriptor.setStorageMode(MTLStorageMode::Private);
+
+        let texture = device
+            .newTextureWithDescriptor(&descriptor)
+            .ok_or_else(|| "Failed to create offscreen color texture".to_string())?;
+
+        Ok(texture)
+    }
+
+    fn create_depth_texture(
+        device: &ProtocolObject<dyn MTLDevice>,
+        width: u32,
+        height: u32,
+    ) -> Result<Retained<ProtocolObject<dyn MTLTexture>>, String> {
+        let descriptor = unsafe { MTLTextureDescriptor::new() };
+        descriptor.setTextureType(objc2_metal::MTLTextureType::Type2D);
+        descriptor.setPixelFormat(MTLPixelFormat::Depth32Float);
+        unsafe {
+            descriptor.setWidth(width as usize);
+            descriptor.setHeight(height as usize);
+        }
+        descriptor.setUsage(MTLTextureUsage::RenderTarget);
+
+        let texture = device
+            .newTextureWithDescriptor(&descriptor)
+            .ok_or_else(|| "Failed to create depth texture".to_string())?;
+
+        Ok(texture)
+    }
+
+    fn create_sampler_state(
+        device: &ProtocolObject<dyn MTLDevice>,
+    ) -> Result<Retained<ProtocolObject<dyn MTLSamplerState>>, String> {
+        let descriptor = MTLSamplerDescriptor::new();
+        descriptor.setMinFilter(MTLSamplerMinMagFilter::Nearest);
+        descriptor.setMagFilter(MTLSamplerMinMagFilter::Nearest);
+
+        let sampler = device
+            .newSamplerStateWithDescriptor(&descriptor)
+            .ok_or_else(|| "Failed to create sampler state".to_string())?;
+
+        Ok(sampler)
+    }
+
+    fn create_buffer_with_slice<T>(
+        device: &ProtocolObject<dyn MTLDevice>,
+        data: &[T],
+    ) -> Result<Retained<ProtocolObject<dyn MTLBuffer>>, String> {
+        let buffer_size = std::mem::size_of_val(data);
+
+        let data_ptr = std::ptr::NonNull::new(data.as_ptr().cast::<std::ffi::c_void>().cast_mut())
+            .ok_or_else(|| "Failed to create NonNull pointer for buffer data".to_string())?;
+
+        // Safety: data_ptr points to valid data that lives at least as long as this function call.
+        // The Metal API will copy the data during buffer creation.
+        unsafe {
+            device.newBufferWithBytes_length_options(
+                data_ptr,
+                buffer_size,
+                MTLResourceOptions::empty(),
+            )
+        }
+        .ok_or_else(|| "Failed to create buffer".to_string())
+    }
+
+    /// Shader source, vertex function, fragment function and a label for `program`
+    fn shader_functions(
+        program: ShaderProgram,
+    ) -> (&'static str, &'static str, &'static str, &'static str) {
+        match program {
+            ShaderProgram::Scene => (
+                include_str!("../shaders/cube.metal"),
+                "cube_vertex",
+                "cube_fragment",
+                "scene",
+            ),
+            ShaderProgram::Skybox => (
+                include_str!("../shaders/skybox.metal"),
+                "skybox_vertex",
+                "skybox_fragment",
+                "skybox",
+            ),
+            ShaderProgram::Grass => (
+                include_str!("../shaders/grass.metal"),
+                "grass_vertex",
+                "grass_fragment",
+                "grass",
+            ),
+            ShaderProgram::Road => (
+                include_str!("../shaders/road.metal"),
+                "road_vertex",
+                "road_fragment",
+                "road",
+            ),
+            ShaderProgram::Tree => (
+                include_str!("../shaders/tree.metal"),
+                "tree_vertex",
+                "tree_fragment",
+                "tree",
+            ),
+            ShaderProgram::Ui => (
+                include_str!("../shaders/ui.metal"),
+                "ui_vertex",
+                "ui_fragment",
+                "UI",
+            ),
+        }
+    }
+
+    fn mesh_vertex_descriptor() -> Retained<MTLVertexDescriptor> {
+        let vertex_descriptor = unsafe { MTLVertexDescriptor::new() };
+
+        unsafe {
+            let position_attr = vertex_descriptor.attributes().objectAtIndexedSubscript(0);
+            position_attr.setFormat(objc2_metal::MTLVertexFormat::Float3);
+            position_attr.setOffset(0);
+            position_attr.setBufferIndex(0);
+
+            let tex_coord_attr = vertex_descriptor.attributes().objectAtIndexedSubscript(1);
+            tex_coord_attr.setFormat(objc2_metal::MTLVertexFormat::Float2);
+            tex_coord_attr.setOffset(std::mem::offset_of!(Vertex, tex_coord));
+            tex_coord_attr.setBufferIndex(0);
+
+            let normal_attr = vertex_descriptor.attributes().objectAtIndexedSubscript(2);
+            normal_attr.setFormat(objc2_metal::MTLVertexFormat::Float3);
+            normal_attr.setOffset(std::mem::offset_of!(Vertex, normal));
+            normal_attr.setBufferIndex(0);
+
+            let layout = vertex_descriptor.layouts().objectAtIndexedSubscript(0);
+            layout.setStride(std::mem::size_of::<Vertex>());
+            layout.setStepFunction(objc2_metal::MTLVertexStepFunction::PerVertex);
+            layout.setStepRate(1);
+        }
+
+        vertex_descriptor
+    }
+
+    fn ui_vertex_descriptor() -> Retained<MTLVertexDescriptor> {
+        let vertex_descriptor = unsafe { MTLVertexDescriptor::new() };
+
+        unsafe {
+            let position_attr = vertex_descriptor.attributes().objectAtIndexedSubscript(0);
+            position_attr.setFormat(objc2_metal::MTLVertexFormat::Float2);
+            position_attr.setOffset(0);
+            position_attr.setBufferIndex(0);
+
+            let tex_coord_attr = vertex_descriptor.attributes().objectAtIndexedSubscript(1);
+            tex_coord_attr.setFormat(objc2_metal::MTLVertexFormat::Float2);
+            tex_coord_attr.setOffset(std::mem::offset_of!(UIVertex, uv));
+            tex_coord_attr.setBufferIndex(0);
+
+            let color_attr = vertex_descriptor.attributes().objectAtIndexedSubscript(2);
+            color_attr.setFormat(objc2_metal::MTLVertexFormat::Float4);
+            color_attr.setOffset(std::mem::offset_of!(UIVertex, color));
+            color_attr.setBufferIndex(0);
+
+            let layout = vertex_descriptor.layouts().objectAtIndexedSubscript(0);
+            layout.setStride(std::mem::size_of::<UIVertex>());
+        }
+
+        vertex_descriptor
+    }
+
+    fn create_pipeline_state(
+        device: &ProtocolObject<dyn MTLDevice>,
+        descriptor: &PipelineDescriptor,
+    ) -> Result<Retained<ProtocolObject<dyn MTLRenderPipelineState>>, String> {
+        let (source, vertex_name, fragment_name, label) =
+            Self::shader_functions(descriptor.program);
+        let shader_source = NSString::from_str(source);
+
+        let compile_options = MTLCompileOptions::new();
+        let library = device
+            .newLibraryWithSource_options_error(&shader_source, Some(&compile_options))
+            .map_err(|e| format!("Failed to compile {label} shaders: {:?}", e))?;
+
+        let vertex_function = library
+            .newFunctionWithName(&NSString::from_str(vertex_name))
+            .ok_or_else(|| format!("Failed to find {label} vertex shader"))?;
+
+        let fragment_function = library
+            .newFunctionWithName(&NSString::from_str(fragment_name))
+            .ok_or_else(|| format!("Failed to find {label} fragment shader"))?;
+
+        let vertex_descriptor = if descriptor.program == ShaderProgram::Ui {
+            Self::ui_vertex_descriptor()
+        } else {
+            Self::mesh_vertex_descriptor()
+        };
+
+        let pipeline_descriptor = MTLRenderPipelineDescriptor::new();
+        pipeline_descriptor.setVertexFunction(Some(&vertex_function));
+        pipeline_descriptor.setFragmentFunction(Some(&fragment_function));
+        pipeline_descriptor.setVertexDescriptor(Some(&vertex_descriptor));
+
+        unsafe {
+            let color_attachment = pipeline_descriptor
+                .colorAttachments()
+                .objectAtIndexedSubscript(0);
+            color_attachment.setPixelFormat(MTLPixelFormat::BGRA8Unorm);
+
+            if descriptor.blend == BlendMode::Alpha {
+                color_attachment.setBlendingEnabled(true);
+                color_attachment.setSourceRGBBlendFactor(MTLBlendFactor::SourceAlpha);
+                color_attachment.setDestinationRGBBlendFactor(MTLBlendFactor::OneMinusSourceAlpha);
+                color_attachment.setRgbBlendOperation(MTLBlendOperation::Add);
+                color_attachment.setSourceAlphaBlendFactor(MTLBlendFactor::One);
+                color_attachment
+                    .setDestinationAlphaBlendFactor(MTLBlendFactor::OneMinusSourceAlpha);
+                color_attachment.setAlphaBlendOperation(MTLBlendOperation::Add);
+            }
+        }
+
+        pipeline_descriptor.setDepthAttachmentPixelFormat(MTLPixelFormat::Depth32Float);
+
+        let pipeline_state = device
+            .newRenderPipelineStateWithDescriptor_error(&pipeline_descriptor)
+            .map_err(|e| format!("Failed to create {label} pipeline state: {:?}", e))?;
+
+        Ok(pipeline_state)
+    }
+
+    fn create_depth_stencil_state(
+        device: &ProtocolObject<dyn MTLDevice>,
+        depth: DepthMode,
+        reversed: bool,
+    ) -> Result<Retained<ProtocolObject<dyn MTLDepthStencilState>>, String> {
+        let (compare_function, write_enabled) = match depth {
+            DepthMode::ReadWrite if reversed => (MTLCompareFunction::Greater, true),
+            DepthMode::ReadWrite => (MTLCompareFunction::Less, true),
+            DepthMode::ReadOnly if reversed => (MTLCompareFunction::GreaterEqual, false),
+            DepthMode::ReadOnly => (MTLCompareFunction::LessEqual, false),
+            DepthMode::Disabled => (MTLCompareFunction::Always, false),
+        };
+
+        let descriptor = unsafe { MTLDepthStencilDescriptor::new() };
+        descriptor.setDepthCompareFunction(compare_function);
+        descriptor.setDepthWriteEnabled(write_enabled);
+
+        let state = device
+            .newDepthStencilStateWithDescriptor(&descriptor)
+            .ok_or_else(|| "Failed to create depth stencil state".to_string())?;
+
+        Ok(state)
+    }
+
+    fn buffer(&self, handle: BufferHandle) -> Option<&ProtocolObject<dyn MTLBuffer>> {
+        self.buffers.get(handle).map(|b| &**b)
+    }
+
+    fn texture(&self, handle: TextureHandle) -> Option<&ProtocolObject<dyn MTLTexture>> {
+        self.textures.get(handle).map(|t| &**t)
+    }
+
+    fn write_uniforms<T>(buffer: &ProtocolObject<dyn MTLBuffer>, value: &T) {
+        if buffer.length() < std::mem::size_of::<T>() {
+            return;
+        }
+        // Safety: The buffer holds at least sizeof(T) bytes (checked above) and the
+        // contents pointer is valid for the lifetime of the buffer.
+        unsafe {
+            let contents = buffer.contents();
+            std::ptr::copy_nonoverlapping(
+                std::ptr::from_ref(value),
+                contents.as_ptr().cast::<T>(),
+                1,
+            );
+        }
+    }
+
+    /// Encodes a copy of the frame's color texture into a new shared buffer
+    fn encode_readback(
+        &self,
+        frame: &MetalFrame,
+    ) -> Result<(Retained<ProtocolObject<dyn MTLBuffer>>, u32, u32), String> {
+        let texture = &frame.color_texture;
+        let (width, height) = (texture.width(), texture.height());
+        let bytes_per_row = width * 4;
+
+        let buffer = self
+            .device
+            .newBufferWithLength_options(
+                (bytes_per_row * height).max(1),
+                MTLResourceOptions::StorageModeShared,
+            )
+            .ok_or_else(|| "Failed to create readback buffer".to_string())?;
+        let blit = frame
+            .command_buffer
+            .blitCommandEncoder()
+            .ok_or_else(|| "Failed to create blit command encoder".to_string())?;
+
+        // Safety: The buffer holds bytes_per_row * height bytes, exactly the copied region,
+        // and is kept alive by the caller until the command buffer completes.
+        unsafe {
+            blit.copyFromTexture_sourceSlice_sourceLevel_sourceOrigin_sourceSize_toBuffer_destinationOffset_destinationBytesPerRow_destinationBytesPerImage(
+                texture,
+                0,
+                0,
+                MTLOrigin { x: 0, y: 0, z: 0 },
+                MTLSize {
+                    width,
+                    height,
+                    depth: 1,
+                },
+                &buffer,
+                0,
+                bytes_per_row,
+                bytes_per_row * height,
+            );
+        }
+        blit.endEncoding();
+
+        let width = u32::try_from(width).map_err(|_| "Frame too wide".to_string())?;
+        let height = u32::try_from(height).map_err(|_| "Frame too tall".to_string())?;
+        Ok((buffer, width, height))
+    }
+
+    /// Converts a completed BGRA readback buffer into an RGBA image
+    fn read_back(
+        buffer: &ProtocolObject<dyn MTLBuffer>,
+        width: u32,
+        height: u32,
+    ) -> Result<RgbaImage, String> {
+        let byte_len = width as usize * height as usize * 4;
+        if buffer.length() < byte_len {
+            return Err("Readback buffer is smaller than the frame".to_string());
+        }
+        // Safety: The buffer uses shared storage, holds at least byte_len bytes (checked
+        // above) and the command buffer writing it has completed.
+        let bgra = unsafe {
+            std::slice::from_raw_parts(buffer.contents().as_ptr().cast::<u8>(), byte_len)
+        };
+
+        let mut rgba = bgra.to_vec();
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        RgbaImage::from_raw(width, height, rgba)
+            .ok_or_else(|| "Readback size does not match the frame".to_string())
+    }
+
+    /// Draws the UI overlay with the given UI pipeline using the UI renderer's own buffers
+    pub fn draw_ui(&mut self, pipeline: PipelineHandle, ui_renderer: &UIRenderer) {
+        self.set_pipeline(pipeline);
+        let Some(frame) = &self.frame else {
+            return;
+        };
+        let render_encoder = &frame.encoder;
+
+        unsafe {
+            render_encoder.setVertexBuffer_offset_atIndex(Some(ui_renderer.vertex_buffer()), 0, 0);
+            render_encoder.setVertexBuffer_offset_atIndex(Some(ui_renderer.uniform_buffer()), 0, 1);
+
+            // Bind font texture and sampler
+            render_encoder.setFragmentTexture_atIndex(Some(ui_renderer.font_texture()), 0);
+            render_encoder.setFragmentSamplerState_atIndex(Some(&self.sampler_state), 0);
+
+            if ui_renderer.index_count() > 0 {
+                render_encoder
+                    .drawIndexedPrimitives_indexCount_indexType_indexBuffer_indexBufferOffset(
+                        MTLPrimitiveType::Triangle,
+                        ui_renderer.index_count(),
+                        MTLIndexType::UInt16,
+                        ui_renderer.index_buffer(),
+                        0,
+                    );
+            }
+        }
+    }
+}
+
+impl RenderBackend for MetalBackend {
+    fn create_buffer(&mut self, data: BufferData<'_>) -> Result<BufferHandle, String> {
+        let buffer = match data {
+            BufferData::Vertices(vertices) => {
+                Self::create_buffer_with_slice(&self.device, vertices)?
+            }
+            BufferData::Indices16(indices) => {
+                Self::create_buffer_with_slice(&self.device, indices)?
+            }
+            BufferData::Indices32(indices) => {
+                Self::create_buffer_with_slice(&self.device, indices)?
+            }
+            BufferData::Instances(instances) => {
+                Self::create_buffer_with_slice(&self.device, instances)?
+            }
+            BufferData::Empty(bytes) => self
+                .device
+                .newBufferWithLength_options(bytes, MTLResourceOptions::empty())
+                .ok_or_else(|| "Failed to create buffer".to_string())?,
+        };
+
+        self.buffers.insert(buffer)
+    }
+
+    fn update_buffer(&mut self, buffer: BufferHandle, data: BufferData<'_>) -> Result<(), String> {
+        let target = self
+            .buffer(buffer)
+            .ok_or_else(|| format!("Unknown buffer handle {}", buffer.index()))?;
+
+        let source = match data {
+            BufferData::Vertices(vertices) => vertices.as_ptr().cast::<u8>(),
+            BufferData::Indices16(indices) => indices.as_ptr().cast::<u8>(),
+            BufferData::Indices32(indices) => indices.as_ptr().cast::<u8>(),
+            BufferData::Instances(instances) => instances.as_ptr().cast::<u8>(),
+            BufferData::Empty(_) => return Ok(()),
+        };
+        let byte_len = data.byte_len();
+        if byte_len > target.length() {
+            return Err(format!(
+                "Buffer update of {byte_len} bytes exceeds buffer size {}",
+                target.length()
+            ));
+        }
+
+        // Safety: source points to byte_len readable bytes and the destination buffer
+        // holds at least byte_len bytes (checked above).
+        unsafe {
+            let contents = target.contents();
+            std::ptr::copy_nonoverlapping(source, contents.as_ptr().cast::<u8>(), byte_len);
+        }
+        Ok(())
+    }
+
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureHandle, String> {
+        let texture = if descriptor.layers > 1 {
+            TextureArray::create_from_data(
+                &self.device,
+                data,
+                descriptor.width,
+                descriptor.height,
+                descriptor.layers,
+                descriptor.format,
+            )?
+            .texture
+        } else {
+            Texture::create_from_data(
+                &self.device,
+                data,
+                descriptor.width,
+                descriptor.height,
+                descriptor.format,
+            )?
+            .texture
+        };
+
+        self.textures.insert(texture)
+    }
+
+    // Command buffers retain the resources they use, so frames in flight keep
+    // destroyed resources alive until they complete
+    fn destroy_buffer(&mut self, buffer: BufferHandle) {
+        self.buffers.remove(buffer);
+    }
+
+    fn destroy_texture(&mut self, texture: TextureHandle) {
+        self.textures.remove(texture);
+    }
+
+    fn create_pipeline(
+        &mut self,
+        descriptor: &PipelineDescriptor,
+    ) -> Result<PipelineHandle, String> {
+        let state = Self::create_pipeline_state(&self.device, descriptor)?;
+        let depth_state = Self::create_depth_stencil_state(&self.device, descriptor.depth, false)?;
+        let reversed_depth_state =
+            Self::create_depth_stencil_state(&self.device, descriptor.depth, true)?;
+
+        let index =
+            u32::try_from(self.pipelines.len()).map_err(|_| "Too many pipelines".to_string())?;
+        self.pipelines.push(MetalPipeline {
+            state,
+            depth_state,
+            reversed_depth_state,
+        });
+        Ok(PipelineHandle::from_index(index))
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        match &mut self.target {
+            RenderTarget::Layer(layer) => {
+                let size = CGSize {
+                    width: f64::from(width),
+                    height: f64::from(height),
+                };
+                unsafe {
+                    layer.setDrawableSize(size);
+                }
+            }
+            RenderTarget::Offscreen(texture) => {
+                if let Ok(color_texture) = Self::create_color_texture(&self.device, width, height) {
+                    *texture = color_texture;
+                }
+            }
+        }
+
+        // Recreate depth texture with new size
+        if let Ok(depth_texture) = Self::create_depth_texture(&self.device, width, height) {
+            self.depth_texture = Some(depth_texture);
+        }
+    }
+
+    fn begin_frame(&mut self, clear_color: Vec4, depth: DepthMapping) -> Result<(), String> {
+        let (color_texture, drawable) = match &self.target {
+            RenderTarget::Layer(layer) => {
+                let drawable = unsafe { layer.nextDrawable() }
+                    .ok_or_else(|| "Failed to get next drawable".to_string())?;
+                (drawable.texture(), Some(drawable))
+            }
+            RenderTarget::Offscreen(texture) => (texture.clone(), None),
+        };
+
+        let command_buffer = self
+            .command_queue
+            .commandBuffer()
+            .ok_or_else(|| "Failed to create command buffer".to_string())?;
+
+        let label = NSString::from_str("Scene Render Pass");
+        command_buffer.setLabel(Some(&label));
+
+        let render_pass_descriptor = unsafe { MTLRenderPassDescriptor::new() };
+        let color_attachment = unsafe {
+            render_pass_descriptor
+                .colorAttachments()
+                .objectAtIndexedSubscript(0)
+        };
+
+        unsafe {
+            color_attachment.setTexture(Some(&color_texture));
+            color_attachment.setLoadAction(MTLLoadAction::Clear);
+            color_attachment.setClearColor(MTLClearColor {
+                red: f64::from(clear_color.x),
+                green: f64::from(clear_color.y),
+                blue: f64::from(clear_color.z),
+                alpha: f64::from(clear_color.w),
+            });
+            color_attachment.setStoreAction(MTLStoreAction::Store);
+        }
+
+        if let Some(depth_texture) = &self.depth_texture {
+            let depth_attachment = render_pass_descriptor.depthAttachment();
+            depth_attachment.setTexture(Some(depth_texture));
+            depth_attachment.setLoadAction(MTLLoadAction::Clear);
+            depth_attachment.setClearDepth(f64::from(depth.clear_depth()));
+            depth_attachment.setStoreAction(MTLStoreAction::DontCare);
+        }
+
+        let encoder = command_buffer
+            .renderCommandEncoderWithDescriptor(&render_pass_descriptor)
+            .ok_or_else(|| "Failed to create render command encoder".to_string())?;
+        let label = NSString::from_str("Scene Encoder");
+        encoder.setLabel(Some(&label));
+
+        self.frame = Some(MetalFrame {
+            command_buffer,
+            encoder,
+            color_texture,
+            drawable,
+            reversed_depth: depth.is_reversed(),
+        });
+        Ok(())
+    }
+
+    fn set_pipeline(&mut self, pipeline: PipelineHandle) {
+        let (Some(frame), Some(pipeline)) =
+            (&self.frame, self.pipelines.get(pipeline.index() as usize))
+        else {
+            return;
+        };
+        frame.encoder.setRenderPipelineState(&pipeline.state);
+        let depth_state = if frame.reversed_depth {
+            &pipeline.reversed_depth_state
+        } else {
+            &pipeline.depth_state
+        };
+        frame.encoder.setDepthStencilState(Some(depth_state));
+    }
+
+    fn set_uniforms(&mut self, buffer: BufferHandle, block: &UniformBlock) {
+        let Some(buffer) = self.buffer(buffer) else {
+            return;
+        };
+        match block {
+            UniformBlock::Scene(uniforms) => Self::write_uniforms::<Uniforms>(buffer, uniforms),
+            UniformBlock::Skybox(uniforms) => {
+                Self::write_uniforms::<SkyboxUniforms>(buffer, uniforms);
+            }
+            UniformBlock::Tree(uniforms) => Self::write_uniforms::<TreeUniforms>(buffer, uniforms),
+        }
+    }
+
+    fn draw(&mut self, call: &DrawCall) {
+        let Some(frame) = &self.frame else {
+            return;
+        };
+        let (Some(vertex_buffer), Some(index_buffer), Some(uniform_buffer)) = (
+            self.buffer(call.vertex_buffer),
+            self.buffer(call.index_buffer),
+            self.buffer(call.uniforms),
+        ) else {
+            return;
+        };
+        let render_encoder = &frame.encoder;
+        let index_type = match call.index_format {
+            IndexFormat::U16 => MTLIndexType::UInt16,
+            IndexFormat::U32 => MTLIndexType::UInt32,
+        };
+
+        unsafe {
+            render_encoder.setVertexBuffer_offset_atIndex(Some(vertex_buffer), 0, 0);
+            render_encoder.setVertexBuffer_offset_atIndex(Some(uniform_buffer), 0, 1);
+            render_encoder.setFragmentBuffer_offset_atIndex(Some(uniform_buffer), 0, 1);
+
+            if let Some(texture) = call.texture.and_then(|handle| self.texture(handle)) {
+                render_encoder.setFragmentTexture_atIndex(Some(texture), 0);
+                render_encoder.setFragmentSamplerState_atIndex(Some(&self.sampler_state), 0);
+            }
+
+            if let Some(instance_buffer) = call.instances.and_then(|handle| self.buffer(handle)) {
+                render_encoder.setVertexBuffer_offset_atIndex(Some(instance_buffer), 0, 2);
+
+                // Use drawIndexedPrimitives:indexCount:indexType:indexBuffer:indexBufferOffset:instanceCount:
+                let _: () = msg_send![
+                    &**render_encoder,
+                    drawIndexedPrimitives: MTLPrimitiveType::Triangle,
+                    indexCount: call.index_count,
+                    indexType: index_type,
+                    indexBuffer: index_buffer,
+                    indexBufferOffset: 0usize,
+                    instanceCount: call.instance_count
+                ];
+            } else {
+                render_encoder
+                    .drawIndexedPrimitives_indexCount_indexType_indexBuffer_indexBufferOffset(
+                        MTLPrimitiveType::Triangle,
+                        call.index_count,
+                        index_type,
+                        index_buffer,
+                        0,
+                    );
+            }
+        }
+    }
+
+    fn end_frame(&mut self) -> Result<(), String> {
+        let frame = self
+            .frame
+            .take()
+            .ok_or_else(|| "end_frame called without begin_frame".to_string())?;
+
+        frame.encoder.endEncoding();
+
+        let capture = self.capture_requested || matches!(self.target, RenderTarget::Offscreen(_));
+        self.capture_requested = false;
+        let readback = if capture {
+            Some(self.encode_readback(&frame)?)
+        } else {
+            None
+        };
+
+        if let Some(drawable) = &frame.drawable {
+            // Safety: The drawable is a valid CAMetalDrawable that conforms to MTLDrawable protocol.
+            // The cast is safe because CAMetalDrawable implements MTLDrawable.
+            unsafe {
+                let mtl_drawable =
+                    (&raw const **drawable).cast::<ProtocolObject<dyn MTLDrawable>>();
+                frame.command_buffer.presentDrawable(&*mtl_drawable);
+            }
+        }
+
+        frame.command_buffer.commit();
+
+        if let Some((buffer, width, height)) = readback {
+            frame.command_buffer.waitUntilCompleted();
+            self.captured = Some(Self::read_back(&buffer, width, height)?);
+        }
+
+        Ok(())
+    }
+
+    fn request_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    fn capture_frame(&self) -> Result<RgbaImage, String> {
+        self.captured
+            .clone()
+            .ok_or_else(|| "No frame has been captured; call request_capture first".to_string())
+    }
+}
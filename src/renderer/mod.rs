@@ -1,5 +1,36 @@
+//! Rendering backends
+//!
+//! [`Renderer`] draws a frame through any [`RenderBackend`]. [`RecordingBackend`] records
+//! commands without a GPU and [`SoftwareBackend`] rasterizes them on the CPU.
+//! [`HeadlessRenderer`] renders offscreen for screenshots and tests. The Metal backend,
+//! [`SceneRenderer`] and GPU culling system require the `metal` feature on macOS.
+
+mod backend;
+mod cpu_shaders;
+mod frame;
+#[cfg(all(feature = "metal", target_os = "macos"))]
 mod gpu_culling;
+mod headless;
+#[cfg(all(feature = "metal", target_os = "macos"))]
+mod metal_backend;
+mod recording;
+#[cfg(all(feature = "metal", target_os = "macos"))]
 mod scene_renderer;
+mod software;
+mod uniforms;
 
+pub use backend::{
+    BlendMode, BufferData, BufferHandle, DepthMode, DrawCall, PipelineDescriptor, PipelineHandle,
+    RenderBackend, ShaderProgram, TextureDescriptor, TextureHandle,
+};
+pub use frame::Renderer;
+#[cfg(all(feature = "metal", target_os = "macos"))]
 pub use gpu_culling::GpuCullingSystem;
+pub use headless::HeadlessRenderer;
+#[cfg(all(feature = "metal", target_os = "macos"))]
+pub use metal_backend::MetalBackend;
+pub use recording::{BufferContents, RecordingBackend, RenderCommand};
+#[cfg(all(feature = "metal", target_os = "macos"))]
 pub use scene_renderer::SceneRenderer;
+pub use software::{rasterize, Framebuffer, SoftwareBackend, WorldLayers};
+pub use uniforms::{SkyboxUniforms, TreeUniforms, UniformBlock, Uniforms};
@@ -0,0 +1,743 @@
+//! Recording "null" backend
+//!
+//! [`RecordingBackend`] implements [`RenderBackend`] without a GPU. It keeps the contents
+//! of every buffer and records each frame as a list of [`RenderCommand`]s so tests can
+//! inspect draw calls, uniform blocks and instance counts.
+
+use crate::math::Vec4;
+use crate::renderer::{
+    BufferData, BufferHandle, DrawCall, PipelineDescriptor, PipelineHandle, RenderBackend,
+    ShaderProgram, TextureDescriptor, TextureHandle, UniformBlock,
+};
+use crate::scene::{DepthMapping, InstanceData, Vertex};
+use image::RgbaImage;
+
+/// A single operation recorded during a frame
+#[allow(clippy::large_enum_variant)] // Commands are stored by value for easy inspection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderCommand {
+    BeginFrame {
+        clear_color: Vec4,
+        depth: DepthMapping,
+    },
+    SetPipeline(PipelineHandle),
+    SetUniforms {
+        buffer: BufferHandle,
+        block: UniformBlock,
+    },
+    Draw(DrawCall),
+    EndFrame,
+}
+
+/// Last data written to a recorded buffer
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum BufferContents {
+    Vertices(Vec<Vertex>),
+    Indices16(Vec<u16>),
+    Indices32(Vec<u32>),
+    Instances(Vec<InstanceData>),
+    Uniforms(UniformBlock),
+    /// Allocated but never written
+    Empty,
+}
+
+#[derive(Debug, Clone)]
+struct RecordedBuffer {
+    byte_len: usize,
+    contents: BufferContents,
+}
+
+/// [`RenderBackend`] that records commands instead of drawing
+///
+/// Handles are never reused, so a destroyed buffer or texture stays `None` and commands
+/// recorded before its destruction still refer to it unambiguously.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingBackend {
+    buffers: Vec<Option<RecordedBuffer>>,
+    textures: Vec<Option<TextureDescriptor>>,
+    pipelines: Vec<PipelineDescriptor>,
+    size: (u32, u32),
+    commands: Vec<RenderCommand>,
+    in_frame: bool,
+    frames_submitted: usize,
+}
+
+impl RecordingBackend {
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            size: (width, height),
+            ..Self::default()
+        }
+    }
+
+    /// All commands recorded since creation or the last [`clear_commands`](Self::clear_commands)
+    #[must_use]
+    pub fn commands(&self) -> &[RenderCommand] {
+        &self.commands
+    }
+
+    pub fn clear_commands(&mut self) {
+        self.commands.clear();
+    }
+
+    /// All recorded draw calls in submission order
+    pub fn draw_calls(&self) -> impl Iterator<Item = &DrawCall> {
+        self.commands.iter().filter_map(|command| match command {
+            RenderCommand::Draw(call) => Some(call),
+            _ => None,
+        })
+    }
+
+    /// Draw calls issued while a pipeline using `program` was bound
+    #[must_use]
+    pub fn draws_with(&self, program: ShaderProgram) -> Vec<DrawCall> {
+        let mut current = None;
+        let mut draws = Vec::new();
+        for command in &self.commands {
+            match command {
+                RenderCommand::SetPipeline(handle) => {
+                    current = self.pipeline(*handle).map(|descriptor| descriptor.program);
+                }
+                RenderCommand::Draw(call) if current == Some(program) => draws.push(*call),
+                _ => {}
+            }
+        }
+        draws
+    }
+
+    /// Uniform blocks written in submission order
+    pub fn uniform_blocks(&self) -> impl Iterator<Item = &UniformBlock> {
+        self.commands.iter().filter_map(|command| match command {
+            RenderCommand::SetUniforms { block, .. } => Some(block),
+            _ => None,
+        })
+    }
+
+    #[must_use]
+    pub fn buffer(&self, handle: BufferHandle) -> Option<&BufferContents> {
+        self.live_buffer(handle).map(|buffer| &buffer.contents)
+    }
+
+    #[must_use]
+    pub fn buffer_len(&self, handle: BufferHandle) -> Option<usize> {
+        self.live_buffer(handle).map(|buffer| buffer.byte_len)
+    }
+
+    #[must_use]
+    pub fn texture(&self, handle: TextureHandle) -> Option<&TextureDescriptor> {
+        self.textures.get(handle.index() as usize)?.as_ref()
+    }
+
+    #[must_use]
+    pub fn pipeline(&self, handle: PipelineHandle) -> Option<&PipelineDescriptor> {
+        self.pipelines.get(handle.index() as usize)
+    }
+
+    /// Number of buffers created and not yet destroyed
+    #[must_use]
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.iter().flatten().count()
+    }
+
+    /// Number of textures created and not yet destroyed
+    #[must_use]
+    pub fn texture_count(&self) -> usize {
+        self.textures.iter().flatten().count()
+    }
+
+    #[must_use]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    #[must_use]
+    pub fn frames_submitted(&self) -> usize {
+        self.frames_submitted
+    }
+
+    fn live_buffer(&self, handle: BufferHandle) -> Option<&RecordedBuffer> {
+        self.buffers.get(handle.index() as usize)?.as_ref()
+    }
+
+    fn buffer_mut(&mut self, handle: BufferHandle) -> Result<&mut RecordedBuffer, String> {
+        self.buffers
+            .get_mut(handle.index() as usize)
+            .and_then(Option::as_mut)
+            .ok_or_else(|| format!("Unknown buffer handle {}", handle.index()))
+    }
+}
+
+fn contents_of(data: BufferData<'_>) -> BufferContents {
+    match data {
+        BufferData::Vertices(vertices) => BufferContents::Vertices(vertices.to_vec()),
+        BufferData::Indices16(indices) => BufferContents::Indices16(indices.to_vec()),
+        BufferData::Indices32(indices) => BufferContents::Indices32(indices.to_vec()),
+        BufferData::Instances(instances) => BufferContents::Instances(instances.to_vec()),
+        BufferData::Empty(_) => BufferContents::Empty,
+    }
+}
+
+fn next_index(len: usize) -> Result<u32, String> {
+    u32::try_from(len).map_err(|_| "Too many resources".to_string())
+}
+
+impl RenderBackend for RecordingBackend {
+    fn create_buffer(&mut self, data: BufferData<'_>) -> Result<BufferHandle, String> {
+        let handle = BufferHandle::from_index(next_index(self.buffers.len())?);
+        self.buffers.push(Some(RecordedBuffer {
+            byte_len: data.byte_len(),
+            contents: contents_of(data),
+        }));
+        Ok(handle)
+    }
+
+    fn update_buffer(&mut self, buffer: BufferHandle, data: BufferData<'_>) -> Result<(), String> {
+        if matches!(data, BufferData::Empty(_)) {
+            return Ok(());
+        }
+        let target = self.buffer_mut(buffer)?;
+        if data.byte_len() > target.byte_len {
+            return Err(format!(
+                "Buffer update of {} bytes exceeds buffer size {}",
+                data.byte_len(),
+                target.byte_len
+            ));
+        }
+        target.contents = contents_of(data);
+        Ok(())
+    }
+
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureHandle, String> {
+        if data.len() != descriptor.byte_len() {
+            return Err(format!(
+                "Texture data size mismatch: expected {}, got {}",
+                descriptor.byte_len(),
+                data.len()
+            ));
+        }
+        let handle = TextureHandle::from_index(next_index(self.textures.len())?);
+        self.textures.push(Some(*descriptor));
+        Ok(handle)
+    }
+
+    fn destroy_buffer(&mut self, buffer: BufferHandle) {
+        if let Some(slot) = self.buffers.get_mut(buffer.index() as usize) {
+            *slot = None;
+        }
+    }
+
+    fn destroy_texture(&mut self, texture: TextureHandle) {
+        if let Some(slot) = self.textures.get_mut(texture.index() as usize) {
+            *slot = None;
+        }
+    }
+
+    fn create_pipeline(
+        &mut self,
+        descriptor: &PipelineDescriptor,
+    ) -> Result<PipelineHandle, String> {
+        let handle = PipelineHandle::from_index(next_index(self.pipelines.len())?);
+        self.pipelines.push(*descriptor);
+        Ok(handle)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.size = (width, height);
+    }
+
+    fn begin_frame(&mut self, clear_color: Vec4, depth: DepthMapping) -> Result<(), String> {
+        if self.in_frame {
+            return Err("begin_frame called twice without end_frame".to_string());
+        }
+        self.in_frame = true;
+        self.commands
+            .push(RenderCommand::BeginFrame { clear_color, depth });
+        Ok(())
+    }
+
+    fn set_pipeline(&mut self, pipeline: PipelineHandle) {
+        self.commands.push(RenderCommand::SetPipeline(pipeline));
+    }
+
+    fn set_uniforms(&mut self, buffer: BufferHandle, block: &UniformBlock) {
+        if let Ok(target) = self.buffer_mut(buffer) {
+            target.contents = BufferContents::Uniforms(*block);
+        }
+        self.commands.push(RenderCommand::SetUniforms {
+            buffer,
+            block: *block,
+        });
+    }
+
+    fn draw(&mut self, call: &DrawCall) {
+        self.commands.push(RenderCommand::Draw(*call));
+    }
+
+    fn end_frame(&mut self) -> Result<(), String> {
+        if !self.in_frame {
+            return Err("end_frame called without begin_frame".to_string());
+        }
+        self.in_frame = false;
+        self.frames_submitted += 1;
+        self.commands.push(RenderCommand::EndFrame);
+        Ok(())
+    }
+
+    fn capture_frame(&self) -> Result<RgbaImage, String> {
+        Err("The recording backend has no color target to capture".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GrassSystem, RoadSystem, Skybox, TreeSystem};
+    use crate::math::{Frustum, Mat4, Vec3};
+    use crate::renderer::Renderer;
+    use crate::scene::{IndexFormat, Mesh, Node, Scene};
+
+    fn renderer() -> Renderer<RecordingBackend> {
+        Renderer::new(RecordingBackend::new(800, 600), 800, 600).unwrap()
+    }
+
+    fn frustum(renderer: &Renderer<RecordingBackend>) -> Frustum {
+        renderer.camera().frustum()
+    }
+
+    /// Visible blades at each LOD level, in `LodLevel::ALL` order
+    fn visible_grass(grass: &GrassSystem, frustum: &Frustum) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for index in grass.visible_instances(frustum) {
+            counts[grass.instances()[index].lod_level as usize] += 1;
+        }
+        counts
+    }
+
+    fn cube_scene() -> Scene {
+        let mut scene = Scene::new();
+        let mut node = Node::with_mesh("cube".to_string(), Mesh::cube());
+        node.set_position(Vec3::new(1.0, 2.0, 3.0));
+        scene.add_node(node);
+        scene
+    }
+
+    #[test]
+    fn test_empty_scene_records_only_frame_boundaries() {
+        let mut renderer = renderer();
+        renderer.render(&Scene::new()).unwrap();
+
+        let commands = renderer.backend().commands();
+        assert!(matches!(
+            commands.first(),
+            Some(RenderCommand::BeginFrame { .. })
+        ));
+        assert_eq!(commands.last(), Some(&RenderCommand::EndFrame));
+        assert_eq!(renderer.backend().draw_calls().count(), 0);
+        assert_eq!(renderer.backend().frames_submitted(), 1);
+    }
+
+    #[test]
+    fn test_scene_node_draw_uses_world_transform() {
+        let mut renderer = renderer();
+        let scene = cube_scene();
+        renderer.render(&scene).unwrap();
+
+        let draws = renderer.backend().draws_with(ShaderProgram::Scene);
+        assert_eq!(draws.len(), 1);
+        assert_eq!(draws[0].index_count, Mesh::cube().indices.len());
+        assert_eq!(draws[0].instance_count, 1);
+        assert!(draws[0].texture.is_some());
+
+        let Some(BufferContents::Uniforms(UniformBlock::Scene(uniforms))) =
+            renderer.backend().buffer(draws[0].uniforms)
+        else {
+            panic!("scene draw should have scene uniforms");
+        };
+        assert_eq!(uniforms.model_matrix.cols[3].x, 1.0);
+        assert_eq!(uniforms.model_matrix.cols[3].y, 2.0);
+        assert_eq!(uniforms.model_matrix.cols[3].z, 3.0);
+        assert_eq!(uniforms.light_pos, scene.light.position);
+    }
+
+    #[test]
+    fn test_nodes_outside_frustum_are_culled() {
+        let mut renderer = renderer();
+        let mut scene = cube_scene();
+        let mut behind = Node::with_mesh("behind".to_string(), Mesh::cube());
+        behind.set_position(Vec3::new(10.0, 10.0, 10.0));
+        scene.add_node(behind);
+        renderer.render(&scene).unwrap();
+
+        assert_eq!(renderer.backend().draws_with(ShaderProgram::Scene).len(), 1);
+    }
+
+    #[test]
+    fn test_new_scene_does_not_reuse_node_buffers() {
+        let mut renderer = renderer();
+        let first = cube_scene();
+        renderer.render(&first).unwrap();
+        let buffers = renderer.backend().buffer_count();
+
+        // Same slot, generation and revision as the cube, but a different mesh
+        let mut second = Scene::new();
+        let plane = Mesh::plane(1.0, 1.0);
+        let plane_indices = plane.indices.len();
+        let mut node = Node::with_mesh("plane".to_string(), plane);
+        node.set_position(Vec3::new(1.0, 2.0, 3.0));
+        let id = second.add_node(node);
+        assert_eq!(Some(id), first.find_by_name("cube"));
+        assert_ne!(first.id(), second.id());
+
+        renderer.render(&second).unwrap();
+        let draws = renderer.backend().draws_with(ShaderProgram::Scene);
+        assert_eq!(draws.last().unwrap().index_count, plane_indices);
+        assert_eq!(renderer.backend().buffer_count(), buffers);
+    }
+
+    #[test]
+    fn test_node_buffers_follow_scene_edits() {
+        let mut renderer = renderer();
+        let mut scene = cube_scene();
+        let cube = scene.find_by_name("cube").unwrap();
+        renderer.render(&scene).unwrap();
+
+        // A replaced mesh is uploaded again
+        let plane = Mesh::plane(1.0, 1.0);
+        let plane_indices = plane.indices.len();
+        scene.get_mut(cube).unwrap().set_mesh(Some(plane));
+        renderer.render(&scene).unwrap();
+        let draws = renderer.backend().draws_with(ShaderProgram::Scene);
+        assert_eq!(draws.last().unwrap().index_count, plane_indices);
+
+        // A node added in the removed node's slot does not inherit its buffers
+        scene.remove(cube);
+        let mut replacement = Node::new("empty".to_string());
+        replacement.set_position(Vec3::new(1.0, 2.0, 3.0));
+        scene.add_node(replacement);
+        let before = renderer.backend().draws_with(ShaderProgram::Scene).len();
+        renderer.render(&scene).unwrap();
+        assert_eq!(
+            renderer.backend().draws_with(ShaderProgram::Scene).len(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_node_texture_replaces_default_texture() {
+        let mut renderer = renderer();
+        let mut scene = cube_scene();
+        let mut textured = Node::with_mesh("textured".to_string(), Mesh::cube());
+        textured.set_position(Vec3::new(1.0, 2.0, 3.0));
+        textured.set_texture(Some(std::sync::Arc::new(image::RgbaImage::new(4, 4))));
+        scene.add_node(textured);
+        renderer.render(&scene).unwrap();
+
+        let draws = renderer.backend().draws_with(ShaderProgram::Scene);
+        assert_eq!(draws.len(), 2);
+        assert!(draws[0].texture.is_some() && draws[1].texture.is_some());
+        assert_ne!(draws[0].texture, draws[1].texture);
+    }
+
+    #[test]
+    fn test_node_resources_are_shared_and_released() {
+        let mut renderer = renderer();
+        let mut scene = Scene::new();
+        renderer.render(&scene).unwrap();
+        let (buffers, textures) = (
+            renderer.backend().buffer_count(),
+            renderer.backend().texture_count(),
+        );
+
+        // Two nodes sharing one image upload it once
+        let image = std::sync::Arc::new(image::RgbaImage::new(4, 4));
+        let ids: Vec<_> = (0..2)
+            .map(|i| {
+                let mut node = Node::with_mesh(format!("textured {i}"), Mesh::cube());
+                node.set_texture(Some(image.clone()));
+                scene.add_node(node)
+            })
+            .collect();
+        renderer.render(&scene).unwrap();
+        let draws = renderer.backend().draws_with(ShaderProgram::Scene);
+        assert_eq!(draws[0].texture, draws[1].texture);
+        assert_eq!(renderer.backend().texture_count(), textures + 1);
+        let with_nodes = renderer.backend().buffer_count();
+
+        // Replacing a mesh swaps its buffers instead of adding to them
+        for _ in 0..3 {
+            scene.get_mut(ids[0]).unwrap().set_mesh(Some(Mesh::cube()));
+            renderer.render(&scene).unwrap();
+        }
+        assert_eq!(renderer.backend().buffer_count(), with_nodes);
+        assert_eq!(renderer.backend().texture_count(), textures + 1);
+
+        // The texture goes with the last node using it
+        scene.remove(ids[0]);
+        renderer.render(&scene).unwrap();
+        assert_eq!(renderer.backend().texture_count(), textures + 1);
+        scene.get_mut(ids[1]).unwrap().set_texture(None);
+        renderer.render(&scene).unwrap();
+        assert_eq!(renderer.backend().texture_count(), textures);
+        scene.remove(ids[1]);
+        renderer.render(&scene).unwrap();
+        assert_eq!(renderer.backend().buffer_count(), buffers);
+    }
+
+    #[test]
+    fn test_index_format_follows_vertex_count() {
+        let mut renderer = renderer();
+        let mut scene = cube_scene();
+        let cube = scene.find_by_name("cube").unwrap();
+        renderer.render(&scene).unwrap();
+        let draw = renderer.backend().draws_with(ShaderProgram::Scene)[0];
+        assert_eq!(draw.index_format, IndexFormat::U16);
+        assert!(matches!(
+            renderer.backend().buffer(draw.index_buffer),
+            Some(BufferContents::Indices16(_))
+        ));
+
+        // Past 65536 vertices the mesh is drawn with 32-bit indices
+        let mut large = Mesh::cube();
+        large.vertices.resize(70_000, large.vertices[0]);
+        large.indices.extend([0, 1, 69_999]);
+        scene.get_mut(cube).unwrap().set_mesh(Some(large));
+        renderer.render(&scene).unwrap();
+        let draw = *renderer
+            .backend()
+            .draws_with(ShaderProgram::Scene)
+            .last()
+            .unwrap();
+        assert_eq!(draw.index_format, IndexFormat::U32);
+        let Some(BufferContents::Indices32(indices)) = renderer.backend().buffer(draw.index_buffer)
+        else {
+            panic!("expected 32-bit indices");
+        };
+        assert_eq!(indices.last(), Some(&69_999));
+
+        // Indices past the end of the vertex buffer are rejected instead of drawn
+        let mut broken = Mesh::cube();
+        broken.indices.push(24);
+        scene.get_mut(cube).unwrap().set_mesh(Some(broken));
+        assert!(matches!(renderer.render(&scene), Err(e) if e.contains("out of range")));
+    }
+
+    #[test]
+    fn test_normal_matrix_is_inverse_transpose() {
+        let mut renderer = renderer();
+        let mut scene = Scene::new();
+        let mut node = Node::with_mesh("stretched".to_string(), Mesh::cube());
+        node.transform_mut().scale = Vec3::new(4.0, 1.0, 0.5);
+        scene.add_node(node);
+        renderer.render(&scene).unwrap();
+
+        let draws = renderer.backend().draws_with(ShaderProgram::Scene);
+        let Some(BufferContents::Uniforms(UniformBlock::Scene(uniforms))) =
+            renderer.backend().buffer(draws[0].uniforms)
+        else {
+            panic!("scene draw should have scene uniforms");
+        };
+        assert_eq!(
+            uniforms.normal_matrix,
+            uniforms.model_matrix.normal_matrix()
+        );
+        assert_eq!(uniforms.normal_matrix.cols[0].x, 0.25);
+        assert_eq!(uniforms.normal_matrix.cols[2].z, 2.0);
+    }
+
+    #[test]
+    fn test_mesh_buffers_are_reused_across_frames() {
+        let mut renderer = renderer();
+        let scene = cube_scene();
+        renderer.render(&scene).unwrap();
+        let buffers = renderer.backend().buffer_count();
+        renderer.render(&scene).unwrap();
+
+        assert_eq!(renderer.backend().buffer_count(), buffers);
+        assert_eq!(renderer.backend().frames_submitted(), 2);
+    }
+
+    #[test]
+    fn test_draw_order_matches_passes() {
+        let mut grass = GrassSystem::new(20.0, 1.0);
+        grass.update(Vec3::new(0.0, 21.0, 0.0));
+        let road = RoadSystem::new(20.0, 0.0, std::f32::consts::PI / 2.0, 3.0);
+        let trees = TreeSystem::new(20.0, 10, 0.0, std::f32::consts::PI / 2.0);
+
+        let mut renderer = renderer();
+        renderer.initialize_skybox(&Skybox::new()).unwrap();
+        renderer.initialize_grass(&grass).unwrap();
+        renderer.initialize_road(&road).unwrap();
+        renderer.initialize_tree(&trees).unwrap();
+        renderer.render(&cube_scene()).unwrap();
+
+        let backend = renderer.backend();
+        let programs: Vec<ShaderProgram> = backend
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::SetPipeline(handle) => backend.pipeline(*handle).map(|p| p.program),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            programs,
+            [
+                ShaderProgram::Skybox,
+                ShaderProgram::Grass,
+                ShaderProgram::Road,
+                ShaderProgram::Tree,
+                ShaderProgram::Scene,
+            ]
+        );
+
+        let tree_draws = backend.draws_with(ShaderProgram::Tree);
+        assert_eq!(tree_draws.len(), 1);
+        assert_eq!(
+            tree_draws[0].instance_count,
+            trees.visible_instances(&frustum(&renderer)).len()
+        );
+        assert!(matches!(
+            backend.buffer(tree_draws[0].uniforms),
+            Some(BufferContents::Uniforms(UniformBlock::Tree(_)))
+        ));
+    }
+
+    #[test]
+    fn test_grass_instance_counts_follow_lod() {
+        let mut grass = GrassSystem::new(20.0, 1.0);
+        grass.update(Vec3::new(0.0, 21.0, 0.0));
+
+        let mut renderer = renderer();
+        renderer.initialize_grass(&grass).unwrap();
+        renderer.render(&Scene::new()).unwrap();
+
+        let expected: Vec<usize> = visible_grass(&grass, &frustum(&renderer))
+            .into_iter()
+            .filter(|&count| count > 0)
+            .collect();
+        let draws = renderer.backend().draws_with(ShaderProgram::Grass);
+        let counts: Vec<usize> = draws.iter().map(|call| call.instance_count).collect();
+        assert_eq!(counts, expected);
+        assert!(draws.iter().all(|call| call.instances.is_some()));
+
+        let texture = draws.first().and_then(|call| call.texture);
+        if let Some(texture) = texture {
+            assert!(renderer.backend().texture(texture).unwrap().layers > 1);
+        }
+
+        // Moving the camera far away changes the LOD split without reallocating
+        grass.update(Vec3::new(0.0, -21.0, 0.0));
+        let buffers = renderer.backend().buffer_count();
+        renderer.update_grass(&grass).unwrap();
+        assert_eq!(renderer.backend().buffer_count(), buffers);
+
+        renderer.backend_mut().clear_commands();
+        renderer.render(&Scene::new()).unwrap();
+        let total: usize = renderer
+            .backend()
+            .draws_with(ShaderProgram::Grass)
+            .iter()
+            .map(|call| call.instance_count)
+            .sum();
+        let expected_total: usize = visible_grass(&grass, &frustum(&renderer)).iter().sum();
+        assert_eq!(total, expected_total);
+    }
+
+    #[test]
+    fn test_vegetation_outside_frustum_is_culled() {
+        let planet_radius = 20.0;
+        let mut trees = TreeSystem::new(planet_radius, 200, 0.0, std::f32::consts::PI / 2.0);
+        let mut grass = GrassSystem::new(planet_radius, 1.0);
+        let mut renderer = renderer();
+        renderer
+            .camera_mut()
+            .set_position(Vec3::new(0.0, 0.0, 40.0));
+        renderer
+            .camera_mut()
+            .set_orientation(Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0));
+        renderer.initialize_grass(&grass).unwrap();
+        renderer.initialize_tree(&trees).unwrap();
+
+        let drawn = |renderer: &mut Renderer<RecordingBackend>, program| -> usize {
+            renderer.backend_mut().clear_commands();
+            renderer.render(&Scene::new()).unwrap();
+            renderer
+                .backend()
+                .draws_with(program)
+                .iter()
+                .map(|call| call.instance_count)
+                .sum()
+        };
+        let brute_force = |renderer: &Renderer<RecordingBackend>, trees: &TreeSystem| {
+            let frustum = frustum(renderer);
+            let bounds = trees.instanced_mesh().base_mesh.bounds().unwrap();
+            trees
+                .instanced_mesh()
+                .instances
+                .iter()
+                .filter(|instance| frustum.intersects_aabb(&bounds.transform(&instance.transform)))
+                .count()
+        };
+
+        // Only the half of the planet facing the camera is inside the frustum
+        let total = trees.instanced_mesh().instances.len();
+        let visible = drawn(&mut renderer, ShaderProgram::Tree);
+        assert!(visible > 0 && visible < total);
+        assert_eq!(visible, brute_force(&renderer, &trees));
+        let blades = drawn(&mut renderer, ShaderProgram::Grass);
+        assert!(blades > 0 && blades < grass.instances().len());
+
+        // Looking away from the planet leaves nothing to draw
+        renderer
+            .camera_mut()
+            .set_orientation(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0));
+        renderer.update_trees(&trees).unwrap();
+        renderer.update_grass(&grass).unwrap();
+        assert_eq!(drawn(&mut renderer, ShaderProgram::Tree), 0);
+        assert_eq!(drawn(&mut renderer, ShaderProgram::Grass), 0);
+
+        // Vegetation moved in front of the camera is found through the refit hierarchy
+        let ahead = Mat4::translation(0.0, 0.0, 60.0);
+        trees.move_instances(|_, transform| *transform = ahead.multiply(transform));
+        grass.move_instances(|_, transform| *transform = ahead.multiply(transform));
+        renderer.update_trees(&trees).unwrap();
+        renderer.update_grass(&grass).unwrap();
+        let visible = drawn(&mut renderer, ShaderProgram::Tree);
+        assert!(visible > 0);
+        assert_eq!(visible, brute_force(&renderer, &trees));
+        assert!(drawn(&mut renderer, ShaderProgram::Grass) > 0);
+    }
+
+    #[test]
+    fn test_resize_updates_backend_size() {
+        let mut renderer = renderer();
+        renderer.resize(1024, 768);
+        assert_eq!(renderer.backend().size(), (1024, 768));
+    }
+
+    #[test]
+    fn test_update_buffer_rejects_overflow() {
+        let mut backend = RecordingBackend::new(1, 1);
+        let buffer = backend
+            .create_buffer(BufferData::Indices16(&[0, 1, 2]))
+            .unwrap();
+        assert!(backend
+            .update_buffer(buffer, BufferData::Indices16(&[0, 1, 2, 3]))
+            .is_err());
+        assert!(backend
+            .update_buffer(buffer, BufferData::Indices16(&[2, 1]))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_end_frame_without_begin_fails() {
+        let mut backend = RecordingBackend::new(1, 1);
+        assert!(backend.end_frame().is_err());
+    }
+}
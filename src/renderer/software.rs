@@ -0,0 +1,946 @@
+//! CPU software rasterizer
+//!
+//! [`SoftwareBackend`] implements [`RenderBackend`] entirely on the CPU, running the
+//! ports in [`cpu_shaders`](super::cpu_shaders) of the Metal shaders into a [`Framebuffer`].
+//! Clipping, depth testing and blending follow Metal's conventions (clip-space depth in
+//! `[0, w]`, `Depth32Float` cleared to 1.0, or 0.0 with reverse-Z, no face culling), so
+//! images match the windowed renderer closely enough for thumbnails and golden-image
+//! tests.
+//! UI draws are ignored.
+
+use crate::core::{GrassSystem, RoadSystem, Skybox, TextureFormat, TreeSystem};
+use crate::math::{Vec2, Vec4};
+use crate::renderer::backend::ResourceSlots;
+use crate::renderer::cpu_shaders::{self, Varyings, VertexOut};
+use crate::renderer::{
+    BlendMode, BufferData, BufferHandle, DepthMode, DrawCall, PipelineDescriptor, PipelineHandle,
+    RenderBackend, Renderer, ShaderProgram, TextureDescriptor, TextureHandle, UniformBlock,
+};
+use crate::scene::{Camera, DepthMapping, IndexFormat, InstanceData, Scene, Vertex};
+use image::RgbaImage;
+
+/// RGBA8 color and 32-bit float depth target
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    color: Vec<u8>,
+    depth: Vec<f32>,
+}
+
+impl Framebuffer {
+    /// Creates a transparent black framebuffer with depth cleared to 1.0
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Self {
+        let pixels = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            color: vec![0; pixels * 4],
+            depth: vec![1.0; pixels],
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Tightly packed RGBA8 pixels, top row first
+    #[must_use]
+    pub fn color(&self) -> &[u8] {
+        &self.color
+    }
+
+    /// Depth values in `[0, 1]`, top row first
+    #[must_use]
+    pub fn depth(&self) -> &[f32] {
+        &self.depth
+    }
+
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        let i = self.index(x, y)? * 4;
+        Some([
+            self.color[i],
+            self.color[i + 1],
+            self.color[i + 2],
+            self.color[i + 3],
+        ])
+    }
+
+    #[must_use]
+    pub fn depth_at(&self, x: u32, y: u32) -> Option<f32> {
+        self.index(x, y).map(|i| self.depth[i])
+    }
+
+    /// Copies the color target into an image
+    #[must_use]
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            image::Rgba(self.pixel(x, y).unwrap_or_default())
+        })
+    }
+
+    /// Fills the color target with `color` and the depth target with `depth`
+    pub fn clear(&mut self, color: Vec4, depth: f32) {
+        let rgba = to_rgba8(color);
+        for pixel in self.color.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+        self.depth.fill(depth);
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+
+    fn blend(&mut self, index: usize, color: Vec4, mode: BlendMode) {
+        let pixel = &mut self.color[index * 4..index * 4 + 4];
+        let out = match mode {
+            BlendMode::Opaque => color,
+            BlendMode::Alpha => {
+                let dst = Vec4::new(
+                    f32::from(pixel[0]) / 255.0,
+                    f32::from(pixel[1]) / 255.0,
+                    f32::from(pixel[2]) / 255.0,
+                    f32::from(pixel[3]) / 255.0,
+                );
+                let a = color.w.clamp(0.0, 1.0);
+                Vec4::new(
+                    color.x * a + dst.x * (1.0 - a),
+                    color.y * a + dst.y * (1.0 - a),
+                    color.z * a + dst.z * (1.0 - a),
+                    a + dst.w * (1.0 - a),
+                )
+            }
+        };
+        pixel.copy_from_slice(&to_rgba8(out));
+    }
+}
+
+fn to_rgba8(color: Vec4) -> [u8; 4] {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [
+        channel(color.x),
+        channel(color.y),
+        channel(color.z),
+        channel(color.w),
+    ]
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+enum SoftwareBuffer {
+    Vertices(Vec<Vertex>),
+    /// Indices widened to 32 bits, tagged with the format they were written in
+    Indices(IndexFormat, Vec<u32>),
+    Instances(Vec<InstanceData>),
+    Uniforms(Option<UniformBlock>),
+}
+
+impl SoftwareBuffer {
+    /// Buffer holding `data`, or `None` for [`BufferData::Empty`]
+    fn from_data(data: BufferData<'_>) -> Option<Self> {
+        match data {
+            BufferData::Vertices(vertices) => Some(Self::Vertices(vertices.to_vec())),
+            BufferData::Indices16(indices) => Some(Self::Indices(
+                IndexFormat::U16,
+                indices.iter().copied().map(u32::from).collect(),
+            )),
+            BufferData::Indices32(indices) => {
+                Some(Self::Indices(IndexFormat::U32, indices.to_vec()))
+            }
+            BufferData::Instances(instances) => Some(Self::Instances(instances.to_vec())),
+            BufferData::Empty(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SoftwareTexture {
+    descriptor: TextureDescriptor,
+    data: Vec<u8>,
+}
+
+impl SoftwareTexture {
+    /// Nearest-neighbour sample with clamp-to-edge addressing
+    fn sample(&self, uv: Vec2, layer: u32) -> Vec4 {
+        let TextureDescriptor {
+            width,
+            height,
+            layers,
+            format,
+        } = self.descriptor;
+        let texel = |coord: f32, size: u32| {
+            ((coord.clamp(0.0, 1.0) * size as f32) as u32).min(size.saturating_sub(1)) as usize
+        };
+        let x = texel(uv.x, width);
+        let y = texel(uv.y, height);
+        let layer = layer.min(layers.saturating_sub(1)) as usize;
+
+        let i = ((layer * height as usize + y) * width as usize + x) * format.bytes_per_pixel();
+        let Some(bytes) = self.data.get(i..i + 4) else {
+            return Vec4::zero();
+        };
+        let (r, b) = match format {
+            TextureFormat::Rgba8 => (bytes[0], bytes[2]),
+            TextureFormat::Bgra8 => (bytes[2], bytes[0]),
+        };
+        Vec4::new(
+            f32::from(r) / 255.0,
+            f32::from(bytes[1]) / 255.0,
+            f32::from(b) / 255.0,
+            f32::from(bytes[3]) / 255.0,
+        )
+    }
+}
+
+/// [`RenderBackend`] that rasterizes on the CPU into a [`Framebuffer`]
+#[derive(Debug, Clone)]
+pub struct SoftwareBackend {
+    framebuffer: Framebuffer,
+    buffers: ResourceSlots<SoftwareBuffer>,
+    textures: ResourceSlots<SoftwareTexture>,
+    pipelines: Vec<PipelineDescriptor>,
+    pipeline: Option<PipelineDescriptor>,
+    depth_mapping: DepthMapping,
+    in_frame: bool,
+}
+
+impl SoftwareBackend {
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            framebuffer: Framebuffer::new(width, height),
+            buffers: ResourceSlots::new(),
+            textures: ResourceSlots::new(),
+            pipelines: Vec::new(),
+            pipeline: None,
+            depth_mapping: DepthMapping::Standard,
+            in_frame: false,
+        }
+    }
+
+    /// The last rendered frame
+    #[must_use]
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    #[must_use]
+    pub fn into_framebuffer(self) -> Framebuffer {
+        self.framebuffer
+    }
+
+    fn buffer(&self, handle: BufferHandle) -> Option<&SoftwareBuffer> {
+        self.buffers.get(handle)
+    }
+
+    /// Runs the vertex stage for every vertex of one instance
+    fn shade_vertices(
+        program: ShaderProgram,
+        uniforms: &UniformBlock,
+        instance: Option<&InstanceData>,
+        vertices: &[Vertex],
+    ) -> Option<Vec<VertexOut>> {
+        let shade = |vertex: &Vertex| match (program, uniforms, instance) {
+            (ShaderProgram::Scene | ShaderProgram::Road, UniformBlock::Scene(u), _) => {
+                Some(cpu_shaders::scene_vertex(u, vertex))
+            }
+            (ShaderProgram::Grass, UniformBlock::Scene(u), Some(instance)) => {
+                Some(cpu_shaders::grass_vertex(u, instance, vertex))
+            }
+            (ShaderProgram::Tree, UniformBlock::Tree(u), Some(instance)) => {
+                Some(cpu_shaders::tree_vertex(u, instance, vertex))
+            }
+            (ShaderProgram::Skybox, UniformBlock::Skybox(u), _) => {
+                Some(cpu_shaders::skybox_vertex(u, vertex))
+            }
+            _ => None,
+        };
+        vertices.iter().map(shade).collect()
+    }
+
+    /// Runs the fragment stage; `None` means the fragment is discarded
+    fn shade_fragment(
+        program: ShaderProgram,
+        uniforms: &UniformBlock,
+        instance: Option<&InstanceData>,
+        texture: Option<&SoftwareTexture>,
+        input: &Varyings,
+    ) -> Option<Vec4> {
+        match (program, uniforms) {
+            (ShaderProgram::Scene, UniformBlock::Scene(u)) => {
+                Some(cpu_shaders::scene_fragment(u, input))
+            }
+            (ShaderProgram::Road, UniformBlock::Scene(u)) => {
+                Some(cpu_shaders::road_fragment(u, input))
+            }
+            (ShaderProgram::Grass, UniformBlock::Scene(u)) => {
+                let layer = instance.map_or(0, |instance| instance.texture_index);
+                let tex_color = texture.map_or(Vec4::zero(), |texture| {
+                    texture.sample(input.tex_coord, layer)
+                });
+                cpu_shaders::grass_fragment(u, input, tex_color)
+            }
+            (ShaderProgram::Tree, UniformBlock::Tree(u)) => {
+                Some(cpu_shaders::tree_fragment(u, input))
+            }
+            (ShaderProgram::Skybox, UniformBlock::Skybox(u)) => {
+                Some(cpu_shaders::skybox_fragment(u, input))
+            }
+            _ => None,
+        }
+    }
+
+    fn rasterize_triangle(
+        &mut self,
+        pipeline: &PipelineDescriptor,
+        triangle: [&VertexOut; 3],
+        fragment: &dyn Fn(&Varyings) -> Option<Vec4>,
+    ) {
+        for clipped in clip_triangle(triangle) {
+            self.fill_triangle(pipeline, &clipped, fragment);
+        }
+    }
+
+    fn fill_triangle(
+        &mut self,
+        pipeline: &PipelineDescriptor,
+        triangle: &[VertexOut; 3],
+        fragment: &dyn Fn(&Varyings) -> Option<Vec4>,
+    ) {
+        let width = self.framebuffer.width as f32;
+        let height = self.framebuffer.height as f32;
+        let reversed = self.depth_mapping.is_reversed();
+
+        // Viewport transform: NDC y points up, framebuffer rows go down
+        let screen = triangle.map(|v| {
+            let inv_w = 1.0 / v.position.w;
+            ScreenVertex {
+                x: (v.position.x * inv_w * 0.5 + 0.5) * width,
+                y: (0.5 - v.position.y * inv_w * 0.5) * height,
+                z: v.position.z * inv_w,
+                inv_w,
+            }
+        });
+
+        let area = edge(&screen[0], &screen[1], screen[2].x, screen[2].y);
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+        // Orient counter-clockwise (in framebuffer space) so edge functions are positive inside
+        let order = if area > 0.0 { [0, 1, 2] } else { [0, 2, 1] };
+        let s = order.map(|i| screen[i]);
+        let varyings = order.map(|i| &triangle[i].varyings);
+        let area = area.abs();
+        let top_left = [
+            is_top_left(&s[1], &s[2]),
+            is_top_left(&s[2], &s[0]),
+            is_top_left(&s[0], &s[1]),
+        ];
+
+        let min_x = s
+            .iter()
+            .map(|v| v.x)
+            .fold(f32::INFINITY, f32::min)
+            .floor()
+            .max(0.0) as u32;
+        let max_x = s
+            .iter()
+            .map(|v| v.x)
+            .fold(f32::NEG_INFINITY, f32::max)
+            .ceil()
+            .min(width) as u32;
+        let min_y = s
+            .iter()
+            .map(|v| v.y)
+            .fold(f32::INFINITY, f32::min)
+            .floor()
+            .max(0.0) as u32;
+        let max_y = s
+            .iter()
+            .map(|v| v.y)
+            .fold(f32::NEG_INFINITY, f32::max)
+            .ceil()
+            .min(height) as u32;
+
+        for y in min_y..max_y {
+            let py = y as f32 + 0.5;
+            for x in min_x..max_x {
+                let px = x as f32 + 0.5;
+                let w = [
+                    edge(&s[1], &s[2], px, py),
+                    edge(&s[2], &s[0], px, py),
+                    edge(&s[0], &s[1], px, py),
+                ];
+                let inside = w
+                    .iter()
+                    .zip(top_left)
+                    .all(|(&w, top_left)| w > 0.0 || (w == 0.0 && top_left));
+                if !inside {
+                    continue;
+                }
+
+                let b = w.map(|w| w / area);
+                let depth = b[0] * s[0].z + b[1] * s[1].z + b[2] * s[2].z;
+                let index = y as usize * self.framebuffer.width as usize + x as usize;
+                let stored = self.framebuffer.depth[index];
+                let passes = match pipeline.depth {
+                    DepthMode::ReadWrite if reversed => depth > stored,
+                    DepthMode::ReadWrite => depth < stored,
+                    DepthMode::ReadOnly if reversed => depth >= stored,
+                    DepthMode::ReadOnly => depth <= stored,
+                    DepthMode::Disabled => true,
+                };
+                if !passes {
+                    continue;
+                }
+
+                // Perspective-correct interpolation
+                let pw = [b[0] * s[0].inv_w, b[1] * s[1].inv_w, b[2] * s[2].inv_w];
+                let sum = pw[0] + pw[1] + pw[2];
+                let input = Varyings::blend(varyings, pw.map(|w| w / sum));
+
+                let Some(color) = fragment(&input) else {
+                    continue;
+                };
+                self.framebuffer.blend(index, color, pipeline.blend);
+                if pipeline.depth == DepthMode::ReadWrite {
+                    self.framebuffer.depth[index] = depth;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+}
+
+fn edge(a: &ScreenVertex, b: &ScreenVertex, px: f32, py: f32) -> f32 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+/// Top-left fill rule so pixels on shared edges are drawn exactly once
+fn is_top_left(a: &ScreenVertex, b: &ScreenVertex) -> bool {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    (dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+/// Clips a triangle against Metal's depth range `0 <= z <= w` and fans the result
+fn clip_triangle(triangle: [&VertexOut; 3]) -> Vec<[VertexOut; 3]> {
+    let planes: [fn(&Vec4) -> f32; 2] = [|p| p.z, |p| p.w - p.z];
+
+    let mut polygon: Vec<VertexOut> = triangle.iter().map(|v| **v).collect();
+    for distance in planes {
+        if polygon.is_empty() {
+            break;
+        }
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let d0 = distance(&current.position);
+            let d1 = distance(&next.position);
+            if d0 >= 0.0 {
+                clipped.push(*current);
+            }
+            if (d0 >= 0.0) != (d1 >= 0.0) {
+                let t = d0 / (d0 - d1);
+                clipped.push(lerp_vertex(current, next, t));
+            }
+        }
+        polygon = clipped;
+    }
+
+    if polygon.len() < 3 || polygon.iter().any(|v| v.position.w <= f32::EPSILON) {
+        return Vec::new();
+    }
+    (1..polygon.len() - 1)
+        .map(|i| [polygon[0], polygon[i], polygon[i + 1]])
+        .collect()
+}
+
+fn lerp_vertex(a: &VertexOut, b: &VertexOut, t: f32) -> VertexOut {
+    let p = |a: f32, b: f32| a + (b - a) * t;
+    VertexOut {
+        position: Vec4::new(
+            p(a.position.x, b.position.x),
+            p(a.position.y, b.position.y),
+            p(a.position.z, b.position.z),
+            p(a.position.w, b.position.w),
+        ),
+        varyings: Varyings::lerp(&a.varyings, &b.varyings, t),
+    }
+}
+
+fn next_index(len: usize) -> Result<u32, String> {
+    u32::try_from(len).map_err(|_| "Too many resources".to_string())
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn create_buffer(&mut self, data: BufferData<'_>) -> Result<BufferHandle, String> {
+        self.buffers
+            .insert(SoftwareBuffer::from_data(data).unwrap_or(SoftwareBuffer::Uniforms(None)))
+    }
+
+    fn update_buffer(&mut self, buffer: BufferHandle, data: BufferData<'_>) -> Result<(), String> {
+        let target = self
+            .buffers
+            .get_mut(buffer)
+            .ok_or_else(|| format!("Unknown buffer handle {}", buffer.index()))?;
+        if let Some(contents) = SoftwareBuffer::from_data(data) {
+            *target = contents;
+        }
+        Ok(())
+    }
+
+    fn create_texture(
+        &mut self,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Result<TextureHandle, String> {
+        if data.len() != descriptor.byte_len() {
+            return Err(format!(
+                "Invalid data size: expected {}, got {}",
+                descriptor.byte_len(),
+                data.len()
+            ));
+        }
+        self.textures.insert(SoftwareTexture {
+            descriptor: *descriptor,
+            data: data.to_vec(),
+        })
+    }
+
+    fn destroy_buffer(&mut self, buffer: BufferHandle) {
+        self.buffers.remove(buffer);
+    }
+
+    fn destroy_texture(&mut self, texture: TextureHandle) {
+        self.textures.remove(texture);
+    }
+
+    fn create_pipeline(
+        &mut self,
+        descriptor: &PipelineDescriptor,
+    ) -> Result<PipelineHandle, String> {
+        let handle = PipelineHandle::from_index(next_index(self.pipelines.len())?);
+        self.pipelines.push(*descriptor);
+        Ok(handle)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.framebuffer = Framebuffer::new(width, height);
+    }
+
+    fn begin_frame(&mut self, clear_color: Vec4, depth: DepthMapping) -> Result<(), String> {
+        if self.in_frame {
+            return Err("begin_frame called twice without end_frame".to_string());
+        }
+        self.in_frame = true;
+        self.pipeline = None;
+        self.depth_mapping = depth;
+        self.framebuffer.clear(clear_color, depth.clear_depth());
+        Ok(())
+    }
+
+    fn set_pipeline(&mut self, pipeline: PipelineHandle) {
+        self.pipeline = self.pipelines.get(pipeline.index() as usize).copied();
+    }
+
+    fn set_uniforms(&mut self, buffer: BufferHandle, block: &UniformBlock) {
+        if let Some(target) = self.buffers.get_mut(buffer) {
+            *target = SoftwareBuffer::Uniforms(Some(*block));
+        }
+    }
+
+    fn draw(&mut self, call: &DrawCall) {
+        let Some(pipeline) = self.pipeline else {
+            return;
+        };
+        let (
+            Some(SoftwareBuffer::Vertices(vertices)),
+            Some(SoftwareBuffer::Indices(index_format, indices)),
+            Some(SoftwareBuffer::Uniforms(Some(uniforms))),
+        ) = (
+            self.buffer(call.vertex_buffer),
+            self.buffer(call.index_buffer),
+            self.buffer(call.uniforms),
+        )
+        else {
+            return;
+        };
+        if *index_format != call.index_format {
+            return;
+        }
+        let instances = match call.instances.map(|handle| self.buffer(handle)) {
+            Some(Some(SoftwareBuffer::Instances(instances))) => instances
+                .iter()
+                .take(call.instance_count)
+                .cloned()
+                .map(Some)
+                .collect(),
+            Some(_) => return,
+            None => vec![None],
+        };
+        let vertices = vertices.clone();
+        let indices: Vec<u32> = indices.iter().take(call.index_count).copied().collect();
+        let uniforms = *uniforms;
+        let texture = call
+            .texture
+            .and_then(|handle| self.textures.get(handle))
+            .cloned();
+
+        for instance in instances {
+            let instance = instance.as_ref();
+            let Some(shaded) =
+                Self::shade_vertices(pipeline.program, &uniforms, instance, &vertices)
+            else {
+                return;
+            };
+            let fragment = |input: &Varyings| {
+                Self::shade_fragment(
+                    pipeline.program,
+                    &uniforms,
+                    instance,
+                    texture.as_ref(),
+                    input,
+                )
+            };
+
+            for triangle in indices.chunks_exact(3) {
+                let [Some(a), Some(b), Some(c)] = [0, 1, 2].map(|i| {
+                    usize::try_from(triangle[i])
+                        .ok()
+                        .and_then(|i| shaded.get(i))
+                }) else {
+                    continue;
+                };
+                self.rasterize_triangle(&pipeline, [a, b, c], &fragment);
+            }
+        }
+    }
+
+    fn end_frame(&mut self) -> Result<(), String> {
+        if !self.in_frame {
+            return Err("end_frame called without begin_frame".to_string());
+        }
+        self.in_frame = false;
+        Ok(())
+    }
+
+    fn capture_frame(&self) -> Result<RgbaImage, String> {
+        if self.in_frame {
+            return Err("capture_frame called before end_frame".to_string());
+        }
+        Ok(self.framebuffer.to_image())
+    }
+}
+
+/// World systems drawn by [`rasterize`] in addition to the scene nodes
+#[derive(Clone, Copy, Default)]
+pub struct WorldLayers<'a> {
+    pub skybox: Option<&'a Skybox>,
+    pub grass: Option<&'a GrassSystem>,
+    pub road: Option<&'a RoadSystem>,
+    pub trees: Option<&'a TreeSystem>,
+}
+
+/// Renders `scene` as seen from `camera` into a new `width` x `height` framebuffer
+///
+/// # Errors
+/// Returns an error if the software backend rejects any of the scene's resources
+pub fn rasterize(
+    scene: &Scene,
+    camera: &Camera,
+    layers: WorldLayers<'_>,
+    width: u32,
+    height: u32,
+) -> Result<Framebuffer, String> {
+    let mut renderer = Renderer::new(SoftwareBackend::new(width, height), width, height)?;
+    *renderer.camera_mut() = camera.clone();
+
+    if let Some(skybox) = layers.skybox {
+        renderer.initialize_skybox(skybox)?;
+    }
+    if let Some(grass) = layers.grass {
+        renderer.initialize_grass(grass)?;
+    }
+    if let Some(road) = layers.road {
+        renderer.initialize_road(road)?;
+    }
+    if let Some(trees) = layers.trees {
+        renderer.initialize_tree(trees)?;
+    }
+
+    renderer.render(scene)?;
+    Ok(renderer.into_backend().into_framebuffer())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+    use crate::renderer::RecordingBackend;
+    use crate::scene::{Mesh, Node};
+
+    const SIZE: u32 = 64;
+
+    fn camera_looking_at_origin(distance: f32) -> Camera {
+        Camera::new(Vec3::new(0.0, 0.0, distance), Vec3::zero(), 1.0)
+    }
+
+    fn scene_with_cubes(positions: &[Vec3]) -> Scene {
+        let mut scene = Scene::new();
+        for (i, position) in positions.iter().enumerate() {
+            let mut node = Node::with_mesh(format!("cube{i}"), Mesh::cube());
+            node.set_position(*position);
+            scene.add_node(node);
+        }
+        scene
+    }
+
+    #[test]
+    fn test_empty_scene_is_clear_color() {
+        let camera = camera_looking_at_origin(5.0);
+        let image = rasterize(&Scene::new(), &camera, WorldLayers::default(), SIZE, SIZE).unwrap();
+
+        assert_eq!(image.width(), SIZE);
+        assert_eq!(image.color().len(), (SIZE * SIZE * 4) as usize);
+        assert!(image.depth().iter().all(|&d| d == 1.0));
+        let first = image.pixel(0, 0).unwrap();
+        assert!(image.color().chunks_exact(4).all(|p| p == first));
+    }
+
+    #[test]
+    fn test_cube_covers_center_with_lit_color() {
+        let camera = camera_looking_at_origin(5.0);
+        let scene = scene_with_cubes(&[Vec3::zero()]);
+        let image = rasterize(&scene, &camera, WorldLayers::default(), SIZE, SIZE).unwrap();
+
+        let center = image.pixel(SIZE / 2, SIZE / 2).unwrap();
+        let depth = image.depth_at(SIZE / 2, SIZE / 2).unwrap();
+        assert!(depth < 1.0);
+        // Muted green object color dominates
+        assert!(center[1] > center[0] && center[1] > center[2]);
+        assert_eq!(image.depth_at(0, 0), Some(1.0));
+    }
+
+    #[test]
+    fn test_matches_shader_for_front_face() {
+        let camera = camera_looking_at_origin(5.0);
+        let scene = scene_with_cubes(&[Vec3::zero()]);
+        let image = rasterize(&scene, &camera, WorldLayers::default(), SIZE, SIZE).unwrap();
+
+        // The center pixel sees the +Z face near (0, 0, 0.5)
+        let mut recording = Renderer::new(RecordingBackend::new(SIZE, SIZE), SIZE, SIZE).unwrap();
+        *recording.camera_mut() = camera.clone();
+        recording.render(&scene).unwrap();
+        let uniforms = recording
+            .backend()
+            .uniform_blocks()
+            .find_map(|block| match block {
+                UniformBlock::Scene(u) => Some(*u),
+                _ => None,
+            });
+
+        let expected = cpu_shaders::scene_fragment(
+            &uniforms.unwrap(),
+            &Varyings {
+                world_pos: Vec3::new(0.0, 0.0, 0.5),
+                normal: Vec3::new(0.0, 0.0, 1.0),
+                ..Varyings::default()
+            },
+        );
+        let center = image.pixel(SIZE / 2, SIZE / 2).unwrap();
+        let expected = to_rgba8(expected);
+        for c in 0..3 {
+            assert!(
+                center[c].abs_diff(expected[c]) <= 2,
+                "{center:?} vs {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_depth_test_keeps_nearest_surface() {
+        let camera = camera_looking_at_origin(6.0);
+        let near_only = scene_with_cubes(&[Vec3::new(0.0, 0.0, 2.0)]);
+        let both = scene_with_cubes(&[Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 0.0, -2.0)]);
+        let reversed = scene_with_cubes(&[Vec3::new(0.0, 0.0, -2.0), Vec3::new(0.0, 0.0, 2.0)]);
+
+        let render = |scene| rasterize(scene, &camera, WorldLayers::default(), SIZE, SIZE).unwrap();
+        let near_image = render(&near_only);
+        let center = (SIZE / 2, SIZE / 2);
+        assert_eq!(
+            render(&both).pixel(center.0, center.1),
+            near_image.pixel(center.0, center.1)
+        );
+        assert_eq!(
+            render(&reversed).pixel(center.0, center.1),
+            near_image.pixel(center.0, center.1)
+        );
+    }
+
+    #[test]
+    fn test_reverse_z_keeps_nearest_surface() {
+        let mut camera = camera_looking_at_origin(6.0);
+        let standard = rasterize(
+            &scene_with_cubes(&[Vec3::new(0.0, 0.0, 2.0)]),
+            &camera,
+            WorldLayers::default(),
+            SIZE,
+            SIZE,
+        )
+        .unwrap();
+
+        camera.set_depth_mapping(DepthMapping::ReverseZ);
+        let scene = scene_with_cubes(&[Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 0.0, -2.0)]);
+        let image = rasterize(&scene, &camera, WorldLayers::default(), SIZE, SIZE).unwrap();
+
+        let center = (SIZE / 2, SIZE / 2);
+        assert_eq!(
+            image.pixel(center.0, center.1),
+            standard.pixel(center.0, center.1)
+        );
+        assert!(image.depth_at(center.0, center.1).unwrap() > 0.0);
+        assert_eq!(image.depth_at(0, 0), Some(0.0));
+    }
+
+    #[test]
+    fn test_infinite_far_plane_shows_skybox() {
+        let skybox = Skybox::new();
+        let layers = WorldLayers {
+            skybox: Some(&skybox),
+            ..WorldLayers::default()
+        };
+        let mut camera = camera_looking_at_origin(5.0);
+        let is_flat = |image: &Framebuffer| {
+            let first = image.pixel(0, 0).unwrap();
+            image.color().chunks_exact(4).all(|p| p == first)
+        };
+
+        // The skybox lies beyond the default far plane
+        let clipped = rasterize(&Scene::new(), &camera, layers, SIZE, SIZE).unwrap();
+        assert!(is_flat(&clipped));
+
+        camera.set_depth_mapping(DepthMapping::ReverseZInfinite);
+        let image = rasterize(&Scene::new(), &camera, layers, SIZE, SIZE).unwrap();
+        assert!(!is_flat(&image));
+    }
+
+    #[test]
+    fn test_fog_fades_distant_objects() {
+        let scene = scene_with_cubes(&[Vec3::zero()]);
+        let near = rasterize(
+            &scene,
+            &camera_looking_at_origin(5.0),
+            WorldLayers::default(),
+            SIZE,
+            SIZE,
+        )
+        .unwrap();
+        let far = rasterize(
+            &scene,
+            &camera_looking_at_origin(60.0),
+            WorldLayers::default(),
+            SIZE,
+            SIZE,
+        )
+        .unwrap();
+
+        // Fog pushes the distant cube towards the blue-grey sky color
+        let near_center = near.pixel(SIZE / 2, SIZE / 2).unwrap();
+        let far_center = far.pixel(SIZE / 2, SIZE / 2).unwrap();
+        assert!(far_center[2] > near_center[2]);
+    }
+
+    #[test]
+    fn test_objects_behind_camera_are_clipped() {
+        let camera = camera_looking_at_origin(5.0);
+        let scene = scene_with_cubes(&[Vec3::new(0.0, 0.0, 10.0)]);
+        let image = rasterize(&scene, &camera, WorldLayers::default(), SIZE, SIZE).unwrap();
+        assert!(image.depth().iter().all(|&d| d == 1.0));
+    }
+
+    #[test]
+    fn test_world_layers_render() {
+        let planet_radius = 20.0;
+        let mut grass = GrassSystem::new(planet_radius, 1.0);
+        let eye = Vec3::new(0.0, planet_radius + 2.0, 0.0);
+        grass.update(eye);
+        let road = RoadSystem::new(planet_radius, 0.0, std::f32::consts::PI / 2.0, 3.0);
+        let trees = TreeSystem::new(planet_radius, 20, 0.0, std::f32::consts::PI / 2.0);
+        let skybox = Skybox::new();
+        let camera = Camera::new(eye, Vec3::new(5.0, planet_radius, 0.0), 1.0);
+
+        let layers = WorldLayers {
+            skybox: Some(&skybox),
+            grass: Some(&grass),
+            road: Some(&road),
+            trees: Some(&trees),
+        };
+        let image = rasterize(&Scene::new(), &camera, layers, SIZE, SIZE).unwrap();
+
+        // Looking down at the planet surface: something was drawn
+        assert!(image.depth().iter().any(|&d| d < 1.0));
+        assert!(image.color().chunks_exact(4).all(|p| p[3] > 0));
+    }
+
+    #[test]
+    fn test_stale_handles_do_not_alias_reused_slots() {
+        let mut backend = SoftwareBackend::new(1, 1);
+        let first = backend.create_buffer(BufferData::Empty(64)).unwrap();
+        backend.destroy_buffer(first);
+        let second = backend.create_buffer(BufferData::Empty(64)).unwrap();
+        assert_eq!(second.index(), first.index());
+        assert_ne!(second, first);
+
+        // The stale handle neither writes to nor frees the buffer now in its slot
+        assert!(backend.update_buffer(first, BufferData::Empty(64)).is_err());
+        backend.destroy_buffer(first);
+        assert!(backend.buffer(second).is_some());
+        assert!(backend.update_buffer(second, BufferData::Empty(64)).is_ok());
+
+        let descriptor = TextureDescriptor {
+            width: 1,
+            height: 1,
+            layers: 1,
+            format: TextureFormat::Rgba8,
+        };
+        let texture = backend.create_texture(&descriptor, &[0; 4]).unwrap();
+        backend.destroy_texture(texture);
+        let replacement = backend.create_texture(&descriptor, &[0; 4]).unwrap();
+        backend.destroy_texture(texture);
+        assert!(backend.textures.get(replacement).is_some());
+    }
+
+    #[test]
+    fn test_texture_sampling_is_nearest_and_clamped() {
+        let texture = SoftwareTexture {
+            descriptor: TextureDescriptor {
+                width: 2,
+                height: 1,
+                layers: 1,
+                format: TextureFormat::Bgra8,
+            },
+            data: vec![255, 0, 0, 255, 0, 0, 255, 255],
+        };
+        // BGRA blue then red
+        assert_eq!(texture.sample(Vec2::new(0.1, 0.5), 0).z, 1.0);
+        assert_eq!(texture.sample(Vec2::new(0.9, 0.5), 0).x, 1.0);
+        assert_eq!(texture.sample(Vec2::new(2.0, 0.5), 0).x, 1.0);
+    }
+}
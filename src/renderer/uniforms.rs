@@ -0,0 +1,84 @@
+//! Uniform blocks shared with the shaders
+//!
+//! Layouts mirror the structs declared in `src/shaders/*.metal`; `Vec3` fields occupy
+//! 16 bytes just like Metal's `float3`.
+
+use crate::math::{Mat4, Vec3, Vec4};
+
+/// Uniforms for the scene, road and grass shaders
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Uniforms {
+    pub mvp_matrix: Mat4,
+    pub model_matrix: Mat4,
+    pub normal_matrix: Mat4,
+    pub view_pos: Vec3,
+    pub time: f32,
+    pub light_pos: Vec3,
+    pub _padding1: f32,
+    pub light_color: Vec3,
+    pub ambient_strength: f32,
+    pub diffuse_strength: f32,
+    pub specular_strength: f32,
+    pub fog_density: f32,
+    pub fog_color: Vec3,
+    pub fog_start: f32,
+    pub horizon_color: Vec3,
+    pub _padding2: f32,
+    pub zenith_color: Vec3,
+    pub _padding3: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyboxUniforms {
+    pub view_projection_matrix: Mat4,
+    pub camera_pos: Vec3,
+    pub time: f32,
+    pub sun_direction: Vec3,
+    pub _padding: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeUniforms {
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub light_position: Vec3,
+    pub time: f32,
+    pub view_position: Vec3,
+    pub _padding: f32,
+    pub sky_gradient_bottom: Vec4,
+    pub sky_gradient_top: Vec4,
+    pub sun_direction: Vec3,
+    pub fog_density: f32,
+    pub fog_start: f32,
+    pub _padding2: [f32; 3],
+}
+
+/// A uniform block written to a uniform buffer before a draw
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformBlock {
+    Scene(Uniforms),
+    Skybox(SkyboxUniforms),
+    Tree(TreeUniforms),
+}
+
+impl UniformBlock {
+    /// Size of the largest uniform block, used to size uniform buffers
+    pub const MAX_SIZE: usize = max_size(
+        std::mem::size_of::<Uniforms>(),
+        max_size(
+            std::mem::size_of::<SkyboxUniforms>(),
+            std::mem::size_of::<TreeUniforms>(),
+        ),
+    );
+}
+
+const fn max_size(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
@@ -0,0 +1,703 @@
+//! Bounding volume hierarchy over scene nodes and instances
+//!
+//! [`Bvh`] indexes world-space boxes under caller-chosen keys: [`NodeId`]s for the
+//! meshes of a [`Scene`], instance indices for [`InstanceData`] arrays. The tree is
+//! built top-down by splitting at the median centroid along the widest axis, with up
+//! to four items per leaf. Moving items refits the boxes above them, which keeps
+//! queries exact but lets the tree loosen as things drift far from where they were
+//! built; rebuild after large rearrangements or when items are added or removed.
+
+use crate::math::{Aabb, Containment, Frustum, Ray, Vec3};
+use crate::scene::{InstanceData, Mesh, NodeId, Scene};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+/// Most items kept in a leaf before it is split
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    bounds: Aabb,
+    parent: Option<usize>,
+    /// Range of `Bvh::items` below this node; subtrees are contiguous
+    first: usize,
+    count: usize,
+    /// `None` for leaves
+    children: Option<(usize, usize)>,
+}
+
+/// Hierarchy of axis-aligned boxes answering ray, frustum, radius and
+/// nearest-neighbour queries without visiting every item
+#[derive(Debug, Clone)]
+pub struct Bvh<K> {
+    /// Nodes in depth-first order, so parents come before their children
+    nodes: Vec<BvhNode>,
+    /// Keys and boxes, ordered so each leaf owns a contiguous range
+    items: Vec<(K, Aabb)>,
+    /// Leaf holding each item
+    item_leaf: Vec<usize>,
+    /// Position of each key in `items`
+    lookup: HashMap<K, usize>,
+}
+
+impl<K: Copy + Eq + Hash> Bvh<K> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            items: Vec::new(),
+            item_leaf: Vec::new(),
+            lookup: HashMap::new(),
+        }
+    }
+
+    /// Builds a hierarchy over `items`; keys should be unique, and only the last box
+    /// given for a repeated key can be updated
+    #[must_use]
+    pub fn build(items: impl IntoIterator<Item = (K, Aabb)>) -> Self {
+        let items: Vec<(K, Aabb)> = items.into_iter().collect();
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * items.len().div_ceil(LEAF_SIZE)),
+            item_leaf: vec![0; items.len()],
+            items,
+            lookup: HashMap::new(),
+        };
+        if !bvh.items.is_empty() {
+            bvh.build_range(0, bvh.items.len(), None);
+        }
+        bvh.lookup = bvh
+            .items
+            .iter()
+            .enumerate()
+            .map(|(slot, (key, _))| (*key, slot))
+            .collect();
+        bvh
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Box around everything, or `None` when empty
+    #[must_use]
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|root| root.bounds)
+    }
+
+    /// Current box of `key`
+    #[must_use]
+    pub fn get(&self, key: K) -> Option<Aabb> {
+        let slot = *self.lookup.get(&key)?;
+        Some(self.items[slot].1)
+    }
+
+    /// Moves `key` to `bounds` and refits the boxes above it; returns `false` if the
+    /// key is not in the hierarchy
+    pub fn update(&mut self, key: K, bounds: Aabb) -> bool {
+        let Some(&slot) = self.lookup.get(&key) else {
+            return false;
+        };
+        self.items[slot].1 = bounds;
+        let mut node = Some(self.item_leaf[slot]);
+        while let Some(index) = node {
+            self.refit_node(index);
+            node = self.nodes[index].parent;
+        }
+        true
+    }
+
+    /// Items whose box `ray` enters within `max_distance`, nearest entry first
+    #[must_use]
+    pub fn ray_cast(&self, ray: &Ray, max_distance: f32) -> Vec<(K, f32)> {
+        let mut hits = Vec::new();
+        let mut stack: Vec<usize> = self.root().into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !ray
+                .intersect_aabb(&node.bounds)
+                .is_some_and(|t| t <= max_distance)
+            {
+                continue;
+            }
+            match node.children {
+                Some((left, right)) => stack.extend([left, right]),
+                None => hits.extend(self.node_items(node).iter().filter_map(|(key, bounds)| {
+                    let t = ray.intersect_aabb(bounds)?;
+                    (t <= max_distance).then_some((*key, t))
+                })),
+            }
+        }
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+        hits
+    }
+
+    /// Closest exact hit along `ray`, where `hit` returns the distance at which the
+    /// ray hits an item's geometry, if it does
+    ///
+    /// Boxes are visited nearest first and `hit` is only called for items whose box
+    /// the ray enters before the best hit found so far.
+    pub fn closest_hit<F>(&self, ray: &Ray, mut hit: F) -> Option<(K, f32)>
+    where
+        F: FnMut(K) -> Option<f32>,
+    {
+        let mut best: Option<(K, f32)> = None;
+        let mut heap = BinaryHeap::new();
+        if let Some(root) = self.root() {
+            if let Some(distance) = ray.intersect_aabb(&self.nodes[root].bounds) {
+                heap.push(Candidate::node(distance, root));
+            }
+        }
+
+        while let Some(candidate) = heap.pop() {
+            if best.is_some_and(|(_, best)| candidate.distance > best) {
+                break;
+            }
+            match candidate.entry {
+                Entry::Node(index) => {
+                    let node = &self.nodes[index];
+                    if let Some((left, right)) = node.children {
+                        for child in [left, right] {
+                            if let Some(distance) = ray.intersect_aabb(&self.nodes[child].bounds) {
+                                heap.push(Candidate::node(distance, child));
+                            }
+                        }
+                    } else {
+                        for slot in node.first..node.first + node.count {
+                            if let Some(distance) = ray.intersect_aabb(&self.items[slot].1) {
+                                heap.push(Candidate::item(distance, slot));
+                            }
+                        }
+                    }
+                }
+                Entry::Item(slot) => {
+                    let key = self.items[slot].0;
+                    if let Some(distance) = hit(key) {
+                        if !best.is_some_and(|(_, best)| distance >= best) {
+                            best = Some((key, distance));
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Items whose box is at least partly inside `frustum`
+    #[must_use]
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<K> {
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = self.root().into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            match frustum.classify_aabb(&node.bounds) {
+                Containment::Outside => {}
+                // Everything below is inside too
+                Containment::Inside => {
+                    found.extend(self.node_items(node).iter().map(|(key, _)| *key));
+                }
+                Containment::Intersecting => match node.children {
+                    Some((left, right)) => stack.extend([left, right]),
+                    None => found.extend(
+                        self.node_items(node)
+                            .iter()
+                            .filter(|(_, bounds)| frustum.intersects_aabb(bounds))
+                            .map(|(key, _)| *key),
+                    ),
+                },
+            }
+        }
+        found
+    }
+
+    /// Items whose box comes within `radius` of `center`
+    #[must_use]
+    pub fn query_sphere(&self, center: Vec3, radius: f32) -> Vec<K> {
+        let radius_sq = radius * radius;
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = self.root().into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if distance_squared(&node.bounds, &center) > radius_sq {
+                continue;
+            }
+            match node.children {
+                Some((left, right)) => stack.extend([left, right]),
+                None => found.extend(
+                    self.node_items(node)
+                        .iter()
+                        .filter(|(_, bounds)| distance_squared(bounds, &center) <= radius_sq)
+                        .map(|(key, _)| *key),
+                ),
+            }
+        }
+        found
+    }
+
+    /// Up to `k` items closest to `point` with their distances, nearest first;
+    /// distance is measured to each item's box and is 0 inside it
+    #[must_use]
+    pub fn nearest(&self, point: Vec3, k: usize) -> Vec<(K, f32)> {
+        let mut found = Vec::with_capacity(k.min(self.len()));
+        let mut heap = BinaryHeap::new();
+        if k > 0 {
+            if let Some(root) = self.root() {
+                let distance = distance_squared(&self.nodes[root].bounds, &point);
+                heap.push(Candidate::node(distance, root));
+            }
+        }
+
+        // Best-first over nodes and items together: once an item reaches the top of
+        // the heap, nothing left can be closer
+        while let Some(candidate) = heap.pop() {
+            match candidate.entry {
+                Entry::Node(index) => {
+                    let node = &self.nodes[index];
+                    if let Some((left, right)) = node.children {
+                        for child in [left, right] {
+                            let distance = distance_squared(&self.nodes[child].bounds, &point);
+                            heap.push(Candidate::node(distance, child));
+                        }
+                    } else {
+                        for slot in node.first..node.first + node.count {
+                            let distance = distance_squared(&self.items[slot].1, &point);
+                            heap.push(Candidate::item(distance, slot));
+                        }
+                    }
+                }
+                Entry::Item(slot) => {
+                    found.push((self.items[slot].0, candidate.distance.sqrt()));
+                    if found.len() == k {
+                        break;
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn root(&self) -> Option<usize> {
+        (!self.nodes.is_empty()).then_some(0)
+    }
+
+    fn node_items(&self, node: &BvhNode) -> &[(K, Aabb)] {
+        &self.items[node.first..node.first + node.count]
+    }
+
+    /// Appends the subtree over `items[first..first + count]`, returning its index
+    fn build_range(&mut self, first: usize, count: usize, parent: Option<usize>) -> usize {
+        let index = self.nodes.len();
+        let range = &mut self.items[first..first + count];
+        let bounds = union_of(range.iter().map(|(_, bounds)| bounds));
+        self.nodes.push(BvhNode {
+            bounds,
+            parent,
+            first,
+            count,
+            children: None,
+        });
+
+        if count <= LEAF_SIZE {
+            self.item_leaf[first..first + count].fill(index);
+            return index;
+        }
+
+        let centers: Vec<Vec3> = range.iter().map(|(_, bounds)| bounds.center()).collect();
+        let size = Aabb::from_points(&centers).map_or(Vec3::zero(), |centroids| centroids.size());
+        let axis = if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        };
+        let mid = count / 2;
+        range.select_nth_unstable_by(mid, |a, b| {
+            a.1.center()[axis].total_cmp(&b.1.center()[axis])
+        });
+
+        let left = self.build_range(first, mid, Some(index));
+        let right = self.build_range(first + mid, count - mid, Some(index));
+        self.nodes[index].children = Some((left, right));
+        index
+    }
+
+    fn refit_node(&mut self, index: usize) {
+        let node = &self.nodes[index];
+        let bounds = match node.children {
+            Some((left, right)) => self.nodes[left].bounds.union(&self.nodes[right].bounds),
+            None => union_of(self.node_items(node).iter().map(|(_, bounds)| bounds)),
+        };
+        self.nodes[index].bounds = bounds;
+    }
+
+    /// Refits every box after many items moved, children before parents
+    fn refit_all(&mut self) {
+        for index in (0..self.nodes.len()).rev() {
+            self.refit_node(index);
+        }
+    }
+}
+
+impl<K: Copy + Eq + Hash> Default for Bvh<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bvh<NodeId> {
+    /// Boxes every node with a mesh in world space
+    #[must_use]
+    pub fn from_scene(scene: &Scene) -> Self {
+        Self::build(scene_bounds(scene))
+    }
+
+    /// Refits to the scene's current world transforms; nodes added or removed since
+    /// the build need a rebuild
+    pub fn refit_scene(&mut self, scene: &Scene) {
+        for (id, bounds) in scene_bounds(scene) {
+            if let Some(&slot) = self.lookup.get(&id) {
+                self.items[slot].1 = bounds;
+            }
+        }
+        self.refit_all();
+    }
+}
+
+impl Bvh<usize> {
+    /// Boxes each instance of `mesh`, keyed by its index in `instances`
+    #[must_use]
+    pub fn from_instances(mesh: &Mesh, instances: &[InstanceData]) -> Self {
+        Self::build(instance_bounds(mesh, instances))
+    }
+
+    /// Refits to moved instance transforms; `instances` should be the array the
+    /// hierarchy was built from, and indices beyond the build are ignored
+    pub fn refit_instances(&mut self, mesh: &Mesh, instances: &[InstanceData]) {
+        for (index, bounds) in instance_bounds(mesh, instances) {
+            if let Some(&slot) = self.lookup.get(&index) {
+                self.items[slot].1 = bounds;
+            }
+        }
+        self.refit_all();
+    }
+}
+
+fn scene_bounds(scene: &Scene) -> Vec<(NodeId, Aabb)> {
+    let mut items = Vec::new();
+    scene.traverse(|id, node, world| {
+        if let Some(bounds) = node.mesh().and_then(Mesh::bounds) {
+            items.push((id, bounds.transform(world)));
+        }
+    });
+    items
+}
+
+fn instance_bounds<'a>(
+    mesh: &Mesh,
+    instances: &'a [InstanceData],
+) -> impl Iterator<Item = (usize, Aabb)> + 'a {
+    let bounds = mesh.bounds();
+    instances
+        .iter()
+        .enumerate()
+        .filter_map(move |(index, instance)| Some((index, bounds?.transform(&instance.transform))))
+}
+
+/// Union of non-empty `boxes`
+fn union_of<'a>(mut boxes: impl Iterator<Item = &'a Aabb>) -> Aabb {
+    let first = boxes
+        .next()
+        .copied()
+        .unwrap_or(Aabb::new(Vec3::zero(), Vec3::zero()));
+    boxes.fold(first, |acc, bounds| acc.union(bounds))
+}
+
+/// Squared distance from `point` to the closest point of `aabb`
+fn distance_squared(aabb: &Aabb, point: &Vec3) -> f32 {
+    let closest = point.max(&aabb.min).min(&aabb.max);
+    (closest - *point).length_squared()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Entry {
+    Node(usize),
+    Item(usize),
+}
+
+/// Heap entry ordered so the max-heap pops the smallest distance first
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    distance: f32,
+    entry: Entry,
+}
+
+impl Candidate {
+    fn node(distance: f32, index: usize) -> Self {
+        Self {
+            distance,
+            entry: Entry::Node(index),
+        }
+    }
+
+    fn item(distance: f32, slot: usize) -> Self {
+        Self {
+            distance,
+            entry: Entry::Item(slot),
+        }
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for the max-heap; items win ties with nodes so exact results
+        // surface as early as possible
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| self.entry.cmp(&other.entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Mat4, Vec4};
+    use crate::scene::Node;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn random_box(rng: &mut ChaCha8Rng) -> Aabb {
+        let center = Vec3::new(
+            rng.gen_range(-50.0..50.0),
+            rng.gen_range(-50.0..50.0),
+            rng.gen_range(-50.0..50.0),
+        );
+        let half = Vec3::new(
+            rng.gen_range(0.1..3.0),
+            rng.gen_range(0.1..3.0),
+            rng.gen_range(0.1..3.0),
+        );
+        Aabb::new(center - half, center + half)
+    }
+
+    fn random_boxes(count: usize, seed: u64) -> Vec<(usize, Aabb)> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        (0..count).map(|i| (i, random_box(&mut rng))).collect()
+    }
+
+    fn sorted(mut keys: Vec<usize>) -> Vec<usize> {
+        keys.sort_unstable();
+        keys
+    }
+
+    /// Checks every query against a linear scan of `items`
+    fn assert_matches_brute_force(bvh: &Bvh<usize>, items: &[(usize, Aabb)], seed: u64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        for _ in 0..20 {
+            let origin = Vec3::new(
+                rng.gen_range(-60.0..60.0),
+                rng.gen_range(-60.0..60.0),
+                rng.gen_range(-60.0..60.0),
+            );
+            let toward = random_box(&mut rng).center();
+            let ray = Ray::new(origin, toward - origin);
+
+            let expected: Vec<usize> = items
+                .iter()
+                .filter(|(_, bounds)| ray.intersect_aabb(bounds).is_some_and(|t| t <= 80.0))
+                .map(|(key, _)| *key)
+                .collect();
+            let hits = bvh.ray_cast(&ray, 80.0);
+            assert!(hits.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+            assert_eq!(
+                sorted(hits.iter().map(|(key, _)| *key).collect()),
+                sorted(expected)
+            );
+
+            // Treat each box's center as a hit target for the exact test
+            let exact = |key: usize| {
+                let center = items[key].1.center();
+                let along = (center - ray.origin).dot(&ray.direction);
+                (along > 0.0 && ray.at(along).distance(&center) < 1.0).then_some(along)
+            };
+            let brute = items
+                .iter()
+                .filter_map(|(key, _)| exact(*key).map(|t| (*key, t)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            assert_eq!(bvh.closest_hit(&ray, exact), brute);
+
+            let radius = rng.gen_range(1.0..20.0);
+            let expected: Vec<usize> = items
+                .iter()
+                .filter(|(_, bounds)| distance_squared(bounds, &origin) <= radius * radius)
+                .map(|(key, _)| *key)
+                .collect();
+            assert_eq!(sorted(bvh.query_sphere(origin, radius)), sorted(expected));
+
+            let mut distances: Vec<f32> = items
+                .iter()
+                .map(|(_, bounds)| distance_squared(bounds, &origin).sqrt())
+                .collect();
+            distances.sort_by(f32::total_cmp);
+            let nearest = bvh.nearest(origin, 7);
+            assert_eq!(nearest.len(), 7.min(items.len()));
+            for ((key, distance), expected) in nearest.iter().zip(&distances) {
+                assert_eq!(distance, expected);
+                assert_eq!(distance_squared(&items[*key].1, &origin).sqrt(), *distance);
+            }
+        }
+
+        let frustum = frustum();
+        let expected: Vec<usize> = items
+            .iter()
+            .filter(|(_, bounds)| frustum.intersects_aabb(bounds))
+            .map(|(key, _)| *key)
+            .collect();
+        assert_eq!(sorted(bvh.query_frustum(&frustum)), sorted(expected));
+    }
+
+    /// View of part of the random boxes, from outside them
+    fn frustum() -> Frustum {
+        let projection = Mat4::perspective(1.0, 1.5, 0.5, 60.0);
+        let view = Mat4::look_at(
+            &Vec3::new(10.0, 20.0, 70.0),
+            &Vec3::new(-5.0, 0.0, 0.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        Frustum::from_view_projection(&projection.multiply(&view))
+    }
+
+    #[test]
+    fn test_queries_match_brute_force() {
+        let items = random_boxes(500, 1);
+        let bvh = Bvh::build(items.clone());
+        assert_eq!(bvh.len(), 500);
+        let expected = items
+            .iter()
+            .skip(1)
+            .fold(items[0].1, |acc, (_, bounds)| acc.union(bounds));
+        assert_eq!(bvh.bounds(), Some(expected));
+        assert_matches_brute_force(&bvh, &items, 2);
+
+        // The frustum comparison is not trivially all or nothing
+        let visible = bvh.query_frustum(&frustum()).len();
+        assert!(visible > 0 && visible < items.len());
+    }
+
+    #[test]
+    fn test_refit_after_moves_matches_brute_force() {
+        let mut items = random_boxes(300, 3);
+        let mut bvh = Bvh::build(items.clone());
+
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        for _ in 0..100 {
+            let key = rng.gen_range(0..items.len());
+            let bounds = random_box(&mut rng);
+            items[key].1 = bounds;
+            assert!(bvh.update(key, bounds));
+            assert_eq!(bvh.get(key), Some(bounds));
+        }
+        assert!(!bvh.update(1000, items[0].1));
+        assert_matches_brute_force(&bvh, &items, 5);
+    }
+
+    #[test]
+    fn test_empty_and_small_trees() {
+        let empty: Bvh<usize> = Bvh::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.bounds(), None);
+        let ray = Ray::new(Vec3::zero(), Vec3::new(1.0, 0.0, 0.0));
+        assert!(empty.ray_cast(&ray, f32::INFINITY).is_empty());
+        assert!(empty.nearest(Vec3::zero(), 3).is_empty());
+
+        let items = random_boxes(3, 6);
+        let bvh = Bvh::build(items.clone());
+        assert_eq!(bvh.nearest(Vec3::zero(), 10).len(), 3);
+        assert!(bvh.nearest(Vec3::zero(), 0).is_empty());
+        assert_matches_brute_force(&bvh, &items, 7);
+    }
+
+    #[test]
+    fn test_scene_nodes_refit_with_their_parents() {
+        let mut scene = Scene::new();
+        let mut parent = Node::new("Parent".to_string());
+        parent.set_position(Vec3::new(10.0, 0.0, 0.0));
+        let parent = scene.add_node(parent);
+        let mut child = Node::with_mesh("Child".to_string(), Mesh::cube());
+        child.set_position(Vec3::new(0.0, 2.0, 0.0));
+        let child = scene.add_child(parent, child).unwrap();
+        let other = scene.add_node(Node::with_mesh("Other".to_string(), Mesh::cube()));
+
+        let mut bvh = Bvh::from_scene(&scene);
+        assert_eq!(bvh.len(), 2);
+        let bounds = bvh.get(child).unwrap();
+        assert_eq!(bounds.center(), Vec3::new(10.0, 2.0, 0.0));
+
+        // Moving the parent moves the child's box after a refit
+        scene
+            .get_mut(parent)
+            .unwrap()
+            .set_position(Vec3::new(-10.0, 0.0, 0.0));
+        bvh.refit_scene(&scene);
+        assert_eq!(bvh.get(child).unwrap().center(), Vec3::new(-10.0, 2.0, 0.0));
+        let nearest = bvh.nearest(Vec3::new(-10.0, 5.0, 0.0), 1);
+        assert_eq!(nearest[0].0, child);
+        assert_eq!(bvh.query_sphere(Vec3::zero(), 1.0), vec![other]);
+    }
+
+    #[test]
+    fn test_instances_keyed_by_index() {
+        let mut rng = ChaCha8Rng::seed_from_u64(8);
+        let mut instances: Vec<InstanceData> = (0..200)
+            .map(|_| {
+                let position = Vec3::new(
+                    rng.gen_range(-40.0..40.0),
+                    rng.gen_range(-40.0..40.0),
+                    rng.gen_range(-40.0..40.0),
+                );
+                InstanceData {
+                    transform: Mat4::translation(position.x, position.y, position.z),
+                    color_variation: Vec3::zero(),
+                    lod_level: 0,
+                    texture_index: 0,
+                    _padding: [0; 3],
+                }
+            })
+            .collect();
+        let mesh = Mesh::cube();
+        let mut bvh = Bvh::from_instances(&mesh, &instances);
+
+        for instance in instances.iter_mut().step_by(3) {
+            instance.transform.cols[3] += Vec4::new(5.0, -5.0, 2.0, 0.0);
+        }
+        bvh.refit_instances(&mesh, &instances);
+
+        let items: Vec<(usize, Aabb)> = instances
+            .iter()
+            .enumerate()
+            .map(|(i, instance)| (i, mesh.bounds().unwrap().transform(&instance.transform)))
+            .collect();
+        assert_matches_brute_force(&bvh, &items, 9);
+    }
+}
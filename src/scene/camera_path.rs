@@ -0,0 +1,732 @@
+//! Keyframed camera paths for fly-throughs and reproducible captures
+//!
+//! A [`CameraPath`] holds keyframes sorted by time. Positions follow a Catmull-Rom
+//! spline through the keyframes, with tangents scaled by the keyframe spacing so
+//! uneven timing does not overshoot; view directions and up vectors are slerped and
+//! the field of view is lerped. Each keyframe's [`Easing`] reshapes the segment that
+//! starts at it. A path with a [`SurfaceClearance`] never dips below a minimum
+//! altitude over the planet, however the spline swings between keyframes.
+//!
+//! Looping paths wrap from the last keyframe back to the first, so the last
+//! keyframe should repeat the first; the spline is smooth across the wrap.
+//!
+//! Paths are saved as versioned RON files with positions as `(x, y, z)` triples and
+//! the field of view in degrees:
+//!
+//! ```ron
+//! (
+//!     version: 1,
+//!     looping: true,
+//!     clearance: Some((center: (0.0, 0.0, 0.0), radius: 25.0, min_altitude: 2.0)),
+//!     keyframes: [
+//!         (time: 0.0, position: (0.0, 60.0, 0.0), forward: (0.0, -1.0, 0.1)),
+//!         (time: 4.0, position: (60.0, 0.0, 0.0), forward: (-1.0, 0.0, 0.0), easing: EaseInOut),
+//!         (time: 8.0, position: (0.0, 60.0, 0.0), forward: (0.0, -1.0, 0.1)),
+//!     ],
+//! )
+//! ```
+
+use crate::math::{sphere, Vec3};
+use crate::scene::{Camera, Projection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Version written to new camera path files; files with a newer version are rejected
+pub const CAMERA_PATH_FORMAT_VERSION: u32 = 1;
+
+/// Shape of the progress through one segment of a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly and arrives at full speed
+    EaseIn,
+    /// Leaves at full speed and slows down on arrival
+    EaseOut,
+    /// Slow at both ends
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps linear progress in [0, 1] to eased progress in [0, 1]
+    #[must_use]
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Where the camera is and where it looks at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKeyframe {
+    /// Seconds from the start of the path
+    pub time: f32,
+    pub position: Vec3,
+    pub forward: Vec3,
+    pub up: Vec3,
+    /// Vertical field of view in radians
+    pub fov_y: f32,
+    /// Easing of the segment from this keyframe to the next
+    pub easing: Easing,
+}
+
+impl CameraKeyframe {
+    /// Keyframe with a +Y up vector, a 45 degree field of view and linear easing
+    #[must_use]
+    pub fn new(time: f32, position: Vec3, forward: Vec3) -> Self {
+        Self {
+            time,
+            position,
+            forward,
+            up: Vec3::new(0.0, 1.0, 0.0),
+            fov_y: std::f32::consts::FRAC_PI_4,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Captures the current view of `camera`
+    #[must_use]
+    pub fn from_camera(time: f32, camera: &Camera) -> Self {
+        Self {
+            up: camera.up_vector(),
+            fov_y: camera.fov_y(),
+            ..Self::new(time, camera.position(), camera.forward())
+        }
+    }
+
+    fn pose(&self) -> CameraPose {
+        CameraPose {
+            position: self.position,
+            forward: self.forward.normalize(),
+            up: self.up.normalize(),
+            fov_y: self.fov_y,
+        }
+    }
+}
+
+/// Interpolated camera state along a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    pub position: Vec3,
+    pub forward: Vec3,
+    pub up: Vec3,
+    pub fov_y: f32,
+}
+
+impl CameraPose {
+    /// Moves `camera` to this pose with a perspective projection
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.set_projection(Projection::Perspective);
+        camera.set_position(self.position);
+        camera.set_orientation(self.forward, self.up);
+        camera.set_fov_y(self.fov_y);
+    }
+}
+
+/// Sphere a path must stay clear of, usually the planet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceClearance {
+    pub center: Vec3,
+    pub radius: f32,
+    /// Lowest allowed height above the sphere
+    pub min_altitude: f32,
+}
+
+impl SurfaceClearance {
+    /// `position`, pushed straight up if it is below the minimum altitude
+    #[must_use]
+    pub fn clamp(&self, position: Vec3) -> Vec3 {
+        let offset = position - self.center;
+        let min_distance = self.radius + self.min_altitude;
+        if offset.length() >= min_distance {
+            return position;
+        }
+        let up = offset
+            .try_normalize()
+            .unwrap_or_else(|| Vec3::new(0.0, 1.0, 0.0));
+        self.center + up * min_distance
+    }
+}
+
+/// Keyframes sorted by time with the settings for playing them back
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    /// Wrap around to the start after the last keyframe
+    pub looping: bool,
+    pub clearance: Option<SurfaceClearance>,
+}
+
+impl CameraPath {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a path from keyframes in any order
+    #[must_use]
+    pub fn from_keyframes(keyframes: Vec<CameraKeyframe>) -> Self {
+        let mut path = Self::new();
+        for keyframe in keyframes {
+            path.add_keyframe(keyframe);
+        }
+        path
+    }
+
+    /// Inserts `keyframe` in time order, after any keyframes at the same time
+    pub fn add_keyframe(&mut self, keyframe: CameraKeyframe) {
+        let index = self
+            .keyframes
+            .partition_point(|existing| existing.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    /// Removes and returns the keyframe at `index`
+    pub fn remove_keyframe(&mut self, index: usize) -> Option<CameraKeyframe> {
+        (index < self.keyframes.len()).then(|| self.keyframes.remove(index))
+    }
+
+    #[must_use]
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Time of the first keyframe
+    #[must_use]
+    pub fn start_time(&self) -> f32 {
+        self.keyframes.first().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Time of the last keyframe
+    #[must_use]
+    pub fn end_time(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> f32 {
+        self.end_time() - self.start_time()
+    }
+
+    /// Camera pose at `time`; before the start and after the end of a path that
+    /// does not loop, the first or last keyframe
+    #[must_use]
+    pub fn sample(&self, time: f32) -> Option<CameraPose> {
+        let (first, last) = (self.keyframes.first()?, self.keyframes.last()?);
+        let duration = self.duration();
+        let time = if self.looping && duration > 0.0 {
+            first.time + (time - first.time).rem_euclid(duration)
+        } else {
+            time.clamp(first.time, last.time)
+        };
+
+        // Segment from keyframe `i` to `i + 1` containing `time`
+        let i = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time)
+            .saturating_sub(1)
+            .min(self.keyframes.len().saturating_sub(2));
+        let Some(next) = self.keyframes.get(i + 1) else {
+            return Some(self.clamped(first.pose()));
+        };
+        let current = &self.keyframes[i];
+
+        let span = next.time - current.time;
+        let progress = if span > 0.0 {
+            (time - current.time) / span
+        } else {
+            1.0
+        };
+        let t = current.easing.apply(progress);
+
+        // Cubic Hermite with Catmull-Rom tangents, scaled to this segment's length
+        let (m0, m1) = (self.tangent(i) * span, self.tangent(i + 1) * span);
+        let (t2, t3) = (t * t, t * t * t);
+        let position = current.position * (2.0 * t3 - 3.0 * t2 + 1.0)
+            + m0 * (t3 - 2.0 * t2 + t)
+            + next.position * (-2.0 * t3 + 3.0 * t2)
+            + m1 * (t3 - t2);
+
+        Some(self.clamped(CameraPose {
+            position,
+            forward: sphere::slerp(&current.forward, &next.forward, t),
+            up: sphere::slerp(&current.up, &next.up, t),
+            fov_y: current.fov_y + (next.fov_y - current.fov_y) * t,
+        }))
+    }
+
+    /// Velocity at keyframe `index`: the central difference of its neighbors, which
+    /// wrap around on a looping path and are one-sided at the ends of an open one
+    fn tangent(&self, index: usize) -> Vec3 {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+        let duration = self.duration();
+        let wraps = self.looping && last >= 2 && duration > 0.0;
+
+        let (before, before_time) = match index {
+            0 if wraps => (
+                keyframes[last - 1].position,
+                keyframes[last - 1].time - duration,
+            ),
+            0 => (keyframes[0].position, keyframes[0].time),
+            _ => (keyframes[index - 1].position, keyframes[index - 1].time),
+        };
+        let (after, after_time) = match index {
+            i if i == last && wraps => (keyframes[1].position, keyframes[1].time + duration),
+            i if i == last => (keyframes[last].position, keyframes[last].time),
+            _ => (keyframes[index + 1].position, keyframes[index + 1].time),
+        };
+
+        let span = after_time - before_time;
+        if span > 0.0 {
+            (after - before) / span
+        } else {
+            Vec3::zero()
+        }
+    }
+
+    fn clamped(&self, mut pose: CameraPose) -> CameraPose {
+        if let Some(clearance) = &self.clearance {
+            pose.position = clearance.clamp(pose.position);
+        }
+        pose
+    }
+
+    /// Serializes the path as pretty-printed RON
+    ///
+    /// # Errors
+    /// Returns an error if RON serialization fails
+    pub fn to_ron_string(&self) -> Result<String, String> {
+        let file = CameraPathFile {
+            version: CAMERA_PATH_FORMAT_VERSION,
+            looping: self.looping,
+            clearance: self.clearance.map(|clearance| ClearanceData {
+                center: to_array3(&clearance.center),
+                radius: clearance.radius,
+                min_altitude: clearance.min_altitude,
+            }),
+            keyframes: self
+                .keyframes
+                .iter()
+                .map(|keyframe| KeyframeData {
+                    time: keyframe.time,
+                    position: to_array3(&keyframe.position),
+                    forward: to_array3(&keyframe.forward),
+                    up: to_array3(&keyframe.up),
+                    fov_degrees: keyframe.fov_y.to_degrees(),
+                    easing: keyframe.easing,
+                })
+                .collect(),
+        };
+        ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::new())
+            .map_err(|e| format!("Failed to serialize camera path: {e}"))
+    }
+
+    /// Parses a camera path from RON text
+    ///
+    /// # Errors
+    /// Returns an error if the text is not a valid camera path, its version is not
+    /// supported, or a keyframe has a non-finite value or zero-length direction
+    pub fn from_ron_str(text: &str) -> Result<Self, String> {
+        let header: VersionHeader =
+            ron::from_str(text).map_err(|e| format!("Failed to parse camera path header: {e}"))?;
+        if header.version == 0 || header.version > CAMERA_PATH_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported camera path format version {} (supported: 1 to {CAMERA_PATH_FORMAT_VERSION})",
+                header.version
+            ));
+        }
+
+        let file: CameraPathFile =
+            ron::from_str(text).map_err(|e| format!("Failed to parse camera path: {e}"))?;
+        let mut path = Self {
+            looping: file.looping,
+            clearance: file.clearance.map(|clearance| SurfaceClearance {
+                center: from_array3(clearance.center),
+                radius: clearance.radius,
+                min_altitude: clearance.min_altitude,
+            }),
+            ..Self::new()
+        };
+        for (index, data) in file.keyframes.into_iter().enumerate() {
+            let keyframe = CameraKeyframe {
+                time: data.time,
+                position: from_array3(data.position),
+                forward: from_array3(data.forward),
+                up: from_array3(data.up),
+                fov_y: data.fov_degrees.to_radians(),
+                easing: data.easing,
+            };
+            let finite = [keyframe.position, keyframe.forward, keyframe.up]
+                .iter()
+                .all(|v| v.x.is_finite() && v.y.is_finite() && v.z.is_finite())
+                && keyframe.time.is_finite()
+                && keyframe.fov_y.is_finite();
+            if !finite {
+                return Err(format!("Camera keyframe {index} has a non-finite value"));
+            }
+            if keyframe.forward.try_normalize().is_none() || keyframe.up.try_normalize().is_none() {
+                return Err(format!(
+                    "Camera keyframe {index} has a zero-length forward or up vector"
+                ));
+            }
+            path.add_keyframe(keyframe);
+        }
+        Ok(path)
+    }
+
+    /// Writes the path to a RON file
+    ///
+    /// # Errors
+    /// Returns an error if serialization or writing the file fails
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = self.to_ron_string()?;
+        std::fs::write(path, text)
+            .map_err(|e| format!("Failed to write camera path {}: {e}", path.display()))
+    }
+
+    /// Reads a camera path from a RON file
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not a valid camera path
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read camera path {}: {e}", path.display()))?;
+        Self::from_ron_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+/// Plays a [`CameraPath`] back onto a camera
+#[derive(Debug, Clone)]
+pub struct CameraPathPlayer {
+    path: CameraPath,
+    time: f32,
+    /// Playback rate; 1 is real time and negative values play backwards
+    pub speed: f32,
+    playing: bool,
+}
+
+impl CameraPathPlayer {
+    /// Player paused at the start of `path`
+    #[must_use]
+    pub fn new(path: CameraPath) -> Self {
+        Self {
+            time: path.start_time(),
+            path,
+            speed: 1.0,
+            playing: false,
+        }
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &CameraPath {
+        &self.path
+    }
+
+    #[must_use]
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Jumps to `time`, wrapped or clamped like [`CameraPath::sample`]
+    pub fn seek(&mut self, time: f32) {
+        self.time = time;
+        self.wrap_or_stop();
+    }
+
+    /// Whether a path that does not loop has played to its end
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        !self.path.looping
+            && if self.speed < 0.0 {
+                self.time <= self.path.start_time()
+            } else {
+                self.time >= self.path.end_time()
+            }
+    }
+
+    /// Advances playback by `delta_time` seconds and moves `camera` to the pose at
+    /// the new time
+    pub fn advance(&mut self, camera: &mut Camera, delta_time: f32) {
+        if self.playing {
+            self.time += delta_time * self.speed;
+            self.wrap_or_stop();
+        }
+        self.apply(camera);
+    }
+
+    /// Moves `camera` to the pose at the current time
+    pub fn apply(&self, camera: &mut Camera) {
+        if let Some(pose) = self.path.sample(self.time) {
+            pose.apply(camera);
+        }
+    }
+
+    fn wrap_or_stop(&mut self) {
+        let (start, duration) = (self.path.start_time(), self.path.duration());
+        if self.path.looping && duration > 0.0 {
+            self.time = start + (self.time - start).rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(start, self.path.end_time());
+            if self.is_finished() {
+                self.playing = false;
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraPathFile {
+    version: u32,
+    #[serde(default)]
+    looping: bool,
+    #[serde(default)]
+    clearance: Option<ClearanceData>,
+    keyframes: Vec<KeyframeData>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClearanceData {
+    center: [f32; 3],
+    radius: f32,
+    min_altitude: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeData {
+    time: f32,
+    position: [f32; 3],
+    forward: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    #[serde(default = "default_fov_degrees")]
+    fov_degrees: f32,
+    #[serde(default)]
+    easing: Easing,
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_fov_degrees() -> f32 {
+    45.0
+}
+
+fn to_array3(v: &Vec3) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+fn from_array3([x, y, z]: [f32; 3]) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orbit_path() -> CameraPath {
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        let mut path = CameraPath::from_keyframes(vec![
+            CameraKeyframe::new(8.0, Vec3::new(0.0, 40.0, 0.0), down),
+            CameraKeyframe::new(0.0, Vec3::new(0.0, 40.0, 0.0), down),
+            CameraKeyframe::new(4.0, Vec3::new(0.0, -40.0, 0.0), -down),
+            CameraKeyframe::new(2.0, Vec3::new(40.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+            CameraKeyframe::new(6.0, Vec3::new(-40.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+        ]);
+        path.looping = true;
+        path
+    }
+
+    #[test]
+    fn test_keyframes_are_sorted_and_hit_exactly() {
+        let path = orbit_path();
+        let times: Vec<f32> = path.keyframes().iter().map(|k| k.time).collect();
+        assert_eq!(times, [0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(path.duration(), 8.0);
+
+        for keyframe in path.keyframes() {
+            let pose = path.sample(keyframe.time).unwrap();
+            assert!(pose.position.distance(&keyframe.position) < 1e-4);
+            assert!(pose.forward.dot(&keyframe.forward.normalize()) > 0.9999);
+        }
+        assert!(CameraPath::new().sample(0.0).is_none());
+    }
+
+    #[test]
+    fn test_spline_is_smooth_and_loops() {
+        let path = orbit_path();
+        // Halfway between two keyframes the spline bulges outwards like a circle
+        // would, rather than cutting the corner like a straight line
+        let between = path.sample(1.0).unwrap().position;
+        assert!(between.length() > 40.0 * std::f32::consts::FRAC_1_SQRT_2 + 5.0);
+
+        // Looping wraps time, and the wrap has no kink in velocity
+        let step = 0.01;
+        let before = path.sample(8.0 - step).unwrap().position;
+        let at = path.sample(16.0).unwrap().position;
+        let after = path.sample(8.0 + step).unwrap().position;
+        let (v0, v1) = ((at - before) / step, (after - at) / step);
+        assert!((v0 - v1).length() < v0.length() * 0.05);
+        assert!(at.distance(&Vec3::new(0.0, 40.0, 0.0)) < 1e-4);
+    }
+
+    #[test]
+    fn test_open_path_clamps_and_clears_surface() {
+        let mut path = orbit_path();
+        path.looping = false;
+        assert!(
+            path.sample(-5.0)
+                .unwrap()
+                .position
+                .distance(&Vec3::new(0.0, 40.0, 0.0))
+                < 1e-4
+        );
+        assert!(
+            path.sample(50.0)
+                .unwrap()
+                .position
+                .distance(&Vec3::new(0.0, 40.0, 0.0))
+                < 1e-4
+        );
+
+        // Two keyframes on opposite sides cut through the planet without clearance
+        let mut through = CameraPath::from_keyframes(vec![
+            CameraKeyframe::new(0.0, Vec3::new(0.0, 30.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            CameraKeyframe::new(1.0, Vec3::new(0.0, -30.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+        ]);
+        assert!(through.sample(0.5).unwrap().position.length() < 1.0);
+        through.clearance = Some(SurfaceClearance {
+            center: Vec3::zero(),
+            radius: 25.0,
+            min_altitude: 2.0,
+        });
+        for step in 0..=20 {
+            let position = through.sample(step as f32 / 20.0).unwrap().position;
+            assert!(position.length() >= 27.0 - 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+
+        // An eased segment moves less at its start than a linear one
+        let mut path = CameraPath::from_keyframes(vec![
+            CameraKeyframe::new(0.0, Vec3::zero(), Vec3::new(0.0, 0.0, -1.0)),
+            CameraKeyframe::new(1.0, Vec3::new(10.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+        ]);
+        let linear = path.sample(0.1).unwrap().position.x;
+        path.keyframes[0].easing = Easing::EaseIn;
+        assert!(path.sample(0.1).unwrap().position.x < linear);
+    }
+
+    #[test]
+    fn test_round_trip_through_ron() {
+        let mut path = orbit_path();
+        path.keyframes[1].easing = Easing::EaseInOut;
+        path.keyframes[2].fov_y = 1.0;
+        path.clearance = Some(SurfaceClearance {
+            center: Vec3::zero(),
+            radius: 25.0,
+            min_altitude: 1.5,
+        });
+        let text = path.to_ron_string().unwrap();
+        let loaded = CameraPath::from_ron_str(&text).unwrap();
+        assert_eq!(loaded.to_ron_string().unwrap(), text);
+        assert_eq!(loaded.keyframes()[1].easing, Easing::EaseInOut);
+        assert!((loaded.keyframes()[2].fov_y - 1.0).abs() < 1e-6);
+        assert!(loaded.looping);
+    }
+
+    #[test]
+    fn test_minimal_file_and_errors() {
+        let path = CameraPath::from_ron_str(
+            "(version: 1, keyframes: [(time: 0.0, position: (0.0, 30.0, 0.0), forward: (0.0, 0.0, -1.0))])",
+        )
+        .unwrap();
+        assert_eq!(path.keyframes()[0].up, Vec3::new(0.0, 1.0, 0.0));
+        assert!((path.keyframes()[0].fov_y - std::f32::consts::FRAC_PI_4).abs() < 1e-6);
+        assert!(!path.looping && path.clearance.is_none());
+
+        assert!(CameraPath::from_ron_str("(version: 2, keyframes: [])")
+            .unwrap_err()
+            .contains("version 2"));
+        assert!(CameraPath::from_ron_str(
+            "(version: 1, keyframes: [(time: 0.0, position: (0.0, 0.0, 0.0), forward: (0.0, 0.0, 0.0))])"
+        )
+        .unwrap_err()
+        .contains("zero-length"));
+    }
+
+    #[test]
+    fn test_player_drives_camera() {
+        let mut path = orbit_path();
+        path.looping = false;
+        let mut player = CameraPathPlayer::new(path);
+        let mut camera = Camera::new(Vec3::zero(), Vec3::new(0.0, 0.0, -1.0), 1.0);
+
+        // Paused players still put the camera on the path
+        player.advance(&mut camera, 1.0);
+        assert_eq!(player.time(), 0.0);
+        assert!(camera.position().distance(&Vec3::new(0.0, 40.0, 0.0)) < 1e-4);
+
+        player.play();
+        player.advance(&mut camera, 2.0);
+        assert!(camera.position().distance(&Vec3::new(40.0, 0.0, 0.0)) < 1e-4);
+        assert!(camera.forward().dot(&Vec3::new(-1.0, 0.0, 0.0)) > 0.999);
+
+        player.advance(&mut camera, 100.0);
+        assert!(player.is_finished() && !player.is_playing());
+        assert_eq!(player.time(), 8.0);
+
+        player.speed = -2.0;
+        player.play();
+        player.advance(&mut camera, 1.0);
+        assert_eq!(player.time(), 6.0);
+    }
+}
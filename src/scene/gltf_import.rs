@@ -0,0 +1,586 @@
+//! glTF 2.0 import
+//!
+//! Reads `.gltf` files (with external or embedded buffers and images) and binary
+//! `.glb` files into [`Scene`] nodes. Each glTF node becomes a [`Node`] with its
+//! local transform; a mesh with a single primitive is attached to the node itself,
+//! and one with several primitives gets a child node per primitive so each keeps
+//! its own material. Only the base color of the metallic-roughness material is
+//! used: its texture becomes the node texture, tinted by the base color factor, and
+//! a factor without a texture becomes a 1x1 texture.
+
+use crate::math::{Quat, Transform, Vec2, Vec3};
+use crate::scene::{mesh_ops, IndexFormat, Mesh, Node, NodeId, Scene, Vertex};
+use crate::warn;
+use gltf::image::Format;
+use gltf::mesh::Mode;
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+use std::sync::Arc;
+
+impl Scene {
+    /// Imports the default scene of a `.gltf` or `.glb` file under `parent`, or as
+    /// new roots, returning the ids of the imported top-level nodes
+    ///
+    /// # Errors
+    /// Returns an error if the file or any resource it references cannot be read,
+    /// or a mesh cannot be converted
+    pub fn import_gltf<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        parent: Option<NodeId>,
+    ) -> Result<Vec<NodeId>, String> {
+        let path = path.as_ref();
+        let (document, buffers, images) = gltf::import(path)
+            .map_err(|e| format!("Failed to import glTF {}: {e}", path.display()))?;
+        GltfImporter::new(&buffers, &images)
+            .import(self, &document, parent)
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Like [`Self::import_gltf`], from the contents of a `.glb` file or a `.gltf`
+    /// file whose resources are all embedded
+    ///
+    /// # Errors
+    /// Returns an error if the data is not valid glTF or a mesh cannot be converted
+    pub fn import_gltf_slice(
+        &mut self,
+        data: &[u8],
+        parent: Option<NodeId>,
+    ) -> Result<Vec<NodeId>, String> {
+        let (document, buffers, images) =
+            gltf::import_slice(data).map_err(|e| format!("Failed to import glTF: {e}"))?;
+        GltfImporter::new(&buffers, &images).import(self, &document, parent)
+    }
+}
+
+/// Mesh and base color texture of one glTF primitive
+type Primitive = (Mesh, Option<Arc<RgbaImage>>);
+
+struct GltfImporter<'a> {
+    buffers: &'a [gltf::buffer::Data],
+    images: &'a [gltf::image::Data],
+    /// Decoded images, shared by every primitive that uses them
+    textures: Vec<Option<Arc<RgbaImage>>>,
+}
+
+impl<'a> GltfImporter<'a> {
+    fn new(buffers: &'a [gltf::buffer::Data], images: &'a [gltf::image::Data]) -> Self {
+        Self {
+            buffers,
+            images,
+            textures: vec![None; images.len()],
+        }
+    }
+
+    fn import(
+        &mut self,
+        scene: &mut Scene,
+        document: &gltf::Document,
+        parent: Option<NodeId>,
+    ) -> Result<Vec<NodeId>, String> {
+        let roots: Vec<gltf::Node> = match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(gltf_scene) => gltf_scene.nodes().collect(),
+            // Without scenes, every node that is nobody's child is a root
+            None => {
+                let children: Vec<usize> = document
+                    .nodes()
+                    .flat_map(|node| node.children().map(|child| child.index()))
+                    .collect();
+                document
+                    .nodes()
+                    .filter(|node| !children.contains(&node.index()))
+                    .collect()
+            }
+        };
+
+        roots
+            .iter()
+            .map(|node| self.import_node(scene, node, parent))
+            .collect()
+    }
+
+    fn import_node(
+        &mut self,
+        scene: &mut Scene,
+        gltf_node: &gltf::Node,
+        parent: Option<NodeId>,
+    ) -> Result<NodeId, String> {
+        let name = gltf_node
+            .name()
+            .map_or_else(|| format!("node{}", gltf_node.index()), str::to_string);
+        let mut node = Node::new(name.clone());
+        let ([tx, ty, tz], [rx, ry, rz, rw], [sx, sy, sz]) = gltf_node.transform().decomposed();
+        node.set_transform(Transform::new(
+            Vec3::new(tx, ty, tz),
+            Quat::new(rx, ry, rz, rw),
+            Vec3::new(sx, sy, sz),
+        ));
+
+        let mut primitives = Vec::new();
+        if let Some(mesh) = gltf_node.mesh() {
+            for primitive in mesh.primitives() {
+                if let Some(converted) = self.convert_primitive(&primitive)? {
+                    primitives.push(converted);
+                }
+            }
+        }
+
+        // A single primitive lives on the node itself
+        if primitives.len() == 1 {
+            if let Some((mesh, texture)) = primitives.pop() {
+                node.set_mesh(Some(mesh));
+                node.set_texture(texture);
+            }
+        }
+
+        let id = match parent {
+            Some(parent) => scene.add_child(parent, node)?,
+            None => scene.add_node(node),
+        };
+        for (i, (mesh, texture)) in primitives.into_iter().enumerate() {
+            let mut child = Node::with_mesh(format!("{name}.primitive{i}"), mesh);
+            child.set_texture(texture);
+            scene.add_child(id, child)?;
+        }
+        for child in gltf_node.children() {
+            self.import_node(scene, &child, Some(id))?;
+        }
+        Ok(id)
+    }
+
+    /// Triangle mesh and base color texture of a primitive, or `None` for point and
+    /// line primitives
+    fn convert_primitive(
+        &mut self,
+        primitive: &gltf::Primitive,
+    ) -> Result<Option<Primitive>, String> {
+        let mode = primitive.mode();
+        if !matches!(
+            mode,
+            Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+        ) {
+            warn!("Skipping glTF primitive with mode {:?}", mode);
+            return Ok(None);
+        }
+
+        let reader = primitive.reader(|buffer| self.buffers.get(buffer.index()).map(|d| &d.0[..]));
+        let positions: Vec<[f32; 3]> = reader
+            .read_positions()
+            .ok_or("glTF primitive has no positions")?
+            .collect();
+        IndexFormat::for_vertex_count(positions.len())?;
+
+        let material = primitive.material().pbr_metallic_roughness();
+        let base_color = material.base_color_texture();
+        let tex_coord_set = base_color
+            .as_ref()
+            .map_or(0, gltf::texture::Info::tex_coord);
+        let tex_coords: Vec<[f32; 2]> = reader
+            .read_tex_coords(tex_coord_set)
+            .map(|coords| coords.into_f32().collect())
+            .unwrap_or_default();
+
+        let vertex_count = u32::try_from(positions.len()).unwrap_or(u32::MAX);
+        let order: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertex_count).collect(),
+        };
+        if let Some(index) = order.iter().find(|&&i| i >= vertex_count) {
+            return Err(format!(
+                "glTF index {index} is out of range for {vertex_count} vertices"
+            ));
+        }
+        let indices = triangle_list(mode, &order);
+
+        let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(Iterator::collect);
+
+        let vertices = positions
+            .iter()
+            .enumerate()
+            .map(|(i, &[x, y, z])| {
+                let [nx, ny, nz] = normals
+                    .as_ref()
+                    .and_then(|normals| normals.get(i).copied())
+                    .unwrap_or([0.0, 1.0, 0.0]);
+                let [u, v] = tex_coords.get(i).copied().unwrap_or([0.0, 0.0]);
+                Vertex {
+                    position: Vec3::new(x, y, z),
+                    tex_coord: Vec2::new(u, v),
+                    normal: Vec3::new(nx, ny, nz),
+                }
+            })
+            .collect();
+
+        let factor = material.base_color_factor();
+        let texture = match base_color {
+            Some(info) => Some(self.base_color_texture(info.texture().source().index(), factor)?),
+            None if factor != [1.0; 4] => Some(Arc::new(RgbaImage::from_pixel(
+                1,
+                1,
+                Rgba(factor.map(unit_to_byte)),
+            ))),
+            None => None,
+        };
+
+        let mut mesh = Mesh { vertices, indices };
+        if normals.is_none() {
+            mesh_ops::recompute_smooth_normals(&mut mesh);
+        }
+        Ok(Some((mesh, texture)))
+    }
+
+    fn base_color_texture(
+        &mut self,
+        image_index: usize,
+        factor: [f32; 4],
+    ) -> Result<Arc<RgbaImage>, String> {
+        let image = match self.textures.get(image_index).cloned().flatten() {
+            Some(image) => image,
+            None => {
+                let data = self
+                    .images
+                    .get(image_index)
+                    .ok_or_else(|| format!("glTF image {image_index} is missing"))?;
+                let image = Arc::new(to_rgba(data)?);
+                if let Some(slot) = self.textures.get_mut(image_index) {
+                    *slot = Some(image.clone());
+                }
+                image
+            }
+        };
+
+        if factor == [1.0; 4] {
+            return Ok(image);
+        }
+        let mut tinted = (*image).clone();
+        for pixel in tinted.pixels_mut() {
+            for (channel, scale) in pixel.0.iter_mut().zip(factor) {
+                *channel = unit_to_byte(f32::from(*channel) / 255.0 * scale);
+            }
+        }
+        Ok(Arc::new(tinted))
+    }
+}
+
+/// Expands strip and fan index orders into a plain triangle list
+fn triangle_list(mode: Mode, order: &[u32]) -> Vec<u32> {
+    match mode {
+        Mode::TriangleStrip => order
+            .windows(3)
+            .enumerate()
+            .flat_map(|(i, w)| {
+                // Every other triangle is flipped to keep a consistent winding
+                if i % 2 == 0 {
+                    [w[0], w[1], w[2]]
+                } else {
+                    [w[1], w[0], w[2]]
+                }
+            })
+            .collect(),
+        Mode::TriangleFan => order
+            .get(1..)
+            .unwrap_or_default()
+            .windows(2)
+            .flat_map(|w| [order[0], w[0], w[1]])
+            .collect(),
+        _ => order.to_vec(),
+    }
+}
+
+fn to_rgba(data: &gltf::image::Data) -> Result<RgbaImage, String> {
+    let pixels: Vec<u8> = match data.format {
+        Format::R8 => data.pixels.iter().flat_map(|&r| [r, r, r, 255]).collect(),
+        Format::R8G8 => data
+            .pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[1], 0, 255])
+            .collect(),
+        Format::R8G8B8 => data
+            .pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        Format::R8G8B8A8 => data.pixels.clone(),
+        // Keep the high byte of each little-endian 16-bit channel
+        Format::R16G16B16 => data
+            .pixels
+            .chunks_exact(6)
+            .flat_map(|p| [p[1], p[3], p[5], 255])
+            .collect(),
+        Format::R16G16B16A16 => data
+            .pixels
+            .chunks_exact(8)
+            .flat_map(|p| [p[1], p[3], p[5], p[7]])
+            .collect(),
+        format => return Err(format!("Unsupported glTF image format {format:?}")),
+    };
+    RgbaImage::from_raw(data.width, data.height, pixels)
+        .ok_or_else(|| "glTF image data does not match its size".to_string())
+}
+
+fn unit_to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Little-endian bytes of a unit quad in the XY plane with normals and UVs,
+    /// followed by its six `u16` indices
+    fn quad_buffer() -> Vec<u8> {
+        let positions = [
+            [0.0f32, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let uvs = [[0.0f32, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+        let mut bytes = Vec::new();
+        for p in positions.iter().flatten() {
+            bytes.extend_from_slice(&p.to_le_bytes());
+        }
+        for _ in 0..4 {
+            for n in [0.0f32, 0.0, 1.0] {
+                bytes.extend_from_slice(&n.to_le_bytes());
+            }
+        }
+        for uv in uvs.iter().flatten() {
+            bytes.extend_from_slice(&uv.to_le_bytes());
+        }
+        for i in [0u16, 1, 2, 0, 2, 3] {
+            bytes.extend_from_slice(&i.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Offsets into [`quad_buffer`]: positions 0, normals 48, UVs 96, indices 128
+    fn quad_json(buffer: &str, extra_images: &str, base_color: &str) -> String {
+        format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "scene": 0,
+                "scenes": [{{"nodes": [0]}}],
+                "nodes": [
+                    {{"name": "prop", "translation": [1.0, 2.0, 3.0], "children": [1]}},
+                    {{"name": "quad", "mesh": 0, "scale": [2.0, 2.0, 2.0]}}
+                ],
+                "meshes": [{{"primitives": [{{
+                    "attributes": {{"POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2}},
+                    "indices": 3,
+                    "material": 0
+                }}]}}],
+                "materials": [{{"pbrMetallicRoughness": {base_color}}}],
+                {extra_images}
+                "buffers": [{buffer}],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 128}},
+                    {{"buffer": 0, "byteOffset": 128, "byteLength": 12}}
+                ],
+                "accessors": [
+                    {{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                      "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]}},
+                    {{"bufferView": 0, "byteOffset": 48, "componentType": 5126, "count": 4, "type": "VEC3"}},
+                    {{"bufferView": 0, "byteOffset": 96, "componentType": 5126, "count": 4, "type": "VEC2"}},
+                    {{"bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR"}}
+                ]
+            }}"#
+        )
+    }
+
+    fn png_bytes() -> Vec<u8> {
+        let image = RgbaImage::from_fn(2, 2, |x, y| {
+            Rgba([(x * 255) as u8, (y * 255) as u8, 0, 255])
+        });
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let pad = |mut chunk: Vec<u8>, fill: u8| {
+            while !chunk.len().is_multiple_of(4) {
+                chunk.push(fill);
+            }
+            chunk
+        };
+        let json = pad(json.as_bytes().to_vec(), b' ');
+        let bin = pad(bin.to_vec(), 0);
+        let total = 12 + 8 + json.len() + 8 + bin.len();
+
+        let mut out = Vec::new();
+        out.extend_from_slice(b"glTF");
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&(total as u32).to_le_bytes());
+        out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"JSON");
+        out.extend_from_slice(&json);
+        out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"BIN\0");
+        out.extend_from_slice(&bin);
+        out
+    }
+
+    fn check_quad_hierarchy(scene: &Scene, roots: &[NodeId]) -> NodeId {
+        assert_eq!(roots.len(), 1);
+        let prop = roots[0];
+        assert_eq!(scene.get(prop).unwrap().name, "prop");
+        assert!(scene.get(prop).unwrap().mesh().is_none());
+
+        let quad = scene.find_by_path("prop/quad").unwrap();
+        let node = scene.get(quad).unwrap();
+        let mesh = node.mesh().unwrap();
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.vertices[2].position, Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(mesh.vertices[2].normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(mesh.vertices[0].tex_coord, Vec2::new(0.0, 1.0));
+
+        let corner = scene
+            .world_transform(quad)
+            .unwrap()
+            .transform_point(&Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(corner, Vec3::new(3.0, 4.0, 3.0));
+        quad
+    }
+
+    #[test]
+    fn test_import_glb_with_embedded_texture() {
+        let mut bin = quad_buffer();
+        let image_offset = bin.len();
+        let png = png_bytes();
+        bin.extend_from_slice(&png);
+
+        let images = r#""images": [{"bufferView": 2, "mimeType": "image/png"}],
+            "textures": [{"source": 0}],"#;
+        let json = quad_json(
+            &format!(r#"{{"byteLength": {}}}"#, bin.len()),
+            images,
+            r#"{"baseColorTexture": {"index": 0}}"#,
+        )
+        .replace(
+            r#"{"buffer": 0, "byteOffset": 128, "byteLength": 12}"#,
+            &format!(
+                r#"{{"buffer": 0, "byteOffset": 128, "byteLength": 12}},
+                   {{"buffer": 0, "byteOffset": {image_offset}, "byteLength": {}}}"#,
+                png.len()
+            ),
+        );
+
+        let mut scene = Scene::new();
+        let roots = scene.import_gltf_slice(&glb(&json, &bin), None).unwrap();
+        let quad = check_quad_hierarchy(&scene, &roots);
+
+        let texture = scene.get(quad).unwrap().texture().unwrap();
+        assert_eq!(texture.dimensions(), (2, 2));
+        assert_eq!(texture.get_pixel(1, 0), &Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_import_gltf_with_external_files() {
+        let dir = std::env::temp_dir().join(format!("gltf-import-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("quad.bin"), quad_buffer()).unwrap();
+        std::fs::write(dir.join("color.png"), png_bytes()).unwrap();
+        let json = quad_json(
+            r#"{"byteLength": 140, "uri": "quad.bin"}"#,
+            r#""images": [{"uri": "color.png"}], "textures": [{"source": 0}],"#,
+            r#"{"baseColorTexture": {"index": 0}, "baseColorFactor": [0.5, 1.0, 1.0, 1.0]}"#,
+        );
+        std::fs::write(dir.join("quad.gltf"), json).unwrap();
+
+        let mut scene = Scene::new();
+        let parent = scene.add_node(Node::new("props".to_string()));
+        let imported = scene.import_gltf(dir.join("quad.gltf"), Some(parent));
+        let missing = Scene::new().import_gltf(dir.join("missing.gltf"), None);
+        std::fs::remove_dir_all(&dir).ok();
+
+        let roots = imported.unwrap();
+        assert_eq!(scene.parent(roots[0]), Some(parent));
+        scene
+            .reparent(roots[0], None, crate::scene::ReparentMode::KeepLocal)
+            .unwrap();
+        let quad = check_quad_hierarchy(&scene, &roots);
+
+        // The base color factor tints the texture
+        let texture = scene.get(quad).unwrap().texture().unwrap();
+        assert_eq!(texture.get_pixel(1, 0), &Rgba([128, 0, 0, 255]));
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_color_factor_without_texture() {
+        let json = quad_json(
+            &format!(
+                r#"{{"byteLength": 140, "uri": "data:application/octet-stream;base64,{}"}}"#,
+                base64(&quad_buffer())
+            ),
+            "",
+            r#"{"baseColorFactor": [0.0, 0.5, 1.0, 1.0]}"#,
+        );
+        let mut scene = Scene::new();
+        scene.import_gltf_slice(json.as_bytes(), None).unwrap();
+        let quad = scene.find_by_name("quad").unwrap();
+        let texture = scene.get(quad).unwrap().texture().unwrap();
+        assert_eq!(texture.dimensions(), (1, 1));
+        assert_eq!(texture.get_pixel(0, 0), &Rgba([0, 128, 255, 255]));
+    }
+
+    #[test]
+    fn test_strips_fans_and_missing_normals() {
+        assert_eq!(
+            triangle_list(Mode::TriangleStrip, &[0, 1, 2, 3]),
+            [0, 1, 2, 2, 1, 3]
+        );
+        assert_eq!(
+            triangle_list(Mode::TriangleFan, &[0, 1, 2, 3]),
+            [0, 1, 2, 0, 2, 3]
+        );
+        assert!(triangle_list(Mode::TriangleFan, &[]).is_empty());
+
+        // A primitive without normals gets smooth ones from its faces
+        let json = quad_json(
+            &format!(
+                r#"{{"byteLength": 140, "uri": "data:application/octet-stream;base64,{}"}}"#,
+                base64(&quad_buffer())
+            ),
+            "",
+            "{}",
+        )
+        .replace(r#""NORMAL": 1, "#, "");
+        assert!(!json.contains("NORMAL"));
+        let mut scene = Scene::new();
+        scene.import_gltf_slice(json.as_bytes(), None).unwrap();
+        let quad = scene.find_by_name("quad").unwrap();
+        let mesh = scene.get(quad).unwrap().mesh().unwrap();
+        for vertex in &mesh.vertices {
+            assert_eq!(vertex.normal, Vec3::new(0.0, 0.0, 1.0));
+        }
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+}
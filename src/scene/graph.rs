@@ -0,0 +1,938 @@
+//! Arena-backed scene hierarchy
+//!
+//! Nodes live in a slot map owned by [`Scene`] and are addressed by [`NodeId`]
+//! handles. A handle carries the generation of its slot, so once a node is removed
+//! every id that pointed at it goes stale instead of aliasing whatever reuses the
+//! slot. Parent, child and sibling links are ids too, which keeps inserting and
+//! unlinking a node O(1) and the whole scene `Send + Sync`.
+//!
+//! World transforms are cached per slot. Mutable access to a node, reparenting and
+//! removal invalidate the cache of the node's whole subtree; the next
+//! [`Scene::world_transform`] recomputes only the invalidated chain.
+
+use crate::math::{Mat4, Transform, Vec3};
+use crate::scene::{Light, Mesh, MeshSource};
+use image::RgbaImage;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Generational handle to a node in a [`Scene`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+/// What to preserve when a node moves to a new parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReparentMode {
+    /// Adjust the local transform so the node stays where it is in the world
+    KeepWorld,
+    /// Keep the local transform, so the node moves with its new parent
+    KeepLocal,
+}
+
+#[derive(Clone)]
+pub struct Node {
+    pub name: String,
+    transform: Transform,
+    mesh: Option<Mesh>,
+    mesh_source: Option<MeshSource>,
+    texture: Option<Arc<RgbaImage>>,
+    revision: u32,
+}
+
+impl Node {
+    #[must_use]
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            transform: Transform::identity(),
+            mesh: None,
+            mesh_source: None,
+            texture: None,
+            revision: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_mesh(name: String, mesh: Mesh) -> Self {
+        Self {
+            mesh: Some(mesh),
+            ..Self::new(name)
+        }
+    }
+
+    /// Node whose mesh is built from `source`, which is kept so saved scenes store the
+    /// recipe instead of the vertex data
+    ///
+    /// # Errors
+    /// Returns an error if the mesh cannot be built
+    pub fn from_source(name: String, source: MeshSource) -> Result<Self, String> {
+        let mesh = source.build()?;
+        Ok(Self {
+            mesh: Some(mesh),
+            mesh_source: Some(source),
+            ..Self::new(name)
+        })
+    }
+
+    #[must_use]
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    pub fn set_position(&mut self, position: Vec3) {
+        self.transform.position = position;
+    }
+
+    #[must_use]
+    pub fn mesh(&self) -> Option<&Mesh> {
+        self.mesh.as_ref()
+    }
+
+    /// Recipe the mesh was built from, if it came from [`Self::from_source`] and has
+    /// not been edited since
+    #[must_use]
+    pub fn mesh_source(&self) -> Option<&MeshSource> {
+        self.mesh_source.as_ref()
+    }
+
+    /// Mutable access to the mesh; bumps [`Self::revision`] and forgets the mesh
+    /// source
+    pub fn mesh_mut(&mut self) -> Option<&mut Mesh> {
+        self.revision = self.revision.wrapping_add(1);
+        self.mesh_source = None;
+        self.mesh.as_mut()
+    }
+
+    /// Replaces the mesh; bumps [`Self::revision`] and forgets the mesh source
+    pub fn set_mesh(&mut self, mesh: Option<Mesh>) {
+        self.revision = self.revision.wrapping_add(1);
+        self.mesh_source = None;
+        self.mesh = mesh;
+    }
+
+    /// Base color texture; nodes without one are drawn with the default texture
+    #[must_use]
+    pub fn texture(&self) -> Option<&Arc<RgbaImage>> {
+        self.texture.as_ref()
+    }
+
+    /// Replaces the base color texture; bumps [`Self::revision`]
+    pub fn set_texture(&mut self, texture: Option<Arc<RgbaImage>>) {
+        self.revision = self.revision.wrapping_add(1);
+        self.texture = texture;
+    }
+
+    /// Counter that changes whenever the mesh or texture may have changed, so
+    /// renderers know when to re-upload them
+    #[must_use]
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+}
+
+/// Hierarchy links of an occupied slot
+#[derive(Clone, Copy, Default)]
+struct Links {
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    prev_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+struct Entry {
+    node: Node,
+    links: Links,
+    /// Cached local-to-world matrix, `None` while it is dirty. A dirty node's
+    /// descendants are always dirty too.
+    world: Mutex<Option<Mat4>>,
+}
+
+struct Slot {
+    generation: u32,
+    entry: Option<Entry>,
+}
+
+/// Source of [`Scene::id`]
+static NEXT_SCENE_ID: AtomicU64 = AtomicU64::new(0);
+
+pub struct Scene {
+    id: u64,
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    first_root: Option<NodeId>,
+    last_root: Option<NodeId>,
+    len: usize,
+    pub light: Light,
+}
+
+impl Scene {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_SCENE_ID.fetch_add(1, Ordering::Relaxed),
+            slots: Vec::new(),
+            free: Vec::new(),
+            first_root: None,
+            last_root: None,
+            len: 0,
+            light: Light::new(Vec3::new(5.0, 10.0, 5.0), Vec3::new(1.0, 1.0, 1.0)),
+        }
+    }
+
+    /// Identifier no other scene in the process shares, so caches keyed by
+    /// [`NodeId`] can tell one scene's nodes from another's
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Number of nodes in the scene
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether `id` refers to a live node
+    #[must_use]
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.entry(id).is_some()
+    }
+
+    #[must_use]
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.entry(id).map(|entry| &entry.node)
+    }
+
+    /// Mutable access to a node; invalidates the cached world transforms of its subtree
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.mark_dirty(id);
+        self.entry_mut(id).map(|entry| &mut entry.node)
+    }
+
+    /// Adds `node` as the last root
+    pub fn add_node(&mut self, node: Node) -> NodeId {
+        let id = self.allocate(node);
+        self.link(id, None);
+        id
+    }
+
+    /// Adds `node` as the last child of `parent`
+    ///
+    /// # Errors
+    /// Returns an error if `parent` is not in the scene
+    pub fn add_child(&mut self, parent: NodeId, node: Node) -> Result<NodeId, String> {
+        if !self.contains(parent) {
+            return Err(format!("Parent node {parent:?} is not in the scene"));
+        }
+        let id = self.allocate(node);
+        self.link(id, Some(parent));
+        Ok(id)
+    }
+
+    /// Removes `id` and its whole subtree, returning the node itself. Every id into
+    /// the removed subtree becomes stale.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        self.entry(id)?;
+        self.mark_dirty(id);
+        self.unlink(id);
+
+        let descendants: Vec<NodeId> = self.descendants(id).map(|(id, _)| id).collect();
+        for descendant in descendants {
+            self.release(descendant);
+        }
+        self.release(id)
+    }
+
+    #[must_use]
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.entry(id)?.links.parent
+    }
+
+    /// Direct children of `id` in insertion order
+    pub fn children(&self, id: NodeId) -> Siblings<'_> {
+        Siblings {
+            scene: self,
+            next: self.entry(id).and_then(|entry| entry.links.first_child),
+        }
+    }
+
+    /// Root nodes in insertion order
+    pub fn roots(&self) -> Siblings<'_> {
+        Siblings {
+            scene: self,
+            next: self.first_root,
+        }
+    }
+
+    /// Whether `ancestor` appears on `id`'s parent chain
+    #[must_use]
+    pub fn is_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut current = self.parent(id);
+        while let Some(parent) = current {
+            if parent == ancestor {
+                return true;
+            }
+            current = self.parent(parent);
+        }
+        false
+    }
+
+    /// Moves `id` to the end of `new_parent`'s children, or of the roots when `None`
+    ///
+    /// # Errors
+    /// Returns an error if either node is not in the scene, or if `new_parent` is
+    /// `id` itself or one of its descendants
+    pub fn reparent(
+        &mut self,
+        id: NodeId,
+        new_parent: Option<NodeId>,
+        mode: ReparentMode,
+    ) -> Result<(), String> {
+        let world = self
+            .world_transform(id)
+            .ok_or_else(|| format!("Node {id:?} is not in the scene"))?;
+        let parent_world = match new_parent {
+            Some(parent) => {
+                let parent_world = self
+                    .world_transform(parent)
+                    .ok_or_else(|| format!("Parent node {parent:?} is not in the scene"))?;
+                if parent == id || self.is_ancestor(id, parent) {
+                    return Err(format!(
+                        "Cannot reparent '{}' under its own descendant '{}'",
+                        self.get(id).map_or("", |n| n.name.as_str()),
+                        self.get(parent).map_or("", |n| n.name.as_str())
+                    ));
+                }
+                parent_world
+            }
+            None => Mat4::identity(),
+        };
+
+        self.unlink(id);
+        self.link(id, new_parent);
+        self.mark_dirty(id);
+
+        if mode == ReparentMode::KeepWorld {
+            let local = parent_world
+                .inverse_affine()
+                .map_or(world, |inverse| inverse.multiply(&world));
+            if let Some(node) = self.get_mut(id) {
+                node.set_transform(Transform::from_matrix(&local));
+            }
+        }
+        Ok(())
+    }
+
+    /// Local-to-world matrix of `id`, including every ancestor's transform
+    #[must_use]
+    pub fn world_transform(&self, id: NodeId) -> Option<Mat4> {
+        let entry = self.entry(id)?;
+        if let Some(world) = *entry.cached_world() {
+            return Some(world);
+        }
+
+        let local = entry.node.transform.to_matrix();
+        let world = match entry.links.parent {
+            Some(parent) => self.world_transform(parent)?.multiply(&local),
+            None => local,
+        };
+        *entry.cached_world() = Some(world);
+        Some(world)
+    }
+
+    /// Direct child of `parent` with the given name
+    #[must_use]
+    pub fn find_child(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+        self.children(parent)
+            .find(|&child| self.has_name(child, name))
+    }
+
+    /// First node named `name` in hierarchy order
+    #[must_use]
+    pub fn find_by_name(&self, name: &str) -> Option<NodeId> {
+        self.iter()
+            .find(|(_, node)| node.name == name)
+            .map(|(id, _)| id)
+    }
+
+    /// Node at a slash-separated path starting from a root, e.g. `"robot/arm/hand"`
+    #[must_use]
+    pub fn find_by_path(&self, path: &str) -> Option<NodeId> {
+        let mut segments = path.split('/').filter(|s| !s.is_empty());
+        let root_name = segments.next()?;
+        let mut current = self.roots().find(|&root| self.has_name(root, root_name))?;
+        for segment in segments {
+            current = self.find_child(current, segment)?;
+        }
+        Some(current)
+    }
+
+    /// Every node in hierarchy order: each node comes before its children, and
+    /// siblings keep their insertion order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            scene: self,
+            next: self.first_root,
+            stop_at: None,
+        }
+    }
+
+    /// The subtree below `id` in hierarchy order, excluding `id` itself
+    pub fn descendants(&self, id: NodeId) -> Iter<'_> {
+        Iter {
+            scene: self,
+            next: self.entry(id).and_then(|entry| entry.links.first_child),
+            stop_at: Some(id),
+        }
+    }
+
+    /// Visits every node in hierarchy order with its world transform
+    pub fn traverse<F>(&self, mut callback: F)
+    where
+        F: FnMut(NodeId, &Node, &Mat4),
+    {
+        self.traverse_siblings(self.first_root, &Mat4::identity(), &mut callback);
+    }
+
+    fn traverse_siblings<F>(&self, first: Option<NodeId>, parent_world: &Mat4, callback: &mut F)
+    where
+        F: FnMut(NodeId, &Node, &Mat4),
+    {
+        let mut current = first;
+        while let Some(id) = current {
+            let Some(entry) = self.entry(id) else {
+                return;
+            };
+            let world = parent_world.multiply(&entry.node.transform.to_matrix());
+            callback(id, &entry.node, &world);
+            self.traverse_siblings(entry.links.first_child, &world, callback);
+            current = entry.links.next_sibling;
+        }
+    }
+
+    fn has_name(&self, id: NodeId, name: &str) -> bool {
+        self.get(id).is_some_and(|node| node.name == name)
+    }
+
+    fn entry(&self, id: NodeId) -> Option<&Entry> {
+        let slot = self.slots.get(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.entry.as_ref()
+    }
+
+    fn entry_mut(&mut self, id: NodeId) -> Option<&mut Entry> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        slot.entry.as_mut()
+    }
+
+    fn links_mut(&mut self, id: NodeId) -> Option<&mut Links> {
+        self.entry_mut(id).map(|entry| &mut entry.links)
+    }
+
+    /// Drops the cached world transforms of `id` and its descendants
+    fn mark_dirty(&mut self, id: NodeId) {
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            let Some(entry) = self.entry_mut(id) else {
+                continue;
+            };
+            let world = entry
+                .world
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner);
+            // A subtree whose root is already dirty is dirty throughout
+            if world.take().is_none() {
+                continue;
+            }
+            let mut child = entry.links.first_child;
+            while let Some(id) = child {
+                pending.push(id);
+                child = self.entry(id).and_then(|entry| entry.links.next_sibling);
+            }
+        }
+    }
+
+    /// Stores `node` in a free slot without linking it into the hierarchy
+    fn allocate(&mut self, node: Node) -> NodeId {
+        let entry = Some(Entry {
+            node,
+            links: Links::default(),
+            world: Mutex::new(None),
+        });
+        self.len += 1;
+
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.entry = entry;
+            return NodeId {
+                index,
+                generation: slot.generation,
+            };
+        }
+
+        let index = u32::try_from(self.slots.len()).unwrap_or(u32::MAX);
+        self.slots.push(Slot {
+            generation: 0,
+            entry,
+        });
+        NodeId {
+            index,
+            generation: 0,
+        }
+    }
+
+    /// Empties the slot of an already unlinked node and retires its generation
+    fn release(&mut self, id: NodeId) -> Option<Node> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        let entry = slot.entry.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        self.len -= 1;
+        Some(entry.node)
+    }
+
+    /// Appends an unlinked `id` to `parent`'s children, or to the roots
+    fn link(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let last = match parent {
+            Some(parent) => self.entry(parent).and_then(|entry| entry.links.last_child),
+            None => self.last_root,
+        };
+
+        if let Some(links) = self.links_mut(id) {
+            links.parent = parent;
+            links.prev_sibling = last;
+            links.next_sibling = None;
+        }
+        match last {
+            Some(last) => {
+                if let Some(links) = self.links_mut(last) {
+                    links.next_sibling = Some(id);
+                }
+            }
+            None => self.set_first(parent, Some(id)),
+        }
+        self.set_last(parent, Some(id));
+    }
+
+    /// Removes `id` from its sibling list, keeping its own subtree attached
+    fn unlink(&mut self, id: NodeId) {
+        let Some(links) = self.entry(id).map(|entry| entry.links) else {
+            return;
+        };
+
+        match links.prev_sibling {
+            Some(prev) => {
+                if let Some(prev) = self.links_mut(prev) {
+                    prev.next_sibling = links.next_sibling;
+                }
+            }
+            None => self.set_first(links.parent, links.next_sibling),
+        }
+        match links.next_sibling {
+            Some(next) => {
+                if let Some(next) = self.links_mut(next) {
+                    next.prev_sibling = links.prev_sibling;
+                }
+            }
+            None => self.set_last(links.parent, links.prev_sibling),
+        }
+
+        if let Some(links) = self.links_mut(id) {
+            links.parent = None;
+            links.prev_sibling = None;
+            links.next_sibling = None;
+        }
+    }
+
+    fn set_first(&mut self, parent: Option<NodeId>, first: Option<NodeId>) {
+        match parent {
+            Some(parent) => {
+                if let Some(links) = self.links_mut(parent) {
+                    links.first_child = first;
+                }
+            }
+            None => self.first_root = first,
+        }
+    }
+
+    fn set_last(&mut self, parent: Option<NodeId>, last: Option<NodeId>) {
+        match parent {
+            Some(parent) => {
+                if let Some(links) = self.links_mut(parent) {
+                    links.last_child = last;
+                }
+            }
+            None => self.last_root = last,
+        }
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over a run of siblings, see [`Scene::children`] and [`Scene::roots`]
+pub struct Siblings<'a> {
+    scene: &'a Scene,
+    next: Option<NodeId>,
+}
+
+impl Iterator for Siblings<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self
+            .scene
+            .entry(id)
+            .and_then(|entry| entry.links.next_sibling);
+        Some(id)
+    }
+}
+
+/// Depth-first, pre-order iterator, see [`Scene::iter`] and [`Scene::descendants`]
+pub struct Iter<'a> {
+    scene: &'a Scene,
+    next: Option<NodeId>,
+    /// Subtree root whose siblings must not be visited
+    stop_at: Option<NodeId>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (NodeId, &'a Node);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        let entry = self.scene.entry(id)?;
+
+        // Descend first, otherwise move to the next sibling of the nearest ancestor
+        // that has one, without leaving the subtree being iterated
+        self.next = entry.links.first_child.or_else(|| {
+            let mut current = Some(id);
+            while let Some(node) = current {
+                if Some(node) == self.stop_at {
+                    return None;
+                }
+                let links = self.scene.entry(node)?.links;
+                if links.next_sibling.is_some() {
+                    return links.next_sibling;
+                }
+                current = links.parent;
+            }
+            None
+        });
+        Some((id, &entry.node))
+    }
+}
+
+impl Entry {
+    fn cached_world(&self) -> std::sync::MutexGuard<'_, Option<Mat4>> {
+        self.world.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<'a> IntoIterator for &'a Scene {
+    type Item = (NodeId, &'a Node);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Quat;
+
+    fn node_at(name: &str, x: f32, y: f32, z: f32) -> Node {
+        let mut node = Node::new(name.to_string());
+        node.set_position(Vec3::new(x, y, z));
+        node
+    }
+
+    fn translation_of(m: &Mat4) -> Vec3 {
+        m.transform_point(&Vec3::zero())
+    }
+
+    fn names(scene: &Scene) -> Vec<String> {
+        scene.iter().map(|(_, node)| node.name.clone()).collect()
+    }
+
+    /// robot(1,0,0) / arm(0,2,0) / hand(0,0,3)
+    fn robot_scene() -> (Scene, NodeId, NodeId, NodeId) {
+        let mut scene = Scene::new();
+        let robot = scene.add_node(node_at("robot", 1.0, 0.0, 0.0));
+        let arm = scene
+            .add_child(robot, node_at("arm", 0.0, 2.0, 0.0))
+            .unwrap();
+        let hand = scene
+            .add_child(arm, node_at("hand", 0.0, 0.0, 3.0))
+            .unwrap();
+        (scene, robot, arm, hand)
+    }
+
+    #[test]
+    fn test_add_child_sets_parent() {
+        let (scene, robot, arm, hand) = robot_scene();
+        assert_eq!(scene.parent(arm), Some(robot));
+        assert_eq!(scene.parent(hand), Some(arm));
+        assert_eq!(scene.parent(robot), None);
+        assert_eq!(scene.children(robot).collect::<Vec<_>>(), [arm]);
+        assert_eq!(scene.len(), 3);
+    }
+
+    #[test]
+    fn test_world_transform_includes_ancestors() {
+        let (scene, ..) = robot_scene();
+        let hand = scene.find_by_name("hand").unwrap();
+        assert_eq!(
+            translation_of(&scene.world_transform(hand).unwrap()),
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn test_traverse_matches_world_transform() {
+        let (scene, robot, arm, hand) = robot_scene();
+        let mut visited = Vec::new();
+        scene.traverse(|id, node, world| {
+            assert_eq!(Some(*world), scene.world_transform(id));
+            visited.push((id, node.name.clone()));
+        });
+        assert_eq!(
+            visited,
+            [
+                (robot, "robot".to_string()),
+                (arm, "arm".to_string()),
+                (hand, "hand".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transform_change_moves_descendants() {
+        let (mut scene, robot, arm, hand) = robot_scene();
+        // Fill the cache before moving the root
+        assert_eq!(
+            translation_of(&scene.world_transform(hand).unwrap()),
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+        scene
+            .get_mut(robot)
+            .unwrap()
+            .set_position(Vec3::new(-4.0, 0.0, 0.0));
+        assert_eq!(
+            translation_of(&scene.world_transform(hand).unwrap()),
+            Vec3::new(-4.0, 2.0, 3.0)
+        );
+
+        scene.get_mut(robot).unwrap().transform_mut().rotation =
+            Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_2);
+        let hand_world = translation_of(&scene.world_transform(hand).unwrap());
+        assert!(hand_world.distance(&Vec3::new(-1.0, 2.0, 0.0)) < 1e-5);
+
+        // Moving the arm to a new root carries the cached hand along
+        let base = scene.add_node(node_at("base", 0.0, 10.0, 0.0));
+        scene
+            .reparent(arm, Some(base), ReparentMode::KeepLocal)
+            .unwrap();
+        assert_eq!(
+            translation_of(&scene.world_transform(hand).unwrap()),
+            Vec3::new(0.0, 12.0, 3.0)
+        );
+        scene.get_mut(base).unwrap().set_position(Vec3::zero());
+        assert_eq!(
+            translation_of(&scene.world_transform(hand).unwrap()),
+            Vec3::new(0.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn test_cycles_are_rejected() {
+        let (mut scene, robot, _, hand) = robot_scene();
+        assert!(scene
+            .reparent(robot, Some(hand), ReparentMode::KeepLocal)
+            .is_err());
+        assert!(scene
+            .reparent(robot, Some(robot), ReparentMode::KeepLocal)
+            .is_err());
+        assert!(scene.is_ancestor(robot, hand));
+        assert_eq!(names(&scene), ["robot", "arm", "hand"]);
+    }
+
+    #[test]
+    fn test_remove_drops_subtree() {
+        let (mut scene, robot, arm, hand) = robot_scene();
+        let removed = scene.remove(arm).unwrap();
+        assert_eq!(removed.name, "arm");
+        assert!(scene.remove(arm).is_none());
+        assert!(!scene.contains(hand));
+        assert!(scene.world_transform(hand).is_none());
+        assert_eq!(scene.children(robot).count(), 0);
+        assert_eq!(scene.len(), 1);
+        assert_eq!(names(&scene), ["robot"]);
+    }
+
+    #[test]
+    fn test_stale_ids_do_not_alias_reused_slots() {
+        let (mut scene, _, arm, hand) = robot_scene();
+        scene.remove(arm);
+        let leg = scene.add_node(Node::new("leg".to_string()));
+        let foot = scene.add_node(Node::new("foot".to_string()));
+
+        // The freed slots are reused, under a new generation
+        assert_eq!(scene.slots.len(), 3);
+        assert!(leg != arm && leg != hand && foot != arm && foot != hand);
+        assert!(scene.get(arm).is_none());
+        assert!(scene.get(hand).is_none());
+        assert!(scene.get_mut(hand).is_none());
+        assert_eq!(scene.get(leg).unwrap().name, "leg");
+        assert!(scene.add_child(arm, Node::new("toe".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_removing_middle_sibling_keeps_order() {
+        let mut scene = Scene::new();
+        let ids: Vec<NodeId> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| scene.add_node(Node::new((*name).to_string())))
+            .collect();
+
+        scene.remove(ids[1]);
+        scene.remove(ids[3]);
+        assert_eq!(names(&scene), ["a", "c"]);
+        scene.remove(ids[0]);
+        assert_eq!(scene.roots().collect::<Vec<_>>(), [ids[2]]);
+
+        scene.add_node(Node::new("e".to_string()));
+        assert_eq!(names(&scene), ["c", "e"]);
+    }
+
+    #[test]
+    fn test_iteration_is_in_hierarchy_order() {
+        let (mut scene, robot, arm, _) = robot_scene();
+        scene
+            .add_child(arm, Node::new("elbow".to_string()))
+            .unwrap();
+        let leg = scene
+            .add_child(robot, Node::new("leg".to_string()))
+            .unwrap();
+        scene.add_child(leg, Node::new("foot".to_string())).unwrap();
+        scene.add_node(Node::new("lamp".to_string()));
+
+        assert_eq!(
+            names(&scene),
+            ["robot", "arm", "hand", "elbow", "leg", "foot", "lamp"]
+        );
+        let below_arm: Vec<_> = scene
+            .descendants(arm)
+            .map(|(_, node)| node.name.as_str())
+            .collect();
+        assert_eq!(below_arm, ["hand", "elbow"]);
+        assert_eq!(scene.descendants(leg).count(), 1);
+    }
+
+    #[test]
+    fn test_reparent_keep_world() {
+        let (mut scene, robot, _, hand) = robot_scene();
+        let mut base = node_at("base", 10.0, 0.0, 0.0);
+        base.transform_mut().scale = Vec3::new(2.0, 2.0, 2.0);
+        let base = scene.add_node(base);
+
+        scene
+            .reparent(hand, Some(base), ReparentMode::KeepWorld)
+            .unwrap();
+        let world = translation_of(&scene.world_transform(hand).unwrap());
+        assert!(world.distance(&Vec3::new(1.0, 2.0, 3.0)) < 1e-5);
+        assert_eq!(scene.parent(hand), Some(base));
+
+        // Moving to the root keeps it in place too
+        scene.reparent(hand, None, ReparentMode::KeepWorld).unwrap();
+        let world = translation_of(&scene.world_transform(hand).unwrap());
+        assert!(world.distance(&Vec3::new(1.0, 2.0, 3.0)) < 1e-5);
+        assert_eq!(scene.parent(hand), None);
+        assert_eq!(scene.roots().count(), 3);
+        assert_eq!(scene.descendants(robot).count(), 1);
+    }
+
+    #[test]
+    fn test_reparent_keep_local() {
+        let (mut scene, robot, _, hand) = robot_scene();
+        scene
+            .reparent(hand, Some(robot), ReparentMode::KeepLocal)
+            .unwrap();
+        assert_eq!(
+            translation_of(&scene.world_transform(hand).unwrap()),
+            Vec3::new(1.0, 0.0, 3.0)
+        );
+        assert_eq!(names(&scene), ["robot", "arm", "hand"]);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let (scene, robot, arm, hand) = robot_scene();
+        assert_eq!(scene.find_by_name("hand"), Some(hand));
+        assert_eq!(scene.find_by_name("robot"), Some(robot));
+        assert!(scene.find_by_name("leg").is_none());
+
+        assert_eq!(scene.find_by_path("robot"), Some(robot));
+        assert_eq!(scene.find_by_path("/robot/arm"), Some(arm));
+        assert_eq!(scene.find_by_path("robot/arm/hand"), Some(hand));
+        assert!(scene.find_by_path("robot/hand").is_none());
+        assert!(scene.find_by_path("arm").is_none());
+        assert_eq!(scene.find_child(arm, "hand"), Some(hand));
+    }
+
+    #[test]
+    fn test_mesh_and_texture_changes_bump_revision() {
+        let mut node = Node::with_mesh("cube".to_string(), Mesh::cube());
+        let revision = node.revision();
+        node.set_position(Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(node.revision(), revision);
+        node.set_mesh(Some(Mesh::plane(1.0, 1.0)));
+        assert_ne!(node.revision(), revision);
+
+        let revision = node.revision();
+        node.set_texture(Some(Arc::new(RgbaImage::new(1, 1))));
+        assert_ne!(node.revision(), revision);
+    }
+
+    #[test]
+    fn test_scene_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Scene>();
+        assert_send_sync::<NodeId>();
+
+        // World generation can build a scene on a worker thread
+        let scene = std::thread::spawn(|| robot_scene().0).join().unwrap();
+        assert_eq!(scene.len(), 3);
+    }
+}
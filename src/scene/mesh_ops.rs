@@ -0,0 +1,597 @@
+//! Mesh processing
+//!
+//! Normal and tangent generation, welding, merging, winding flips, bounds, surface
+//! area and validation for indexed triangle [`Mesh`]es. The bounding box is
+//! [`Mesh::bounds`]; everything else lives here so generators and importers stop
+//! hand-rolling their own versions.
+
+use crate::math::{BoundingSphere, Mat4, Vec3, Vec4};
+use crate::scene::{IndexFormat, Mesh, Vertex};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Largest difference in normal or texture coordinate components for which
+/// [`weld_vertices`] still treats two vertices as the same
+pub const WELD_ATTRIBUTE_TOLERANCE: f32 = 1e-3;
+
+/// Problem found by [`validate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshIssue {
+    /// The index count is not a multiple of three; the last `count` indices are unused
+    TrailingIndices { count: usize },
+    /// `triangle` references a vertex that does not exist
+    IndexOutOfRange { triangle: usize, index: u32 },
+    /// `triangle` repeats a vertex or has (nearly) zero area
+    DegenerateTriangle { triangle: usize },
+    /// `vertex` has a NaN or infinite position or normal
+    NonFiniteVertex { vertex: usize },
+}
+
+impl fmt::Display for MeshIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TrailingIndices { count } => {
+                write!(f, "{count} trailing indices do not form a triangle")
+            }
+            Self::IndexOutOfRange { triangle, index } => {
+                write!(f, "triangle {triangle} uses out-of-range index {index}")
+            }
+            Self::DegenerateTriangle { triangle } => write!(f, "triangle {triangle} is degenerate"),
+            Self::NonFiniteVertex { vertex } => write!(f, "vertex {vertex} is not finite"),
+        }
+    }
+}
+
+/// Vertex indices of each complete triangle whose indices are all in range
+fn triangles(mesh: &Mesh) -> impl Iterator<Item = [usize; 3]> + '_ {
+    let vertex_count = mesh.vertices.len();
+    mesh.indices
+        .chunks_exact(3)
+        .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+        .filter(move |t| t.iter().all(|&i| i < vertex_count))
+}
+
+/// Area-weighted vertex normals; vertices not used by any triangle point up
+#[must_use]
+pub fn smooth_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::zero(); positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| triangle[k] as usize);
+        let (Some(pa), Some(pb), Some(pc)) = (positions.get(a), positions.get(b), positions.get(c))
+        else {
+            continue;
+        };
+        // The cross product's length is twice the area, which does the weighting
+        let face = (*pb - *pa).cross(&(*pc - *pa));
+        for i in [a, b, c] {
+            normals[i] += face;
+        }
+    }
+    normals
+        .iter()
+        .map(|n| n.try_normalize().unwrap_or(Vec3::new(0.0, 1.0, 0.0)))
+        .collect()
+}
+
+/// Replaces the normals of `mesh` with area-weighted averages of the faces around
+/// each vertex, keeping hard edges only where vertices are already split
+pub fn recompute_smooth_normals(mesh: &mut Mesh) {
+    let positions: Vec<Vec3> = mesh.vertices.iter().map(|v| v.position).collect();
+    for (vertex, normal) in mesh
+        .vertices
+        .iter_mut()
+        .zip(smooth_normals(&positions, &mesh.indices))
+    {
+        vertex.normal = normal;
+    }
+}
+
+/// Gives every triangle its own three vertices carrying the face normal
+///
+/// # Errors
+/// Returns an error if the unshared mesh would need more vertices than 32-bit
+/// indices can address
+pub fn recompute_flat_normals(mesh: &mut Mesh) -> Result<(), String> {
+    IndexFormat::for_vertex_count(mesh.indices.len())?;
+    let mut vertices = Vec::with_capacity(mesh.indices.len());
+    for [a, b, c] in triangles(mesh) {
+        let [va, vb, vc] = [a, b, c].map(|i| mesh.vertices[i]);
+        let normal = (vb.position - va.position)
+            .cross(&(vc.position - va.position))
+            .try_normalize()
+            .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+        vertices.extend([va, vb, vc].map(|v| Vertex { normal, ..v }));
+    }
+    mesh.indices = (0..vertices.len() as u32).collect();
+    mesh.vertices = vertices;
+    Ok(())
+}
+
+/// Per-vertex tangents in the MikkTSpace convention: `xyz` is the tangent along
+/// increasing U, orthogonal to the normal, and `w` is the handedness sign such that
+/// `bitangent = w * cross(normal, tangent)`
+///
+/// Each corner contributes its triangle's UV-space tangent weighted by the corner
+/// angle. Vertices without usable texture coordinates get an arbitrary tangent
+/// perpendicular to their normal.
+#[must_use]
+pub fn generate_tangents(mesh: &Mesh) -> Vec<Vec4> {
+    let mut tangents = vec![Vec3::zero(); mesh.vertices.len()];
+    let mut bitangents = vec![Vec3::zero(); mesh.vertices.len()];
+
+    for triangle in triangles(mesh) {
+        let [v0, v1, v2] = triangle.map(|i| &mesh.vertices[i]);
+        let e1 = v1.position - v0.position;
+        let e2 = v2.position - v0.position;
+        let d1 = v1.tex_coord - v0.tex_coord;
+        let d2 = v2.tex_coord - v0.tex_coord;
+        let det = d1.x * d2.y - d2.x * d1.y;
+        if det.abs() <= f32::EPSILON {
+            continue;
+        }
+        let tangent = (e1 * d2.y - e2 * d1.y) / det;
+        let bitangent = (e2 * d1.x - e1 * d2.x) / det;
+
+        for k in 0..3 {
+            let corner = &mesh.vertices[triangle[k]].position;
+            let to_next = mesh.vertices[triangle[(k + 1) % 3]].position - *corner;
+            let to_prev = mesh.vertices[triangle[(k + 2) % 3]].position - *corner;
+            let (Some(to_next), Some(to_prev)) = (to_next.try_normalize(), to_prev.try_normalize())
+            else {
+                continue;
+            };
+            let angle = to_next.dot(&to_prev).clamp(-1.0, 1.0).acos();
+            tangents[triangle[k]] += tangent * angle;
+            bitangents[triangle[k]] += bitangent * angle;
+        }
+    }
+
+    mesh.vertices
+        .iter()
+        .zip(tangents.iter().zip(&bitangents))
+        .map(|(vertex, (tangent, bitangent))| {
+            let normal = vertex.normal;
+            let tangent = (*tangent - normal * normal.dot(tangent))
+                .try_normalize()
+                .unwrap_or_else(|| any_perpendicular(&normal));
+            let handedness = if normal.cross(&tangent).dot(bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            Vec4::new(tangent.x, tangent.y, tangent.z, handedness)
+        })
+        .collect()
+}
+
+fn any_perpendicular(normal: &Vec3) -> Vec3 {
+    let axis = if normal.x.abs() < 0.9 {
+        Vec3::new(1.0, 0.0, 0.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    };
+    normal
+        .cross(&axis)
+        .try_normalize()
+        .unwrap_or(Vec3::new(1.0, 0.0, 0.0))
+}
+
+/// Merges vertices whose positions lie within `epsilon` of each other and whose
+/// normals and texture coordinates agree within [`WELD_ATTRIBUTE_TOLERANCE`],
+/// returning how many vertices were removed
+///
+/// UV seams and hard edges therefore survive welding. Vertices no triangle uses are
+/// kept.
+pub fn weld_vertices(mesh: &mut Mesh, epsilon: f32) -> usize {
+    let epsilon = epsilon.max(0.0);
+    let cell_size = epsilon.max(f32::EPSILON);
+    let cell_of = |p: &Vec3| [p.x, p.y, p.z].map(|c| (c / cell_size).floor() as i64);
+
+    let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+    let mut vertices: Vec<Vertex> = Vec::with_capacity(mesh.vertices.len());
+    let mut remap = Vec::with_capacity(mesh.vertices.len());
+
+    for vertex in &mesh.vertices {
+        let [x, y, z] = cell_of(&vertex.position);
+        let existing = (-1..=1)
+            .flat_map(|dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [dx, dy, dz])))
+            .filter_map(|[dx, dy, dz]| grid.get(&[x + dx, y + dy, z + dz]))
+            .flatten()
+            .copied()
+            .find(|&i| same_vertex(&vertices[i as usize], vertex, epsilon));
+
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                let index = vertices.len() as u32;
+                vertices.push(*vertex);
+                grid.entry([x, y, z]).or_default().push(index);
+                index
+            }
+        };
+        remap.push(index);
+    }
+
+    let removed = mesh.vertices.len() - vertices.len();
+    for index in &mut mesh.indices {
+        if let Some(&new_index) = remap.get(*index as usize) {
+            *index = new_index;
+        }
+    }
+    mesh.vertices = vertices;
+    removed
+}
+
+fn same_vertex(a: &Vertex, b: &Vertex, epsilon: f32) -> bool {
+    let close = |x: f32, y: f32| (x - y).abs() <= WELD_ATTRIBUTE_TOLERANCE;
+    a.position.distance(&b.position) <= epsilon
+        && close(a.normal.x, b.normal.x)
+        && close(a.normal.y, b.normal.y)
+        && close(a.normal.z, b.normal.z)
+        && close(a.tex_coord.x, b.tex_coord.x)
+        && close(a.tex_coord.y, b.tex_coord.y)
+}
+
+/// Concatenates meshes, moving each into a shared space with its transform
+///
+/// Normals go through the inverse-transpose, and mirroring transforms reverse the
+/// winding so front faces stay front faces.
+///
+/// # Errors
+/// Returns an error if the merged mesh would have more vertices than 32-bit indices
+/// can address
+pub fn merge(parts: &[(&Mesh, Mat4)]) -> Result<Mesh, String> {
+    let vertex_count: usize = parts.iter().map(|(mesh, _)| mesh.vertices.len()).sum();
+    IndexFormat::for_vertex_count(vertex_count)?;
+
+    let mut merged = Mesh {
+        vertices: Vec::with_capacity(vertex_count),
+        indices: Vec::with_capacity(parts.iter().map(|(mesh, _)| mesh.indices.len()).sum()),
+    };
+    for (mesh, transform) in parts {
+        let offset = merged.vertices.len() as u32;
+        let normal_matrix = transform.normal_matrix();
+        merged.vertices.extend(mesh.vertices.iter().map(|v| {
+            Vertex {
+                position: transform.transform_point(&v.position),
+                tex_coord: v.tex_coord,
+                normal: normal_matrix
+                    .transform_direction(&v.normal)
+                    .try_normalize()
+                    .unwrap_or(v.normal),
+            }
+        }));
+
+        let mirrored = transform.determinant() < 0.0;
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| offset + i);
+            if mirrored {
+                merged.indices.extend([a, c, b]);
+            } else {
+                merged.indices.extend([a, b, c]);
+            }
+        }
+    }
+    Ok(merged)
+}
+
+/// Turns the mesh inside out: reverses the winding of every triangle and negates
+/// every normal
+pub fn flip_triangles(mesh: &mut Mesh) {
+    for triangle in mesh.indices.chunks_exact_mut(3) {
+        triangle.swap(1, 2);
+    }
+    for vertex in &mut mesh.vertices {
+        vertex.normal = -vertex.normal;
+    }
+}
+
+/// Bounding sphere of the vertex positions from Ritter's algorithm, usually tighter
+/// than the sphere around [`Mesh::bounds`]; `None` for an empty mesh
+#[must_use]
+pub fn bounding_sphere(mesh: &Mesh) -> Option<BoundingSphere> {
+    let first = mesh.vertices.first()?.position;
+    let farthest_from = |from: Vec3| {
+        mesh.vertices
+            .iter()
+            .map(|v| v.position)
+            .fold(from, |best, p| {
+                if p.distance(&from) > best.distance(&from) {
+                    p
+                } else {
+                    best
+                }
+            })
+    };
+    let a = farthest_from(first);
+    let b = farthest_from(a);
+
+    let mut center = (a + b) * 0.5;
+    let mut radius = a.distance(&b) * 0.5;
+    for vertex in &mesh.vertices {
+        let distance = vertex.position.distance(&center);
+        if distance > radius {
+            // Grow just enough to reach the outlier, keeping the far side fixed
+            let new_radius = (radius + distance) * 0.5;
+            center = center + (vertex.position - center) * ((new_radius - radius) / distance);
+            radius = new_radius;
+        }
+    }
+    Some(BoundingSphere::new(center, radius))
+}
+
+/// Total area of all triangles, skipping triangles with out-of-range indices
+#[must_use]
+pub fn surface_area(mesh: &Mesh) -> f32 {
+    triangles(mesh)
+        .map(|[a, b, c]| {
+            let [pa, pb, pc] = [a, b, c].map(|i| mesh.vertices[i].position);
+            (pb - pa).cross(&(pc - pa)).length() * 0.5
+        })
+        .sum()
+}
+
+/// Lists every problem in `mesh`; an empty list means the mesh is safe to upload
+/// and process
+///
+/// A triangle counts as degenerate when it repeats a vertex or its area is
+/// negligible relative to its longest edge.
+#[must_use]
+pub fn validate(mesh: &Mesh) -> Vec<MeshIssue> {
+    let mut issues: Vec<MeshIssue> = mesh
+        .vertices
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| {
+            ![
+                v.position.x,
+                v.position.y,
+                v.position.z,
+                v.normal.x,
+                v.normal.y,
+                v.normal.z,
+            ]
+            .iter()
+            .all(|c| c.is_finite())
+        })
+        .map(|(vertex, _)| MeshIssue::NonFiniteVertex { vertex })
+        .collect();
+
+    for (triangle, indices) in mesh.indices.chunks_exact(3).enumerate() {
+        if let Some(&index) = indices.iter().find(|&&i| i as usize >= mesh.vertices.len()) {
+            issues.push(MeshIssue::IndexOutOfRange { triangle, index });
+            continue;
+        }
+        let [a, b, c] = [0, 1, 2].map(|k| mesh.vertices[indices[k] as usize].position);
+        let longest = (b - a)
+            .length_squared()
+            .max((c - b).length_squared())
+            .max((a - c).length_squared());
+        let doubled_area = (b - a).cross(&(c - a)).length();
+        let repeated =
+            indices[0] == indices[1] || indices[1] == indices[2] || indices[0] == indices[2];
+        if repeated || doubled_area <= longest * 1e-6 {
+            issues.push(MeshIssue::DegenerateTriangle { triangle });
+        }
+    }
+
+    let trailing = mesh.indices.len() % 3;
+    if trailing != 0 {
+        issues.push(MeshIssue::TrailingIndices { count: trailing });
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SphericalWorld;
+    use crate::math::Vec2;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!(a.distance(&b) < 1e-4, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_smooth_normals_follow_faces() {
+        let normals = smooth_normals(
+            &[
+                Vec3::zero(),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            &[0, 1, 2, 0, 1, 7],
+        );
+        assert_eq!(normals, [Vec3::new(0.0, 0.0, 1.0); 3]);
+
+        let mut cube = Mesh::cube();
+        let expected: Vec<Vec3> = cube.vertices.iter().map(|v| v.normal).collect();
+        for vertex in &mut cube.vertices {
+            vertex.normal = Vec3::zero();
+        }
+        // The cube's faces do not share vertices, so smoothing keeps them flat
+        recompute_smooth_normals(&mut cube);
+        for (vertex, normal) in cube.vertices.iter().zip(expected) {
+            assert_close(vertex.normal, normal);
+        }
+
+        // A welded sphere gets normals pointing away from its center
+        let mut sphere = SphericalWorld::new(2.0, 2).generate_mesh();
+        recompute_smooth_normals(&mut sphere);
+        for vertex in &sphere.vertices {
+            assert!(vertex.normal.dot(&vertex.position.normalize()) > 0.99);
+        }
+    }
+
+    #[test]
+    fn test_flat_normals_unshare_vertices() {
+        let mut sphere = SphericalWorld::new(1.0, 1).generate_mesh();
+        let triangle_count = sphere.indices.len() / 3;
+        recompute_flat_normals(&mut sphere).unwrap();
+        assert_eq!(sphere.vertices.len(), triangle_count * 3);
+        for triangle in sphere.vertices.chunks_exact(3) {
+            assert_eq!(triangle[0].normal, triangle[1].normal);
+            assert_eq!(triangle[1].normal, triangle[2].normal);
+            assert!(triangle[0].normal.dot(&triangle[0].position) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_tangents_follow_u_direction() {
+        let plane = Mesh::plane(2.0, 2.0);
+        for (vertex, tangent) in plane.vertices.iter().zip(generate_tangents(&plane)) {
+            assert!(tangent.truncate().dot(&vertex.normal).abs() < 1e-5);
+            assert!((tangent.truncate().length() - 1.0).abs() < 1e-5);
+            assert!(tangent.w == 1.0 || tangent.w == -1.0);
+        }
+
+        // Mirroring U flips the tangent but keeps the bitangent, so the sign changes
+        let mut mirrored = plane.clone();
+        for vertex in &mut mirrored.vertices {
+            vertex.tex_coord = Vec2::new(1.0 - vertex.tex_coord.x, vertex.tex_coord.y);
+        }
+        let original = generate_tangents(&plane);
+        let flipped = generate_tangents(&mirrored);
+        assert_close(flipped[0].truncate(), -original[0].truncate());
+        assert_eq!(flipped[0].w, -original[0].w);
+    }
+
+    #[test]
+    fn test_tangents_without_uvs_are_perpendicular() {
+        let mut cube = Mesh::cube();
+        for vertex in &mut cube.vertices {
+            vertex.tex_coord = Vec2::zero();
+        }
+        for (vertex, tangent) in cube.vertices.iter().zip(generate_tangents(&cube)) {
+            assert!(tangent.truncate().dot(&vertex.normal).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_weld_merges_duplicates_but_keeps_seams() {
+        let mesh = SphericalWorld::new(1.0, 1).generate_mesh();
+        let mut unshared = mesh.clone();
+        recompute_flat_normals(&mut unshared).unwrap();
+        for vertex in &mut unshared.vertices {
+            vertex.normal = vertex.position.normalize();
+        }
+        let area = surface_area(&unshared);
+
+        // Copies of shared vertices differ only by rounding noise
+        for (i, vertex) in unshared.vertices.iter_mut().enumerate() {
+            vertex.position.x += if i % 2 == 0 { 1e-6 } else { -1e-6 };
+        }
+        let removed = weld_vertices(&mut unshared, 1e-4);
+        assert!(removed > 0);
+        assert!(unshared.vertices.len() <= mesh.vertices.len());
+        assert!(validate(&unshared).is_empty());
+        assert!((surface_area(&unshared) - area).abs() < 1e-3);
+
+        // The cube's corners share positions but not normals
+        let mut cube = Mesh::cube();
+        assert_eq!(weld_vertices(&mut cube, 1e-3), 0);
+        assert_eq!(cube.vertices.len(), 24);
+    }
+
+    #[test]
+    fn test_merge_applies_transforms() {
+        let cube = Mesh::cube();
+        let merged = merge(&[
+            (&cube, Mat4::identity()),
+            (&cube, Mat4::translation(5.0, 0.0, 0.0)),
+            (&cube, Mat4::scale(-1.0, 1.0, 1.0)),
+        ])
+        .unwrap();
+        assert_eq!(merged.vertices.len(), 72);
+        assert_eq!(merged.indices.len(), 108);
+        assert!(validate(&merged).is_empty());
+
+        let bounds = merged.bounds().unwrap();
+        assert_close(bounds.max, Vec3::new(5.5, 0.5, 0.5));
+        assert!((surface_area(&merged) - 18.0).abs() < 1e-4);
+
+        // The mirrored copy keeps outward normals and outward winding
+        let mirrored = &merged.vertices[48..];
+        for triangle in merged.indices[72..].chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| merged.vertices[triangle[k] as usize]);
+            let face = (b.position - a.position).cross(&(c.position - a.position));
+            assert!(face.dot(&a.normal) > 0.0);
+        }
+        assert!(mirrored.iter().all(|v| v.normal.dot(&v.position) > 0.0));
+    }
+
+    #[test]
+    fn test_flip_reverses_winding_and_normals() {
+        let mut plane = Mesh::plane(1.0, 1.0);
+        let original = plane.clone();
+        flip_triangles(&mut plane);
+        assert_eq!(
+            plane.indices[..3],
+            [
+                original.indices[0],
+                original.indices[2],
+                original.indices[1]
+            ]
+        );
+        assert_close(plane.vertices[0].normal, -original.vertices[0].normal);
+        flip_triangles(&mut plane);
+        assert_eq!(plane.indices, original.indices);
+    }
+
+    #[test]
+    fn test_bounding_sphere_contains_all_vertices() {
+        let sphere_mesh = SphericalWorld::new(3.0, 2).generate_mesh();
+        let sphere = bounding_sphere(&sphere_mesh).unwrap();
+        assert!(sphere.center.length() < 0.1);
+        assert!(sphere.radius < 3.1);
+
+        let merged = merge(&[
+            (&Mesh::cube(), Mat4::identity()),
+            (&Mesh::cube(), Mat4::translation(10.0, 3.0, -2.0)),
+        ])
+        .unwrap();
+        let sphere = bounding_sphere(&merged).unwrap();
+        for vertex in &merged.vertices {
+            assert!(vertex.position.distance(&sphere.center) <= sphere.radius + 1e-4);
+        }
+        assert!(bounding_sphere(&Mesh {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn test_surface_area_of_sphere_approaches_analytic() {
+        let sphere = SphericalWorld::new(1.0, 4).generate_mesh();
+        let area = surface_area(&sphere);
+        let analytic = 4.0 * std::f32::consts::PI;
+        assert!(area < analytic && area > analytic * 0.99);
+    }
+
+    #[test]
+    fn test_validate_reports_problems() {
+        assert!(validate(&Mesh::cube()).is_empty());
+
+        let mut mesh = Mesh::plane(1.0, 1.0);
+        mesh.indices.extend([0, 0, 1, 0, 1, 9, 2]);
+        mesh.vertices[3].position.x = f32::NAN;
+        let issues = validate(&mesh);
+        assert_eq!(
+            issues,
+            [
+                MeshIssue::NonFiniteVertex { vertex: 3 },
+                MeshIssue::DegenerateTriangle { triangle: 2 },
+                MeshIssue::IndexOutOfRange {
+                    triangle: 3,
+                    index: 9
+                },
+                MeshIssue::TrailingIndices { count: 1 },
+            ]
+        );
+        assert_eq!(
+            issues[2].to_string(),
+            "triangle 3 uses out-of-range index 9"
+        );
+    }
+}
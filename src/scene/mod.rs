@@ -1,14 +1,43 @@
 //! Scene graph and 3D object management
 //!
 //! This module provides the scene graph structure and components:
-//! - Camera with first-person controls
-//! - Mesh data structures
-//! - Scene nodes with hierarchical transforms
+//! - Camera with first-person controls, perspective or orthographic projection and
+//!   reverse-Z depth with clip planes fitted to the planet
+//! - Keyframed camera paths with spline playback, saved as RON files
+//! - Mesh data structures and processing (normals, tangents, welding, validation)
+//! - Parametric primitives: spheres, cylinders, cones, capsules, tori and grids
+//! - Quadric error simplification and LOD chains
+//! - Arena-backed scene hierarchy addressed by generational node handles
+//! - Bounding volume hierarchy over nodes and instances for ray, frustum, radius and
+//!   nearest-neighbour queries
+//! - Saving and loading scenes as versioned RON files
+//! - glTF 2.0 import of meshes, hierarchies and base color textures
+//! - Wavefront OBJ/MTL import and OBJ export
 //! - Lighting system
 
-use crate::math::{Mat4, Transform, Vec2, Vec3};
-use std::cell::RefCell;
-use std::rc::Rc;
+mod bvh;
+mod camera_path;
+mod gltf_import;
+mod graph;
+pub mod mesh_ops;
+mod obj;
+mod primitives;
+mod serialization;
+mod simplify;
+
+pub use bvh::Bvh;
+pub use camera_path::{
+    CameraKeyframe, CameraPath, CameraPathPlayer, CameraPose, Easing, SurfaceClearance,
+    CAMERA_PATH_FORMAT_VERSION,
+};
+pub use graph::{Iter, Node, NodeId, ReparentMode, Scene, Siblings};
+pub use mesh_ops::MeshIssue;
+pub use obj::{write_obj, ObjMaterial, ObjModel, ObjObject};
+pub(crate) use primitives::icosphere_positions;
+pub use serialization::{MeshSource, SCENE_FORMAT_VERSION};
+pub use simplify::{LodChain, Simplified, SimplifyTarget};
+
+use crate::math::{Aabb, BoundingSphere, Frustum, Mat4, Ray, Vec2, Vec3};
 
 /// Point light with Phong shading parameters
 #[derive(Debug, Clone, Copy)]
@@ -33,7 +62,55 @@ impl Light {
     }
 }
 
+/// Largest pitch the camera looks up or down, just short of straight along `up`
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+/// How the camera maps view space to clip space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Perspective with the camera's vertical field of view
+    Perspective,
+    /// Parallel projection showing `height` world units from the bottom of the
+    /// screen to the top
+    Orthographic { height: f32 },
+}
+
+/// How view depth is mapped into the depth buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthMapping {
+    /// Depth grows with distance, as produced by [`Mat4::perspective`]
+    Standard,
+    /// Depth falls from 1 at the near plane to 0 at the far plane, keeping float
+    /// precision roughly even over distance ([`Mat4::perspective_reverse_z`])
+    ReverseZ,
+    /// Reverse-Z with the far plane of perspective views at infinity; orthographic
+    /// views still stop at the far plane
+    ReverseZInfinite,
+}
+
+impl DepthMapping {
+    /// Whether nearer surfaces have greater depth
+    #[must_use]
+    pub const fn is_reversed(self) -> bool {
+        matches!(self, Self::ReverseZ | Self::ReverseZInfinite)
+    }
+
+    /// Depth the buffer is cleared to at the start of a frame, behind everything
+    #[must_use]
+    pub const fn clear_depth(self) -> f32 {
+        if self.is_reversed() {
+            0.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Closest the near plane gets when fitted to the planet
+const MIN_FITTED_NEAR: f32 = 0.05;
+
 /// First-person camera with yaw/pitch controls
+#[derive(Debug, Clone)]
 pub struct Camera {
     position: Vec3,
     yaw: f32,
@@ -44,18 +121,21 @@ pub struct Camera {
     up: Vec3,
     target_up: Vec3,
     up_smoothing: f32,
+    projection: Projection,
     fov_y: f32,
     aspect_ratio: f32,
     near: f32,
     far: f32,
+    depth_mapping: DepthMapping,
 }
 
 impl Camera {
     #[must_use]
     pub fn new(position: Vec3, target: Vec3, aspect_ratio: f32) -> Self {
-        // Calculate initial yaw/pitch from position and target
+        // Calculate initial yaw/pitch from position and target, inverting forward():
+        // yaw 0 faces -Z and positive yaw turns towards +X
         let direction = target.sub(&position).normalize();
-        let yaw = direction.z.atan2(direction.x);
+        let yaw = direction.x.atan2(-direction.z);
         let pitch = (-direction.y).asin();
 
         Self {
@@ -68,10 +148,12 @@ impl Camera {
             up: Vec3::new(0.0, 1.0, 0.0),
             target_up: Vec3::new(0.0, 1.0, 0.0),
             up_smoothing: 0.1,
+            projection: Projection::Perspective,
             fov_y: std::f32::consts::PI / 4.0,
             aspect_ratio,
             near: 0.1,
             far: 100.0,
+            depth_mapping: DepthMapping::Standard,
         }
     }
 
@@ -81,20 +163,27 @@ impl Camera {
         Mat4::look_at(&self.position, &target, &self.up)
     }
 
-    #[must_use]
-    pub fn forward(&self) -> Vec3 {
+    /// Yaw-zero direction and the direction yaw turns towards, both perpendicular
+    /// to `up`
+    fn horizontal_basis(up: Vec3) -> (Vec3, Vec3) {
         // For spherical movement, forward needs to be calculated relative to the up vector
         // First, get a right vector perpendicular to up
         let world_up = Vec3::new(0.0, 1.0, 0.0);
-        let right = if (self.up.dot(&world_up).abs() - 1.0).abs() < 0.01 {
+        let right = if (up.dot(&world_up).abs() - 1.0).abs() < 0.01 {
             // If up is parallel to world up, use a different vector for cross product
             Vec3::new(1.0, 0.0, 0.0)
         } else {
-            world_up.cross(&self.up).normalize()
+            world_up.cross(&up).normalize()
         };
 
         // Create a forward vector in the plane perpendicular to up
-        let forward_flat = self.up.cross(&right).normalize();
+        let forward_flat = up.cross(&right).normalize();
+        (forward_flat, right)
+    }
+
+    #[must_use]
+    pub fn forward(&self) -> Vec3 {
+        let (forward_flat, right) = Self::horizontal_basis(self.up);
 
         // Apply yaw rotation around up axis
         let cos_yaw = self.yaw.cos();
@@ -117,7 +206,223 @@ impl Camera {
 
     #[must_use]
     pub fn projection_matrix(&self) -> Mat4 {
-        Mat4::perspective(self.fov_y, self.aspect_ratio, self.near, self.far)
+        let (fov_y, aspect, near, far) = (self.fov_y, self.aspect_ratio, self.near, self.far);
+        match (self.projection, self.depth_mapping) {
+            (Projection::Perspective, DepthMapping::Standard) => {
+                Mat4::perspective(fov_y, aspect, near, far)
+            }
+            (Projection::Perspective, DepthMapping::ReverseZ) => {
+                Mat4::perspective_reverse_z(fov_y, aspect, near, far)
+            }
+            (Projection::Perspective, DepthMapping::ReverseZInfinite) => {
+                Mat4::perspective_reverse_z_infinite(fov_y, aspect, near)
+            }
+            (Projection::Orthographic { height }, mapping) => {
+                let (half_width, half_height) = (height * aspect / 2.0, height / 2.0);
+                let orthographic = if mapping.is_reversed() {
+                    Mat4::orthographic_reverse_z
+                } else {
+                    Mat4::orthographic
+                };
+                orthographic(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                )
+            }
+        }
+    }
+
+    /// Ray from the camera through `pixel`, measured in pixels from the top-left
+    /// corner of a `viewport` of the given width and height
+    ///
+    /// Perspective rays start at the camera position; orthographic rays start on
+    /// the camera plane and all point along [`Self::forward`].
+    #[must_use]
+    pub fn screen_ray(&self, pixel: Vec2, viewport: Vec2) -> Ray {
+        // Same basis as `Mat4::look_at` in `view_matrix`
+        let forward = self.forward();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward);
+
+        let ndc_x = 2.0 * pixel.x / viewport.x.max(1.0) - 1.0;
+        let ndc_y = 1.0 - 2.0 * pixel.y / viewport.y.max(1.0);
+        match self.projection {
+            Projection::Perspective => {
+                let half_height = (self.fov_y / 2.0).tan();
+                let half_width = half_height * self.aspect_ratio;
+                let direction = forward + right * (ndc_x * half_width) + up * (ndc_y * half_height);
+                Ray::new(self.position, direction)
+            }
+            Projection::Orthographic { height } => {
+                let (half_width, half_height) = (height * self.aspect_ratio / 2.0, height / 2.0);
+                let origin =
+                    self.position + right * (ndc_x * half_width) + up * (ndc_y * half_height);
+                Ray::new(origin, forward)
+            }
+        }
+    }
+
+    /// Pixel that `point` projects onto, measured from the top-left corner of a
+    /// `viewport` of the given width and height; the inverse of [`Self::screen_ray`]
+    ///
+    /// Points off the sides of the screen still get a pixel outside the viewport, so
+    /// markers can be pushed onto the edge. Points behind the camera (or behind the
+    /// camera plane of an orthographic view) return `None`, as do points hidden by
+    /// `occluder`, usually the planet, per [`Self::is_occluded_by_sphere`].
+    #[must_use]
+    pub fn world_to_screen(
+        &self,
+        point: Vec3,
+        viewport: Vec2,
+        occluder: Option<&BoundingSphere>,
+    ) -> Option<Vec2> {
+        if occluder.is_some_and(|sphere| self.is_occluded_by_sphere(point, sphere)) {
+            return None;
+        }
+
+        // Same basis as `screen_ray`
+        let forward = self.forward();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward);
+
+        let offset = point - self.position;
+        let depth = offset.dot(&forward);
+        let (half_width, half_height) = match self.projection {
+            Projection::Perspective => {
+                if depth <= f32::EPSILON {
+                    return None;
+                }
+                let half_height = (self.fov_y / 2.0).tan() * depth;
+                (half_height * self.aspect_ratio, half_height)
+            }
+            Projection::Orthographic { height } => {
+                if depth < 0.0 {
+                    return None;
+                }
+                (height * self.aspect_ratio / 2.0, height / 2.0)
+            }
+        };
+
+        let ndc_x = offset.dot(&right) / half_width;
+        let ndc_y = offset.dot(&up) / half_height;
+        if !(ndc_x.is_finite() && ndc_y.is_finite()) {
+            return None;
+        }
+        Some(Vec2::new(
+            (ndc_x + 1.0) / 2.0 * viewport.x,
+            (1.0 - ndc_y) / 2.0 * viewport.y,
+        ))
+    }
+
+    /// Whether `sphere`, usually the planet, hides `point` from the camera
+    ///
+    /// Points on the visible side of the sphere's surface count as visible, so
+    /// markers placed on the ground only disappear once they pass the horizon. A
+    /// camera inside the sphere sees no horizon and nothing is hidden.
+    #[must_use]
+    pub fn is_occluded_by_sphere(&self, point: Vec3, sphere: &BoundingSphere) -> bool {
+        if self.position.distance(&sphere.center) <= sphere.radius {
+            return false;
+        }
+        let eye = match self.projection {
+            Projection::Perspective => self.position,
+            Projection::Orthographic { .. } => {
+                let forward = self.forward();
+                point - forward * (point - self.position).dot(&forward)
+            }
+        };
+        let distance = eye.distance(&point);
+        let Some(hit) = Ray::new(eye, point - eye).intersect_sphere(sphere) else {
+            return false;
+        };
+        // Leave room for points resting on the surface
+        hit < distance - 1e-3 * distance.max(1.0)
+    }
+
+    /// Vertical field of view in radians
+    #[must_use]
+    pub fn fov_y(&self) -> f32 {
+        self.fov_y
+    }
+
+    /// Sets the vertical field of view, kept strictly between 0 and 180 degrees
+    pub fn set_fov_y(&mut self, fov_y: f32) {
+        self.fov_y = fov_y.clamp(0.01, std::f32::consts::PI - 0.01);
+    }
+
+    /// Distance to the near clip plane
+    #[must_use]
+    pub fn near(&self) -> f32 {
+        self.near
+    }
+
+    /// Distance to the far clip plane; perspective views ignore it under
+    /// [`DepthMapping::ReverseZInfinite`]
+    #[must_use]
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    /// Sets the distances to the near and far clip planes
+    ///
+    /// # Errors
+    /// Returns an error unless both are finite and `0 < near < far`
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) -> Result<(), String> {
+        if !(near.is_finite() && far.is_finite() && near > 0.0 && near < far) {
+            return Err(format!(
+                "Clip planes need 0 < near < far, got near {near} and far {far}"
+            ));
+        }
+        self.near = near;
+        self.far = far;
+        Ok(())
+    }
+
+    /// Fits the clip planes to the camera's height above a spherical planet
+    ///
+    /// The far plane reaches the horizon plus the distance at which features up to
+    /// `feature_height` above the surface still show over it. The near plane backs
+    /// off as the camera climbs above the tallest features, so depth precision holds
+    /// up from ground level to orbit.
+    pub fn fit_clip_planes(
+        &mut self,
+        planet_center: Vec3,
+        planet_radius: f32,
+        feature_height: f32,
+    ) {
+        let radius = planet_radius.max(0.0);
+        let feature_height = feature_height.max(0.0);
+        let altitude = (self.position.distance(&planet_center) - radius).max(0.0);
+        // Distance to the horizon from `height` above the surface
+        let horizon = |height: f32| (height * (2.0 * radius + height)).sqrt();
+
+        let clearance = (altitude - feature_height).max(0.0);
+        let near = (clearance * 0.5).max(MIN_FITTED_NEAR);
+        let far = ((horizon(altitude) + horizon(feature_height)) * 1.1).max(near * 2.0);
+        self.near = near;
+        self.far = far;
+    }
+
+    #[must_use]
+    pub fn depth_mapping(&self) -> DepthMapping {
+        self.depth_mapping
+    }
+
+    pub fn set_depth_mapping(&mut self, depth_mapping: DepthMapping) {
+        self.depth_mapping = depth_mapping;
+    }
+
+    #[must_use]
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
     }
 
     #[must_use]
@@ -125,16 +430,43 @@ impl Camera {
         self.projection_matrix().multiply(&self.view_matrix())
     }
 
+    /// Planes bounding what the camera sees, extracted to match its depth mapping
+    #[must_use]
+    pub fn frustum(&self) -> Frustum {
+        let view_projection = self.view_projection_matrix();
+        if self.depth_mapping.is_reversed() {
+            Frustum::from_view_projection_reverse_z(&view_projection)
+        } else {
+            Frustum::from_view_projection(&view_projection)
+        }
+    }
+
     pub fn set_position(&mut self, position: Vec3) {
         self.position = position;
     }
 
     pub fn rotate(&mut self, yaw_delta: f32, pitch_delta: f32) {
         self.target_yaw += yaw_delta;
-        self.target_pitch = (self.target_pitch + pitch_delta).clamp(
-            -std::f32::consts::FRAC_PI_2 + 0.01,
-            std::f32::consts::FRAC_PI_2 - 0.01,
-        );
+        self.target_pitch = (self.target_pitch + pitch_delta).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Looks along `forward` with `up` as the up vector right away, skipping the
+    /// smoothing of [`Self::rotate`] and [`Self::set_up_vector`]; `forward` is
+    /// limited to the same pitch range as `rotate`
+    pub fn set_orientation(&mut self, forward: Vec3, up: Vec3) {
+        let up = up.try_normalize().unwrap_or(self.up);
+        self.up = up;
+        self.target_up = up;
+
+        let Some(direction) = forward.try_normalize() else {
+            return;
+        };
+        let (forward_flat, right) = Self::horizontal_basis(up);
+        self.yaw = direction.dot(&right).atan2(direction.dot(&forward_flat));
+        self.pitch = (-direction.dot(&up)).clamp(-1.0, 1.0).asin();
+        self.pitch = self.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        self.target_yaw = self.yaw;
+        self.target_pitch = self.pitch;
     }
 
     pub fn move_forward(&mut self, distance: f32) {
@@ -196,10 +528,46 @@ pub struct InstanceData {
     pub _padding: [u32; 3],
 }
 
+/// Width of the indices uploaded for a mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexFormat {
+    U16,
+    U32,
+}
+
+impl IndexFormat {
+    /// Narrowest format that can address `vertex_count` vertices
+    ///
+    /// # Errors
+    /// Returns an error if even 32-bit indices cannot address every vertex
+    pub fn for_vertex_count(vertex_count: usize) -> Result<Self, String> {
+        if vertex_count <= usize::from(u16::MAX) + 1 {
+            Ok(Self::U16)
+        } else if u32::try_from(vertex_count - 1).is_ok() {
+            Ok(Self::U32)
+        } else {
+            Err(format!(
+                "{vertex_count} vertices are more than 32-bit indices can address"
+            ))
+        }
+    }
+
+    /// Size of one index in bytes
+    #[must_use]
+    pub const fn byte_size(self) -> usize {
+        match self {
+            Self::U16 => 2,
+            Self::U32 => 4,
+        }
+    }
+}
+
+/// Indexed triangle list; indices are 32-bit on the CPU and narrowed to
+/// [`IndexFormat::U16`] on upload when the vertex count allows it
 #[derive(Clone)]
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
-    pub indices: Vec<u16>,
+    pub indices: Vec<u32>,
 }
 
 pub struct InstancedMesh {
@@ -208,6 +576,51 @@ pub struct InstancedMesh {
 }
 
 impl Mesh {
+    /// Bounding box of the vertex positions in model space, or `None` for an empty mesh
+    #[must_use]
+    pub fn bounds(&self) -> Option<Aabb> {
+        Aabb::from_points(self.vertices.iter().map(|v| &v.position))
+    }
+
+    /// Index format used when the mesh is uploaded, see [`IndexFormat::for_vertex_count`]
+    ///
+    /// # Errors
+    /// Returns an error if the mesh has more vertices than 32-bit indices can address
+    pub fn index_format(&self) -> Result<IndexFormat, String> {
+        IndexFormat::for_vertex_count(self.vertices.len())
+    }
+
+    /// Checks that every index addresses a vertex
+    ///
+    /// # Errors
+    /// Returns an error naming the first out-of-range index
+    pub fn check_indices(&self) -> Result<(), String> {
+        let vertex_count = self.vertices.len();
+        match self
+            .indices
+            .iter()
+            .find(|&&i| usize::try_from(i).map_or(true, |i| i >= vertex_count))
+        {
+            Some(index) => Err(format!(
+                "Mesh index {index} is out of range for {vertex_count} vertices"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Indices narrowed to 16 bits
+    ///
+    /// # Errors
+    /// Returns an error if an index does not fit in 16 bits
+    pub fn indices_u16(&self) -> Result<Vec<u16>, String> {
+        self.indices
+            .iter()
+            .map(|&i| {
+                u16::try_from(i).map_err(|_| format!("Mesh index {i} does not fit in 16 bits"))
+            })
+            .collect()
+    }
+
     #[must_use]
     pub fn cube() -> Self {
         let vertices = vec![
@@ -419,97 +832,3 @@ impl Mesh {
         Self { vertices, indices }
     }
 }
-
-pub type NodeRef = Rc<RefCell<Node>>;
-
-pub struct Node {
-    pub name: String,
-    pub transform: Transform,
-    pub mesh: Option<Mesh>,
-    pub children: Vec<NodeRef>,
-    parent: Option<NodeRef>,
-}
-
-impl Node {
-    #[must_use]
-    pub fn new(name: String) -> Self {
-        Self {
-            name,
-            transform: Transform::identity(),
-            mesh: None,
-            children: Vec::new(),
-            parent: None,
-        }
-    }
-
-    #[must_use]
-    pub fn with_mesh(name: String, mesh: Mesh) -> Self {
-        Self {
-            name,
-            transform: Transform::identity(),
-            mesh: Some(mesh),
-            children: Vec::new(),
-            parent: None,
-        }
-    }
-
-    pub fn add_child(&mut self, child: NodeRef) {
-        self.children.push(child);
-    }
-
-    #[must_use]
-    pub fn world_transform(&self) -> Mat4 {
-        let local_transform = self.transform.to_matrix();
-        if let Some(parent) = &self.parent {
-            parent.borrow().world_transform().multiply(&local_transform)
-        } else {
-            local_transform
-        }
-    }
-}
-
-pub struct Scene {
-    pub root_nodes: Vec<NodeRef>,
-    pub light: Light,
-}
-
-impl Scene {
-    #[must_use]
-    pub fn new() -> Self {
-        Self {
-            root_nodes: Vec::new(),
-            light: Light::new(Vec3::new(5.0, 10.0, 5.0), Vec3::new(1.0, 1.0, 1.0)),
-        }
-    }
-
-    pub fn add_node(&mut self, node: NodeRef) {
-        self.root_nodes.push(node);
-    }
-
-    pub fn traverse<F>(&self, mut callback: F)
-    where
-        F: FnMut(&Node, &Mat4),
-    {
-        for root in &self.root_nodes {
-            self.traverse_node(&root.borrow(), &Mat4::identity(), &mut callback);
-        }
-    }
-
-    fn traverse_node<F>(&self, node: &Node, parent_transform: &Mat4, callback: &mut F)
-    where
-        F: FnMut(&Node, &Mat4),
-    {
-        let world_transform = parent_transform.multiply(&node.transform.to_matrix());
-        callback(node, &world_transform);
-
-        for child in &node.children {
-            self.traverse_node(&child.borrow(), &world_transform, callback);
-        }
-    }
-}
-
-impl Default for Scene {
-    fn default() -> Self {
-        Self::new()
-    }
-}
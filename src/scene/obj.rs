@@ -0,0 +1,587 @@
+//! Wavefront OBJ import and export
+//!
+//! A small reader and writer for quick round trips through tools like Blender.
+//! Import understands positions, texture coordinates, normals, polygonal faces
+//! (fan-triangulated), negative indices, `o`/`g` objects and `usemtl` materials,
+//! whose diffuse color and opacity are read from the referenced MTL files. Faces
+//! without normals get smooth normals. Export writes one `o` block per mesh with a
+//! texture coordinate and normal for every vertex.
+
+use crate::math::{Vec2, Vec3};
+use crate::scene::{mesh_ops, IndexFormat, Mesh, Node, NodeId, Scene, Vertex};
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Material from an MTL file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Vec3,
+    /// `d`, or `1 - Tr`
+    pub opacity: f32,
+}
+
+impl ObjMaterial {
+    #[must_use]
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            diffuse: Vec3::new(1.0, 1.0, 1.0),
+            opacity: 1.0,
+        }
+    }
+
+    /// Parses the materials of an MTL file
+    ///
+    /// # Errors
+    /// Returns an error if a color or opacity statement has malformed numbers
+    pub fn parse_mtl(text: &str) -> Result<Vec<Self>, String> {
+        let mut materials: Vec<Self> = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            let context = |e: String| format!("MTL line {}: {e}", line_number + 1);
+            match tokens.next() {
+                Some("newmtl") => {
+                    materials.push(Self::new(tokens.collect::<Vec<_>>().join(" ")));
+                }
+                Some(keyword @ ("Kd" | "d" | "Tr")) => {
+                    let Some(material) = materials.last_mut() else {
+                        return Err(context(format!("'{keyword}' before any 'newmtl'")));
+                    };
+                    let values = parse_floats(tokens).map_err(context)?;
+                    match (keyword, values.as_slice()) {
+                        ("Kd", [r, g, b]) => material.diffuse = Vec3::new(*r, *g, *b),
+                        ("d", [d]) => material.opacity = *d,
+                        ("Tr", [tr]) => material.opacity = 1.0 - tr,
+                        _ => return Err(context(format!("malformed '{keyword}' statement"))),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(materials)
+    }
+}
+
+/// Mesh of one OBJ object and material
+#[derive(Clone)]
+pub struct ObjObject {
+    pub name: String,
+    pub mesh: Mesh,
+    pub material: Option<ObjMaterial>,
+}
+
+/// Contents of an OBJ file, split wherever the object, group or material changes
+#[derive(Clone, Default)]
+pub struct ObjModel {
+    pub objects: Vec<ObjObject>,
+}
+
+impl ObjModel {
+    /// Parses OBJ text; `usemtl` names are looked up in `materials`
+    ///
+    /// # Errors
+    /// Returns an error on malformed statements, out-of-range indices or a mesh with
+    /// more vertices than 32-bit indices can address
+    pub fn parse(text: &str, materials: &[ObjMaterial]) -> Result<Self, String> {
+        let mut parser = ObjParser::default();
+        for (line_number, line) in text.lines().enumerate() {
+            parser
+                .statement(line, materials)
+                .map_err(|e| format!("OBJ line {}: {e}", line_number + 1))?;
+        }
+        parser.finish_object()?;
+        Ok(Self {
+            objects: parser.objects,
+        })
+    }
+
+    /// Reads an OBJ file along with the MTL files it references
+    ///
+    /// # Errors
+    /// Returns an error if the OBJ or an MTL file cannot be read or parsed
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read OBJ {}: {e}", path.display()))?;
+
+        let mut materials = Vec::new();
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        for line in text.lines() {
+            if let Some(libraries) = line.trim_start().strip_prefix("mtllib ") {
+                for library in libraries.split_whitespace() {
+                    let mtl_path = directory.join(library);
+                    let mtl = std::fs::read_to_string(&mtl_path)
+                        .map_err(|e| format!("Failed to read MTL {}: {e}", mtl_path.display()))?;
+                    materials.extend(
+                        ObjMaterial::parse_mtl(&mtl)
+                            .map_err(|e| format!("{}: {e}", mtl_path.display()))?,
+                    );
+                }
+            }
+        }
+
+        Self::parse(&text, &materials).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// All objects merged into a single mesh
+    #[must_use]
+    pub fn merged_mesh(&self) -> Mesh {
+        let mut merged = Mesh {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+        for object in &self.objects {
+            let offset = merged.vertices.len();
+            merged.vertices.extend_from_slice(&object.mesh.vertices);
+            merged.indices.extend(
+                object
+                    .mesh
+                    .indices
+                    .iter()
+                    .map(|&i| u32::try_from(offset + i as usize).unwrap_or(u32::MAX)),
+            );
+        }
+        merged
+    }
+}
+
+impl Mesh {
+    /// Parses OBJ text into a single mesh, ignoring materials
+    ///
+    /// # Errors
+    /// Returns an error if the text is not valid OBJ or the merged mesh has more
+    /// vertices than 32-bit indices can address
+    pub fn from_obj_str(text: &str) -> Result<Self, String> {
+        let model = ObjModel::parse(text, &[])?;
+        let vertex_count: usize = model.objects.iter().map(|o| o.mesh.vertices.len()).sum();
+        check_vertex_count(vertex_count)?;
+        Ok(model.merged_mesh())
+    }
+
+    /// Reads an OBJ file into a single mesh, see [`Self::from_obj_str`]
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed
+    pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read OBJ {}: {e}", path.display()))?;
+        Self::from_obj_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// The mesh as an OBJ object called `name`
+    #[must_use]
+    pub fn to_obj(&self, name: &str) -> String {
+        write_obj(&[(name, self)])
+    }
+
+    /// Writes the mesh to an OBJ file, naming the object after the file
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written
+    pub fn save_obj<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map_or_else(|| "mesh".into(), |stem| stem.to_string_lossy());
+        std::fs::write(path, self.to_obj(&name))
+            .map_err(|e| format!("Failed to write OBJ {}: {e}", path.display()))
+    }
+}
+
+impl Scene {
+    /// Imports an OBJ file under `parent`, or as new roots, with one node per object.
+    /// Material diffuse colors become 1x1 node textures.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or `parent` is not in
+    /// the scene
+    pub fn import_obj<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        parent: Option<NodeId>,
+    ) -> Result<Vec<NodeId>, String> {
+        let model = ObjModel::load(path)?;
+        let mut colors: HashMap<String, Arc<RgbaImage>> = HashMap::new();
+
+        let mut ids = Vec::with_capacity(model.objects.len());
+        for object in model.objects {
+            let mut node = Node::with_mesh(object.name, object.mesh);
+            if let Some(material) = object.material {
+                let texture = colors.entry(material.name.clone()).or_insert_with(|| {
+                    let [r, g, b, a] = [
+                        material.diffuse.x,
+                        material.diffuse.y,
+                        material.diffuse.z,
+                        material.opacity,
+                    ]
+                    .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                    Arc::new(RgbaImage::from_pixel(1, 1, Rgba([r, g, b, a])))
+                });
+                node.set_texture(Some(texture.clone()));
+            }
+            ids.push(match parent {
+                Some(parent) => self.add_child(parent, node)?,
+                None => self.add_node(node),
+            });
+        }
+        Ok(ids)
+    }
+}
+
+/// OBJ text holding one object per `(name, mesh)` pair
+#[must_use]
+pub fn write_obj(objects: &[(&str, &Mesh)]) -> String {
+    let mut out = String::new();
+    let mut offset = 1;
+    for (name, mesh) in objects {
+        // Writing to a String cannot fail
+        let _ = writeln!(out, "o {name}");
+        for v in &mesh.vertices {
+            let _ = writeln!(out, "v {} {} {}", v.position.x, v.position.y, v.position.z);
+        }
+        for v in &mesh.vertices {
+            let _ = writeln!(out, "vt {} {}", v.tex_coord.x, 1.0 - v.tex_coord.y);
+        }
+        for v in &mesh.vertices {
+            let _ = writeln!(out, "vn {} {} {}", v.normal.x, v.normal.y, v.normal.z);
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| triangle[k] as usize + offset);
+            let _ = writeln!(out, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}");
+        }
+        offset += mesh.vertices.len();
+    }
+    out
+}
+
+#[derive(Default)]
+struct ObjParser {
+    positions: Vec<[f32; 3]>,
+    tex_coords: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+    objects: Vec<ObjObject>,
+    name: String,
+    material: Option<ObjMaterial>,
+    /// Vertices of the object being read, keyed by their position/uv/normal indices
+    corners: HashMap<(usize, Option<usize>, Option<usize>), u32>,
+    vertices: Vec<(usize, Option<usize>, Option<usize>)>,
+    indices: Vec<u32>,
+}
+
+impl ObjParser {
+    fn statement(&mut self, line: &str, materials: &[ObjMaterial]) -> Result<(), String> {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let values = parse_floats(tokens)?;
+                let [x, y, z, ..] = values[..] else {
+                    return Err("'v' needs three coordinates".to_string());
+                };
+                self.positions.push([x, y, z]);
+            }
+            Some("vt") => {
+                let values = parse_floats(tokens)?;
+                let [u, v, ..] = values[..] else {
+                    return Err("'vt' needs two coordinates".to_string());
+                };
+                // OBJ puts v = 0 at the bottom of the image, our meshes at the top
+                self.tex_coords.push([u, 1.0 - v]);
+            }
+            Some("vn") => {
+                let values = parse_floats(tokens)?;
+                let [x, y, z, ..] = values[..] else {
+                    return Err("'vn' needs three components".to_string());
+                };
+                self.normals.push([x, y, z]);
+            }
+            Some("f") => {
+                let corners = tokens
+                    .map(|corner| self.corner(corner))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if corners.len() < 3 {
+                    return Err("a face needs at least three vertices".to_string());
+                }
+                for i in 1..corners.len() - 1 {
+                    self.indices
+                        .extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            Some("o" | "g") => {
+                self.finish_object()?;
+                self.name = tokens.collect::<Vec<_>>().join(" ");
+            }
+            Some("usemtl") => {
+                self.finish_object()?;
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                self.material = Some(
+                    materials
+                        .iter()
+                        .find(|material| material.name == name)
+                        .cloned()
+                        .unwrap_or_else(|| ObjMaterial::new(name)),
+                );
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Index of the vertex for a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner
+    fn corner(&mut self, corner: &str) -> Result<u32, String> {
+        let mut parts = corner.split('/');
+        let position = resolve(parts.next(), self.positions.len())?
+            .ok_or_else(|| format!("face corner '{corner}' has no position"))?;
+        let tex_coord = resolve(parts.next(), self.tex_coords.len())?;
+        let normal = resolve(parts.next(), self.normals.len())?;
+
+        let key = (position, tex_coord, normal);
+        if let Some(&index) = self.corners.get(&key) {
+            return Ok(index);
+        }
+        let index = u32::try_from(self.vertices.len())
+            .map_err(|_| "object has more vertices than 32-bit indices can address")?;
+        self.corners.insert(key, index);
+        self.vertices.push(key);
+        Ok(index)
+    }
+
+    /// Emits the faces read so far as an object
+    fn finish_object(&mut self) -> Result<(), String> {
+        if self.indices.is_empty() {
+            return Ok(());
+        }
+
+        let positions: Vec<Vec3> = self
+            .vertices
+            .iter()
+            .map(|&(position, ..)| {
+                let [x, y, z] = self.positions[position];
+                Vec3::new(x, y, z)
+            })
+            .collect();
+        let smooth = if self.vertices.iter().all(|(.., normal)| normal.is_some()) {
+            Vec::new()
+        } else {
+            mesh_ops::smooth_normals(&positions, &self.indices)
+        };
+
+        let vertices = self
+            .vertices
+            .iter()
+            .zip(&positions)
+            .enumerate()
+            .map(|(i, (&(_, tex_coord, normal), &position))| {
+                let normal = normal
+                    .map(|n| {
+                        let [x, y, z] = self.normals[n];
+                        Vec3::new(x, y, z)
+                    })
+                    .or_else(|| smooth.get(i).copied())
+                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+                let [u, v] = tex_coord.map_or([0.0, 0.0], |t| self.tex_coords[t]);
+                Vertex {
+                    position,
+                    tex_coord: Vec2::new(u, v),
+                    normal,
+                }
+            })
+            .collect();
+
+        let name = if self.name.is_empty() {
+            format!("object{}", self.objects.len())
+        } else {
+            self.name.clone()
+        };
+        self.objects.push(ObjObject {
+            name,
+            mesh: Mesh {
+                vertices,
+                indices: std::mem::take(&mut self.indices),
+            },
+            material: self.material.clone(),
+        });
+        self.corners.clear();
+        self.vertices.clear();
+        Ok(())
+    }
+}
+
+/// Resolves a 1-based or negative (relative to the end) OBJ index
+fn resolve(part: Option<&str>, len: usize) -> Result<Option<usize>, String> {
+    let Some(part) = part.filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    let index: i64 = part
+        .parse()
+        .map_err(|_| format!("invalid index '{part}'"))?;
+    let len = i64::try_from(len).unwrap_or(i64::MAX);
+    let resolved = if index < 0 { len + index } else { index - 1 };
+    if (0..len).contains(&resolved) {
+        Ok(usize::try_from(resolved).ok())
+    } else {
+        Err(format!("index {index} is out of range"))
+    }
+}
+
+fn parse_floats<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<Vec<f32>, String> {
+    tokens
+        .map(|token| {
+            token
+                .parse::<f32>()
+                .map_err(|_| format!("invalid number '{token}'"))
+        })
+        .collect()
+}
+
+fn check_vertex_count(count: usize) -> Result<(), String> {
+    IndexFormat::for_vertex_count(count).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SphericalWorld;
+
+    const PANELS: &str = "
+        # Two quads sharing an edge, one per material
+        mtllib scene.mtl
+        o panel
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 2 0 0
+        v 2 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 1
+        usemtl red
+        f 1/1/1 2/2/1 3/3/1 4/4/1
+        usemtl glass
+        f -5//1 5//1 6//1 -4//1
+    ";
+
+    const MTL: &str = "
+        newmtl red
+        Kd 1.0 0.0 0.0
+        newmtl glass
+        Kd 0.2 0.4 0.6
+        Tr 0.75
+    ";
+
+    #[test]
+    fn test_parse_mtl() {
+        let materials = ObjMaterial::parse_mtl(MTL).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "red");
+        assert_eq!(materials[0].diffuse, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(materials[0].opacity, 1.0);
+        assert_eq!(materials[1].opacity, 0.25);
+        assert!(ObjMaterial::parse_mtl("Kd 1 1 1").is_err());
+        assert!(ObjMaterial::parse_mtl("newmtl a\nKd 1 x 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_splits_by_material() {
+        let materials = ObjMaterial::parse_mtl(MTL).unwrap();
+        let model = ObjModel::parse(PANELS, &materials).unwrap();
+        assert_eq!(model.objects.len(), 2);
+
+        let red = &model.objects[0];
+        assert_eq!(red.name, "panel");
+        assert_eq!(
+            red.material.as_ref().unwrap().diffuse,
+            Vec3::new(1.0, 0.0, 0.0)
+        );
+        assert_eq!(red.mesh.vertices.len(), 4);
+        assert_eq!(red.mesh.indices, [0, 1, 2, 0, 2, 3]);
+        // OBJ texture coordinates are flipped vertically
+        assert_eq!(red.mesh.vertices[0].tex_coord, Vec2::new(0.0, 1.0));
+
+        // Negative indices count back from the last vertex read so far
+        let glass = &model.objects[1];
+        assert_eq!(glass.material.as_ref().unwrap().name, "glass");
+        assert_eq!(glass.mesh.vertices[0].position, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(glass.mesh.vertices[3].position, Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(glass.mesh.vertices[1].normal, Vec3::new(0.0, 0.0, 1.0));
+
+        let merged = model.merged_mesh();
+        assert_eq!(merged.vertices.len(), 8);
+        assert_eq!(&merged.indices[6..9], [4, 5, 6]);
+    }
+
+    #[test]
+    fn test_missing_normals_are_smoothed() {
+        let mesh = Mesh::from_obj_str("v 0 0 0\nv 1 0 0\nv 0 0 -1\nf 1 2 3").unwrap();
+        for vertex in &mesh.vertices {
+            assert!(vertex.normal.distance(&Vec3::new(0.0, 1.0, 0.0)) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_obj() {
+        assert!(Mesh::from_obj_str("v 0 0 0\nf 1 2 3").is_err());
+        assert!(Mesh::from_obj_str("v 0 0\n").is_err());
+        assert!(Mesh::from_obj_str("v 0 0 0\nv 1 0 0\nf 1 2").is_err());
+        assert!(Mesh::from_obj_str("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 x 3").is_err());
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let mesh = SphericalWorld::new(3.0, 2).generate_mesh();
+        let text = mesh.to_obj("planet");
+        assert!(text.starts_with("o planet\n"));
+
+        // Vertices are renumbered in the order faces use them, so compare triangles
+        let loaded = Mesh::from_obj_str(&text).unwrap();
+        assert_eq!(loaded.vertices.len(), mesh.vertices.len());
+        assert_eq!(loaded.indices.len(), mesh.indices.len());
+        for (&a, &b) in mesh.indices.iter().zip(&loaded.indices) {
+            let (a, b) = (&mesh.vertices[a as usize], &loaded.vertices[b as usize]);
+            assert_eq!(a.position, b.position);
+            assert_eq!(a.normal, b.normal);
+            assert!((a.tex_coord.x - b.tex_coord.x).abs() < 1e-6);
+            assert!((a.tex_coord.y - b.tex_coord.y).abs() < 1e-6);
+        }
+
+        // Several meshes go into separate objects with offset indices
+        let cube = Mesh::cube();
+        let plane = Mesh::plane(1.0, 1.0);
+        let model =
+            ObjModel::parse(&write_obj(&[("cube", &cube), ("plane", &plane)]), &[]).unwrap();
+        assert_eq!(model.objects.len(), 2);
+        assert_eq!(model.objects[1].name, "plane");
+        assert_eq!(model.objects[1].mesh.indices, plane.indices);
+    }
+
+    #[test]
+    fn test_import_obj_with_materials() {
+        let dir = std::env::temp_dir().join(format!("obj-import-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("scene.obj"), PANELS).unwrap();
+        std::fs::write(dir.join("scene.mtl"), MTL).unwrap();
+        Mesh::cube().save_obj(dir.join("cube.obj")).unwrap();
+
+        let mut scene = Scene::new();
+        let imported = scene.import_obj(dir.join("scene.obj"), None);
+        let cube = Mesh::load_obj(dir.join("cube.obj"));
+        let cube_text = std::fs::read_to_string(dir.join("cube.obj"));
+        std::fs::remove_dir_all(&dir).ok();
+
+        let ids = imported.unwrap();
+        assert_eq!(ids.len(), 2);
+        let glass = scene.get(ids[1]).unwrap().texture().unwrap();
+        assert_eq!(glass.get_pixel(0, 0), &Rgba([51, 102, 153, 64]));
+        assert_eq!(cube.unwrap().indices, Mesh::cube().indices);
+        assert!(cube_text.unwrap().starts_with("o cube\n"));
+    }
+}
@@ -0,0 +1,562 @@
+//! Parametric primitive meshes
+//!
+//! Every generator is centered on the origin with +Y up, winds triangles
+//! counter-clockwise seen from outside and sets analytic normals. Texture
+//! coordinates increase in U to the right and in V downwards as seen from outside,
+//! matching [`Mesh::cube`]; round surfaces duplicate the seam column so the texture
+//! does not wrap backwards across it. Segment counts are clamped to the smallest
+//! value that still gives a closed shape.
+
+use crate::math::{Vec2, Vec3};
+use crate::scene::{Mesh, Vertex};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+/// Golden-ratio icosahedron subdivided `subdivisions` times, as unit-length positions
+/// and counter-clockwise triangles; midpoints are shared between neighboring faces
+pub(crate) fn icosphere_positions(subdivisions: u32) -> (Vec<Vec3>, Vec<u32>) {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut positions: Vec<Vec3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+    let mut indices = vec![
+        0, 11, 5, 0, 5, 1, 0, 1, 7, 0, 7, 10, 0, 10, 11, //
+        1, 5, 9, 5, 11, 4, 11, 10, 2, 10, 7, 6, 7, 1, 8, //
+        3, 9, 4, 3, 4, 2, 3, 2, 6, 3, 6, 8, 3, 8, 9, //
+        4, 9, 5, 2, 4, 11, 6, 2, 10, 8, 6, 7, 9, 8, 1,
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, positions: &mut Vec<Vec3>| {
+            let key = (a.min(b), a.max(b));
+            *midpoints.entry(key).or_insert_with(|| {
+                let p = (positions[a as usize] + positions[b as usize]) * 0.5;
+                positions.push(p.normalize());
+                positions.len() as u32 - 1
+            })
+        };
+
+        let mut subdivided = Vec::with_capacity(indices.len() * 4);
+        for triangle in indices.chunks_exact(3) {
+            let [v1, v2, v3] = [triangle[0], triangle[1], triangle[2]];
+            let a = midpoint(v1, v2, &mut positions);
+            let b = midpoint(v2, v3, &mut positions);
+            let c = midpoint(v3, v1, &mut positions);
+            subdivided.extend_from_slice(&[v1, a, c, v2, b, a, v3, c, b, a, b, c]);
+        }
+        indices = subdivided;
+    }
+    (positions, indices)
+}
+
+/// Grid of `(rows + 1) * (cols + 1)` vertices with two triangles per cell, skipping
+/// triangles that collapse where a row meets at a pole or apex
+///
+/// `vertex(row, col)` must place increasing columns to the right and increasing rows
+/// downwards as seen from the side the normals face.
+fn surface(rows: usize, cols: usize, vertex: impl Fn(usize, usize) -> Vertex) -> Mesh {
+    let mut vertices = Vec::with_capacity((rows + 1) * (cols + 1));
+    for row in 0..=rows {
+        for col in 0..=cols {
+            vertices.push(vertex(row, col));
+        }
+    }
+
+    let mut indices = Vec::with_capacity(rows * cols * 6);
+    let index = |row: usize, col: usize| (row * (cols + 1) + col) as u32;
+    for row in 0..rows {
+        for col in 0..cols {
+            let a = index(row, col);
+            let b = index(row + 1, col);
+            let c = index(row + 1, col + 1);
+            let d = index(row, col + 1);
+            for triangle in [[a, b, c], [a, c, d]] {
+                if !is_degenerate(&vertices, triangle) {
+                    indices.extend(triangle);
+                }
+            }
+        }
+    }
+    Mesh { vertices, indices }
+}
+
+fn is_degenerate(vertices: &[Vertex], triangle: [u32; 3]) -> bool {
+    let [a, b, c] = triangle.map(|i| vertices[i as usize].position);
+    let longest = (b - a)
+        .length_squared()
+        .max((c - b).length_squared())
+        .max((a - c).length_squared());
+    (b - a).cross(&(c - a)).length() <= longest * 1e-6
+}
+
+/// Unit direction in the XZ plane at `angle` radians from +Z towards +X
+fn around(angle: f32) -> Vec3 {
+    Vec3::new(angle.sin(), 0.0, angle.cos())
+}
+
+/// Appends a flat disc at height `y` facing +Y (`up`) or -Y
+fn add_cap(mesh: &mut Mesh, radius: f32, y: f32, segments: usize, up: bool) {
+    let normal = Vec3::new(0.0, if up { 1.0 } else { -1.0 }, 0.0);
+    let center = mesh.vertices.len() as u32;
+    mesh.vertices.push(Vertex {
+        position: Vec3::new(0.0, y, 0.0),
+        tex_coord: Vec2::new(0.5, 0.5),
+        normal,
+    });
+    for i in 0..segments {
+        let direction = around(i as f32 / segments as f32 * TAU);
+        // Seen from outside the bottom cap is mirrored in Z
+        let flip = if up { 1.0 } else { -1.0 };
+        mesh.vertices.push(Vertex {
+            position: Vec3::new(0.0, y, 0.0) + direction * radius,
+            tex_coord: Vec2::new(0.5 + 0.5 * direction.x, 0.5 + 0.5 * direction.z * flip),
+            normal,
+        });
+    }
+    for i in 0..segments as u32 {
+        let current = center + 1 + i;
+        let next = center + 1 + (i + 1) % segments as u32;
+        if up {
+            mesh.indices.extend([center, current, next]);
+        } else {
+            mesh.indices.extend([center, next, current]);
+        }
+    }
+}
+
+impl Mesh {
+    /// Latitude/longitude sphere with `segments` columns around Y and `rings` rows from
+    /// pole to pole
+    #[must_use]
+    pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> Self {
+        let (segments, rings) = (segments.max(3), rings.max(2));
+        surface(rings, segments, |row, col| {
+            let u = col as f32 / segments as f32;
+            let v = row as f32 / rings as f32;
+            let theta = v * PI;
+            let normal = around(u * TAU) * theta.sin() + Vec3::new(0.0, theta.cos(), 0.0);
+            Vertex {
+                position: normal * radius,
+                tex_coord: Vec2::new(u, v),
+                normal,
+            }
+        })
+    }
+
+    /// Subdivided icosahedron, the most even triangulation of a sphere
+    ///
+    /// Texture coordinates are equirectangular like [`Self::uv_sphere`]; triangles
+    /// crossing the seam or touching a pole get their own copies of those vertices.
+    #[must_use]
+    pub fn icosphere(radius: f32, subdivisions: u32) -> Self {
+        let (positions, indices) = icosphere_positions(subdivisions);
+        let uv_of = |p: &Vec3| {
+            Vec2::new(
+                (p.x.atan2(p.z) / TAU).rem_euclid(1.0),
+                p.y.clamp(-1.0, 1.0).acos() / PI,
+            )
+        };
+        let is_pole = |p: &Vec3| p.x * p.x + p.z * p.z < 1e-8;
+
+        let mut vertices: Vec<Vertex> = positions
+            .iter()
+            .map(|p| Vertex {
+                position: *p * radius,
+                tex_coord: uv_of(p),
+                normal: *p,
+            })
+            .collect();
+        let mut seam_copies: HashMap<u32, u32> = HashMap::new();
+        let mut mesh_indices = Vec::with_capacity(indices.len());
+
+        for triangle in indices.chunks_exact(3) {
+            let mut corners = [triangle[0], triangle[1], triangle[2]];
+            let poles = corners.map(|i| is_pole(&positions[i as usize]));
+            let us: Vec<f32> = (0..3)
+                .filter(|&k| !poles[k])
+                .map(|k| vertices[corners[k] as usize].tex_coord.x)
+                .collect();
+            let spans_seam = us.iter().fold(0.0_f32, |m, &u| m.max(u))
+                - us.iter().fold(1.0_f32, |m, &u| m.min(u))
+                > 0.5;
+
+            if spans_seam {
+                for k in 0..3 {
+                    let original = corners[k];
+                    if poles[k] || vertices[original as usize].tex_coord.x >= 0.5 {
+                        continue;
+                    }
+                    corners[k] = *seam_copies.entry(original).or_insert_with(|| {
+                        let mut copy = vertices[original as usize];
+                        copy.tex_coord.x += 1.0;
+                        vertices.push(copy);
+                        vertices.len() as u32 - 1
+                    });
+                }
+            }
+
+            // A pole has no longitude; give it the one halfway between its neighbors
+            for k in (0..3).filter(|&k| poles[k]) {
+                let others = [corners[(k + 1) % 3], corners[(k + 2) % 3]];
+                let u = others
+                    .iter()
+                    .map(|&i| vertices[i as usize].tex_coord.x)
+                    .sum::<f32>()
+                    / 2.0;
+                let mut copy = vertices[corners[k] as usize];
+                copy.tex_coord.x = u;
+                vertices.push(copy);
+                corners[k] = vertices.len() as u32 - 1;
+            }
+            mesh_indices.extend(corners);
+        }
+
+        Self {
+            vertices,
+            indices: mesh_indices,
+        }
+    }
+
+    /// Cylinder along Y of the given height, tapering linearly from `bottom_radius`
+    /// to `top_radius`; a zero radius closes that end in a point, and `caps` adds
+    /// flat discs over the open ends
+    #[must_use]
+    pub fn cylinder(
+        bottom_radius: f32,
+        top_radius: f32,
+        height: f32,
+        segments: usize,
+        caps: bool,
+    ) -> Self {
+        let segments = segments.max(3);
+        let half = height / 2.0;
+        let mut mesh = surface(1, segments, |row, col| {
+            let u = col as f32 / segments as f32;
+            let (radius, y) = if row == 0 {
+                (top_radius, half)
+            } else {
+                (bottom_radius, -half)
+            };
+            let direction = around(u * TAU);
+            // The side leans inwards by the taper, so its normal tilts up by the same slope
+            let normal = (direction * height + Vec3::new(0.0, bottom_radius - top_radius, 0.0))
+                .try_normalize()
+                .unwrap_or(direction);
+            Vertex {
+                position: direction * radius + Vec3::new(0.0, y, 0.0),
+                tex_coord: Vec2::new(u, row as f32),
+                normal,
+            }
+        });
+        if caps {
+            if top_radius > 0.0 {
+                add_cap(&mut mesh, top_radius, half, segments, true);
+            }
+            if bottom_radius > 0.0 {
+                add_cap(&mut mesh, bottom_radius, -half, segments, false);
+            }
+        }
+        mesh
+    }
+
+    /// Cone along Y with its apex at `height / 2` and a capped base at `-height / 2`
+    #[must_use]
+    pub fn cone(radius: f32, height: f32, segments: usize) -> Self {
+        Self::cylinder(radius, 0.0, height, segments, true)
+    }
+
+    /// Cylinder of the given `height` along Y closed by two hemispheres, so the total
+    /// length is `height + 2 * radius`; `rings` rows make up each hemisphere
+    #[must_use]
+    pub fn capsule(radius: f32, height: f32, segments: usize, rings: usize) -> Self {
+        let (segments, rings) = (segments.max(3), rings.max(1));
+        let length = PI * radius + height;
+        // Rows 0..=rings cover the top hemisphere, the rest the bottom one
+        surface(2 * rings + 1, segments, |row, col| {
+            let u = col as f32 / segments as f32;
+            let (theta, y, arc) = if row <= rings {
+                let theta = row as f32 / rings as f32 * FRAC_PI_2;
+                (theta, height / 2.0, theta * radius)
+            } else {
+                let theta = FRAC_PI_2 + (row - rings - 1) as f32 / rings as f32 * FRAC_PI_2;
+                (theta, -height / 2.0, theta * radius + height)
+            };
+            let normal = around(u * TAU) * theta.sin() + Vec3::new(0.0, theta.cos(), 0.0);
+            Vertex {
+                position: normal * radius + Vec3::new(0.0, y, 0.0),
+                tex_coord: Vec2::new(u, arc / length),
+                normal,
+            }
+        })
+    }
+
+    /// Ring in the XZ plane; `major_radius` is the distance from the center to the
+    /// middle of the tube and `minor_radius` the tube's radius
+    #[must_use]
+    pub fn torus(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: usize,
+        minor_segments: usize,
+    ) -> Self {
+        let (major_segments, minor_segments) = (major_segments.max(3), minor_segments.max(3));
+        surface(minor_segments, major_segments, |row, col| {
+            let u = col as f32 / major_segments as f32;
+            let v = row as f32 / minor_segments as f32;
+            let outward = around(u * TAU);
+            // The tube starts at its top and runs down the outside first
+            let theta = v * TAU;
+            let normal = outward * theta.sin() + Vec3::new(0.0, theta.cos(), 0.0);
+            Vertex {
+                position: outward * major_radius + normal * minor_radius,
+                tex_coord: Vec2::new(u, v),
+                normal,
+            }
+        })
+    }
+
+    /// [`Self::plane`] split into `x_segments` by `z_segments` cells
+    #[must_use]
+    pub fn grid(width: f32, depth: f32, x_segments: usize, z_segments: usize) -> Self {
+        let (x_segments, z_segments) = (x_segments.max(1), z_segments.max(1));
+        surface(z_segments, x_segments, |row, col| {
+            let u = col as f32 / x_segments as f32;
+            let v = row as f32 / z_segments as f32;
+            Vertex {
+                position: Vec3::new((u - 0.5) * width, 0.0, (v - 0.5) * depth),
+                tex_coord: Vec2::new(u, v),
+                normal: Vec3::new(0.0, 1.0, 0.0),
+            }
+        })
+    }
+
+    /// Cube with `segments` by `segments` cells per face inflated into a sphere
+    ///
+    /// Cells stay close to square everywhere, and each face keeps its own `[0, 1]`
+    /// texture coordinates like [`Self::cube`].
+    #[must_use]
+    pub fn cube_sphere(radius: f32, segments: usize) -> Self {
+        let segments = segments.max(1);
+        // Outward axis and right axis of each face seen from outside; down is right x out
+        let faces = [
+            (Vec3::new(0.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 0.0)),
+            (Vec3::new(0.0, 0.0, -1.0), Vec3::new(-1.0, 0.0, 0.0)),
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+            (Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+            (Vec3::new(0.0, -1.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+        ];
+
+        let mut mesh = Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+        for (out, right) in faces {
+            let down = right.cross(&out);
+            let face = surface(segments, segments, |row, col| {
+                let u = col as f32 / segments as f32;
+                let v = row as f32 / segments as f32;
+                let p = out + right * (2.0 * u - 1.0) + down * (2.0 * v - 1.0);
+                // Maps the cube onto the sphere with less bunching than normalizing
+                let (x2, y2, z2) = (p.x * p.x, p.y * p.y, p.z * p.z);
+                let normal = Vec3::new(
+                    p.x * (1.0 - y2 / 2.0 - z2 / 2.0 + y2 * z2 / 3.0).sqrt(),
+                    p.y * (1.0 - z2 / 2.0 - x2 / 2.0 + z2 * x2 / 3.0).sqrt(),
+                    p.z * (1.0 - x2 / 2.0 - y2 / 2.0 + x2 * y2 / 3.0).sqrt(),
+                );
+                Vertex {
+                    position: normal * radius,
+                    tex_coord: Vec2::new(u, v),
+                    normal,
+                }
+            });
+            let offset = mesh.vertices.len() as u32;
+            mesh.vertices.extend(face.vertices);
+            mesh.indices.extend(face.indices.iter().map(|i| offset + i));
+        }
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::mesh_ops;
+
+    /// Checks indices, winding against the normals and unit normals
+    fn assert_well_formed(mesh: &Mesh) {
+        assert!(mesh_ops::validate(mesh).is_empty());
+        for vertex in &mesh.vertices {
+            assert!((vertex.normal.length() - 1.0).abs() < 1e-4);
+            assert!((0.0..=1.0).contains(&vertex.tex_coord.y));
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| mesh.vertices[triangle[k] as usize]);
+            let face = (b.position - a.position).cross(&(c.position - a.position));
+            let normal = a.normal + b.normal + c.normal;
+            assert!(
+                face.dot(&normal) > 0.0,
+                "triangle {triangle:?} winds inwards"
+            );
+        }
+    }
+
+    /// Checks that no triangle's texture coordinates jump across a seam
+    fn assert_no_uv_wrap(mesh: &Mesh) {
+        for triangle in mesh.indices.chunks_exact(3) {
+            let us = [0, 1, 2].map(|k| mesh.vertices[triangle[k] as usize].tex_coord.x);
+            let spread = us.iter().fold(f32::MIN, |m, &u| m.max(u))
+                - us.iter().fold(f32::MAX, |m, &u| m.min(u));
+            assert!(spread < 0.5, "triangle {triangle:?} wraps around in U");
+        }
+    }
+
+    fn assert_on_sphere(mesh: &Mesh, radius: f32) {
+        for vertex in &mesh.vertices {
+            assert!((vertex.position.length() - radius).abs() < 1e-4);
+            assert!(vertex.normal.dot(&vertex.position.normalize()) > 0.9999);
+        }
+    }
+
+    #[test]
+    fn test_uv_sphere() {
+        let sphere = Mesh::uv_sphere(2.0, 16, 8);
+        assert_eq!(sphere.vertices.len(), 17 * 9);
+        // The pole rows have one triangle per column instead of two
+        assert_eq!(sphere.indices.len(), (16 * 8 * 2 - 2 * 16) * 3);
+        assert_well_formed(&sphere);
+        assert_on_sphere(&sphere, 2.0);
+        assert_no_uv_wrap(&sphere);
+
+        let area = mesh_ops::surface_area(&sphere);
+        assert!((area - 16.0 * PI).abs() / (16.0 * PI) < 0.05);
+    }
+
+    #[test]
+    fn test_icosphere_splits_seam() {
+        let sphere = Mesh::icosphere(1.5, 3);
+        let (positions, indices) = icosphere_positions(3);
+        assert_eq!(sphere.indices.len(), indices.len());
+        assert!(sphere.vertices.len() > positions.len());
+        assert_well_formed(&sphere);
+        assert_on_sphere(&sphere, 1.5);
+        assert_no_uv_wrap(&sphere);
+    }
+
+    #[test]
+    fn test_cylinder_with_taper_and_caps() {
+        let cylinder = Mesh::cylinder(1.0, 1.0, 2.0, 12, true);
+        // Side with a seam column plus two caps with a center each
+        assert_eq!(cylinder.vertices.len(), 13 * 2 + 2 * 13);
+        assert_eq!(cylinder.indices.len(), (12 * 2 + 2 * 12) * 3);
+        assert_well_formed(&cylinder);
+        let bounds = cylinder.bounds().unwrap();
+        assert!((bounds.max.y - 1.0).abs() < 1e-6 && (bounds.min.y + 1.0).abs() < 1e-6);
+        let area = mesh_ops::surface_area(&cylinder);
+        let analytic = 2.0 * PI * 2.0 + 2.0 * PI;
+        assert!((area - analytic).abs() / analytic < 0.05);
+
+        // Tapering tilts the side normals up
+        let tapered = Mesh::cylinder(1.0, 0.5, 2.0, 12, false);
+        assert_well_formed(&tapered);
+        assert!(tapered.vertices.iter().all(|v| v.normal.y > 0.2));
+    }
+
+    #[test]
+    fn test_cone_collapses_apex() {
+        let cone = Mesh::cone(1.0, 3.0, 8);
+        assert_well_formed(&cone);
+        // One side triangle per segment plus the base cap
+        assert_eq!(cone.indices.len(), (8 + 8) * 3);
+        let apex = cone.bounds().unwrap().max.y;
+        assert!((apex - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_capsule() {
+        let capsule = Mesh::capsule(0.5, 2.0, 12, 4);
+        assert_well_formed(&capsule);
+        assert_no_uv_wrap(&capsule);
+        let bounds = capsule.bounds().unwrap();
+        assert!((bounds.max.y - 1.5).abs() < 1e-5 && (bounds.min.y + 1.5).abs() < 1e-5);
+        assert!((bounds.max.x - 0.5).abs() < 1e-5);
+        let last = capsule.vertices.last().unwrap();
+        assert!((last.tex_coord.y - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_torus() {
+        let torus = Mesh::torus(2.0, 0.5, 24, 12);
+        assert_eq!(torus.vertices.len(), 25 * 13);
+        assert_eq!(torus.indices.len(), 24 * 12 * 6);
+        assert_well_formed(&torus);
+        for vertex in &torus.vertices {
+            // Every vertex is minor_radius away from the tube's center circle
+            let flat = Vec3::new(vertex.position.x, 0.0, vertex.position.z);
+            let center = flat.normalize() * 2.0;
+            assert!(((vertex.position - center).length() - 0.5).abs() < 1e-4);
+        }
+        let area = mesh_ops::surface_area(&torus);
+        let analytic = 4.0 * PI * PI * 2.0 * 0.5;
+        assert!((area - analytic).abs() / analytic < 0.05);
+    }
+
+    #[test]
+    fn test_grid_matches_plane() {
+        let grid = Mesh::grid(4.0, 2.0, 4, 3);
+        assert_eq!(grid.vertices.len(), 5 * 4);
+        assert_eq!(grid.indices.len(), 4 * 3 * 6);
+        assert_well_formed(&grid);
+        assert!((mesh_ops::surface_area(&grid) - 8.0).abs() < 1e-4);
+
+        let plane = Mesh::plane(4.0, 2.0);
+        let single = Mesh::grid(4.0, 2.0, 1, 1);
+        for vertex in &plane.vertices {
+            assert!(single
+                .vertices
+                .iter()
+                .any(|v| v.position == vertex.position && v.tex_coord == vertex.tex_coord));
+        }
+    }
+
+    #[test]
+    fn test_cube_sphere() {
+        let sphere = Mesh::cube_sphere(3.0, 6);
+        assert_eq!(sphere.vertices.len(), 6 * 7 * 7);
+        assert_eq!(sphere.indices.len(), 6 * 6 * 6 * 6);
+        assert_well_formed(&sphere);
+        assert_on_sphere(&sphere, 3.0);
+
+        // Neighboring faces meet exactly along their shared edges
+        let distinct: std::collections::HashSet<[i32; 3]> = sphere
+            .vertices
+            .iter()
+            .map(|v| [v.position.x, v.position.y, v.position.z].map(|c| (c * 1e4).round() as i32))
+            .collect();
+        assert_eq!(distinct.len(), 6 * 6 * 6 + 2);
+    }
+
+    #[test]
+    fn test_segment_counts_are_clamped() {
+        assert_well_formed(&Mesh::uv_sphere(1.0, 0, 0));
+        assert_well_formed(&Mesh::cylinder(1.0, 1.0, 1.0, 1, true));
+        assert_well_formed(&Mesh::torus(1.0, 0.25, 0, 0));
+        assert_well_formed(&Mesh::grid(1.0, 1.0, 0, 0));
+        assert_well_formed(&Mesh::cube_sphere(1.0, 0));
+    }
+}
@@ -0,0 +1,687 @@
+//! Saving and loading scenes as RON
+//!
+//! A scene file holds a format version, the light and the node hierarchy. Meshes are
+//! stored as the [`MeshSource`] recipe they were built from when there is one, so a
+//! planet is saved as its radius and subdivision level rather than thousands of
+//! vertices; other meshes are written out inline. Node textures are embedded as PNG
+//! in a scene-wide list that nodes refer to by index, so a texture shared between
+//! nodes is stored once. Transforms, light settings, textures and children can be
+//! left out and take their defaults, which keeps hand-written files short:
+//!
+//! ```ron
+//! (
+//!     version: 1,
+//!     nodes: [
+//!         (
+//!             name: "Planet",
+//!             mesh: Some(SphericalWorld(radius: 25.0, subdivision_level: 4)),
+//!         ),
+//!     ],
+//! )
+//! ```
+
+use crate::core::SphericalWorld;
+use crate::math::{Quat, Transform, Vec2, Vec3};
+use crate::scene::{Light, Mesh, Node, NodeId, Scene, Vertex};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Version written to new scene files; files with a newer version are rejected.
+/// Version 2 added embedded node textures.
+pub const SCENE_FORMAT_VERSION: u32 = 2;
+
+/// Highest icosphere subdivision level accepted from a file; level 8 is already
+/// 655 362 vertices and every further level quadruples the mesh
+const MAX_SUBDIVISION_LEVEL: u32 = 8;
+
+/// How a node's mesh is produced when a scene is loaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MeshSource {
+    /// [`Mesh::cube`]
+    Cube,
+    /// [`Mesh::plane`]
+    Plane { width: f32, depth: f32 },
+    /// [`Mesh::grass_blade`]
+    GrassBlade,
+    /// Icosphere generated by [`SphericalWorld::generate_mesh`]
+    SphericalWorld { radius: f32, subdivision_level: u32 },
+    /// Explicit vertex data; every attribute list has one entry per vertex
+    Inline {
+        positions: Vec<[f32; 3]>,
+        normals: Vec<[f32; 3]>,
+        tex_coords: Vec<[f32; 2]>,
+        indices: Vec<u32>,
+    },
+}
+
+impl MeshSource {
+    /// Captures `mesh` as [`MeshSource::Inline`] data
+    #[must_use]
+    pub fn inline(mesh: &Mesh) -> Self {
+        Self::Inline {
+            positions: mesh
+                .vertices
+                .iter()
+                .map(|v| to_array3(&v.position))
+                .collect(),
+            normals: mesh.vertices.iter().map(|v| to_array3(&v.normal)).collect(),
+            tex_coords: mesh
+                .vertices
+                .iter()
+                .map(|v| [v.tex_coord.x, v.tex_coord.y])
+                .collect(),
+            indices: mesh.indices.clone(),
+        }
+    }
+
+    /// Builds the mesh this source describes
+    ///
+    /// # Errors
+    /// Returns an error if the parameters are out of range or inline data is
+    /// inconsistent
+    pub fn build(&self) -> Result<Mesh, String> {
+        match self {
+            Self::Cube => Ok(Mesh::cube()),
+            Self::Plane { width, depth } => {
+                if !(width.is_finite() && depth.is_finite() && *width > 0.0 && *depth > 0.0) {
+                    return Err(format!(
+                        "Plane size must be finite and positive, got {width} x {depth}"
+                    ));
+                }
+                Ok(Mesh::plane(*width, *depth))
+            }
+            Self::GrassBlade => Ok(Mesh::grass_blade()),
+            Self::SphericalWorld {
+                radius,
+                subdivision_level,
+            } => {
+                if !radius.is_finite() || *radius <= 0.0 {
+                    return Err(format!(
+                        "Spherical world radius must be finite and positive, got {radius}"
+                    ));
+                }
+                if *subdivision_level > MAX_SUBDIVISION_LEVEL {
+                    return Err(format!(
+                        "Subdivision level {subdivision_level} exceeds the maximum of {MAX_SUBDIVISION_LEVEL}"
+                    ));
+                }
+                Ok(SphericalWorld::new(*radius, *subdivision_level).generate_mesh())
+            }
+            Self::Inline {
+                positions,
+                normals,
+                tex_coords,
+                indices,
+            } => {
+                if normals.len() != positions.len() || tex_coords.len() != positions.len() {
+                    return Err(format!(
+                        "Inline mesh has {} positions, {} normals and {} texture coordinates",
+                        positions.len(),
+                        normals.len(),
+                        tex_coords.len()
+                    ));
+                }
+                if let Some(index) = indices.iter().find(|&&i| i as usize >= positions.len()) {
+                    return Err(format!(
+                        "Inline mesh index {index} is out of range for {} vertices",
+                        positions.len()
+                    ));
+                }
+
+                let vertices = positions
+                    .iter()
+                    .zip(normals)
+                    .zip(tex_coords)
+                    .map(|((position, normal), tex_coord)| Vertex {
+                        position: from_array3(*position),
+                        tex_coord: Vec2::new(tex_coord[0], tex_coord[1]),
+                        normal: from_array3(*normal),
+                    })
+                    .collect();
+                Ok(Mesh {
+                    vertices,
+                    indices: indices.clone(),
+                })
+            }
+        }
+    }
+}
+
+impl Scene {
+    /// Serializes the scene to RON text
+    ///
+    /// # Errors
+    /// Returns an error if serialization or encoding a texture fails
+    pub fn to_ron_string(&self) -> Result<String, String> {
+        let mut textures = TextureTable::default();
+        let nodes = self
+            .roots()
+            .map(|root| self.node_data(root, &mut textures))
+            .collect::<Result<_, _>>()?;
+        let file = SceneFile {
+            version: SCENE_FORMAT_VERSION,
+            light: LightData::from(&self.light),
+            textures: textures.png,
+            nodes,
+        };
+        ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::new())
+            .map_err(|e| format!("Failed to serialize scene: {e}"))
+    }
+
+    /// Parses a scene from RON text
+    ///
+    /// # Errors
+    /// Returns an error if the text is not a valid scene, its version is newer than
+    /// [`SCENE_FORMAT_VERSION`], one of its meshes cannot be built or one of its
+    /// textures cannot be decoded
+    pub fn from_ron_str(text: &str) -> Result<Self, String> {
+        let header: VersionHeader =
+            ron::from_str(text).map_err(|e| format!("Failed to parse scene header: {e}"))?;
+        if header.version == 0 || header.version > SCENE_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported scene format version {} (supported: 1 to {SCENE_FORMAT_VERSION})",
+                header.version
+            ));
+        }
+
+        let file: SceneFile =
+            ron::from_str(text).map_err(|e| format!("Failed to parse scene: {e}"))?;
+        let textures = file
+            .textures
+            .iter()
+            .enumerate()
+            .map(|(index, png)| {
+                image::load_from_memory_with_format(png, image::ImageFormat::Png)
+                    .map(|image| Arc::new(image.to_rgba8()))
+                    .map_err(|e| format!("Failed to decode texture {index}: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut scene = Self::new();
+        scene.light = file.light.into();
+        for node in file.nodes {
+            scene.insert_node_data(None, node, &textures)?;
+        }
+        Ok(scene)
+    }
+
+    /// Writes the scene to a RON file
+    ///
+    /// # Errors
+    /// Returns an error if serialization or writing the file fails
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = self.to_ron_string()?;
+        std::fs::write(path, text)
+            .map_err(|e| format!("Failed to write scene {}: {e}", path.display()))
+    }
+
+    /// Reads a scene from a RON file
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not a valid scene
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read scene {}: {e}", path.display()))?;
+        Self::from_ron_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn node_data(&self, id: NodeId, textures: &mut TextureTable) -> Result<NodeData, String> {
+        let node = self.get(id);
+        let texture = match node.and_then(Node::texture) {
+            Some(texture) => Some(textures.index_of(texture)?),
+            None => None,
+        };
+        Ok(NodeData {
+            name: node.map(|n| n.name.clone()).unwrap_or_default(),
+            transform: node
+                .map(|n| TransformData::from(n.transform()))
+                .unwrap_or_default(),
+            mesh: node.and_then(|n| {
+                n.mesh_source()
+                    .cloned()
+                    .or_else(|| n.mesh().map(MeshSource::inline))
+            }),
+            texture,
+            children: self
+                .children(id)
+                .map(|child| self.node_data(child, textures))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn insert_node_data(
+        &mut self,
+        parent: Option<NodeId>,
+        data: NodeData,
+        textures: &[Arc<RgbaImage>],
+    ) -> Result<(), String> {
+        let transform = Transform::try_from(data.transform)
+            .map_err(|e| format!("Node \"{}\": {e}", data.name))?;
+        let mut node = match data.mesh {
+            Some(source) => Node::from_source(data.name, source)?,
+            None => Node::new(data.name),
+        };
+        node.set_transform(transform);
+        if let Some(index) = data.texture {
+            let texture = textures.get(index).ok_or_else(|| {
+                format!(
+                    "Texture index {index} is out of range for {} textures",
+                    textures.len()
+                )
+            })?;
+            node.set_texture(Some(Arc::clone(texture)));
+        }
+
+        let id = match parent {
+            Some(parent) => self.add_child(parent, node)?,
+            None => self.add_node(node),
+        };
+        for child in data.children {
+            self.insert_node_data(Some(id), child, textures)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    version: u32,
+    #[serde(default)]
+    light: LightData,
+    /// PNG-encoded textures referenced by [`NodeData::texture`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    textures: Vec<Vec<u8>>,
+    #[serde(default)]
+    nodes: Vec<NodeData>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NodeData {
+    name: String,
+    #[serde(default)]
+    transform: TransformData,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mesh: Option<MeshSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    texture: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<NodeData>,
+}
+
+/// Textures collected while saving, each encoded once however many nodes share it
+#[derive(Default)]
+struct TextureTable {
+    png: Vec<Vec<u8>>,
+    indices: HashMap<*const RgbaImage, usize>,
+}
+
+impl TextureTable {
+    fn index_of(&mut self, texture: &Arc<RgbaImage>) -> Result<usize, String> {
+        if let Some(&index) = self.indices.get(&Arc::as_ptr(texture)) {
+            return Ok(index);
+        }
+        let mut png = Vec::new();
+        texture
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode texture: {e}"))?;
+        let index = self.png.len();
+        self.png.push(png);
+        self.indices.insert(Arc::as_ptr(texture), index);
+        Ok(index)
+    }
+}
+
+/// Rotation is a quaternion in `(x, y, z, w)` order
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TransformData {
+    position: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+}
+
+impl Default for TransformData {
+    fn default() -> Self {
+        Self::from(&Transform::identity())
+    }
+}
+
+impl From<&Transform> for TransformData {
+    fn from(transform: &Transform) -> Self {
+        let r = transform.rotation;
+        Self {
+            position: to_array3(&transform.position),
+            rotation: [r.x, r.y, r.z, r.w],
+            scale: to_array3(&transform.scale),
+        }
+    }
+}
+
+/// Normalizes the rotation, which may have been typed in by hand
+impl TryFrom<TransformData> for Transform {
+    type Error = String;
+
+    fn try_from(data: TransformData) -> Result<Self, String> {
+        let [x, y, z, w] = data.rotation;
+        let rotation = Quat::new(x, y, z, w);
+        let length = rotation.length();
+        if !length.is_finite() || length == 0.0 {
+            return Err(format!(
+                "Rotation must be a finite, non-zero quaternion, got {:?}",
+                data.rotation
+            ));
+        }
+        // Unit rotations are kept as written, so saved scenes load back bit for bit
+        let rotation = if (length - 1.0).abs() > 1e-6 {
+            rotation.normalize()
+        } else {
+            rotation
+        };
+        Ok(Self::new(
+            from_array3(data.position),
+            rotation,
+            from_array3(data.scale),
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LightData {
+    position: [f32; 3],
+    color: [f32; 3],
+    ambient: f32,
+    diffuse: f32,
+    specular: f32,
+}
+
+impl Default for LightData {
+    fn default() -> Self {
+        Self::from(&Scene::new().light)
+    }
+}
+
+impl From<&Light> for LightData {
+    fn from(light: &Light) -> Self {
+        Self {
+            position: to_array3(&light.position),
+            color: to_array3(&light.color),
+            ambient: light.ambient,
+            diffuse: light.diffuse,
+            specular: light.specular,
+        }
+    }
+}
+
+impl From<LightData> for Light {
+    fn from(data: LightData) -> Self {
+        Self {
+            position: from_array3(data.position),
+            color: from_array3(data.color),
+            ambient: data.ambient,
+            diffuse: data.diffuse,
+            specular: data.specular,
+        }
+    }
+}
+
+fn to_array3(v: &Vec3) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+fn from_array3([x, y, z]: [f32; 3]) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Mat4;
+
+    fn sample_scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.light.position = Vec3::new(10.0, 45.0, 10.0);
+        scene.light.specular = 0.25;
+
+        let planet = scene.add_node(
+            Node::from_source(
+                "Planet".to_string(),
+                MeshSource::SphericalWorld {
+                    radius: 25.0,
+                    subdivision_level: 2,
+                },
+            )
+            .unwrap(),
+        );
+        let mut marker = Node::with_mesh("marker".to_string(), Mesh::plane(2.0, 3.0));
+        marker.set_position(Vec3::new(0.0, 25.5, 0.0));
+        marker.transform_mut().rotation = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 0.7);
+        marker.transform_mut().scale = Vec3::new(1.0, 2.0, 0.5);
+        let marker = scene.add_child(planet, marker).unwrap();
+        scene
+            .add_child(marker, Node::new("anchor".to_string()))
+            .unwrap();
+        scene.add_node(Node::from_source("crate".to_string(), MeshSource::Cube).unwrap());
+        scene
+    }
+
+    fn world_transforms(scene: &Scene) -> Vec<(String, Mat4)> {
+        let mut transforms = Vec::new();
+        scene.traverse(|_, node, world| transforms.push((node.name.clone(), *world)));
+        transforms
+    }
+
+    #[test]
+    fn test_round_trip_preserves_scene() {
+        let scene = sample_scene();
+        let text = scene.to_ron_string().unwrap();
+        let loaded = Scene::from_ron_str(&text).unwrap();
+
+        assert_eq!(world_transforms(&loaded), world_transforms(&scene));
+        assert_eq!(loaded.len(), scene.len());
+        assert_eq!(loaded.light.position, scene.light.position);
+        assert_eq!(loaded.light.specular, 0.25);
+        assert_eq!(loaded.to_ron_string().unwrap(), text);
+
+        for ((_, original), (_, restored)) in scene.iter().zip(loaded.iter()) {
+            if let Some(source) = original.mesh_source() {
+                assert_eq!(Some(source), restored.mesh_source());
+            }
+            let (Some(a), Some(b)) = (original.mesh(), restored.mesh()) else {
+                assert!(original.mesh().is_none() && restored.mesh().is_none());
+                continue;
+            };
+            assert_eq!(a.indices, b.indices);
+            assert_eq!(a.vertices.len(), b.vertices.len());
+            for (u, v) in a.vertices.iter().zip(&b.vertices) {
+                assert_eq!(u.position, v.position);
+                assert_eq!(u.normal, v.normal);
+                assert_eq!(u.tex_coord, v.tex_coord);
+            }
+        }
+    }
+
+    #[test]
+    fn test_procedural_meshes_are_saved_as_recipes() {
+        let text = sample_scene().to_ron_string().unwrap();
+        assert!(text.contains("SphericalWorld("));
+        assert!(text.contains("subdivision_level: 2"));
+        assert!(text.contains("Cube"));
+
+        // A mesh without a recipe is written out inline
+        assert!(text.contains("Inline("));
+        assert_eq!(text.matches("Inline(").count(), 1);
+    }
+
+    #[test]
+    fn test_textures_are_embedded_once() {
+        let texture = Arc::new(RgbaImage::from_fn(3, 2, |x, y| {
+            image::Rgba([x as u8 * 80, y as u8 * 200, 7, 255])
+        }));
+        let mut scene = Scene::new();
+        for name in ["left", "right"] {
+            let mut node = Node::with_mesh(name.to_string(), Mesh::plane(1.0, 1.0));
+            node.set_texture(Some(Arc::clone(&texture)));
+            scene.add_node(node);
+        }
+        scene.add_node(Node::from_source("crate".to_string(), MeshSource::Cube).unwrap());
+
+        let text = scene.to_ron_string().unwrap();
+        assert!(text.contains("texture: Some(0)"));
+        assert!(!text.contains("texture: Some(1)"));
+
+        let loaded = Scene::from_ron_str(&text).unwrap();
+        let left = loaded.find_by_name("left").unwrap();
+        let right = loaded.find_by_name("right").unwrap();
+        let crate_node = loaded.find_by_name("crate").unwrap();
+        let left_texture = loaded.get(left).unwrap().texture().unwrap();
+        assert_eq!(**left_texture, *texture);
+        assert!(Arc::ptr_eq(
+            left_texture,
+            loaded.get(right).unwrap().texture().unwrap()
+        ));
+        assert!(loaded.get(crate_node).unwrap().texture().is_none());
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let path = std::env::temp_dir().join(format!("scene-{}.ron", std::process::id()));
+        let scene = sample_scene();
+        scene.save(&path).unwrap();
+        let loaded = Scene::load(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(world_transforms(&loaded.unwrap()), world_transforms(&scene));
+        assert!(Scene::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_minimal_file_uses_defaults() {
+        let scene = Scene::from_ron_str(
+            r#"(
+                version: 1,
+                nodes: [
+                    (name: "Planet", mesh: Some(SphericalWorld(radius: 25.0, subdivision_level: 1)), children: [
+                        (name: "pole", transform: (position: (0.0, 25.0, 0.0))),
+                    ]),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+        let pole = scene.find_by_path("Planet/pole").unwrap();
+        let world = scene.world_transform(pole).unwrap();
+        assert_eq!(
+            world.transform_point(&Vec3::zero()),
+            Vec3::new(0.0, 25.0, 0.0)
+        );
+        assert_eq!(
+            scene.get(pole).unwrap().transform().scale,
+            Vec3::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(scene.light.position, Scene::new().light.position);
+        assert!(scene.get(pole).unwrap().mesh().is_none());
+    }
+
+    #[test]
+    fn test_rotations_are_normalized_on_load() {
+        let scene = Scene::from_ron_str(
+            r#"(
+                version: 2,
+                nodes: [(name: "turned", transform: (rotation: (0.0, 0.7, 0.0, 0.7)))],
+            )"#,
+        )
+        .unwrap();
+        let turned = scene.find_by_name("turned").unwrap();
+        let rotation = scene.get(turned).unwrap().transform().rotation;
+        assert!((rotation.length() - 1.0).abs() < 1e-6);
+        let expected =
+            Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_2);
+        assert!(rotation.dot(&expected) > 1.0 - 1e-6);
+
+        // Saved back as the unit rotation, which loads unchanged
+        let text = scene.to_ron_string().unwrap();
+        let loaded = Scene::from_ron_str(&text).unwrap();
+        assert_eq!(world_transforms(&loaded), world_transforms(&scene));
+        assert_eq!(loaded.to_ron_string().unwrap(), text);
+
+        for rotation in [
+            "(0.0, 0.0, 0.0, 0.0)",
+            "(0.0, NaN, 0.0, 1.0)",
+            "(inf, 0.0, 0.0, 1.0)",
+        ] {
+            let text = format!(
+                r#"(version: 2, nodes: [(name: "bad", transform: (rotation: {rotation}))])"#
+            );
+            assert!(matches!(
+                Scene::from_ron_str(&text),
+                Err(e) if e.contains("\"bad\"") && e.contains("Rotation")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsupported_versions() {
+        let newer = format!("(version: {}, nodes: [])", SCENE_FORMAT_VERSION + 1);
+        assert!(matches!(
+            Scene::from_ron_str(&newer),
+            Err(e) if e.contains("Unsupported scene format version")
+        ));
+        assert!(Scene::from_ron_str("(version: 0)").is_err());
+        assert!(Scene::from_ron_str("(nodes: [])").is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_scenes() {
+        // Typos are reported instead of silently ignored
+        assert!(
+            Scene::from_ron_str(r#"(version: 1, nodes: [(name: "a", mseh: Some(Cube))])"#).is_err()
+        );
+        assert!(Scene::from_ron_str(
+            r#"(version: 1, nodes: [(name: "a", mesh: Some(SphericalWorld(radius: 1.0, subdivision_level: 12)))])"#
+        )
+        .is_err());
+        for (width, depth) in [
+            (0.0, 1.0),
+            (1.0, -2.0),
+            (f32::NAN, 1.0),
+            (1.0, f32::INFINITY),
+        ] {
+            assert!(MeshSource::Plane { width, depth }.build().is_err());
+        }
+        assert!(Scene::from_ron_str(
+            r#"(version: 1, nodes: [(name: "a", mesh: Some(Plane(width: 0.0, depth: 1.0)))])"#
+        )
+        .is_err());
+        for radius in [0.0, -1.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let world = MeshSource::SphericalWorld {
+                radius,
+                subdivision_level: 1,
+            };
+            assert!(world.build().is_err());
+        }
+        assert!(
+            Scene::from_ron_str(r#"(version: 2, nodes: [(name: "a", texture: Some(0))])"#).is_err()
+        );
+        assert!(Scene::from_ron_str(
+            r#"(version: 1, nodes: [(name: "a", mesh: Some(Inline(
+                positions: [(0.0, 0.0, 0.0)], normals: [(0.0, 1.0, 0.0)], tex_coords: [(0.0, 0.0)],
+                indices: [0, 0, 1],
+            )))])"#
+        )
+        .is_err());
+    }
+}
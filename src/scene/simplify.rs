@@ -0,0 +1,838 @@
+//! Quadric error mesh simplification and LOD chains
+//!
+//! [`Mesh::simplified`] repeatedly collapses the edge whose removal changes the
+//! surface least, measured with Garland–Heckbert quadrics: every vertex accumulates
+//! the planes of the triangles around it, and moving it onto a neighbor costs the
+//! squared distance of the neighbor to those planes. Collapses move a vertex onto an
+//! existing neighbor, so surviving vertices keep their texture coordinates and
+//! normals unchanged.
+//!
+//! Open borders only collapse along themselves. Vertices duplicated along a UV seam or
+//! hard edge collapse in pairs along the seam, both copies onto the next seam vertex,
+//! so the seam stays closed and each side keeps its own attributes. Border and seam
+//! edges carry extra quadrics for planes standing on them, so silhouettes such as a
+//! grass blade's outline and the creases of hard-edged meshes survive. Vertices
+//! where more than two copies meet, such as a cube's corners, never move.
+
+use crate::math::Vec3;
+use crate::scene::Mesh;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// When to stop simplifying: at `triangles` triangles, or before the first collapse
+/// whose error would exceed `max_error`, whichever comes first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimplifyTarget {
+    pub triangles: usize,
+    /// Largest allowed root-mean-square distance, in mesh units, between a removed
+    /// vertex's new position and the original surface around it
+    pub max_error: f32,
+}
+
+impl SimplifyTarget {
+    /// Simplify down to `count` triangles regardless of the error
+    #[must_use]
+    pub const fn triangles(count: usize) -> Self {
+        Self {
+            triangles: count,
+            max_error: f32::INFINITY,
+        }
+    }
+
+    /// Simplify as far as possible while staying within `max_error`
+    #[must_use]
+    pub const fn error(max_error: f32) -> Self {
+        Self {
+            triangles: 0,
+            max_error,
+        }
+    }
+}
+
+/// A simplified mesh and the largest error of the collapses that produced it
+#[derive(Clone)]
+pub struct Simplified {
+    pub mesh: Mesh,
+    pub error: f32,
+}
+
+/// Progressively simplified versions of a mesh, from the original at level 0 to the
+/// coarsest
+#[derive(Clone)]
+pub struct LodChain {
+    pub levels: Vec<Simplified>,
+}
+
+impl LodChain {
+    /// Builds up to `level_count` levels, each aiming for `ratio` times the triangles
+    /// of the previous one; stops early once a level no longer gets smaller
+    #[must_use]
+    pub fn generate(mesh: &Mesh, level_count: usize, ratio: f32) -> Self {
+        let mut levels = vec![Simplified {
+            mesh: mesh.clone(),
+            error: 0.0,
+        }];
+        let ratio = ratio.clamp(0.0, 1.0);
+        while levels.len() < level_count {
+            let Some(previous) = levels.last() else {
+                break;
+            };
+            let triangles = previous.mesh.indices.len() / 3;
+            let target = (triangles as f32 * ratio) as usize;
+            let mut next = previous.mesh.simplified(SimplifyTarget::triangles(target));
+            if next.mesh.indices.len() >= previous.mesh.indices.len() {
+                break;
+            }
+            // Errors accumulate because each level starts from the previous one
+            next.error += previous.error;
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// Coarsest level whose error is at most `max_error`; level 0 always qualifies
+    #[must_use]
+    pub fn level_for_error(&self, max_error: f32) -> &Simplified {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.error <= max_error)
+            .unwrap_or(&self.levels[0])
+    }
+}
+
+/// Symmetric 4x4 plane quadric `sum(w * (n·p + d)^2)` with its total weight
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    /// Upper triangle of the matrix: xx, xy, xz, xd, yy, yz, yd, zz, zd, dd
+    m: [f64; 10],
+    weight: f64,
+}
+
+impl Quadric {
+    fn from_plane(normal: Vec3, point: Vec3, weight: f64) -> Self {
+        let [a, b, c] = [normal.x, normal.y, normal.z].map(f64::from);
+        let d = -(a * f64::from(point.x) + b * f64::from(point.y) + c * f64::from(point.z));
+        let m = [
+            a * a,
+            a * b,
+            a * c,
+            a * d,
+            b * b,
+            b * c,
+            b * d,
+            c * c,
+            c * d,
+            d * d,
+        ]
+        .map(|v| v * weight);
+        Self { m, weight }
+    }
+
+    fn add(&mut self, other: &Self) {
+        for (a, b) in self.m.iter_mut().zip(other.m) {
+            *a += b;
+        }
+        self.weight += other.weight;
+    }
+
+    /// Weighted mean squared distance of `p` to the quadric's planes
+    fn error(&self, p: Vec3) -> f64 {
+        let [x, y, z] = [p.x, p.y, p.z].map(f64::from);
+        let m = &self.m;
+        let sum = m[0] * x * x
+            + 2.0 * m[1] * x * y
+            + 2.0 * m[2] * x * z
+            + 2.0 * m[3] * x
+            + m[4] * y * y
+            + 2.0 * m[5] * y * z
+            + 2.0 * m[6] * y
+            + m[7] * z * z
+            + 2.0 * m[8] * z
+            + m[9];
+        if self.weight > 0.0 {
+            sum.max(0.0) / self.weight
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Collapse of vertex `from` onto vertex `to`, queued by increasing cost
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the max-heap pops the cheapest collapse first
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.from.cmp(&self.from))
+            .then_with(|| other.to.cmp(&self.to))
+    }
+}
+
+/// How a vertex may move, decided once from the original mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VertexKind {
+    /// Interior vertex without copies; collapses onto any neighbor
+    Manifold,
+    /// Vertex on an open border; collapses along the border
+    Border,
+    /// One of two copies along a seam; collapses along the seam together with the
+    /// other copy
+    Seam,
+    /// Never moves
+    Locked,
+}
+
+struct Simplifier<'a> {
+    mesh: &'a Mesh,
+    triangles: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    /// Alive triangles around each vertex
+    adjacent: Vec<Vec<usize>>,
+    /// Vertices sharing a position are one group, named after its first vertex
+    group: Vec<u32>,
+    /// Vertices used by triangles in each group, indexed by group
+    members: Vec<Vec<u32>>,
+    kind: Vec<VertexKind>,
+    quadrics: Vec<Quadric>,
+    version: Vec<u32>,
+    removed: Vec<bool>,
+    heap: BinaryHeap<Collapse>,
+}
+
+/// Group id per vertex, shared by vertices closer than a millionth of the mesh size;
+/// generators that wrap around (`Mesh::uv_sphere`'s seam) land a rounding error
+/// apart rather than exactly on top of each other
+fn position_groups(mesh: &Mesh) -> Vec<u32> {
+    let size = mesh
+        .bounds()
+        .map_or(0.0, |bounds| bounds.min.distance(&bounds.max));
+    let epsilon = (size * 1e-6).max(f32::MIN_POSITIVE);
+    let cell_of = |p: &Vec3| [p.x, p.y, p.z].map(|c| (c / epsilon).floor() as i64);
+
+    let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+    let mut group = Vec::with_capacity(mesh.vertices.len());
+    for (index, vertex) in mesh.vertices.iter().enumerate() {
+        let [x, y, z] = cell_of(&vertex.position);
+        let existing = (-1..=1)
+            .flat_map(|dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [dx, dy, dz])))
+            .filter_map(|[dx, dy, dz]| grid.get(&[x + dx, y + dy, z + dz]))
+            .flatten()
+            .copied()
+            .find(|&i| {
+                mesh.vertices[i as usize]
+                    .position
+                    .distance(&vertex.position)
+                    <= epsilon
+            });
+        match existing {
+            Some(representative) => group.push(group[representative as usize]),
+            None => {
+                group.push(index as u32);
+                grid.entry([x, y, z]).or_default().push(index as u32);
+            }
+        }
+    }
+    group
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(mesh: &'a Mesh) -> Self {
+        let vertex_count = mesh.vertices.len();
+        let group = position_groups(mesh);
+
+        let triangles: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .filter(|t| t.iter().all(|&i| (i as usize) < vertex_count))
+            .collect();
+        let mut adjacent = vec![Vec::new(); vertex_count];
+        for (t, triangle) in triangles.iter().enumerate() {
+            for &i in triangle {
+                adjacent[i as usize].push(t);
+            }
+        }
+
+        let mut members = vec![Vec::new(); vertex_count];
+        for (vertex, triangles) in adjacent.iter().enumerate() {
+            if !triangles.is_empty() {
+                members[group[vertex] as usize].push(vertex as u32);
+            }
+        }
+
+        let mut simplifier = Self {
+            mesh,
+            alive: vec![true; triangles.len()],
+            triangles,
+            adjacent,
+            group,
+            members,
+            kind: vec![VertexKind::Locked; vertex_count],
+            quadrics: vec![Quadric::default(); vertex_count],
+            version: vec![0; vertex_count],
+            removed: vec![false; vertex_count],
+            heap: BinaryHeap::new(),
+        };
+        simplifier.kind = (0..vertex_count as u32)
+            .map(|vertex| simplifier.classify(vertex))
+            .collect();
+        simplifier.accumulate_quadrics();
+        simplifier
+    }
+
+    fn classify(&self, vertex: u32) -> VertexKind {
+        let copies = &self.members[self.group[vertex as usize] as usize];
+        let neighbors = self.neighbors(vertex);
+
+        // Touching two copies of a neighbor makes this the end of a seam, where
+        // either side's attributes would be wrong for the other
+        let mut neighbor_groups: Vec<u32> =
+            neighbors.iter().map(|&n| self.group[n as usize]).collect();
+        neighbor_groups.sort_unstable();
+        if neighbor_groups.windows(2).any(|pair| pair[0] == pair[1]) {
+            return VertexKind::Locked;
+        }
+
+        let border = neighbors
+            .iter()
+            .any(|&n| self.group_edge_faces(vertex, n) == 1);
+        match copies.len() {
+            1 if border => VertexKind::Border,
+            1 => VertexKind::Manifold,
+            // A seam passing through leaves each copy with exactly two open edges,
+            // both shared with the other side
+            2 if !border => {
+                let is_seam_copy = |copy: u32| {
+                    let open: Vec<u32> = self
+                        .neighbors(copy)
+                        .into_iter()
+                        .filter(|&n| self.index_edge_faces(copy, n) == 1)
+                        .collect();
+                    open.len() == 2 && open.iter().all(|&n| self.group_edge_faces(copy, n) == 2)
+                };
+                if copies.iter().all(|&copy| is_seam_copy(copy)) {
+                    VertexKind::Seam
+                } else {
+                    VertexKind::Locked
+                }
+            }
+            _ => VertexKind::Locked,
+        }
+    }
+
+    fn position(&self, vertex: u32) -> Vec3 {
+        self.mesh.vertices[vertex as usize].position
+    }
+
+    fn accumulate_quadrics(&mut self) {
+        let mut edge_faces: HashMap<(u32, u32), usize> = HashMap::new();
+        for triangle in &self.triangles {
+            for k in 0..3 {
+                let (i, j) = (triangle[k], triangle[(k + 1) % 3]);
+                *edge_faces.entry((i.min(j), i.max(j))).or_default() += 1;
+            }
+            let [a, b, c] = triangle.map(|i| self.position(i));
+            let cross = (b - a).cross(&(c - a));
+            let Some(normal) = cross.try_normalize() else {
+                continue;
+            };
+            let quadric = Quadric::from_plane(normal, a, f64::from(cross.length() * 0.5));
+            for &i in triangle {
+                self.quadrics[i as usize].add(&quadric);
+            }
+        }
+
+        // Border and seam edges, which have a single triangle on their side, get a
+        // plane standing on the edge, weighted strongly enough to keep the outline or
+        // crease in place
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|i| self.position(i));
+            let face_normal = (b - a).cross(&(c - a));
+            for k in 0..3 {
+                let (i, j) = (triangle[k], triangle[(k + 1) % 3]);
+                if edge_faces.get(&(i.min(j), i.max(j))) != Some(&1) {
+                    continue;
+                }
+                let edge = self.position(j) - self.position(i);
+                let Some(normal) = edge.cross(&face_normal).try_normalize() else {
+                    continue;
+                };
+                let weight = f64::from(edge.length_squared()) * 10.0;
+                let quadric = Quadric::from_plane(normal, self.position(i), weight);
+                self.quadrics[i as usize].add(&quadric);
+                self.quadrics[j as usize].add(&quadric);
+            }
+        }
+    }
+
+    /// Vertices sharing an alive triangle with `vertex`
+    fn neighbors(&self, vertex: u32) -> Vec<u32> {
+        let mut neighbors: Vec<u32> = self.adjacent[vertex as usize]
+            .iter()
+            .flat_map(|&t| self.triangles[t])
+            .filter(|&i| i != vertex)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Number of alive triangles containing both vertices
+    fn index_edge_faces(&self, a: u32, b: u32) -> usize {
+        self.adjacent[a as usize]
+            .iter()
+            .filter(|&&t| self.triangles[t].contains(&b))
+            .count()
+    }
+
+    /// Number of alive triangles containing both position groups, across all copies
+    fn group_edge_faces(&self, a: u32, b: u32) -> usize {
+        let gb = self.group[b as usize];
+        self.members[self.group[a as usize] as usize]
+            .iter()
+            .flat_map(|&copy| &self.adjacent[copy as usize])
+            .filter(|&&t| {
+                self.triangles[t]
+                    .iter()
+                    .any(|&i| self.group[i as usize] == gb)
+            })
+            .count()
+    }
+
+    /// Position groups next to any copy of `vertex`
+    fn neighbor_groups(&self, vertex: u32) -> HashSet<u32> {
+        self.members[self.group[vertex as usize] as usize]
+            .iter()
+            .flat_map(|&copy| self.neighbors(copy))
+            .map(|n| self.group[n as usize])
+            .collect()
+    }
+
+    /// The collapse of the other copy that has to accompany collapsing seam vertex
+    /// `from` onto `to`, if `from` and `to` are joined by a seam edge
+    fn seam_partner(&self, from: u32, to: u32) -> Option<(u32, u32)> {
+        if !matches!(
+            self.kind[to as usize],
+            VertexKind::Seam | VertexKind::Locked
+        ) || self.index_edge_faces(from, to) != 1
+            || self.group_edge_faces(from, to) != 2
+        {
+            return None;
+        }
+        let group_to = self.group[to as usize];
+        let other = self.members[self.group[from as usize] as usize]
+            .iter()
+            .copied()
+            .find(|&copy| copy != from && !self.removed[copy as usize])?;
+        let other_to = self
+            .neighbors(other)
+            .into_iter()
+            .find(|&n| self.group[n as usize] == group_to)?;
+        (self.index_edge_faces(other, other_to) == 1).then_some((other, other_to))
+    }
+
+    fn queue_collapses(&mut self, from: u32) {
+        let kind = self.kind[from as usize];
+        if kind == VertexKind::Locked || self.removed[from as usize] {
+            return;
+        }
+        for to in self.neighbors(from) {
+            let mut quadric = self.quadrics[from as usize];
+            quadric.add(&self.quadrics[to as usize]);
+            match kind {
+                VertexKind::Manifold => {}
+                VertexKind::Border if self.group_edge_faces(from, to) == 1 => {}
+                VertexKind::Seam => {
+                    let Some((other, other_to)) = self.seam_partner(from, to) else {
+                        continue;
+                    };
+                    quadric.add(&self.quadrics[other as usize]);
+                    if other_to != to {
+                        quadric.add(&self.quadrics[other_to as usize]);
+                    }
+                }
+                VertexKind::Border | VertexKind::Locked => continue,
+            }
+            self.heap.push(Collapse {
+                cost: quadric.error(self.position(to)),
+                from,
+                to,
+                from_version: self.version[from as usize],
+                to_version: self.version[to as usize],
+            });
+        }
+    }
+
+    /// Collapses to perform together for moving `from` onto `to`: the pair itself
+    /// and, for a seam vertex, the matching collapse on the other side
+    fn collapse_set(&self, from: u32, to: u32) -> Option<Vec<(u32, u32)>> {
+        if self.kind[from as usize] != VertexKind::Seam {
+            return Some(vec![(from, to)]);
+        }
+        let partner = self.seam_partner(from, to)?;
+        Some(vec![(from, to), partner])
+    }
+
+    /// Whether collapsing keeps the surface manifold and no triangle flips over
+    fn can_collapse(&self, collapses: &[(u32, u32)]) -> bool {
+        let Some(&(from, to)) = collapses.first() else {
+            return false;
+        };
+
+        // Link condition: the two ends may only share the neighbors of their edge
+        let common = self
+            .neighbor_groups(from)
+            .intersection(&self.neighbor_groups(to))
+            .count();
+        if common > self.group_edge_faces(from, to) {
+            return false;
+        }
+
+        collapses.iter().all(|&(from, to)| self.can_move(from, to))
+    }
+
+    /// Whether moving `from` onto `to` keeps attributes consistent and folds no
+    /// triangle
+    fn can_move(&self, from: u32, to: u32) -> bool {
+        let group_to = self.group[to as usize];
+
+        // Moving onto one copy of a seam vertex while touching another copy would
+        // give the surviving triangles the wrong side's attributes
+        let touches_other_copy = self.adjacent[from as usize].iter().any(|&t| {
+            self.triangles[t]
+                .iter()
+                .any(|&i| i != to && self.group[i as usize] == group_to)
+        });
+        if touches_other_copy {
+            return false;
+        }
+
+        let target = self.position(to);
+        self.adjacent[from as usize].iter().all(|&t| {
+            let triangle = self.triangles[t];
+            if triangle.iter().any(|&i| self.group[i as usize] == group_to) {
+                return true;
+            }
+            let [a, b, c] = triangle.map(|i| self.position(i));
+            let before = (b - a).cross(&(c - a));
+            let [a, b, c] = triangle.map(|i| if i == from { target } else { self.position(i) });
+            let after = (b - a).cross(&(c - a));
+            // Same slack as `mesh_ops::validate`, so no collapse creates a sliver
+            let longest = (b - a)
+                .length_squared()
+                .max((c - b).length_squared())
+                .max((a - c).length_squared());
+            after.dot(&before) > 0.0 && after.length() > longest * 1e-6
+        })
+    }
+
+    fn collapse(&mut self, collapses: &[(u32, u32)]) -> usize {
+        let killed = collapses
+            .iter()
+            .map(|&(from, to)| self.move_vertex(from, to))
+            .sum();
+
+        // Every collapse touching a target or its ring, or another copy of them, now
+        // has a different cost
+        let mut ring = Vec::new();
+        for &(_, to) in collapses {
+            ring.extend(self.neighbors(to));
+            ring.push(to);
+        }
+        let mut affected: Vec<u32> = ring
+            .into_iter()
+            .flat_map(|vertex| self.members[self.group[vertex as usize] as usize].clone())
+            .collect();
+        affected.sort_unstable();
+        affected.dedup();
+        for &vertex in &affected {
+            self.version[vertex as usize] += 1;
+        }
+        for vertex in affected {
+            self.queue_collapses(vertex);
+        }
+        killed
+    }
+
+    /// Moves `from` onto `to`, dropping the triangles that degenerate
+    fn move_vertex(&mut self, from: u32, to: u32) -> usize {
+        let group_to = self.group[to as usize];
+        let mut killed = 0;
+        for t in std::mem::take(&mut self.adjacent[from as usize]) {
+            let triangle = &mut self.triangles[t];
+            if triangle.iter().any(|&i| self.group[i as usize] == group_to) {
+                self.alive[t] = false;
+                killed += 1;
+                for &i in triangle.iter() {
+                    if i != from {
+                        self.adjacent[i as usize].retain(|&other| other != t);
+                    }
+                }
+            } else {
+                for i in triangle.iter_mut() {
+                    if *i == from {
+                        *i = to;
+                    }
+                }
+                self.adjacent[to as usize].push(t);
+            }
+        }
+
+        let from_quadric = self.quadrics[from as usize];
+        self.quadrics[to as usize].add(&from_quadric);
+        self.removed[from as usize] = true;
+        killed
+    }
+
+    fn run(mut self, target: SimplifyTarget) -> Simplified {
+        let mut remaining = self.alive.iter().filter(|&&alive| alive).count();
+        for vertex in 0..self.mesh.vertices.len() as u32 {
+            self.queue_collapses(vertex);
+        }
+
+        let max_cost = f64::from(target.max_error) * f64::from(target.max_error);
+        let mut error = 0.0_f64;
+        while remaining > target.triangles {
+            let Some(collapse) = self.heap.pop() else {
+                break;
+            };
+            let (from, to) = (collapse.from, collapse.to);
+            if self.removed[from as usize]
+                || self.removed[to as usize]
+                || self.version[from as usize] != collapse.from_version
+                || self.version[to as usize] != collapse.to_version
+            {
+                continue;
+            }
+            if collapse.cost > max_cost {
+                break;
+            }
+            let Some(collapses) = self.collapse_set(from, to) else {
+                continue;
+            };
+            if !self.can_collapse(&collapses) {
+                continue;
+            }
+            remaining -= self.collapse(&collapses);
+            error = error.max(collapse.cost);
+        }
+
+        Simplified {
+            mesh: self.compact(),
+            error: error.sqrt() as f32,
+        }
+    }
+
+    /// Alive triangles over the vertices they still use
+    fn compact(&self) -> Mesh {
+        let mut remap: Vec<Option<u32>> = vec![None; self.mesh.vertices.len()];
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (triangle, _) in self
+            .triangles
+            .iter()
+            .zip(&self.alive)
+            .filter(|(_, &alive)| alive)
+        {
+            for &i in triangle {
+                let index = *remap[i as usize].get_or_insert_with(|| {
+                    vertices.push(self.mesh.vertices[i as usize]);
+                    vertices.len() as u32 - 1
+                });
+                indices.push(index);
+            }
+        }
+        Mesh { vertices, indices }
+    }
+}
+
+impl Mesh {
+    /// Simplifies the mesh by quadric error edge collapses until `target` is met or
+    /// no collapse is possible without tearing seams, folding triangles or making the
+    /// surface non-manifold
+    #[must_use]
+    pub fn simplified(&self, target: SimplifyTarget) -> Simplified {
+        Simplifier::new(self).run(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SphericalWorld;
+    use crate::math::Mat4;
+    use crate::scene::mesh_ops;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn triangle_count(mesh: &Mesh) -> usize {
+        mesh.indices.len() / 3
+    }
+
+    #[test]
+    fn test_sphere_reaches_target_and_stays_round() {
+        let sphere = SphericalWorld::new(1.0, 3).generate_mesh();
+        let simplified = sphere.simplified(SimplifyTarget::triangles(200));
+        assert!(triangle_count(&simplified.mesh) <= 200);
+        assert!(triangle_count(&simplified.mesh) >= 190);
+        assert!(mesh_ops::validate(&simplified.mesh).is_empty());
+
+        // Surviving vertices are original vertices, still on the sphere
+        for vertex in &simplified.mesh.vertices {
+            assert!((vertex.position.length() - 1.0).abs() < 1e-5);
+        }
+        let area = mesh_ops::surface_area(&simplified.mesh);
+        assert!((area - mesh_ops::surface_area(&sphere)).abs() < 0.5);
+        assert!(simplified.error > 0.0 && simplified.error < 0.2);
+
+        // Winding is preserved
+        for triangle in simplified.mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| simplified.mesh.vertices[triangle[k] as usize]);
+            let face = (b.position - a.position).cross(&(c.position - a.position));
+            assert!(face.dot(&a.position) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_flat_grid_collapses_without_error() {
+        let grid = Mesh::grid(4.0, 4.0, 8, 8);
+        let simplified = grid.simplified(SimplifyTarget::error(1e-4));
+        assert_eq!(triangle_count(&simplified.mesh), 2);
+        assert!(simplified.error < 1e-4);
+        assert!((mesh_ops::surface_area(&simplified.mesh) - 16.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_error_bound_stops_early() {
+        let sphere = SphericalWorld::new(1.0, 3).generate_mesh();
+        let coarse = sphere.simplified(SimplifyTarget::error(0.1));
+        let fine = sphere.simplified(SimplifyTarget::error(0.03));
+        assert!(coarse.error <= 0.1 && fine.error <= 0.03);
+        assert!(triangle_count(&coarse.mesh) < triangle_count(&fine.mesh));
+        assert!(triangle_count(&fine.mesh) < triangle_count(&sphere));
+    }
+
+    /// Edges between welded positions that only one triangle uses
+    fn open_edges(mesh: &Mesh) -> usize {
+        let key = |i: u32| {
+            let p = mesh.vertices[i as usize].position;
+            [p.x, p.y, p.z].map(|c| (c * 1e4).round() as i64)
+        };
+        let mut edges: HashMap<_, usize> = HashMap::new();
+        for triangle in mesh.indices.chunks_exact(3) {
+            for k in 0..3 {
+                let (a, b) = (key(triangle[k]), key(triangle[(k + 1) % 3]));
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+        edges.values().filter(|&&faces| faces == 1).count()
+    }
+
+    #[test]
+    fn test_seams_collapse_but_stay_closed() {
+        let sphere = Mesh::uv_sphere(1.0, 24, 12);
+        assert_eq!(open_edges(&sphere), 0);
+        let simplified = sphere.simplified(SimplifyTarget::triangles(100));
+        assert!(triangle_count(&simplified.mesh) <= 100);
+        assert!(mesh_ops::validate(&simplified.mesh).is_empty());
+        assert_eq!(open_edges(&simplified.mesh), 0);
+
+        // Seam vertices move along the seam in pairs: every surviving copy on one
+        // side still has its partner on the other
+        let seam = |mesh: &Mesh, u: f32| -> Vec<Vec3> {
+            mesh.vertices
+                .iter()
+                .filter(|v| v.tex_coord.x == u && v.position.y.abs() < 0.999)
+                .map(|v| v.position)
+                .collect()
+        };
+        let (left, right) = (seam(&simplified.mesh, 0.0), seam(&simplified.mesh, 1.0));
+        assert_eq!(left.len(), right.len());
+        assert!(left
+            .iter()
+            .all(|p| right.iter().any(|q| q.distance(p) < 1e-5)));
+        assert!(left.len() < seam(&sphere, 0.0).len());
+    }
+
+    #[test]
+    fn test_hard_edged_box_collapses_to_its_faces() {
+        // Six separately tessellated faces: vertices along the edges have one copy
+        // per face and the corners three
+        let face = Mesh::grid(2.0, 2.0, 4, 4);
+        let rotations = [
+            Mat4::identity(),
+            Mat4::rotation_x(PI),
+            Mat4::rotation_x(FRAC_PI_2),
+            Mat4::rotation_x(-FRAC_PI_2),
+            Mat4::rotation_z(FRAC_PI_2),
+            Mat4::rotation_z(-FRAC_PI_2),
+        ];
+        let lift = Mat4::translation(0.0, 1.0, 0.0);
+        let faces: Vec<(&Mesh, Mat4)> = rotations
+            .iter()
+            .map(|rotation| (&face, rotation.multiply(&lift)))
+            .collect();
+        let cube = mesh_ops::merge(&faces).unwrap();
+        assert_eq!(triangle_count(&cube), 192);
+
+        let simplified = cube.simplified(SimplifyTarget::error(1e-3));
+        assert_eq!(triangle_count(&simplified.mesh), 12);
+        assert!(simplified.error < 1e-3);
+        assert!(mesh_ops::validate(&simplified.mesh).is_empty());
+        assert_eq!(open_edges(&simplified.mesh), 0);
+        assert!((mesh_ops::surface_area(&simplified.mesh) - 24.0).abs() < 1e-3);
+        for vertex in &simplified.mesh.vertices {
+            let p = vertex.position;
+            assert!([p.x, p.y, p.z].iter().all(|c| (c.abs() - 1.0).abs() < 1e-5));
+        }
+    }
+
+    #[test]
+    fn test_hard_edged_cube_is_left_alone() {
+        let cube = Mesh::cube();
+        let simplified = cube.simplified(SimplifyTarget::triangles(0));
+        assert_eq!(triangle_count(&simplified.mesh), 12);
+        assert_eq!(simplified.error, 0.0);
+    }
+
+    #[test]
+    fn test_lod_chain() {
+        let sphere = SphericalWorld::new(2.0, 3).generate_mesh();
+        let chain = LodChain::generate(&sphere, 4, 0.5);
+        assert_eq!(chain.levels.len(), 4);
+        assert_eq!(triangle_count(&chain.levels[0].mesh), 1280);
+        for pair in chain.levels.windows(2) {
+            assert!(triangle_count(&pair[1].mesh) < triangle_count(&pair[0].mesh));
+            assert!(pair[1].error >= pair[0].error);
+        }
+        assert_eq!(chain.level_for_error(0.0).error, 0.0);
+        assert_eq!(
+            triangle_count(&chain.level_for_error(f32::INFINITY).mesh),
+            triangle_count(&chain.levels[3].mesh)
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Placement of text labels anchored to world positions
+//!
+//! [`LabelPlacer`] turns a world anchor into a screen position and opacity for the
+//! overlay: labels sit centered above their anchor, hide behind the camera or past
+//! the planet's horizon, fade out with distance and can be kept on screen by pushing
+//! them onto its edges. Drawing is left to `UIRenderer::draw_label`.
+
+use crate::math::{BoundingSphere, Vec2, Vec3};
+use crate::scene::Camera;
+
+/// Where and how strongly to draw a label this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelPlacement {
+    /// Top-left corner of the text in pixels
+    pub position: Vec2,
+    /// Alpha multiplier in `(0, 1]` from the distance fade
+    pub opacity: f32,
+    /// Whether the anchor was off screen and the label was pushed onto the edge
+    pub clamped: bool,
+    /// Distance from the camera to the anchor
+    pub distance: f32,
+}
+
+/// Places world-anchored labels for one viewport, configured with the `with_*`
+/// methods
+#[derive(Debug, Clone, Copy)]
+pub struct LabelPlacer {
+    viewport: Vec2,
+    planet: Option<BoundingSphere>,
+    fade_start: f32,
+    fade_end: f32,
+    margin: f32,
+    clamp_to_edges: bool,
+}
+
+impl LabelPlacer {
+    /// Places labels anywhere in front of the camera at full opacity, hiding them
+    /// once their anchor leaves the screen
+    #[must_use]
+    pub fn new(viewport: Vec2) -> Self {
+        Self {
+            viewport,
+            planet: None,
+            fade_start: f32::INFINITY,
+            fade_end: f32::INFINITY,
+            margin: 0.0,
+            clamp_to_edges: false,
+        }
+    }
+
+    /// Hides labels whose anchor is past the horizon of a spherical planet
+    #[must_use]
+    pub fn with_planet(mut self, center: Vec3, radius: f32) -> Self {
+        self.planet = Some(BoundingSphere::new(center, radius));
+        self
+    }
+
+    /// Fades labels out linearly between `start` and `end` units from the camera
+    #[must_use]
+    pub fn with_fade(mut self, start: f32, end: f32) -> Self {
+        self.fade_start = start.max(0.0);
+        self.fade_end = end.max(self.fade_start);
+        self
+    }
+
+    /// Keeps labels whose anchor is off screen on the nearest edge, `margin`
+    /// pixels in
+    #[must_use]
+    pub fn with_edge_clamping(mut self, margin: f32) -> Self {
+        self.clamp_to_edges = true;
+        self.margin = margin.max(0.0);
+        self
+    }
+
+    #[must_use]
+    pub fn viewport(&self) -> Vec2 {
+        self.viewport
+    }
+
+    /// Placement of a label of `text_size` pixels anchored at `anchor`, or `None`
+    /// when it should not be drawn
+    #[must_use]
+    pub fn place(&self, camera: &Camera, anchor: Vec3, text_size: Vec2) -> Option<LabelPlacement> {
+        let pixel = camera.world_to_screen(anchor, self.viewport, self.planet.as_ref())?;
+
+        let distance = camera.position().distance(&anchor);
+        let opacity = self.opacity_at(distance);
+        if opacity <= 0.0 {
+            return None;
+        }
+
+        let position = Vec2::new(pixel.x - text_size.x / 2.0, pixel.y - text_size.y);
+        let on_screen = (0.0..=self.viewport.x).contains(&pixel.x)
+            && (0.0..=self.viewport.y).contains(&pixel.y);
+        if on_screen {
+            return Some(LabelPlacement {
+                position,
+                opacity,
+                clamped: false,
+                distance,
+            });
+        }
+        if !self.clamp_to_edges {
+            return None;
+        }
+
+        let clamp = |value: f32, max: f32| value.min(max - self.margin).max(self.margin);
+        Some(LabelPlacement {
+            position: Vec2::new(
+                clamp(position.x, self.viewport.x - text_size.x),
+                clamp(position.y, self.viewport.y - text_size.y),
+            ),
+            opacity,
+            clamped: true,
+            distance,
+        })
+    }
+
+    fn opacity_at(&self, distance: f32) -> f32 {
+        if distance <= self.fade_start {
+            1.0
+        } else if distance >= self.fade_end {
+            0.0
+        } else {
+            1.0 - (distance - self.fade_start) / (self.fade_end - self.fade_start)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIEWPORT: Vec2 = Vec2::new(800.0, 600.0);
+    const TEXT: Vec2 = Vec2::new(40.0, 10.0);
+
+    fn camera() -> Camera {
+        Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::zero(), 800.0 / 600.0)
+    }
+
+    #[test]
+    fn test_label_sits_centered_above_anchor() {
+        let placement = LabelPlacer::new(VIEWPORT)
+            .place(&camera(), Vec3::zero(), TEXT)
+            .unwrap();
+        assert_eq!(placement.position, Vec2::new(380.0, 290.0));
+        assert_eq!(placement.opacity, 1.0);
+        assert!(!placement.clamped);
+        assert_eq!(placement.distance, 10.0);
+    }
+
+    #[test]
+    fn test_behind_camera_and_off_screen() {
+        let placer = LabelPlacer::new(VIEWPORT);
+        let camera = camera();
+        assert!(placer
+            .place(&camera, Vec3::new(0.0, 0.0, 20.0), TEXT)
+            .is_none());
+
+        // Far off to the right: hidden unless clamped onto the edge
+        let anchor = Vec3::new(100.0, 0.0, 0.0);
+        assert!(placer.place(&camera, anchor, TEXT).is_none());
+        let placement = placer
+            .with_edge_clamping(4.0)
+            .place(&camera, anchor, TEXT)
+            .unwrap();
+        assert!(placement.clamped);
+        assert_eq!(placement.position.x, VIEWPORT.x - TEXT.x - 4.0);
+        assert_eq!(placement.position.y, 290.0);
+    }
+
+    #[test]
+    fn test_fades_with_distance() {
+        let placer = LabelPlacer::new(VIEWPORT).with_fade(20.0, 40.0);
+        let camera = camera();
+        let opacity = |z: f32| {
+            placer
+                .place(&camera, Vec3::new(0.0, 0.0, z), TEXT)
+                .map(|placement| placement.opacity)
+        };
+        assert_eq!(opacity(0.0), Some(1.0));
+        assert!((opacity(-20.0).unwrap() - 0.5).abs() < 1e-5);
+        assert_eq!(opacity(-30.0), None);
+    }
+
+    #[test]
+    fn test_planet_hides_labels_past_the_horizon() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 100.0), Vec3::zero(), 1.0);
+        let placer = LabelPlacer::new(Vec2::new(600.0, 600.0)).with_planet(Vec3::zero(), 25.0);
+
+        // Facing the camera, on the surface and in orbit behind the planet
+        assert!(placer
+            .place(&camera, Vec3::new(0.0, 0.0, 25.0), TEXT)
+            .is_some());
+        assert!(placer
+            .place(&camera, Vec3::new(0.0, 0.0, -25.0), TEXT)
+            .is_none());
+        assert!(placer
+            .place(&camera, Vec3::new(0.0, 0.0, -40.0), TEXT)
+            .is_none());
+        // Past the limb but high enough to show over it
+        assert!(placer
+            .place(&camera, Vec3::new(0.0, 30.0, -5.0), TEXT)
+            .is_some());
+    }
+}
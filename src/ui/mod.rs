@@ -1,11 +1,27 @@
+//! UI overlay rendering and frame statistics
+//!
+//! `UIRenderer` and its bitmap font require the `metal` feature on macOS;
+//! the vertex layout, `FPSCounter` and world label placement are available
+//! everywhere.
+
+#[cfg(all(feature = "metal", target_os = "macos"))]
 mod font;
+mod labels;
 
-use crate::math::{Mat4, Vec2};
-use font::BitmapFont;
-use objc2::rc::Retained;
-use objc2::runtime::ProtocolObject;
-use objc2_metal::{MTLBuffer, MTLDevice, MTLTexture};
+pub use labels::{LabelPlacement, LabelPlacer};
 
+use crate::math::{Mat4, Vec2};
+#[cfg(all(feature = "metal", target_os = "macos"))]
+use {
+    crate::math::Vec3,
+    crate::scene::Camera,
+    font::BitmapFont,
+    objc2::rc::Retained,
+    objc2::runtime::ProtocolObject,
+    objc2_metal::{MTLBuffer, MTLDevice, MTLTexture},
+};
+
+#[cfg(all(feature = "metal", target_os = "macos"))]
 pub struct UIRenderer {
     vertex_buffer: Retained<ProtocolObject<dyn MTLBuffer>>,
     index_buffer: Retained<ProtocolObject<dyn MTLBuffer>>,
@@ -31,6 +47,7 @@ pub struct UIUniforms {
     pub projection: Mat4,
 }
 
+#[cfg(all(feature = "metal", target_os = "macos"))]
 impl UIRenderer {
     pub fn new(device: &ProtocolObject<dyn MTLDevice>) -> Result<Self, String> {
         let max_vertices = 4096;
@@ -135,6 +152,32 @@ impl UIRenderer {
         }
     }
 
+    /// Size in pixels of `text` as laid out by [`Self::draw_text`]
+    pub fn text_size(&self, text: &str) -> Vec2 {
+        let char_size = self.font.char_size();
+        let advance = char_size.x + 1.0;
+        let width = (text.chars().count() as f32 * advance - 1.0).max(0.0);
+        Vec2::new(width, char_size.y)
+    }
+
+    /// Draws `text` over `anchor` as placed by `placer`, fading `color` with
+    /// distance; returns whether the label was drawn
+    pub fn draw_label(
+        &mut self,
+        placer: &LabelPlacer,
+        camera: &Camera,
+        anchor: Vec3,
+        text: &str,
+        color: [f32; 4],
+    ) -> bool {
+        let Some(placement) = placer.place(camera, anchor, self.text_size(text)) else {
+            return false;
+        };
+        let [r, g, b, a] = color;
+        self.draw_text(text, placement.position, [r, g, b, a * placement.opacity]);
+        true
+    }
+
     pub fn draw_rect(&mut self, position: Vec2, size: Vec2, color: [f32; 4]) {
         let base_idx = self.vertex_count as u16;
 
@@ -242,3 +285,9 @@ impl FPSCounter {
         self.current_fps
     }
 }
+
+impl Default for FPSCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,10 +1,11 @@
-use game_engine::math::{Mat4, Vec3, Vec4};
-use game_engine::scene::{Camera, Mesh, Scene};
+use game_engine::core::SphericalWorld;
+use game_engine::math::{BoundingSphere, Frustum, Mat4, Ray, Vec3, Vec4};
+use game_engine::scene::{Camera, IndexFormat, Mesh, Scene};
 
 #[test]
 fn test_scene_and_camera() {
     // Test creating a scene - it starts empty
-    let scene = Scene::new();
+    let _scene = Scene::new();
 
     // Test that we can create and configure a camera
     let camera = Camera::new(
@@ -40,12 +41,12 @@ fn test_mesh_creation() {
 
 #[test]
 fn test_transform_composition() {
-    use game_engine::math::Transform;
+    use game_engine::math::{Quat, Transform};
 
     // Test that transform composition works correctly
     let transform = Transform::new(
         Vec3::new(1.0, 2.0, 3.0), // position
-        Vec3::new(0.0, 0.0, 0.0), // rotation
+        Quat::identity(),         // rotation
         Vec3::new(2.0, 2.0, 2.0), // scale
     );
 
@@ -86,3 +87,249 @@ fn test_camera_matrices() {
     );
     assert_ne!(camera_wide.projection_matrix(), Mat4::identity());
 }
+
+#[test]
+fn test_camera_faces_target() {
+    let position = Vec3::new(3.0, 2.0, 5.0);
+    let target = Vec3::new(-1.0, 0.5, 0.0);
+    let camera = Camera::new(position, target, 1.0);
+
+    let expected = target.sub(&position).normalize();
+    let forward = camera.forward();
+    assert!((forward.dot(&expected) - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn test_mesh_bounds_cull_and_pick() {
+    let cube = Mesh::cube();
+    let bounds = cube.bounds().unwrap();
+    assert_eq!(bounds.min, Vec3::new(-0.5, -0.5, -0.5));
+    assert_eq!(bounds.max, Vec3::new(0.5, 0.5, 0.5));
+
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zero(), 1.0);
+    let frustum = Frustum::from_view_projection(&camera.view_projection_matrix());
+    let sphere = BoundingSphere::from(bounds);
+    assert!(frustum.intersects_sphere(&sphere));
+    assert!(!frustum.intersects_sphere(&sphere.transform(&Mat4::translation(0.0, 0.0, 10.0))));
+
+    let ray = Ray::new(camera.position(), camera.forward());
+    let t = ray.intersect_aabb(&bounds).unwrap();
+    assert!((t - 4.5).abs() < 1e-5);
+}
+
+#[test]
+fn test_bundled_scene_loads() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/scenes/planet.ron");
+    let scene = Scene::load(path).unwrap();
+    let planet = scene.find_by_name("Planet").unwrap();
+    assert!(scene.get(planet).unwrap().mesh().is_some());
+
+    // Saving and loading again gives the same file
+    let text = scene.to_ron_string().unwrap();
+    assert_eq!(
+        Scene::from_ron_str(&text).unwrap().to_ron_string().unwrap(),
+        text
+    );
+}
+
+#[test]
+fn test_index_format_for_vertex_count() {
+    assert_eq!(IndexFormat::for_vertex_count(0), Ok(IndexFormat::U16));
+    assert_eq!(IndexFormat::for_vertex_count(65_536), Ok(IndexFormat::U16));
+    assert_eq!(IndexFormat::for_vertex_count(65_537), Ok(IndexFormat::U32));
+    assert_eq!(IndexFormat::U32.byte_size(), 4);
+}
+
+#[test]
+fn test_dense_planet_keeps_valid_indices() {
+    // Level 7 has more vertices than 16-bit indices can address
+    let mesh = SphericalWorld::new(10.0, 7).generate_mesh();
+    assert_eq!(mesh.vertices.len(), 163_842);
+    assert_eq!(mesh.index_format(), Ok(IndexFormat::U32));
+    assert!(mesh.check_indices().is_ok());
+    assert!(mesh.indices.iter().any(|&i| i > u32::from(u16::MAX)));
+}
+
+#[test]
+fn test_generated_grass_lod() {
+    use game_engine::core::{GrassLodMeshes, LodLevel};
+    use game_engine::scene::mesh_ops;
+
+    let lods = GrassLodMeshes::generate();
+    let full = lods.get_mesh(LodLevel::Full);
+    let reduced = lods.get_mesh(LodLevel::Reduced);
+    assert_eq!(reduced.indices.len() / 3, 4);
+    assert!(mesh_ops::validate(reduced).is_empty());
+
+    // The blade keeps its height and curved tip
+    let (full_bounds, reduced_bounds) = (full.bounds().unwrap(), reduced.bounds().unwrap());
+    assert_eq!(reduced_bounds.max.y, full_bounds.max.y);
+    assert_eq!(reduced_bounds.min.y, full_bounds.min.y);
+}
+
+#[test]
+fn test_screen_ray_through_pixels() {
+    use game_engine::math::Vec2;
+    use game_engine::scene::Projection;
+
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::zero(), 2.0);
+    let viewport = Vec2::new(800.0, 400.0);
+
+    let center = camera.screen_ray(Vec2::new(400.0, 200.0), viewport);
+    assert_eq!(center.origin, camera.position());
+    assert!((center.direction.dot(&camera.forward()) - 1.0).abs() < 1e-6);
+
+    // The top edge of the screen is half the vertical field of view up
+    let top = camera.screen_ray(Vec2::new(400.0, 0.0), viewport);
+    assert!((top.direction.y - (std::f32::consts::PI / 8.0).sin()).abs() < 1e-5);
+
+    // Rays through a pixel land on the point that projects onto it
+    let ray = camera.screen_ray(Vec2::new(600.0, 300.0), viewport);
+    let clip = camera.view_projection_matrix().multiply_vec4(&Vec4::new(
+        ray.at(5.0).x,
+        ray.at(5.0).y,
+        ray.at(5.0).z,
+        1.0,
+    ));
+    assert!((clip.x / clip.w - 0.5).abs() < 1e-5);
+    assert!((clip.y / clip.w + 0.5).abs() < 1e-5);
+
+    // Orthographic rays are parallel and start across the camera plane
+    camera.set_projection(Projection::Orthographic { height: 4.0 });
+    let corner = camera.screen_ray(Vec2::new(800.0, 0.0), viewport);
+    assert!((corner.direction.dot(&camera.forward()) - 1.0).abs() < 1e-6);
+    assert!(corner.origin.distance(&Vec3::new(4.0, 2.0, 10.0)) < 1e-5);
+}
+
+#[test]
+fn test_bundled_camera_path_stays_above_planet() {
+    use game_engine::scene::CameraPath;
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/scenes/flythrough.ron");
+    let path = CameraPath::load(path).unwrap();
+    assert!(path.looping);
+    assert_eq!(path.duration(), 20.0);
+    for step in 0..=200 {
+        let pose = path.sample(step as f32 * 0.1).unwrap();
+        assert!(pose.position.length() >= 28.0 - 1e-3);
+        assert!(pose.forward.x.is_finite() && pose.fov_y > 0.0);
+    }
+}
+
+#[test]
+fn test_clip_planes_follow_altitude() {
+    use game_engine::scene::{DepthMapping, Projection};
+
+    let mut camera = Camera::new(Vec3::new(0.0, 40.0, 0.0), Vec3::zero(), 1.0);
+    assert!(camera.set_clip_planes(0.0, 10.0).is_err());
+    assert!(camera.set_clip_planes(5.0, 5.0).is_err());
+    assert!(camera.set_clip_planes(0.5, f32::INFINITY).is_err());
+    camera.set_clip_planes(0.5, 500.0).unwrap();
+    assert_eq!((camera.near(), camera.far()), (0.5, 500.0));
+
+    // Standing on the surface keeps the near plane close
+    camera.set_position(Vec3::new(0.0, 26.0, 0.0));
+    camera.fit_clip_planes(Vec3::zero(), 25.0, 10.0);
+    let (ground_near, ground_far) = (camera.near(), camera.far());
+    assert!(ground_near <= 0.1);
+    // Far enough to see 10 unit features peeking over the horizon
+    let horizon = |h: f32| (h * (50.0 + h)).sqrt();
+    assert!(ground_far >= horizon(1.0) + horizon(10.0));
+
+    // From orbit the whole visible hemisphere is in range and the ratio stays tame
+    camera.set_position(Vec3::new(0.0, 125.0, 0.0));
+    camera.fit_clip_planes(Vec3::zero(), 25.0, 10.0);
+    assert!(camera.near() > ground_near * 100.0);
+    assert!(camera.far() >= horizon(100.0));
+    assert!(camera.far() / camera.near() < 10.0);
+
+    // Reverse-Z maps the fitted near plane to depth 1 in both projections
+    camera.set_depth_mapping(DepthMapping::ReverseZ);
+    let depth_at = |camera: &Camera, distance: f32| {
+        let point = camera.position() + camera.forward() * distance;
+        let clip = camera
+            .view_projection_matrix()
+            .multiply_vec4(&Vec4::new(point.x, point.y, point.z, 1.0));
+        clip.z / clip.w
+    };
+    assert!((depth_at(&camera, camera.near()) - 1.0).abs() < 1e-4);
+    assert!(depth_at(&camera, camera.far()).abs() < 1e-4);
+    camera.set_projection(Projection::Orthographic { height: 60.0 });
+    assert!((depth_at(&camera, camera.near()) - 1.0).abs() < 1e-4);
+    assert!(depth_at(&camera, camera.far()).abs() < 1e-4);
+}
+
+#[test]
+fn test_camera_frustum_follows_depth_mapping() {
+    use game_engine::scene::DepthMapping;
+
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zero(), 1.0);
+    camera.set_clip_planes(0.5, 100.0).unwrap();
+    let at = |camera: &Camera, distance: f32| camera.position() + camera.forward() * distance;
+
+    for mapping in [DepthMapping::Standard, DepthMapping::ReverseZ] {
+        camera.set_depth_mapping(mapping);
+        let frustum = camera.frustum();
+        assert!(frustum.contains_point(&at(&camera, 50.0)));
+        assert!(!frustum.contains_point(&at(&camera, 150.0)));
+        assert!(!frustum.contains_point(&at(&camera, 0.25)));
+        assert!(!frustum.contains_point(&at(&camera, -10.0)));
+    }
+
+    camera.set_depth_mapping(DepthMapping::ReverseZInfinite);
+    let frustum = camera.frustum();
+    assert!(frustum.contains_point(&at(&camera, 1.0e5)));
+    assert!(!frustum.contains_point(&at(&camera, 0.25)));
+    assert!(!frustum.contains_point(&at(&camera, -10.0)));
+}
+
+#[test]
+fn test_world_to_screen_inverts_screen_ray() {
+    use game_engine::math::Vec2;
+    use game_engine::scene::Projection;
+
+    let mut camera = Camera::new(Vec3::new(3.0, 4.0, 10.0), Vec3::new(0.0, 1.0, 0.0), 1.5);
+    let viewport = Vec2::new(900.0, 600.0);
+    for projection in [
+        Projection::Perspective,
+        Projection::Orthographic { height: 8.0 },
+    ] {
+        camera.set_projection(projection);
+        for pixel in [
+            Vec2::new(450.0, 300.0),
+            Vec2::new(10.0, 590.0),
+            Vec2::new(-200.0, 50.0),
+        ] {
+            let point = camera.screen_ray(pixel, viewport).at(7.0);
+            let back = camera.world_to_screen(point, viewport, None).unwrap();
+            assert!((back.x - pixel.x).abs() < 1e-2 && (back.y - pixel.y).abs() < 1e-2);
+
+            // The GPU path agrees: clip space over w gives NDC, then pixels
+            let clip = camera
+                .view_projection_matrix()
+                .multiply_vec4(&Vec4::new(point.x, point.y, point.z, 1.0));
+            let (ndc_x, ndc_y) = (clip.x / clip.w, clip.y / clip.w);
+            let gpu = Vec2::new(
+                (ndc_x + 1.0) / 2.0 * viewport.x,
+                (1.0 - ndc_y) / 2.0 * viewport.y,
+            );
+            assert!((back.x - gpu.x).abs() < 1e-2 && (back.y - gpu.y).abs() < 1e-2);
+        }
+
+        let behind = camera.position() - camera.forward() * 2.0;
+        assert!(camera.world_to_screen(behind, viewport, None).is_none());
+    }
+
+    // Standing on a planet, the far side is below the horizon
+    let planet = BoundingSphere::new(Vec3::zero(), 25.0);
+    let camera = Camera::new(Vec3::new(0.0, 27.0, 0.0), Vec3::new(0.0, 25.0, -10.0), 1.0);
+    assert!(!camera.is_occluded_by_sphere(Vec3::new(0.0, 25.0, -5.0).normalize() * 25.0, &planet));
+    assert!(camera.is_occluded_by_sphere(Vec3::new(0.0, -25.0, 0.0), &planet));
+
+    // In front of the camera but past the horizon, so it has no pixel
+    let far_side = Vec3::new(0.0, 5.0, -10.0).normalize() * 25.0;
+    assert!(camera.world_to_screen(far_side, viewport, None).is_some());
+    assert!(camera
+        .world_to_screen(far_side, viewport, Some(&planet))
+        .is_none());
+}